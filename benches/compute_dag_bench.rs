@@ -46,6 +46,16 @@ fn compute_dag_bench(bencher: &mut Criterion) {
     }
 
     bencher.bench_function("compute dag", |b| b.iter(|| compute_dag(tasks.clone())));
+
+    #[cfg(feature = "bench")]
+    {
+        let mut dag = Dag::with_tasks(tasks);
+        let mut env = EnvVar::new();
+        env.set("base", 2usize);
+        dag.set_env(env);
+        assert!(dag.start().unwrap());
+        println!("{}", dag.overhead_report());
+    }
 }
 
 criterion_group!(
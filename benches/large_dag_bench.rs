@@ -0,0 +1,35 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use dagrs::{Dag, DefaultTask, Output, Task};
+
+/// `size` tasks without work, each depending on the previous one and on the one at half its
+/// position, so that the dag is both deep and wide.
+fn tasks(size: usize) -> Vec<DefaultTask> {
+    let mut tasks: Vec<DefaultTask> = (0..size)
+        .map(|i| DefaultTask::with_closure(&i.to_string(), |_, _| Output::empty()))
+        .collect();
+    for i in 2..size {
+        let predecessors = vec![tasks[i - 1].id(), tasks[i / 2].id()];
+        tasks[i].set_predecessors_by_id(predecessors);
+    }
+    tasks
+}
+
+fn large_dag_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large dag");
+    group.sample_size(10);
+    for size in [10_000, 100_000] {
+        let tasks = tasks(size);
+        group.bench_with_input(BenchmarkId::new("build", size), &tasks, |b, tasks| {
+            b.iter(|| Dag::with_tasks(tasks.clone()))
+        });
+        let mut dag = Dag::with_tasks(tasks);
+        group.bench_function(BenchmarkId::new("run", size), |b| {
+            b.iter(|| assert!(dag.start().unwrap()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, large_dag_bench);
+criterion_main!(benches);
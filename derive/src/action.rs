@@ -0,0 +1,184 @@
+use proc_macro2::{Ident, TokenStream};
+use syn::{FnArg, GenericArgument, ItemFn, PathArguments, ReturnType, Type};
+
+/// Generate a unit struct named after the function, in camel case, whose [`Complex`] action
+/// calls the function, with a constructor of tasks running it.
+///
+/// A function `fn(Input, Arc<EnvVar>) -> Output` gets the inputs of its task as they are. Any
+/// other function takes a single typed value, optionally followed by the `Arc<EnvVar>`, and
+/// returns a value or a `Result`: the action then also implements `TypedAction`.
+pub(crate) fn generate_action(item: ItemFn) -> syn::Result<TokenStream> {
+    let sig = &item.sig;
+    let inputs = sig.inputs.iter().collect::<Vec<_>>();
+    if inputs.is_empty()
+        || inputs.len() > 2
+        || inputs.iter().any(|arg| matches!(arg, FnArg::Receiver(_)))
+    {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            "expect `fn(input: Input, env: Arc<EnvVar>) -> Output` or `fn(value: T) -> U`",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "An action function cannot be generic.",
+        ));
+    }
+    let vis = &item.vis;
+    let function = &sig.ident;
+    let struct_ident = camel_case(function);
+    let doc = format!("The action of [`{}`].", function);
+    let first = match inputs[0] {
+        FnArg::Typed(arg) => &*arg.ty,
+        FnArg::Receiver(_) => unreachable!(),
+    };
+    let (run, typed) = if inputs.len() == 2 && last_segment(first).is_some_and(|s| s == "Input") {
+        let run = if sig.asyncness.is_some() {
+            quote::quote!(dagrs::Complex::run(&dagrs::AsyncClosure::new(#function), input, env))
+        } else {
+            quote::quote!(#function(input, env))
+        };
+        (run, None)
+    } else {
+        typed_run(&item, first, inputs.len() == 2)
+    };
+    let typed = typed.map(|(in_ty, out_ty)| {
+        quote::quote!(
+            impl dagrs::TypedAction for #struct_ident {
+                type In = #in_ty;
+                type Out = #out_ty;
+            }
+
+            impl #struct_ident {
+                /// Create a typed task with the given name that runs this action.
+                #vis fn typed_task(name: &str) -> dagrs::TypedTask<#in_ty, #out_ty> {
+                    dagrs::TypedTask::new(name, #struct_ident)
+                }
+            }
+        )
+    });
+    Ok(quote::quote!(
+        #item
+
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #struct_ident;
+
+        impl dagrs::Complex for #struct_ident {
+            fn run(&self, input: dagrs::Input, env: std::sync::Arc<dagrs::EnvVar>) -> dagrs::Output {
+                #run
+            }
+        }
+
+        #typed
+
+        impl #struct_ident {
+            /// Create a task with the given name that runs this action.
+            #vis fn task(name: &str) -> dagrs::DefaultTask {
+                dagrs::DefaultTask::with_action(name, #struct_ident)
+            }
+        }
+    ))
+}
+
+/// The body of the action of a typed function, which takes the first input of type `In`, and
+/// the types `In` and `Out`.
+fn typed_run(item: &ItemFn, first: &Type, with_env: bool) -> (TokenStream, Option<(Type, Type)>) {
+    let function = &item.sig.ident;
+    // A reference is passed as is, a value is cloned out of the input.
+    let (in_ty, value) = match first {
+        Type::Reference(reference) => ((*reference.elem).clone(), quote::quote!(value)),
+        ty => (ty.clone(), quote::quote!(::std::clone::Clone::clone(value))),
+    };
+    let missing = format!(
+        "expected an input of type `{}`",
+        quote::quote!(#in_ty).to_string().replace(' ', "")
+    );
+    let (out_ty, fallible) = match &item.sig.output {
+        ReturnType::Default => (syn::parse_quote!(()), false),
+        ReturnType::Type(_, ty) => match result_ok(ty) {
+            Some(ok) => (ok, true),
+            None => ((**ty).clone(), false),
+        },
+    };
+    let env = with_env.then(|| quote::quote!(, env));
+    let call = if item.sig.asyncness.is_some() {
+        quote::quote!(#function(#value #env).await)
+    } else {
+        quote::quote!(#function(#value #env))
+    };
+    let output = if fallible {
+        quote::quote!(match #call {
+            Ok(value) => dagrs::Output::new(value),
+            Err(err) => dagrs::Output::error(err.to_string()),
+        })
+    } else {
+        quote::quote!(dagrs::Output::new(#call))
+    };
+    let body = quote::quote!(
+        let value = match input.get_iter().find_map(|content| content.get::<#in_ty>()) {
+            Some(value) => value,
+            None => return dagrs::Output::error(#missing.to_string()),
+        };
+        #output
+    );
+    let run = if item.sig.asyncness.is_some() {
+        quote::quote!(dagrs::Complex::run(
+            &dagrs::AsyncClosure::new(|input: dagrs::Input, env: std::sync::Arc<dagrs::EnvVar>| async move {
+                let _ = &env;
+                #body
+            }),
+            input,
+            env,
+        ))
+    } else {
+        quote::quote!(
+            let _ = &env;
+            #body
+        )
+    };
+    (run, Some((in_ty, out_ty)))
+}
+
+/// The name of the last segment of the path of a type.
+fn last_segment(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// `T` of a `Result<T, E>`.
+fn result_ok(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(ok) => Some(ok.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `fetch_data` becomes `FetchData`.
+pub(crate) fn camel_case(ident: &Ident) -> Ident {
+    let name: String = ident
+        .to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    Ident::new(&name, ident.span())
+}
@@ -0,0 +1,64 @@
+use proc_macro2::TokenStream;
+use syn::{FnArg, ItemFn};
+
+use crate::action::camel_case;
+
+/// Generate a unit struct named after the function, in camel case, whose [`Condition`] calls
+/// the function, with a constructor of tasks guarded by it.
+pub(crate) fn generate_condition(item: ItemFn) -> syn::Result<TokenStream> {
+    let sig = &item.sig;
+    let inputs = sig.inputs.iter().collect::<Vec<_>>();
+    if inputs.len() != 2 || inputs.iter().any(|arg| matches!(arg, FnArg::Receiver(_))) {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            "expect `fn(input: &Input, env: &EnvVar) -> bool`",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "A condition function cannot be generic.",
+        ));
+    }
+    let vis = &item.vis;
+    let function = &sig.ident;
+    let struct_ident = camel_case(function);
+    let doc = format!("The condition of [`{}`].", function);
+    let check = if sig.asyncness.is_some() {
+        quote::quote!(dagrs::Condition::check(
+            &dagrs::AsyncCondition::new(|input: dagrs::Input, env: dagrs::EnvVar| async move {
+                #function(&input, &env).await
+            }),
+            input,
+            env,
+        ))
+    } else {
+        quote::quote!(#function(input, env))
+    };
+    Ok(quote::quote!(
+        #item
+
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #struct_ident;
+
+        impl dagrs::Condition for #struct_ident {
+            fn check(&self, input: &dagrs::Input, env: &dagrs::EnvVar) -> bool {
+                #check
+            }
+        }
+
+        impl #struct_ident {
+            /// Create a task with the given name and action that runs only if this condition
+            /// is true.
+            #vis fn task(
+                name: &str,
+                action: impl dagrs::Complex + Send + Sync + 'static,
+            ) -> dagrs::DefaultTask {
+                let mut task = dagrs::DefaultTask::with_action(name, action);
+                task.set_condition(#struct_ident);
+                task
+            }
+        }
+    ))
+}
@@ -4,6 +4,10 @@ extern crate proc_macro2;
 extern crate quote;
 extern crate syn;
 
+#[cfg(feature = "derive")]
+mod action;
+#[cfg(feature = "derive")]
+mod condition;
 #[cfg(feature = "derive")]
 mod relay;
 #[cfg(feature = "derive")]
@@ -13,6 +17,10 @@ mod task;
 /// marked on the structure, and the user needs to specify four attributes of the custom task
 /// type, which are task(attr="id"), task(attr = "name"), task(attr = "precursors ") and
 /// task(attr = "action"), which are used in the `derive_task` example.
+///
+/// Marking the structure `#[task(constructors)]` also generates `new(name)` and
+/// `with_action(name, action)` constructors, like the ones of `DefaultTask`: they allocate the
+/// id, leave the precursors empty, and give the other fields their default value.
 #[cfg(feature = "derive")]
 #[proc_macro_derive(CustomTask, attributes(task))]
 pub fn derive_task(input: TokenStream) -> TokenStream {
@@ -24,6 +32,33 @@ pub fn derive_task(input: TokenStream) -> TokenStream {
 
 /// The [`dependencies!`] macro allows users to specify all task dependencies in an easy-to-understand
 /// way. It will return to the user a series of `DefaultTask` in the order of tasks given by the user.
+///
+/// The dependencies are checked when compiling: a cycle among them is a compile error naming the
+/// tasks along it, such as `Cycle in the task dependencies! [b -> c -> b]`.
+///
+/// ```
+/// use dagrs::{dependencies, Task};
+/// let tasks = dependencies!(
+///     a -> b c,
+///     b -> c,
+///     c ->
+/// );
+/// assert_eq!(tasks[2].precursors().len(), 2);
+/// ```
+///
+/// Two tasks depending on each other do not compile:
+///
+/// ```compile_fail
+/// use dagrs::dependencies;
+/// let tasks = dependencies!(a -> b, b -> a);
+/// ```
+///
+/// Nor does a task depending on itself:
+///
+/// ```compile_fail
+/// use dagrs::dependencies;
+/// let tasks = dependencies!(a -> a);
+/// ```
 #[cfg(feature = "derive")]
 #[proc_macro]
 pub fn dependencies(input: TokenStream) -> TokenStream {
@@ -37,3 +72,69 @@ pub fn dependencies(input: TokenStream) -> TokenStream {
     let token = generate_task(relies.unwrap());
     token.into()
 }
+
+/// The [`action`] attribute turns a function `fn(Input, Arc<EnvVar>) -> Output`, which may be
+/// `async`, into an action: it generates a unit struct named after the function in camel case,
+/// which implements `Complex` by calling the function, and whose `task(name)` creates a
+/// `DefaultTask` running it. An async function runs on a runtime of its own, like an
+/// `AsyncClosure`.
+///
+/// A function of a single typed value, like `async fn double(x: u32) -> u64`, becomes a
+/// `TypedAction`: the action takes the first input of its task holding a `u32` and outputs the
+/// result. The value may be followed by the `Arc<EnvVar>`, taken by reference, and the function
+/// may return a `Result`, whose error fails the task. Its `typed_task(name)` creates a
+/// `TypedTask`, whose connections to other typed tasks are checked at compile time.
+///
+/// # Example
+///
+/// ```ignore
+/// #[action]
+/// async fn fetch(_input: Input, env: Arc<EnvVar>) -> Output {
+///     Output::new(env.get::<String>("URL").unwrap())
+/// }
+///
+///
+/// #[action]
+/// async fn double(x: u32) -> u64 {
+///     u64::from(x) * 2
+/// }
+///
+/// let task = Fetch::task("fetch");
+/// let mut double = Double::task("double");
+/// double.set_predecessors(&[&task]);
+/// ```
+#[cfg(feature = "derive")]
+#[proc_macro_attribute]
+pub fn action(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    use crate::action::generate_action;
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+    generate_action(item)
+        .unwrap_or_else(|err| err.into_compile_error())
+        .into()
+}
+
+/// The [`condition`] attribute turns a function `fn(&Input, &EnvVar) -> bool`, which may be
+/// `async`, into a condition: it generates a unit struct named after the function in camel case,
+/// which implements `Condition` by calling the function, and whose `task(name, action)` creates
+/// a `DefaultTask` running the action only if the condition is true. An async function runs on
+/// a runtime of its own, like an `AsyncCondition`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[condition]
+/// async fn deploy_enabled(_input: &Input, env: &EnvVar) -> bool {
+///     env.get::<bool>("DEPLOY") == Some(true)
+/// }
+///
+/// let deploy = DeployEnabled::task("deploy", Deploy);
+/// ```
+#[cfg(feature = "derive")]
+#[proc_macro_attribute]
+pub fn condition(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    use crate::condition::generate_condition;
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+    generate_condition(item)
+        .unwrap_or_else(|err| err.into_compile_error())
+        .into()
+}
@@ -50,8 +50,29 @@ impl Tasks {
         Ok(())
     }
 
+    /// Fail on the first cycle of the declared edges, naming the tasks along it.
+    fn check_cycle(&self) -> syn::Result<()> {
+        let successors: HashMap<&Ident, &Vec<Ident>> = self
+            .0
+            .iter()
+            .map(|relay| (&relay.task, &relay.successors))
+            .collect();
+        // The tasks whose successors were all visited.
+        let mut done = HashSet::new();
+        for relay in self.0.iter() {
+            let mut path = Vec::new();
+            if let Some(cycle) = find_cycle(&relay.task, &successors, &mut path, &mut done) {
+                let names: Vec<String> = cycle.iter().map(|task| task.to_string()).collect();
+                let err_msg = format!("Cycle in the task dependencies! [{}]", names.join(" -> "));
+                return Err(syn::Error::new_spanned(cycle[0], err_msg));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn resolve_dependencies(self) -> syn::Result<Vec<Task>> {
         self.check_duplicate()?;
+        self.check_cycle()?;
         let mut seq = Vec::new();
         let tasks: HashMap<Ident, Vec<Ident>> = self
             .0
@@ -80,6 +101,37 @@ impl Tasks {
     }
 }
 
+/// Depth-first search from `task`, returning the tasks of a cycle, the first one repeated at the
+/// end, if one is reachable.
+fn find_cycle<'a>(
+    task: &'a Ident,
+    successors: &HashMap<&'a Ident, &'a Vec<Ident>>,
+    path: &mut Vec<&'a Ident>,
+    done: &mut HashSet<&'a Ident>,
+) -> Option<Vec<&'a Ident>> {
+    if done.contains(task) {
+        return None;
+    }
+    if let Some(start) = path.iter().position(|visited| *visited == task) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(task);
+        return Some(cycle);
+    }
+    path.push(task);
+    for successor in successors
+        .get(task)
+        .into_iter()
+        .flat_map(|succ| succ.iter())
+    {
+        if let Some(cycle) = find_cycle(successor, successors, path, done) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    done.insert(task);
+    None
+}
+
 fn init_tasks(tasks: &[Task]) -> proc_macro2::TokenStream {
     let mut token = proc_macro2::TokenStream::new();
     for task in tasks.iter() {
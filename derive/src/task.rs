@@ -26,7 +26,106 @@ pub(crate) fn parse_task(input: &DeriveInput) -> TokenStream {
     if let Err(e) = attr_token {
         return e.into_compile_error();
     }
-    generate_impl(struct_ident, attr_token.unwrap())
+    let mut token = generate_impl(struct_ident, attr_token.unwrap());
+    match wants_constructors(input) {
+        Ok(true) => match generate_constructors(struct_ident, fields) {
+            Ok(constructors) => token.extend(constructors),
+            Err(e) => return e.into_compile_error(),
+        },
+        Ok(false) => {}
+        Err(e) => return e.into_compile_error(),
+    }
+    token
+}
+
+/// Whether the struct is marked `#[task(constructors)]`.
+fn wants_constructors(input: &DeriveInput) -> syn::Result<bool> {
+    let mut wants = false;
+    for attr in input.attrs.iter() {
+        if attr.path().is_ident("task") {
+            let ident: Ident = attr.parse_args()?;
+            if ident != "constructors" {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "expect `task(constructors)`",
+                ));
+            }
+            wants = true;
+        }
+    }
+    Ok(wants)
+}
+
+/// Generate `new(name)` and `with_action(name, action)`, like the ones of `DefaultTask`. The id
+/// is allocated, the precursors are empty, and the fields without a `task` attribute get their
+/// default value.
+fn generate_constructors(struct_ident: &Ident, fields: &Fields) -> syn::Result<TokenStream> {
+    let mut inits = Vec::new();
+    let mut missing = vec![ID, NAME, PRECURSORS, ACTION];
+    for field in fields.iter() {
+        let ident = field.ident.as_ref().ok_or_else(|| {
+            syn::Error::new_spanned(field, "Constructors need a struct with named fields.")
+        })?;
+        let attr = field_attr(field);
+        missing.retain(|name| attr.as_deref() != Some(*name));
+        let value = match attr.as_deref() {
+            Some(ID) => quote::quote!(dagrs::alloc_id()),
+            Some(NAME) => quote::quote!(name.to_owned()),
+            Some(PRECURSORS) => quote::quote!(Vec::new()),
+            Some(ACTION) => quote::quote!(action),
+            _ => quote::quote!(Default::default()),
+        };
+        inits.push(quote::quote!(#ident: #value));
+    }
+    if !missing.is_empty() {
+        return Err(syn::Error::new_spanned(
+            struct_ident,
+            format!(
+                "Constructors need the fields with attr [{}]",
+                missing.join(",")
+            ),
+        ));
+    }
+    Ok(quote::quote!(
+        impl #struct_ident {
+            /// Create a task with the given name, whose action does nothing.
+            pub fn new(name: &str) -> Self {
+                let action = dagrs::Action::Closure(std::sync::Arc::new(|_, _| {
+                    dagrs::Output::empty()
+                }));
+                Self {
+                    #(#inits,)*
+                }
+            }
+
+            /// Create a task with the given name, and a type that implements `Complex` as its
+            /// action.
+            pub fn with_action(
+                name: &str,
+                action: impl dagrs::Complex + Send + Sync + 'static,
+            ) -> Self {
+                let action = dagrs::Action::Structure(std::sync::Arc::new(action));
+                Self {
+                    #(#inits,)*
+                }
+            }
+        }
+    ))
+}
+
+/// The value of the `task(attr = "...")` of a field, if it has one.
+fn field_attr(field: &Field) -> Option<String> {
+    field.attrs.iter().find_map(|attr| {
+        if !attr.path().is_ident("task") {
+            return None;
+        }
+        match attr.parse_args::<MetaNameValue>().ok()?.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => Some(lit.value()),
+            _ => None,
+        }
+    })
 }
 
 fn generate_field_function(fields: &Fields) -> syn::Result<proc_macro2::TokenStream> {
@@ -91,7 +91,7 @@ impl ConfigParser {
         let attr: Vec<&str> = item.split(',').collect();
 
         let pres_item = *attr.get(2).unwrap();
-        let pres = if pres_item.eq("") {
+        let pres = if pres_item.is_empty() {
             Vec::new()
         } else {
             pres_item.split(' ').map(|pre| pre.to_string()).collect()
@@ -1,34 +1,34 @@
+//! The `dependencies` macro allows users to specify all task dependencies in an easy-to-understand
+//! way. It will return to the user a series of `DefaultTask` in the order of tasks given by the user.
+//!
+//! # Example
+//!
+//!    ↱----------↴
+//!    B -→ E --→ G
+//!  ↗    ↗     ↗
+//! A --→ C    /
+//!  ↘    ↘  /
+//!   D -→ F
+//!
+//! If you want to define a task graph with such dependencies, the code is as follows:
+//!
+//! let mut tasks=dependencies!(
+//!     a -> b c d,
+//!     b -> e g,
+//!     c -> e f,
+//!     d -> f,
+//!     e -> g,
+//!     f -> g,
+//!     g ->
+//! );
+//!
+//! Note that although task g has no successor tasks, "g->" must also be written. The return
+//! value type tasks is a Vec<DefaultTask>. The name of each task is the same as the given
+//! identifier, which can be expressed as an array as [ "a","b","c","d","e","f","g"].
+
 use dagrs::{dependencies, Complex, EnvVar, Input, Output};
 use std::sync::Arc;
 
-/// The `dependencies` macro allows users to specify all task dependencies in an easy-to-understand
-/// way. It will return to the user a series of `DefaultTask` in the order of tasks given by the user.
-///
-/// # Example
-///
-///    ↱----------↴
-///    B -→ E --→ G
-///  ↗    ↗     ↗
-/// A --→ C    /
-///  ↘    ↘  /
-///   D -→ F
-///
-/// If you want to define a task graph with such dependencies, the code is as follows:
-///
-/// let mut tasks=dependencies!(
-///     a -> b c d,
-///     b -> e g,
-///     c -> e f,
-///     d -> f,
-///     e -> g,
-///     f -> g,
-///     g ->
-/// );
-///
-/// Note that although task g has no successor tasks, "g->" must also be written. The return
-/// value type tasks is a Vec<DefaultTask>. The name of each task is the same as the given
-/// identifier, which can be expressed as an array as [ "a","b","c","d","e","f","g"].
-
 struct Compute(usize);
 
 impl Complex for Compute {
@@ -5,6 +5,7 @@ use std::sync::Arc;
 /// marked on the structure, and the user needs to specify four attributes of the custom task
 /// type, which are task(attr="id"), task(attr = "name"), task(attr = "precursors ") and
 /// task(attr = "action"), which are used in the `derive_task` example.
+/// With `#[task(constructors)]`, it also generates the `new` and `with_action` constructors.
 ///
 /// # Example
 ///
@@ -22,6 +23,7 @@ use std::sync::Arc;
 /// }
 /// ```
 #[derive(CustomTask)]
+#[task(constructors)]
 struct MyTask {
     #[task(attr = "id")]
     id: usize,
@@ -42,4 +44,7 @@ fn main() {
         action,
     };
     println!("{}\t{}\t{:?}", task.id(), task.name(), task.precursors());
+
+    let task = MyTask::new("generated");
+    println!("{}\t{}\t{:?}", task.id(), task.name(), task.precursors());
 }
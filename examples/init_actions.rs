@@ -0,0 +1,40 @@
+//! Runs the workflow with the action types of this crate.
+
+use dagrs::{
+    Action, ActionRegistry, ArtifactDir, CleanupPolicy, Complex, Dag, EnvVar, Input, Output,
+    Parser, YamlParser,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// A custom action: greets the given name.
+struct Greet {
+    who: String,
+}
+
+impl Complex for Greet {
+    fn run(&self, _input: Input, _env: Arc<EnvVar>) -> Output {
+        Output::new(format!("Hello, {}!", self.who))
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let mut registry = ActionRegistry::new();
+    registry.register("greet", |args| {
+        let who = args
+            .get("who")
+            .cloned()
+            .unwrap_or_else(|| "world".to_owned());
+        Action::Structure(Arc::new(Greet { who }))
+    });
+    let workflow = concat!(env!("CARGO_MANIFEST_DIR"), "/../workflow.yaml");
+    let tasks = YamlParser::new()
+        .action_registry(registry)
+        .parse_tasks(workflow, HashMap::new())
+        .unwrap();
+    let runs = concat!(env!("CARGO_MANIFEST_DIR"), "/../runs");
+    let mut dag =
+        Dag::with_tasks_dyn(tasks).with_artifacts(ArtifactDir::new(runs, CleanupPolicy::OnSuccess));
+    assert!(dag.start().unwrap());
+    println!("{}", dag.get_result::<String>().unwrap());
+}
@@ -1,20 +1,127 @@
-use std::{collections::HashMap, fs::File, str::FromStr};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
 
 use clap::Parser;
-use dagrs::Dag;
+use dagrs::{
+    utils::{ContextLogger, RotatingFileWriter, Rotation},
+    ArtifactDir, CleanupPolicy, Dag, GraphFormat, LiveGraph, RunHistory, YamlParser,
+};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Junit,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Create a starter project: an example yaml workflow, a crate with a custom action type
+    /// that runs it, and an ignored run directory.
+    Init {
+        /// The directory of the project, created if it does not exist.
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// List the runs recorded in a history file, or show one of them.
+    History {
+        /// The history file, as given to '--history'.
+        #[arg(long)]
+        file: PathBuf,
+        /// Show the run with this number, with the outcome of each task.
+        number: Option<u64>,
+    },
+}
 
 #[derive(Parser, Debug)]
-#[command(name = "dagrs", version = "0.2.0")]
+#[command(name = "dagrs", version = "0.2.0", subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Log output file, the default is to print to the terminal.
     #[arg(long)]
     log_path: Option<String>,
-    /// yaml configuration file path.
+    /// yaml configuration file paths, or directories of yaml configuration files, whose tasks
+    /// are merged into one dag.
+    #[arg(long, num_args = 1.., required_unless_present = "schema")]
+    yaml: Vec<String>,
+    /// Print the JSON Schema of the yaml configuration file instead of running a dag.
+    #[arg(long)]
+    schema: bool,
+    /// Apply this profile of the yaml configuration file.
+    #[arg(long)]
+    profile: Option<String>,
+    /// Fail on unknown or misspelled attributes of the yaml configuration file instead of
+    /// ignoring them.
+    #[arg(long)]
+    strict: bool,
+    /// Run commands in the current directory instead of the directory of the yaml
+    /// configuration file.
     #[arg(long)]
-    yaml: String,
+    relative_to_cwd: bool,
+    /// A parameter of the run as 'key=value', referred to as '${params.key}' in the yaml
+    /// configuration file and set as an environment variable of the dag. May be repeated.
+    #[arg(long = "param", value_parser = parse_param)]
+    params: Vec<(String, String)>,
+    /// Print the structural changes from `--yaml` to this yaml configuration file instead of
+    /// running the dag.
+    #[arg(long)]
+    diff: Option<String>,
+    /// Format of the run summary printed to stdout: 'text' (the default) prints nothing,
+    /// 'json' prints the status, duration and output of every task, 'junit' prints a JUnit XML
+    /// report in which every task is a test case. With 'json' and 'junit', the stdout and
+    /// stderr of the commands are captured to files named in the summary, and kept.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// Keep the run directory, holding the captured output of the commands, in this directory
+    /// instead of the temporary directory of the system.
+    #[arg(long)]
+    artifacts: Option<PathBuf>,
     /// Log level, the default is 'info'.
     #[arg(long)]
     log_level: Option<String>,
+    /// Rotate the log file before it grows beyond this many bytes.
+    #[arg(long, requires = "log_path")]
+    log_max_size: Option<u64>,
+    /// Number of rotated log files to keep, the default is 5. With any of the rotation options,
+    /// the log file of the last run is rotated too, instead of overwritten.
+    #[arg(long, requires = "log_path")]
+    log_max_files: Option<usize>,
+    /// Rotate the log file every day.
+    #[arg(long, requires = "log_path")]
+    log_rotate_daily: bool,
+    /// Seconds that running tasks are given to finish after Ctrl-C, the default is 10. A second
+    /// Ctrl-C exits immediately.
+    #[arg(long, default_value_t = 10)]
+    shutdown_grace: u64,
+    /// Record the run in this history file, see 'dagrs history'.
+    #[arg(long)]
+    history: Option<PathBuf>,
+    /// Run the tasks one at a time, in an order drawn from this seed, to reproduce bugs that
+    /// depend on the order tasks run in.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Run only this task and the tasks it depends on. May be repeated.
+    #[arg(long = "target")]
+    targets: Vec<String>,
+    /// Keep this file up to date with the graph of the dag and the state of each task as it
+    /// runs: DOT for '.dot' and '.gv' files, Mermaid for '.mmd' files, JSON otherwise.
+    #[arg(long)]
+    live_graph: Option<PathBuf>,
+    /// Write a standalone HTML report of the run to this file.
+    #[cfg(feature = "report-html")]
+    #[arg(long)]
+    html_report: Option<PathBuf>,
 }
 
 fn main() {
@@ -22,9 +129,265 @@ fn main() {
 
     init_logger(&args);
 
-    let yaml_path = args.yaml;
-    let mut dag = Dag::with_yaml(yaml_path.as_str(), HashMap::new()).unwrap();
-    assert!(dag.start().unwrap());
+    match &args.command {
+        Some(Command::Init { dir }) => {
+            if let Err(err) = init(dir) {
+                eprintln!("dagrs init: {}", err);
+                std::process::exit(1);
+            }
+            println!("Created a dagrs project in '{}'.", dir.display());
+            return;
+        }
+        Some(Command::History { file, number }) => {
+            if let Err(err) = history(&RunHistory::new(file), *number) {
+                eprintln!("dagrs history: {}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+        None => {}
+    }
+    if args.schema {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&YamlParser::json_schema()).unwrap()
+        );
+        return;
+    }
+    if let Some(new_path) = args.diff {
+        let old = Dag::with_tasks_dyn(
+            YamlParser::new()
+                .parse_files(&args.yaml, HashMap::new())
+                .unwrap(),
+        );
+        let new = Dag::with_yaml(new_path.as_str(), HashMap::new()).unwrap();
+        print!("{}", old.export_structure().diff(&new.export_structure()));
+        return;
+    }
+    let mut parser = match &args.profile {
+        Some(profile) => YamlParser::with_profile(profile),
+        None => YamlParser::new(),
+    };
+    if args.strict {
+        parser = parser.strict();
+    }
+    if args.relative_to_cwd {
+        parser = parser.relative_to_cwd();
+    }
+    let params: HashMap<String, String> = args.params.iter().cloned().collect();
+    parser = parser.params(params.clone());
+    for path in args.yaml.iter().filter(|path| Path::new(path).is_file()) {
+        if let Some(dir) = parser.base_dir(path) {
+            log::info!("Running the commands of '{}' in '{}'", path, dir.display());
+        }
+    }
+    let mut dag = Dag::with_tasks_dyn(parser.parse_files(&args.yaml, HashMap::new()).unwrap())
+        .with_params(params);
+    if let Some(seed) = args.seed {
+        dag = dag.deterministic(seed);
+    }
+    if args.output != OutputFormat::Text {
+        // Not teed, the console output of the commands would mix with the summary.
+        dag = dag.capture_command_output(false);
+    }
+    if args.artifacts.is_some() || args.output != OutputFormat::Text {
+        let parent = args.artifacts.clone().unwrap_or_else(std::env::temp_dir);
+        dag = dag.with_artifacts(ArtifactDir::new(parent, CleanupPolicy::Never));
+    }
+    if !args.targets.is_empty() {
+        let tasks = dag.export_structure().tasks;
+        let targets: Vec<usize> = args
+            .targets
+            .iter()
+            .map(|name| match tasks.iter().find(|task| &task.name == name) {
+                Some(task) => task.id,
+                None => {
+                    eprintln!("dagrs: no task named '{}'", name);
+                    std::process::exit(1);
+                }
+            })
+            .collect();
+        dag = dag.subgraph_for(&targets).unwrap();
+    }
+    if let Some(path) = &args.live_graph {
+        let format = GraphFormat::from_path(path);
+        dag.add_observer(LiveGraph::new(&dag.export_structure(), path, format));
+    }
+    let cancel = dag.cancel_handle();
+    let grace = Duration::from_secs(args.shutdown_grace);
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler = interrupted.clone();
+    ctrlc::set_handler(move || {
+        if handler.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        log::warn!("Interrupted, shutting down within {:?}", grace);
+        cancel.shutdown(grace);
+    })
+    .unwrap();
+    let started = SystemTime::now();
+    let success = dag.start().unwrap();
+    if let Some(path) = &args.history {
+        let name = args.yaml.join(",");
+        if let Err(err) =
+            RunHistory::new(path).record(&name, started, SystemTime::now(), &dag.report())
+        {
+            log::error!("Failed to record the run in '{}': {}", path.display(), err);
+        }
+    }
+    #[cfg(feature = "report-html")]
+    if let Some(path) = &args.html_report {
+        if let Err(err) = dag.save_html_report(path) {
+            log::error!(
+                "Failed to write the report to '{}': {}",
+                path.display(),
+                err
+            );
+        }
+    }
+    match args.output {
+        OutputFormat::Text => {}
+        OutputFormat::Json => println!("{}", dag.report().to_json()),
+        OutputFormat::Junit => print!("{}", dag.report().to_junit(&args.yaml.join(","))),
+    }
+    if args.output != OutputFormat::Text && !success {
+        std::process::exit(1);
+    }
+    if interrupted.load(Ordering::SeqCst) && !success {
+        std::process::exit(130);
+    }
+    assert!(success);
+}
+
+/// Print the runs of a history, or the run with the given number.
+fn history(history: &RunHistory, number: Option<u64>) -> io::Result<()> {
+    let Some(number) = number else {
+        for run in history.runs()? {
+            println!(
+                "#{:<5} {}  {:<9} {:>10.2?}  {}",
+                run.number,
+                format_utc(run.started_at),
+                if run.success { "succeeded" } else { "failed" },
+                run.duration(),
+                run.dag
+            );
+        }
+        return Ok(());
+    };
+    let run = history.get(number)?.ok_or(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no run #{}", number),
+    ))?;
+    println!("Run #{} of {}", run.number, run.dag);
+    if let Some(run_id) = &run.run_id {
+        println!("Run id:   {}", run_id);
+    }
+    println!("Started:  {}", format_utc(run.started_at));
+    println!("Finished: {}", format_utc(run.finished_at));
+    println!("Success:  {}", run.success);
+    for task in run.tasks {
+        let status = serde_json::to_value(task.status).unwrap();
+        print!("  {:<9} {}", status.as_str().unwrap_or_default(), task.name);
+        if let Some(ms) = task.duration_ms {
+            print!(" ({} ms)", ms);
+        }
+        match task.error {
+            Some(error) => println!(": {}", error),
+            None => println!(),
+        }
+    }
+    Ok(())
+}
+
+/// Format milliseconds since the Unix epoch as a UTC date and time.
+fn format_utc(millis: u64) -> String {
+    let secs = millis / 1000;
+    let (days, time) = ((secs / 86400) as i64, secs % 86400);
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+const INIT_WORKFLOW: &str = r#"# An example dagrs workflow. The 'greet' task uses an action type of the actions
+# crate, run it with: cargo run --manifest-path actions/Cargo.toml
+dagrs:
+  hello:
+    name: "Hello"
+    cmd: echo hello
+  greet:
+    name: "Greet"
+    after: [ hello ]
+    type: greet
+    args:
+      who: dagrs
+    timeout: 10
+"#;
+
+const INIT_GITIGNORE: &str = "runs/\nactions/target/\n";
+
+const INIT_CARGO_TOML: &str = r#"[package]
+name = "actions"
+version = "0.1.0"
+edition = "2021"
+publish = false
+
+# The release of dagrs this command comes from, which has the API the actions use.
+[dependencies]
+dagrs = { version = "{version}", features = ["yaml"] }
+env_logger = "0.10"
+"#;
+
+/// The crate of actions is an example of this crate, so that it builds against its API.
+const INIT_MAIN_RS: &str = include_str!("../../examples/init_actions.rs");
+
+/// Create a starter project in `dir`, without overwriting any existing file.
+fn init(dir: &Path) -> io::Result<()> {
+    let files = [
+        ("workflow.yaml", INIT_WORKFLOW.to_owned()),
+        (".gitignore", INIT_GITIGNORE.to_owned()),
+        (
+            "actions/Cargo.toml",
+            INIT_CARGO_TOML.replace("{version}", env!("CARGO_PKG_VERSION")),
+        ),
+        ("actions/src/main.rs", INIT_MAIN_RS.to_owned()),
+    ];
+    for (name, _) in &files {
+        if dir.join(name).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", dir.join(name).display()),
+            ));
+        }
+    }
+    std::fs::create_dir_all(dir.join("actions/src"))?;
+    std::fs::create_dir_all(dir.join("runs"))?;
+    for (name, content) in files {
+        std::fs::write(dir.join(name), content)?;
+    }
+    Ok(())
+}
+
+fn parse_param(param: &str) -> Result<(String, String), String> {
+    param
+        .split_once('=')
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .ok_or(format!("'{}' is not a 'key=value' parameter", param))
 }
 
 fn init_logger(args: &Args) {
@@ -37,10 +400,26 @@ fn init_logger(args: &Args) {
 
     // initialize the env_logger with the given log_path
     if let Some(log_path) = &args.log_path {
-        logger_builder.target(env_logger::Target::Pipe(Box::new(
-            File::create(log_path).unwrap(),
-        )));
+        if args.log_max_size.is_some() || args.log_max_files.is_some() || args.log_rotate_daily {
+            let mut rotation = Rotation::new().daily(args.log_rotate_daily);
+            if let Some(max_size) = args.log_max_size {
+                rotation = rotation.max_size(max_size);
+            }
+            if let Some(max_files) = args.log_max_files {
+                rotation = rotation.max_files(max_files);
+            }
+            let mut writer = RotatingFileWriter::new(log_path, rotation).unwrap();
+            writer.rotate_now().unwrap();
+            logger_builder.target(env_logger::Target::Pipe(Box::new(writer)));
+        } else {
+            logger_builder.target(env_logger::Target::Pipe(Box::new(
+                File::create(log_path).unwrap(),
+            )));
+        }
     };
 
-    logger_builder.init();
+    // Log lines emitted inside an action are prefixed with the run and the task.
+    ContextLogger::new(logger_builder.build())
+        .init(log_level)
+        .unwrap();
 }
@@ -0,0 +1,269 @@
+//! Caching of the outputs of tasks by their fingerprints, see [`Dag::with_cache`](super::Dag::with_cache).
+
+#[cfg(feature = "s3")]
+use crate::task::{block_on_thread, s3_client};
+use crate::{task::Content, Fingerprint};
+use std::{fmt::Debug, fs, io, path::PathBuf, sync::Arc};
+
+/// A [`CacheBackend`] stores the outputs of tasks by their [`Fingerprint`]s. A dag with a
+/// cache (see [`Dag::with_cache`](super::Dag::with_cache)) looks up the output of each task
+/// before running it, and stores the output of each task that ran successfully, so that a
+/// task whose fingerprint did not change since a previous run, on this machine or on another
+/// one sharing the cache, does not run again.
+///
+/// [`DiskCache`] stores outputs in a local directory, [`HttpCache`] on an HTTP server with the
+/// `cache-http` feature, and [`S3Cache`] in a bucket of S3 with the `s3` feature.
+pub trait CacheBackend: Send + Sync {
+    /// The entry stored under the fingerprint, or `None` if there is none.
+    fn get(&self, fingerprint: &Fingerprint) -> io::Result<Option<Vec<u8>>>;
+    /// Store an entry under the fingerprint, replacing any previous one.
+    fn put(&self, fingerprint: &Fingerprint, entry: &[u8]) -> io::Result<()>;
+}
+
+impl Debug for dyn CacheBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CacheBackend")
+    }
+}
+
+/// A [`CacheBackend`] in a local directory, with one file per entry.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{engine::DiskCache, Dag, DefaultTask, CommandAction};
+///
+/// let build = DefaultTask::with_action("build", CommandAction::new("echo built"));
+/// let dag = Dag::with_tasks(vec![build])
+///     .with_cache(DiskCache::new(std::env::temp_dir().join("dagrs-cache")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, fingerprint: &Fingerprint) -> PathBuf {
+        let name = fingerprint.to_string();
+        self.dir.join(&name[..2]).join(name)
+    }
+}
+
+impl CacheBackend for DiskCache {
+    fn get(&self, fingerprint: &Fingerprint) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(fingerprint)) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn put(&self, fingerprint: &Fingerprint, entry: &[u8]) -> io::Result<()> {
+        let path = self.path(fingerprint);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Written aside and renamed, so that concurrent readers never see a partial entry.
+        let partial = path.with_extension(format!("{}.partial", std::process::id()));
+        fs::write(&partial, entry)?;
+        fs::rename(&partial, &path)
+    }
+}
+
+/// A [`CacheBackend`] on an HTTP server, such as the remote caches of build systems: an entry
+/// is read with a `GET` and written with a `PUT` of `<url>/<fingerprint>`. A `404` response
+/// means that there is no entry.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::engine::HttpCache;
+///
+/// let cache = HttpCache::new("https://cache.example.com/dagrs")
+///     .with_header("Authorization", "Bearer s3cr3t");
+/// ```
+#[cfg(feature = "cache-http")]
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+#[cfg(feature = "cache-http")]
+impl HttpCache {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_owned(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Send the given header with each request, for example to authenticate.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    fn request(&self, method: &str, fingerprint: &Fingerprint) -> ureq::Request {
+        let url = format!("{}/{}", self.url, fingerprint);
+        self.headers
+            .iter()
+            .fold(ureq::request(method, &url), |request, (name, value)| {
+                request.set(name, value)
+            })
+    }
+}
+
+#[cfg(feature = "cache-http")]
+impl CacheBackend for HttpCache {
+    fn get(&self, fingerprint: &Fingerprint) -> io::Result<Option<Vec<u8>>> {
+        match self.request("GET", fingerprint).call() {
+            Ok(response) => {
+                let mut entry = Vec::new();
+                io::Read::read_to_end(&mut response.into_reader(), &mut entry)?;
+                Ok(Some(entry))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    fn put(&self, fingerprint: &Fingerprint, entry: &[u8]) -> io::Result<()> {
+        self.request("PUT", fingerprint)
+            .send_bytes(entry)
+            .map(drop)
+            .map_err(io::Error::other)
+    }
+}
+
+/// A [`CacheBackend`] in a bucket of S3, or of an S3-compatible object storage, with one
+/// object `<prefix><fingerprint>` per entry. The client is configured from the variables of an
+/// [`EnvVar`](crate::EnvVar), like the one of an [`S3DownloadAction`](crate::S3DownloadAction).
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{engine::S3Cache, EnvVar};
+///
+/// let mut env = EnvVar::new();
+/// env.set_secret("AWS_ACCESS_KEY_ID", "AKIA...");
+/// env.set_secret("AWS_SECRET_ACCESS_KEY", "s3cr3t");
+/// let cache = S3Cache::from_env("ci-cache", "dagrs/", &env).unwrap();
+/// ```
+#[cfg(feature = "s3")]
+#[derive(Debug, Clone)]
+pub struct S3Cache {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    prefix: String,
+}
+
+#[cfg(feature = "s3")]
+impl S3Cache {
+    /// A cache in the given bucket, with a client configured from the variables `AWS_*` of the
+    /// environment. Fails if the credentials are missing.
+    pub fn from_env(bucket: &str, prefix: &str, env: &crate::EnvVar) -> Result<Self, String> {
+        Ok(Self {
+            client: s3_client(env)?,
+            bucket: bucket.to_owned(),
+            prefix: prefix.to_owned(),
+        })
+    }
+
+    fn key(&self, fingerprint: &Fingerprint) -> String {
+        format!("{}{}", self.prefix, fingerprint)
+    }
+}
+
+#[cfg(feature = "s3")]
+impl CacheBackend for S3Cache {
+    fn get(&self, fingerprint: &Fingerprint) -> io::Result<Option<Vec<u8>>> {
+        use aws_sdk_s3::error::DisplayErrorContext;
+
+        let get = async {
+            let object = match self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(self.key(fingerprint))
+                .send()
+                .await
+            {
+                Ok(object) => object,
+                Err(err)
+                    if err
+                        .as_service_error()
+                        .is_some_and(|err| err.is_no_such_key()) =>
+                {
+                    return Ok(None)
+                }
+                Err(err) => return Err(io::Error::other(DisplayErrorContext(err).to_string())),
+            };
+            let bytes = object.body.collect().await.map_err(io::Error::other)?;
+            Ok(Some(bytes.to_vec()))
+        };
+        block_on_thread(get)?
+    }
+
+    fn put(&self, fingerprint: &Fingerprint, entry: &[u8]) -> io::Result<()> {
+        use aws_sdk_s3::{error::DisplayErrorContext, primitives::ByteStream};
+
+        let put = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.key(fingerprint))
+            .body(ByteStream::from(entry.to_vec()))
+            .send();
+        block_on_thread(put)?
+            .map(drop)
+            .map_err(|err| io::Error::other(DisplayErrorContext(err).to_string()))
+    }
+}
+
+/// The kinds of outputs a cache entry holds, as the first byte of the entry.
+const EMPTY: u8 = b'n';
+const STRING: u8 = b's';
+const BYTES: u8 = b'b';
+const SHARED: u8 = b'a';
+const LINES: u8 = b'l';
+
+/// The cache entry of an output, if it is empty, a `String`, bytes, or the `(stdout, stderr)`
+/// lines produced by a [`CommandAction`](crate::CommandAction). Other outputs are not cached.
+pub(crate) fn encode_output(output: Option<&Content>) -> Option<Vec<u8>> {
+    let Some(content) = output else {
+        return Some(vec![EMPTY]);
+    };
+    let (kind, bytes) = if let Some(s) = content.get::<String>() {
+        (STRING, s.as_bytes().to_vec())
+    } else if let Some(bytes) = content.get::<Vec<u8>>() {
+        (BYTES, bytes.clone())
+    } else if let Some(bytes) = content.get::<Arc<[u8]>>() {
+        (SHARED, bytes.to_vec())
+    } else {
+        let lines = content.get::<(Vec<String>, Vec<String>)>()?;
+        (LINES, serde_json::to_vec(lines).ok()?)
+    };
+    let mut entry = Vec::with_capacity(bytes.len() + 1);
+    entry.push(kind);
+    entry.extend(bytes);
+    Some(entry)
+}
+
+/// The output of a cache entry, or `None` if the entry is invalid.
+pub(crate) fn decode_output(entry: Vec<u8>) -> Option<Option<Content>> {
+    let (kind, bytes) = entry.split_first()?;
+    let content = match *kind {
+        EMPTY if bytes.is_empty() => return Some(None),
+        STRING => Content::new(String::from_utf8(bytes.to_vec()).ok()?),
+        BYTES => Content::new(bytes.to_vec()),
+        SHARED => Content::shared(bytes),
+        LINES => Content::new(serde_json::from_slice::<(Vec<String>, Vec<String>)>(bytes).ok()?),
+        _ => return None,
+    };
+    Some(Some(content))
+}
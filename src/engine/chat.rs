@@ -0,0 +1,216 @@
+//! Chat notifications
+//!
+//! [`ChatObserver`] is an [`ExecutionObserver`] that posts a message to a Slack or Discord
+//! channel when a run of a dag starts and when it finishes, with the status and duration of
+//! every task and the errors of the failed tasks.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{ChatObserver, ChatService, Dag, DefaultTask, EnvVar, Output};
+//!
+//! let mut env = EnvVar::new();
+//! env.set("SLACK_WEBHOOK_URL", "https://hooks.slack.com/services/T0/B0/XXXX".to_string());
+//!
+//! let task = DefaultTask::with_closure("Simple Task", |_input, _env| Output::new(1));
+//! let mut dag = Dag::with_tasks(vec![task]);
+//! if let Some(observer) = ChatObserver::from_env(ChatService::Slack, &env) {
+//!     dag.add_observer(observer.with_title("Nightly ETL"));
+//! }
+//! dag.set_env(env);
+//! ```
+
+use super::{Event, ExecutionObserver};
+use crate::{
+    task::Content,
+    utils::{Backoff, Outbox},
+    EnvVar,
+};
+use serde_json::json;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The chat service of a [`ChatObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatService {
+    Slack,
+    Discord,
+}
+
+impl ChatService {
+    /// The key of the [`EnvVar`] variable that holds the webhook URL.
+    pub fn env_key(&self) -> &'static str {
+        match self {
+            ChatService::Slack => "SLACK_WEBHOOK_URL",
+            ChatService::Discord => "DISCORD_WEBHOOK_URL",
+        }
+    }
+
+    /// The JSON document that posts a message.
+    fn body(&self, message: &str) -> serde_json::Value {
+        match self {
+            ChatService::Slack => json!({ "text": message }),
+            // Discord rejects messages longer than 2000 characters.
+            ChatService::Discord => json!({ "content": truncate(message, 2000) }),
+        }
+    }
+}
+
+/// The state of a task in the current run.
+enum TaskState {
+    Running(Instant),
+    Succeeded(Duration),
+    Failed(Duration, String),
+    Skipped,
+}
+
+/// Posts the start and the result of the runs of a dag to an incoming webhook of Slack or
+/// Discord. See the [module documentation](self).
+///
+/// Messages are posted in order on a thread of the observer, so that the tasks do not wait for
+/// them, and a run finishes once its messages are posted. Posting errors are logged and do not
+/// affect the run. A message that cannot be posted is not retried, unless retries are set with
+/// [`ChatObserver::retries`].
+pub struct ChatObserver {
+    service: ChatService,
+    url: String,
+    title: String,
+    retries: u32,
+    backoff: Backoff,
+    /// The ids, names and states of the tasks of the current run, in the order they started
+    /// or were skipped.
+    tasks: Mutex<Vec<(usize, String, TaskState)>>,
+    outbox: Outbox,
+}
+
+impl ChatObserver {
+    /// Post to the given webhook URL.
+    pub fn new(service: ChatService, url: &str) -> Self {
+        Self {
+            service,
+            url: url.to_owned(),
+            title: "dagrs".to_owned(),
+            retries: 0,
+            backoff: Backoff::default(),
+            tasks: Mutex::new(Vec::new()),
+            outbox: Outbox::default(),
+        }
+    }
+
+    /// Post to the webhook URL of the variable [`ChatService::env_key`] of the [`EnvVar`].
+    /// Returns `None` if it is not set.
+    pub fn from_env(service: ChatService, env: &EnvVar) -> Option<Self> {
+        let url = env
+            .get_content(service.env_key())
+            .and_then(Content::as_text)?;
+        Some(Self::new(service, &url))
+    }
+
+    /// Set the title that starts every message, `dagrs` by default.
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_owned();
+        self
+    }
+
+    /// Try to post a message up to `retries` more times if it cannot be posted, waiting for
+    /// the delays of the given backoff in between.
+    pub fn retries(mut self, retries: u32, backoff: Backoff) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// The result of the run, one line per task.
+    fn summary(&self, success: bool) -> String {
+        let tasks = self.tasks.lock().unwrap();
+        let mut message = format!(
+            "{}: run {}",
+            self.title,
+            if success { "succeeded" } else { "failed" }
+        );
+        for (_, name, state) in tasks.iter() {
+            let line = match state {
+                TaskState::Running(_) => format!("• {}: unfinished", name),
+                TaskState::Succeeded(duration) => {
+                    format!("• {}: succeeded in {:.2?}", name, duration)
+                }
+                TaskState::Failed(duration, error) => {
+                    format!("• {}: failed after {:.2?}: {}", name, duration, error)
+                }
+                TaskState::Skipped => format!("• {}: skipped", name),
+            };
+            message.push('\n');
+            message.push_str(&line);
+        }
+        message
+    }
+
+    /// Queue a message to be posted.
+    fn post(&self, message: &str) {
+        let body = self.service.body(message);
+        let (url, retries, backoff) = (self.url.clone(), self.retries, self.backoff);
+        self.outbox.send(move || {
+            if let Err(err) = backoff.retry(retries, || {
+                ureq::post(&url)
+                    .timeout(Duration::from_secs(10))
+                    .send_json(&body)
+                    .map_err(Box::new)
+            }) {
+                log::error!("Failed to post chat notification: {}", err);
+            }
+        });
+    }
+
+    /// Set the state of a task, given the time it started running, if it did.
+    fn set_state(&self, id: usize, name: &str, state: impl FnOnce(Option<Instant>) -> TaskState) {
+        let mut tasks = self.tasks.lock().unwrap();
+        match tasks.iter_mut().find(|(task, ..)| *task == id) {
+            Some((.., current)) => {
+                let started = match current {
+                    TaskState::Running(started) => Some(*started),
+                    _ => None,
+                };
+                *current = state(started);
+            }
+            None => tasks.push((id, name.to_owned(), state(None))),
+        }
+    }
+}
+
+impl ExecutionObserver for ChatObserver {
+    fn on_event(&self, event: &Event) {
+        let elapsed = |started: Option<Instant>| started.map(|s| s.elapsed()).unwrap_or_default();
+        match event {
+            Event::DagStarted { tasks } => {
+                self.tasks.lock().unwrap().clear();
+                self.post(&format!("{}: run started with {} tasks", self.title, tasks));
+            }
+            Event::DagFinished { success } => self.post(&self.summary(*success)),
+            Event::TaskStarted { id, name } => {
+                self.set_state(*id, name, |_| TaskState::Running(Instant::now()))
+            }
+            Event::TaskFinished { id, name, .. } => {
+                self.set_state(*id, name, |started| TaskState::Succeeded(elapsed(started)))
+            }
+            Event::TaskFailed { id, name, error } => self.set_state(*id, name, |started| {
+                TaskState::Failed(elapsed(started), error.clone())
+            }),
+            Event::TaskSkipped { id, name } => self.set_state(*id, name, |_| TaskState::Skipped),
+            Event::TaskStalled { .. } => {}
+        }
+    }
+
+    fn flush(&self) {
+        self.outbox.flush();
+    }
+}
+
+/// The first `max` characters of a message.
+fn truncate(message: &str, max: usize) -> String {
+    match message.char_indices().nth(max) {
+        Some((end, _)) => message[..end].to_owned(),
+        None => message.to_owned(),
+    }
+}
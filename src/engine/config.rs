@@ -0,0 +1,307 @@
+//! Execution settings
+//!
+//! A [`DagConfig`] holds the default execution settings of a dag (see [`Dag::with_config`](super::Dag::with_config)).
+//! Every task inherits them, unless its [`TaskConfig`] (see [`Task::config`](crate::Task::config))
+//! specifies its own values.
+
+use crate::utils::Backoff;
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Default execution settings of the tasks of a dag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DagConfig {
+    /// How long an action may run before the task fails. `None` means no limit.
+    pub timeout: Option<Duration>,
+    /// How many times a failed action is run again before the task fails. As actions cannot
+    /// be interrupted, an attempt that timed out or stalled is only retried once its action
+    /// returned, so that the action never runs twice at the same time.
+    pub retries: u32,
+    /// The delays between the runs of a failed action.
+    pub backoff: Backoff,
+    /// Whether the tasks that do not depend on a failed task keep running, see
+    /// [`Dag::keep_going`](super::Dag::keep_going).
+    pub keep_going: bool,
+    /// How long a running action may go without a [`heartbeat`](crate::utils::heartbeat)
+    /// before the watchdog of the dag reports it as stalled with an
+    /// [`Event::TaskStalled`](super::Event::TaskStalled). `None` disables the watchdog.
+    pub stall_timeout: Option<Duration>,
+    /// Whether a stalled task fails with
+    /// [`ExecutionError::Stalled`](super::ExecutionError::Stalled), instead of only being
+    /// reported. Its action keeps running in the background, like after a timeout.
+    pub kill_stalled: bool,
+    /// Run the tasks one at a time, in an order drawn from this seed, to reproduce bugs that
+    /// depend on the order tasks run in, see [`Dag::deterministic`](super::Dag::deterministic).
+    /// `None` runs ready tasks concurrently.
+    pub seed: Option<u64>,
+    /// The largest output, in bytes, a task may store for its successors. Only outputs whose
+    /// size is known are limited, see [`Content::byte_size`](crate::task::Content::byte_size).
+    /// `None` means no limit.
+    pub max_output_size: Option<usize>,
+    /// What happens to an output larger than [`DagConfig::max_output_size`].
+    pub output_overflow: OverflowPolicy,
+    /// How many bytes of outputs a run keeps in memory. Beyond it, the oldest outputs of type
+    /// `String`, `Vec<u8>` or `Arc<[u8]>` are written to the `spill` directory of the
+    /// [`ArtifactDir`](crate::ArtifactDir) of the run, and read back from there whenever a
+    /// successor or [`Dag::get_result`](super::Dag::get_result) needs them. A spilled output
+    /// is not kept in memory once read back, so it is read from the disk again for each of its
+    /// successors. `None` keeps all outputs in memory.
+    pub memory_budget: Option<usize>,
+    /// The names of the variables of the [`EnvVar`](crate::EnvVar) of the dag that are part of
+    /// the fingerprint of every task, see [`Dag::fingerprints`](super::Dag::fingerprints).
+    pub fingerprint_env: Vec<String>,
+    /// Where the actions of the tasks run, see [`Execution`].
+    pub execution: Execution,
+    /// How many actions of [`Execution::Blocking`] tasks run at the same time. `None` leaves
+    /// it to the blocking threads of the runtime.
+    pub blocking_threads: Option<usize>,
+}
+
+impl DagConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    pub fn stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = Some(stall_timeout);
+        self
+    }
+
+    pub fn kill_stalled(mut self, kill_stalled: bool) -> Self {
+        self.kill_stalled = kill_stalled;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn max_output_size(mut self, max_output_size: usize, overflow: OverflowPolicy) -> Self {
+        self.max_output_size = Some(max_output_size);
+        self.output_overflow = overflow;
+        self
+    }
+
+    pub fn memory_budget(mut self, memory_budget: usize) -> Self {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
+    pub fn fingerprint_env<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.fingerprint_env = names.into_iter().map(str::to_owned).collect();
+        self
+    }
+
+    pub fn execution(mut self, execution: Execution) -> Self {
+        self.execution = execution;
+        self
+    }
+
+    pub fn blocking_threads(mut self, blocking_threads: usize) -> Self {
+        self.blocking_threads = Some(blocking_threads);
+        self
+    }
+}
+
+/// Where the action of a task runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Execution {
+    /// On a thread of the runtime of the dag, which does nothing else until the action
+    /// returns. Actions with a timeout or a stall timeout run on a blocking thread anyway.
+    #[default]
+    Inline,
+    /// On a blocking thread, as with `tokio::task::spawn_blocking`, so that CPU-bound actions
+    /// do not hold up the runtime, which keeps driving the timers and the other tasks. See
+    /// [`DagConfig::blocking_threads`].
+    Blocking,
+}
+
+impl FromStr for Execution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inline" => Ok(Execution::Inline),
+            "blocking" => Ok(Execution::Blocking),
+            _ => Err(format!("Unknown execution '{}'.", s)),
+        }
+    }
+}
+
+/// What happens to the output of a task that is larger than its limit, see
+/// [`DagConfig::max_output_size`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The task fails with [`ExecutionError::OutputTooLarge`](super::ExecutionError::OutputTooLarge).
+    #[default]
+    Fail,
+    /// The output is cut to the limit: the first bytes of text and byte buffers, on a character
+    /// boundary, or the first lines of the output of a [`CommandAction`](crate::CommandAction).
+    /// Other outputs fail.
+    Truncate,
+    /// Text and byte buffers are written to the artifact `output/<id>-<name>.out` of the dag,
+    /// and the task outputs the [`Artifact`](crate::Artifact) instead. Other outputs fail.
+    Spill,
+}
+
+/// Execution settings of a single task. Settings that are `None` are inherited from the
+/// [`DagConfig`] of the dag the task runs in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskConfig {
+    pub timeout: Option<Duration>,
+    /// How many times a failed action is run again, see [`DagConfig::retries`].
+    pub retries: Option<u32>,
+    pub backoff: Option<Backoff>,
+    /// How long the action may go without a heartbeat, see [`DagConfig::stall_timeout`].
+    pub stall_timeout: Option<Duration>,
+    /// How long the task waits once its predecessors finished, before it runs.
+    pub delay: Option<Duration>,
+    /// The task waits until this time of day, unless it is within its window.
+    pub not_before: Option<TimeOfDay>,
+    /// The task fails instead of running after this time of day, unless it is within its
+    /// window.
+    pub not_after: Option<TimeOfDay>,
+    /// The largest output of the task, see [`DagConfig::max_output_size`].
+    pub max_output_size: Option<usize>,
+    pub output_overflow: Option<OverflowPolicy>,
+    /// Whether the output of the task is looked up in and stored into the cache of the dag,
+    /// if it has one, see [`Dag::with_cache`](super::Dag::with_cache). `None` leaves it to
+    /// the action, see [`Complex::is_cacheable`](crate::Complex::is_cacheable).
+    pub cache: Option<bool>,
+    /// Where the action of the task runs, see [`DagConfig::execution`].
+    pub execution: Option<Execution>,
+}
+
+impl TaskConfig {
+    /// The settings that apply to the task in a dag with the given defaults.
+    pub(crate) fn resolve(&self, defaults: &DagConfig) -> TaskConfig {
+        TaskConfig {
+            timeout: self.timeout.or(defaults.timeout),
+            retries: self.retries.or(Some(defaults.retries)),
+            backoff: self.backoff.or(Some(defaults.backoff)),
+            stall_timeout: self.stall_timeout.or(defaults.stall_timeout),
+            max_output_size: self.max_output_size.or(defaults.max_output_size),
+            output_overflow: self.output_overflow.or(Some(defaults.output_overflow)),
+            execution: self.execution.or(Some(defaults.execution)),
+            ..*self
+        }
+    }
+
+    /// How long the task must wait at `now` for its scheduling window, or `None` if it missed
+    /// the window. With both `not_before` and `not_after`, the window is the time between them,
+    /// across midnight if `not_after` is earlier, and a task outside of it waits for the next
+    /// `not_before`.
+    pub(crate) fn window_wait(&self, now: SystemTime) -> Option<Duration> {
+        let now = TimeOfDay::at(now);
+        match (self.not_before, self.not_after) {
+            (None, None) => Some(Duration::ZERO),
+            (Some(start), None) if now < start => Some(start.since(now)),
+            (Some(_), None) => Some(Duration::ZERO),
+            (None, Some(end)) => (now < end).then_some(Duration::ZERO),
+            (Some(start), Some(end)) => {
+                let within = if start <= end {
+                    start <= now && now < end
+                } else {
+                    start <= now || now < end
+                };
+                Some(if within {
+                    Duration::ZERO
+                } else {
+                    start.since(now)
+                })
+            }
+        }
+    }
+}
+
+/// A time of day in UTC, such as the bounds of a maintenance window. It is parsed from
+/// `HH:MM` or `HH:MM:SS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay(Duration);
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl TimeOfDay {
+    /// The time of day with the given hour, minute and second, if they are in range.
+    pub fn new(hour: u32, minute: u32, second: u32) -> Option<Self> {
+        (hour < 24 && minute < 60 && second < 60).then(|| {
+            Self(Duration::from_secs(
+                (hour as u64 * 60 + minute as u64) * 60 + second as u64,
+            ))
+        })
+    }
+
+    /// The time of day of the given instant.
+    fn at(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Self(Duration::from_nanos(
+            (since_epoch.as_nanos() % DAY.as_nanos()) as u64,
+        ))
+    }
+
+    /// The time from `earlier` until this time of day, on the next day if it is earlier.
+    fn since(self, earlier: TimeOfDay) -> Duration {
+        if self.0 >= earlier.0 {
+            self.0 - earlier.0
+        } else {
+            DAY - earlier.0 + self.0
+        }
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<u32> = s
+            .split(':')
+            .map(|part| {
+                part.parse::<u32>()
+                    .map_err(|_| format!("illegal time of day '{}'", s))
+            })
+            .collect::<Result<_, _>>()?;
+        let time = match parts[..] {
+            [hour, minute] => Self::new(hour, minute, 0),
+            [hour, minute, second] => Self::new(hour, minute, second),
+            _ => None,
+        };
+        time.ok_or(format!("illegal time of day '{}'", s))
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
@@ -0,0 +1,107 @@
+//! Grouped console output
+//!
+//! Tasks running concurrently interleave their log lines, which makes the console output of a
+//! run hard to read. With a [`GroupedOutput`] (see
+//! [`Dag::with_grouped_output`](super::Dag::with_grouped_output)), the log lines of each task
+//! are captured, and printed together with the output of the task as one group when the task
+//! finishes. In GitHub Actions, groups are collapsible.
+
+use crate::{task::Content, EnvVar};
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::Mutex,
+};
+
+/// How [`GroupedOutput`] delimits groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupStyle {
+    /// `::group::` and `::endgroup::` workflow commands, which GitHub Actions shows as
+    /// collapsible groups.
+    GitHubActions,
+    /// A header line with the name and the result of the task.
+    Plain,
+}
+
+impl GroupStyle {
+    /// [`GroupStyle::GitHubActions`] when running in GitHub Actions, [`GroupStyle::Plain`]
+    /// otherwise.
+    pub fn detect() -> Self {
+        if std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true") {
+            Self::GitHubActions
+        } else {
+            Self::Plain
+        }
+    }
+}
+
+/// Prints the log lines and the output of each task as one group, see the
+/// [module documentation](self).
+pub struct GroupedOutput {
+    style: GroupStyle,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl GroupedOutput {
+    /// Print the groups to the given writer.
+    pub fn new(style: GroupStyle, writer: impl Write + Send + 'static) -> Self {
+        Self {
+            style,
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Print the groups to stdout.
+    pub fn stdout(style: GroupStyle) -> Self {
+        Self::new(style, io::stdout())
+    }
+
+    /// Print the group of a finished task: its captured log lines, then its output if it is
+    /// text, or its error.
+    pub(crate) fn print(
+        &self,
+        name: &str,
+        logs: &[String],
+        output: Option<&Content>,
+        error: Option<&str>,
+        env: &EnvVar,
+    ) {
+        let result = if error.is_some() { "failed" } else { "ok" };
+        let mut lines: Vec<String> = logs.iter().map(|line| env.redact(line)).collect();
+        if let Some(text) = output
+            .and_then(Content::as_text)
+            .filter(|text| !text.is_empty())
+        {
+            lines.extend(env.redact(&text).lines().map(str::to_owned));
+        }
+        if let Some(error) = error {
+            lines.push(format!("error: {}", env.redact(error)));
+        }
+        let mut writer = self.writer.lock().unwrap();
+        let written = match self.style {
+            GroupStyle::GitHubActions => writeln!(writer, "::group::{} ({})", name, result)
+                .and_then(|()| {
+                    lines
+                        .iter()
+                        .try_for_each(|line| writeln!(writer, "{}", line))?;
+                    writeln!(writer, "::endgroup::")
+                }),
+            GroupStyle::Plain => writeln!(writer, "==> {} ({})", name, result).and_then(|()| {
+                lines
+                    .iter()
+                    .try_for_each(|line| writeln!(writer, "    {}", line))
+            }),
+        };
+        if let Err(err) = written.and_then(|()| writer.flush()) {
+            log::error!("Failed to print the output of task '{}': {}", name, err);
+        }
+    }
+}
+
+impl fmt::Debug for GroupedOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupedOutput")
+            .field("style", &self.style)
+            .finish_non_exhaustive()
+    }
+}
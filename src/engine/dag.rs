@@ -1,19 +1,53 @@
-use super::{graph::Graph, DagError};
+#[cfg(feature = "report-html")]
+use super::html::HtmlReport;
+use super::{
+    cache::{decode_output, encode_output},
+    fair::FairShare,
+    graph::Graph,
+    simulation::StubTask,
+    spill::MemoryBudget,
+    CacheBackend, DagConfig, DagError, DagStructure, Event, Execution, ExecutionError,
+    ExecutionObserver, ExecutionSnapshot, FaultInjector, GraphError, GroupedOutput, Observers,
+    OverflowPolicy, RunReport, Simulation, TaskConfig, TaskReport, TaskStatus, TaskStructure,
+};
+#[cfg(feature = "notify")]
+use super::{Notifier, NotifyOn, TaskFailureNotifier};
 use crate::{
-    task::{ExecState, Input, Task},
-    utils::EnvVar,
-    Action, Parser,
+    task::{
+        output_artifact, ArtifactDir, Condition, Content, ExecState, ExecStateRef, ExecStates,
+        Fingerprint, Fingerprinter, Input, Task, TaskFiles, TaskPool, ARTIFACT_DIR_KEY,
+        CAPTURE_OUTPUT_KEY,
+    },
+    utils::{log_scope, splitmix64, EnvVar, Heartbeat, LogBuffer},
+    Action, ActionRegistry, DefaultTask, Output, ParseError, Parser,
 };
-use log::{debug, error};
+#[cfg(not(feature = "tracing"))]
+use log::{debug, error, warn};
+#[cfg(feature = "report-html")]
+use std::path::Path;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    future::{poll_fn, Future},
+    io,
     panic::{self, AssertUnwindSafe},
+    pin::{pin, Pin},
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
     },
+    task::Poll,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
-use tokio::task::JoinHandle;
+use tokio::{
+    sync::{Mutex as TaskLock, Notify, Semaphore},
+    task::JoinHandle,
+};
+#[cfg(feature = "tracing")]
+use tracing::{debug, error, warn};
+
+/// The environment variable holding the id of the current run, see [`Dag::run_id`].
+const RUN_ID_KEY: &str = "DAGRS_RUN_ID";
 
 /// [`Dag`] is dagrs's main body.
 ///
@@ -24,15 +58,16 @@ use tokio::task::JoinHandle;
 ///
 /// The execution process of Dag is roughly as follows:
 /// - The user gives a list of tasks `tasks`. These tasks can be parsed from configuration files, or provided
-/// by user programming implementations.
+///   by user programming implementations.
 /// - Internally generate `Graph` based on task dependencies, and generate execution sequences based on `rely_graph`.
 /// - The task is scheduled to start executing asynchronously.
 /// - The task will wait to get the result `execute_states` generated by the execution of the predecessor task.
 /// - If the result of the predecessor task can be obtained, check the continuation status `can_continue`, if it
-/// is true, continue to execute the defined logic, if it is false, trigger `handle_error`, and cancel the
-/// execution of the subsequent task.
-/// - After all tasks are executed, set the continuation status to false, which means that the tasks of the dag
-/// cannot be scheduled for execution again.
+///   is true, continue to execute the defined logic, if it is false, trigger `handle_error`, and cancel the
+///   execution of the subsequent task.
+/// - After all tasks are executed, set the continuation status to false, so that no more tasks start.
+/// - A dag can be run again: the next run starts from fresh execution states, without the outputs, errors
+///   and flags of the previous one.
 ///
 ///  # Example
 /// ```rust
@@ -56,36 +91,168 @@ pub struct Dag {
     /// Store dependency relations.
     rely_graph: Graph,
     /// Store a task's running result.Execution results will be read and written asynchronously by several threads.
-    execute_states: HashMap<usize, Arc<ExecState>>,
+    execute_states: Arc<ExecStates>,
     /// Global environment variables for this Dag job. It should be set before the Dag job runs.
     env: Arc<EnvVar>,
+    /// The parameters of the run, kept in the environment variables.
+    params: HashMap<String, String>,
     /// Mark whether the Dag task can continue to execute.
     /// When an error occurs during the execution of any task, this flag will be set to false, and
     /// subsequent tasks will be canceled.
-    /// when all tasks in the dag are executed, the flag will also be set to false, indicating that
-    /// the task cannot be run repeatedly.
+    /// when all tasks in the dag are executed, the flag will also be set to false, until the dag
+    /// runs again.
     can_continue: Arc<AtomicBool>,
-    /// A flag that indicates whether the task should continue to execute as much as possible.
-    keep_going: bool,
+    /// Default execution settings of the tasks, including whether the task should continue
+    /// to execute as much as possible.
+    config: DagConfig,
     /// When `keep_going` is true, and an error occurs during the execution of a task, this flag will be set to true.
     keep_going_errored: Arc<AtomicBool>,
     /// The execution sequence of tasks.
     exe_sequence: Vec<usize>,
+    /// Errors of the tasks that failed during execution, in the order they occurred.
+    errors: Arc<Mutex<Vec<DagError>>>,
+    /// Observers notified of the lifecycle events of this dag.
+    observers: Observers,
+    /// Whether log lines emitted by actions are captured into their execution states.
+    capture_logs: bool,
+    /// Prints the captured log lines and the output of each task as a group.
+    grouped: Option<Arc<GroupedOutput>>,
+    /// Notifiers of the end of a run.
+    #[cfg(feature = "notify")]
+    notifiers: Vec<Arc<Notifier>>,
+    /// Faults injected into the tasks, for resilience testing.
+    faults: Option<Arc<FaultInjector>>,
+    /// The memory budget of the current run, see [`DagConfig::memory_budget`].
+    memory: Option<Arc<MemoryBudget>>,
+    /// The blocking threads of the current run, see [`DagConfig::blocking_threads`].
+    blocking_threads: Option<Arc<Semaphore>>,
+    /// The cache of the outputs of the tasks, see [`Dag::with_cache`].
+    cache: Option<Arc<dyn CacheBackend>>,
+    /// The fingerprints of the tasks in the current run, if the dag has a cache.
+    fingerprints: Arc<HashMap<usize, Fingerprint>>,
+    /// The named locks held by the tasks, see [`Task::locks`].
+    locks: Arc<Mutex<HashMap<String, Arc<TaskLock<()>>>>>,
+    /// The pools of the tasks, by name, with their size and a permit for each task that may
+    /// run, see [`Task::pool`].
+    pools: Arc<Mutex<HashMap<String, Pool>>>,
+    /// The share of the dag in the places of the engine it runs in, see
+    /// [`Engine::set_max_parallel`](super::Engine::set_max_parallel).
+    fair: Option<FairShare>,
+    /// The shutdown requested through a [`CancelHandle`].
+    shutdown: Arc<Shutdown>,
+    /// The run directory of the next full run, see [`Dag::with_artifacts`].
+    artifacts: ArtifactDir,
+    /// The run directory of the last run, an [`ArtifactDir`], also in the environment.
+    run_dir: Option<Content>,
+    /// Whether the last run succeeded.
+    succeeded: AtomicBool,
+    /// Whether the last run was cancelled.
+    cancelled: AtomicBool,
+    /// The id of the last run.
+    run_id: Option<String>,
+}
+
+/// Cancels the run of a [`Dag`], see [`Dag::cancel_handle`]. Tasks that are running are
+/// completed, tasks that have not started yet are skipped, and the run fails.
+#[derive(Debug, Clone)]
+pub struct CancelHandle {
+    can_continue: Arc<AtomicBool>,
+    shutdown: Arc<Shutdown>,
+}
+
+/// The state of a cancel or a shutdown of a run, see [`CancelHandle`].
+#[derive(Debug, Default)]
+struct Shutdown {
+    /// Whether the current run, or the next one between runs, was cancelled, as opposed to
+    /// stopped by a failed task.
+    cancelled: AtomicBool,
+    /// The number of the current run, from 1, so that the grace period of a shutdown only
+    /// expires the run it was requested in.
+    run: AtomicU64,
+    /// The number of the last run whose grace period expired, 0 if none.
+    expired: AtomicU64,
+    notify: Notify,
+}
+
+impl Shutdown {
+    fn start_run(&self) {
+        self.run.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Whether the grace period of a shutdown of the current run expired.
+    fn expired(&self) -> bool {
+        self.expired.load(Ordering::Acquire) == self.run.load(Ordering::Acquire)
+    }
 }
 
+impl CancelHandle {
+    /// Cancel the run in progress, or the next run if the dag is not running: its tasks are
+    /// all skipped.
+    pub fn cancel(&self) {
+        self.shutdown.cancelled.store(true, Ordering::Release);
+        self.can_continue.store(false, Ordering::Release);
+    }
+
+    /// Whether no more tasks start, because the run was cancelled or shut down, a task failed,
+    /// or the run finished. Long-running actions that hold a clone of the handle can check it
+    /// to stop early.
+    pub fn is_cancelled(&self) -> bool {
+        !self.can_continue.load(Ordering::Acquire)
+    }
+
+    /// Cancel the run, and give the tasks that are running `grace` time to finish. The run
+    /// then stops waiting for them: they fail with [`ExecutionError::Aborted`], and the run
+    /// finishes as failed, with its observers notified and its report complete. As actions
+    /// cannot be interrupted, an action that is still running is left behind on its thread.
+    pub fn shutdown(&self, grace: Duration) {
+        self.cancel();
+        let shutdown = self.shutdown.clone();
+        let run = shutdown.run.load(Ordering::Acquire);
+        thread::spawn(move || {
+            thread::sleep(grace);
+            shutdown.expired.store(run, Ordering::Release);
+            shutdown.notify.notify_waiters();
+        });
+    }
+}
+
+/// The size of a pool, and a permit for each task of the pool that may run.
+type Pool = (usize, Arc<Semaphore>);
+
 impl Dag {
     /// Create a dag. This function is not open to the public. There are three ways to create a new
     /// dag, corresponding to three functions: `with_tasks`, `with_yaml`, `with_config_file_and_parser`.
     fn new() -> Dag {
         Dag {
             tasks: HashMap::new(),
-            rely_graph: Graph::new(),
-            execute_states: HashMap::new(),
+            rely_graph: Graph::default(),
+            execute_states: Arc::default(),
             env: Arc::new(EnvVar::new()),
+            params: HashMap::new(),
             can_continue: Arc::new(AtomicBool::new(true)),
             exe_sequence: Vec::new(),
-            keep_going: false,
+            config: DagConfig::default(),
             keep_going_errored: Arc::new(AtomicBool::new(false)),
+            errors: Arc::new(Mutex::new(Vec::new())),
+            observers: Observers::default(),
+            capture_logs: false,
+            grouped: None,
+            #[cfg(feature = "notify")]
+            notifiers: Vec::new(),
+            faults: None,
+            memory: None,
+            blocking_threads: None,
+            cache: None,
+            fingerprints: Arc::default(),
+            locks: Arc::default(),
+            pools: Arc::default(),
+            fair: None,
+            shutdown: Arc::default(),
+            artifacts: ArtifactDir::default(),
+            run_dir: None,
+            succeeded: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            run_id: None,
         }
     }
 
@@ -117,7 +284,7 @@ impl Dag {
         specific_actions: HashMap<String, Action>,
     ) -> Result<Dag, DagError> {
         use crate::YamlParser;
-        let parser = Box::new(YamlParser);
+        let parser = Box::new(YamlParser::new());
         Dag::read_tasks(file, parser, specific_actions)
     }
 
@@ -128,7 +295,7 @@ impl Dag {
         specific_actions: HashMap<String, Action>,
     ) -> Result<Dag, DagError> {
         use crate::YamlParser;
-        let parser = Box::new(YamlParser);
+        let parser = Box::new(YamlParser::new());
         Dag::read_tasks_from_str(content, parser, specific_actions)
     }
 
@@ -150,16 +317,249 @@ impl Dag {
         Dag::read_tasks_from_str(content, parser, specific_actions)
     }
 
+    /// Rebuild a dag from its [`DagStructure`]. Every task becomes a [`DefaultTask`] with a
+    /// newly allocated id, whose action is created by the factory registered in `registry`
+    /// under the task's action type.
+    pub fn import_structure(
+        structure: &DagStructure,
+        registry: &ActionRegistry,
+    ) -> Result<Dag, DagError> {
+        let mut ids = HashMap::with_capacity(structure.tasks.len());
+        let mut tasks = Vec::with_capacity(structure.tasks.len());
+        for task in structure.tasks.iter() {
+            let action_type = task
+                .action
+                .as_deref()
+                .ok_or_else(|| ParseError(format!("Task[{}] has no action type.", task.name)))?;
+            let action = registry.create(action_type, &task.args).ok_or_else(|| {
+                ParseError(format!(
+                    "Task[{}] has unknown action type '{}'.",
+                    task.name, action_type
+                ))
+            })?;
+            let mut default_task = DefaultTask::new(&task.name);
+            default_task.set_action_dyn(action);
+            default_task.set_action_type(action_type);
+            default_task.set_action_args(task.args.clone());
+            ids.insert(task.id, tasks.len());
+            tasks.push(default_task);
+        }
+        for &(from, to) in structure.edges.iter() {
+            let to = *ids
+                .get(&to)
+                .ok_or_else(|| ParseError(format!("Edge to unknown task id {}.", to)))?;
+            let from = *ids
+                .get(&from)
+                .ok_or(DagError::RelyTaskIllegal(tasks[to].name().to_string()))?;
+            let from_id = tasks[from].id();
+            tasks[to].set_predecessors_by_id([from_id]);
+        }
+        Ok(Dag::with_tasks(tasks))
+    }
+
+    /// Describe the tasks of this dag and the dependencies between them as a
+    /// [`DagStructure`], for example to store it as JSON.
+    pub fn export_structure(&self) -> DagStructure {
+        let mut tasks: Vec<TaskStructure> = self
+            .tasks
+            .values()
+            .map(|task| TaskStructure {
+                id: task.id(),
+                name: task.name().to_string(),
+                action: task.action_type().map(str::to_string),
+                args: task.action_args().cloned().unwrap_or_default(),
+            })
+            .collect();
+        tasks.sort_by_key(|task| task.id);
+        let mut edges: Vec<(usize, usize)> = self
+            .tasks
+            .values()
+            .flat_map(|task| task.precursors().iter().map(|pre| (*pre, task.id())))
+            .collect();
+        edges.sort();
+        edges.dedup();
+        DagStructure { tasks, edges }
+    }
+
+    /// A handle to cancel or shut down the run of this dag from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            can_continue: self.can_continue.clone(),
+            shutdown: self.shutdown.clone(),
+        }
+    }
+
     /// Set the flag that indicates whether the task should continue to execute as much as possible.
     /// This means that even if an error occurs during the execution of a task, the subsequent independent tasks
     /// will continue to execute unless a dependency error occurs.
     pub fn keep_going(mut self) -> Dag {
-        self.keep_going = true;
+        self.config.keep_going = true;
         self
     }
 
-    /// Parse the content of the configuration file into a series of tasks and generate a dag.
+    /// Debug the dag in a deterministic mode: instead of running ready tasks concurrently, run
+    /// them one at a time, and pick the next one among the ready tasks with a random generator
+    /// seeded with `seed`. Runs with the same seed and the same tasks, created in the same order,
+    /// run the tasks in the same order, so that a bug that depends on the order tasks run in can
+    /// be reproduced, and other seeds can be tried to find one. Same as
+    /// [`DagConfig::seed`].
+    pub fn deterministic(mut self, seed: u64) -> Dag {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Set the default execution settings of the tasks. Tasks inherit them unless their own
+    /// [`TaskConfig`](super::TaskConfig) specifies other values.
+    pub fn with_config(mut self, config: DagConfig) -> Dag {
+        self.config = config;
+        self
+    }
+
+    /// Capture the log lines emitted inside each task's action, so that they can be read
+    /// with [`Dag::get_logs`] after execution, for example to report a failure together with
+    /// the failed task's own logs. Requires [`ContextLogger`](crate::utils::ContextLogger)
+    /// to be installed as the global logger.
+    pub fn capture_logs(mut self) -> Dag {
+        self.capture_logs = true;
+        self
+    }
+
+    /// Print the log lines and the output of each task as one group when it finishes, instead
+    /// of interleaving the lines of concurrent tasks. Captures the log lines like
+    /// [`Dag::capture_logs`], which requires [`ContextLogger`](crate::utils::ContextLogger).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{Dag, DefaultTask, GroupStyle, GroupedOutput, Output};
+    ///
+    /// let greet = DefaultTask::with_closure("greet", |_input, _env| Output::new("hi".to_string()));
+    /// let mut dag = Dag::with_tasks(vec![greet])
+    ///     .with_grouped_output(GroupedOutput::stdout(GroupStyle::detect()));
+    /// assert!(dag.start().unwrap());
+    /// ```
+    pub fn with_grouped_output(mut self, grouped: GroupedOutput) -> Dag {
+        self.capture_logs = true;
+        self.grouped = Some(Arc::new(grouped));
+        self
+    }
+
+    /// Keep the [`Artifact`](crate::Artifact)s of the tasks in the given run directory,
+    /// instead of a new directory in the temporary directory of the system that is removed
+    /// with the dag. Each later run of the dag gets a new directory of its own, in the same
+    /// parent directory and with the same cleanup policy.
+    pub fn with_artifacts(mut self, artifacts: ArtifactDir) -> Dag {
+        self.artifacts = artifacts;
+        self
+    }
+
+    /// Capture the stdout and stderr of the tasks running a [`CommandAction`](crate::CommandAction)
+    /// into the files `output/<id>-<name>.stdout` and `.stderr` of the run directory (see
+    /// [`Dag::with_artifacts`]), whose paths are in the [`RunReport`]. If `tee` is set, the
+    /// output is also written to the console.
+    pub fn capture_command_output(mut self, tee: bool) -> Dag {
+        Arc::make_mut(&mut self.env).set(CAPTURE_OUTPUT_KEY, tee);
+        self
+    }
+
+    /// Inject faults into the tasks of the dag, to test that its retries, timeouts and
+    /// fallbacks handle them. See [`FaultInjector`].
+    pub fn with_fault_injector(mut self, faults: FaultInjector) -> Dag {
+        self.faults = Some(Arc::new(faults));
+        self
+    }
+
+    /// Look up the output of each task in the given cache by the task's fingerprint (see
+    /// [`Dag::fingerprints`]) before running it, and store the output of each task that ran
+    /// successfully there. A task found in the cache does not run: its successors get the
+    /// cached output. Only outputs that are empty, a `String`, bytes, or the lines produced by
+    /// a [`CommandAction`](crate::CommandAction) are cached, and tasks can opt out with
+    /// [`TaskConfig::cache`](super::TaskConfig::cache). A failure of the cache is logged, and
+    /// the task then runs as if it had no cache.
+    pub fn with_cache(mut self, cache: impl CacheBackend + 'static) -> Dag {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Set the parameters of this run. Each parameter is an environment variable of the dag,
+    /// a `String` for actions and a value for the `{{ env.<key> }}` placeholders of commands,
+    /// also after [`Dag::set_env`]. A yaml configuration file refers to them as
+    /// `${params.<key>}`, with the same parameters given to
+    /// [`YamlParser::params`](crate::YamlParser::params).
+    pub fn with_params(mut self, params: HashMap<String, String>) -> Dag {
+        let env = Arc::make_mut(&mut self.env);
+        for (key, value) in &params {
+            env.set(key, value.clone());
+        }
+        self.params = params;
+        self
+    }
+
+    /// Register an observer that will be notified of the lifecycle events of this dag,
+    /// such as tasks starting, finishing or failing.
+    pub fn add_observer(&mut self, observer: impl ExecutionObserver + 'static) {
+        self.observers.push(Arc::new(observer));
+    }
+
+    /// Make the task `to` depend on the task `from`, in addition to the predecessors it was
+    /// created with: `from` runs first and its output becomes an input of `to`. The edge is
+    /// taken into account by the next run of the dag.
+    ///
+    /// Fails if one of the tasks is not in the dag, or if the edge already exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{Dag, DefaultTask, GraphError, Output, Task};
+    ///
+    /// let a = DefaultTask::with_closure("a", |_input, _env| Output::new(1));
+    /// let b = DefaultTask::with_closure("b", |input, _env| Output::new(input.get_iter().count()));
+    /// let (a_id, b_id) = (a.id(), b.id());
+    /// let mut dag = Dag::with_tasks(vec![a, b]);
+    /// dag.add_edge(a_id, b_id).unwrap();
+    /// assert!(matches!(dag.add_edge(a_id, b_id), Err(GraphError::DuplicateEdge(..))));
+    /// assert!(dag.start().unwrap());
+    /// assert_eq!(*dag.get_result::<usize>().unwrap(), 1);
+    /// ```
+    pub fn add_edge(&mut self, from: usize, to: usize) -> Result<(), GraphError> {
+        if self.try_add_edge(from, to)? {
+            Ok(())
+        } else {
+            Err(GraphError::DuplicateEdge(from, to))
+        }
+    }
+
+    /// Like [`Dag::add_edge`], but an edge that already exists is left as it is. Returns
+    /// whether the edge was added.
+    pub fn try_add_edge(&mut self, from: usize, to: usize) -> Result<bool, GraphError> {
+        if !self.tasks.contains_key(&from) {
+            return Err(GraphError::UnknownFrom(from));
+        }
+        let task = self.tasks.remove(&to).ok_or(GraphError::UnknownTo(to))?;
+        if task.precursors().contains(&from) {
+            self.tasks.insert(to, task);
+            return Ok(false);
+        }
+        let mut precursors = task.precursors().to_vec();
+        precursors.push(from);
+        self.tasks
+            .insert(to, Box::new(LinkedTask { task, precursors }));
+        Ok(true)
+    }
+
+    /// Register a notifier that sends a message when a run of this dag finishes or fails, or
+    /// when one of its tasks fails.
+    #[cfg(feature = "notify")]
+    pub fn add_notifier(&mut self, notifier: Notifier) {
+        let notifier = Arc::new(notifier);
+        if notifier.on() == NotifyOn::TaskFailure {
+            self.observers
+                .push(Arc::new(TaskFailureNotifier(notifier.clone())));
+        }
+        self.notifiers.push(notifier);
+    }
 
+    /// Parse the content of the configuration file into a series of tasks and generate a dag.
     fn read_tasks(
         file: &str,
         parser: Box<dyn Parser>,
@@ -174,7 +574,6 @@ impl Dag {
     }
 
     /// Parse the content of the configuration file into a series of tasks and generate a dag.
-
     fn read_tasks_from_str(
         content: &str,
         parser: Box<dyn Parser>,
@@ -190,32 +589,32 @@ impl Dag {
 
     /// create rely map between tasks.
     ///
-    /// This operation will initialize `dagrs.rely_graph` if no error occurs.
-    fn create_graph(&mut self) -> Result<(), DagError> {
-        let size = self.tasks.len();
-        self.rely_graph.set_graph_size(size);
-
+    /// The returned graph is used to initialize `dagrs.rely_graph`.
+    fn build_graph(&self) -> Result<Graph, DagError> {
         // Add Node (create id - index mapping)
-        self.tasks
-            .iter()
-            .for_each(|(&n, _)| self.rely_graph.add_node(n));
+        let tasks: Vec<&dyn Task> = self.tasks.values().map(|task| task.as_ref()).collect();
+        let mut graph = Graph::new(tasks.iter().map(|task| task.id()).collect());
 
         // Form Graph
-        for (&id, task) in self.tasks.iter() {
-            let index = self.rely_graph.find_index_by_id(&id).unwrap();
-
+        let mut edges = Vec::with_capacity(tasks.len());
+        for (index, task) in tasks.iter().enumerate() {
             for rely_task_id in task.precursors() {
                 // Rely task existence check
-                let rely_index = self
-                    .rely_graph
+                let rely_index = graph
                     .find_index_by_id(rely_task_id)
                     .ok_or(DagError::RelyTaskIllegal(task.name().to_string()))?;
 
-                self.rely_graph.add_edge(rely_index, index);
+                edges.push((rely_index, index));
             }
         }
+        graph.set_edges(&edges);
+
+        Ok(graph)
+    }
 
-        Ok(())
+    /// Make the tasks of the dag wait for their turn in the given share of an engine.
+    pub(crate) fn set_fair_share(&mut self, fair: FairShare) {
+        self.fair = Some(fair);
     }
 
     /// Initialize dags. The initialization process completes three actions:
@@ -223,13 +622,8 @@ impl Dag {
     /// - Create a graph from task dependencies.
     /// - Generate task heart sequence according to topological sorting of graph.
     pub(crate) fn init(&mut self) -> Result<(), DagError> {
-        self.execute_states.reserve(self.tasks.len());
-        self.tasks.values().for_each(|task| {
-            self.execute_states
-                .insert(task.id(), Arc::new(ExecState::new()));
-        });
-
-        self.create_graph()?;
+        self.rely_graph = self.build_graph()?;
+        self.execute_states = ExecStates::new(self.tasks.len());
 
         match self.rely_graph.topo_sort() {
             Some(seq) => {
@@ -247,55 +641,171 @@ impl Dag {
         }
     }
 
-    /// This function is used for the execution of a single dag.
+    /// This function is used for the execution of a single dag. It can be called again once the
+    /// run finished, to run all tasks again.
     pub fn start(&mut self) -> Result<bool, DagError> {
-        // If the current continuable state is false, the task will start failing.
-        if self.can_continue.load(Ordering::Acquire) {
-            self.init().map_or_else(Err, |_| {
-                Ok(tokio::runtime::Runtime::new()
-                    .unwrap()
-                    .block_on(async { self.run().await }))
-            })
-        } else {
-            Ok(false)
-        }
+        self.init().map_or_else(Err, |_| Ok(block_on(self.run())))
     }
 
     /// Execute tasks sequentially according to the execution sequence given by
     /// topological sorting, and cancel the execution of subsequent tasks if an
     /// error is encountered during task execution.
-    pub(crate) async fn run(&self) -> bool {
+    pub(crate) async fn run(&mut self) -> bool {
+        if self.run_id.is_some() {
+            self.reset();
+        }
+        // A full run starts in a new run directory; the previous one is cleaned up.
+        let next = self.artifacts.next();
+        self.run_dir = Some(Content::new(std::mem::replace(&mut self.artifacts, next)));
+        let sequence = self.exe_sequence.clone();
+        self.run_sequence(&sequence).await
+    }
+
+    /// Forget the previous run: every task gets a fresh execution state, whose semaphore has
+    /// no permits left over, and the errors and flags of the run are cleared. Cancel handles
+    /// stay valid for the next run.
+    fn reset(&mut self) {
+        self.execute_states = ExecStates::new(self.tasks.len());
+        self.errors.lock().unwrap().clear();
+        self.can_continue.store(true, Ordering::Release);
+        self.keep_going_errored.store(false, Ordering::Release);
+        self.succeeded.store(false, Ordering::Release);
+        self.cancelled.store(false, Ordering::Release);
+    }
+
+    /// Execute the given tasks, which must be in topological order, as a new run.
+    async fn run_sequence(&mut self, sequence: &[usize]) -> bool {
+        self.shutdown.start_run();
+        // A cancel requested before the run started skips all of its tasks.
+        if self.shutdown.cancelled.load(Ordering::Acquire) {
+            self.can_continue.store(false, Ordering::Release);
+        }
+        let run_id = new_run_id();
+        // The tasks that run_affected runs again share the directory of the previous run with
+        // the tasks whose outputs they receive.
+        let run_dir: Content = match &self.run_dir {
+            Some(run_dir) => run_dir.clone(),
+            None => {
+                let next = self.artifacts.next();
+                Content::new(std::mem::replace(&mut self.artifacts, next))
+            }
+        };
+        self.run_dir = Some(run_dir.clone());
+        let env = Arc::make_mut(&mut self.env);
+        env.set_content(ARTIFACT_DIR_KEY, run_dir.clone());
+        env.set(RUN_ID_KEY, run_id.clone());
+        self.observers.set_run_id(&run_id);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("dag", run.id = %run_id, tasks = sequence.len());
+        self.run_id = Some(run_id);
+        self.memory = self
+            .config
+            .memory_budget
+            .map(|limit| Arc::new(MemoryBudget::new(limit, run_dir.clone())));
+        self.fingerprints = match &self.cache {
+            Some(_) => Arc::new(self.fingerprints().unwrap_or_else(|err| {
+                warn!("Running without the cache, fingerprinting failed: {}", err);
+                HashMap::new()
+            })),
+            None => Arc::default(),
+        };
+        self.blocking_threads = self
+            .config
+            .blocking_threads
+            .map(|threads| Arc::new(Semaphore::new(threads.max(1))));
+        self.create_pools();
+        let _run = self
+            .fair
+            .as_ref()
+            .map(|fair| fair.start_run(self.cancel_handle()));
+        let run = self.execute_sequence(sequence);
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::instrument(run, span);
+        run.await
+    }
+
+    /// Create the pools of the tasks, with the smallest size the tasks give to each of them.
+    /// A pool keeps its permits between the runs that share it, unless its size changed.
+    fn create_pools(&self) {
+        let mut sizes: HashMap<&str, usize> = HashMap::new();
+        for pool in self.tasks.values().filter_map(|task| task.pool()) {
+            let size = sizes.entry(&pool.name).or_insert(pool.size);
+            *size = (*size).min(pool.size);
+        }
+        let mut pools = self.pools.lock().unwrap();
+        for (name, size) in sizes {
+            let size = size.max(1);
+            if pools.get(name).map(|(current, _)| *current) != Some(size) {
+                pools.insert(name.to_owned(), (size, Arc::new(Semaphore::new(size))));
+            }
+        }
+    }
+
+    async fn execute_sequence(&self, sequence: &[usize]) -> bool {
         debug!("[Start]{} -> [End]", {
-            self.exe_sequence
+            sequence
                 .iter()
                 .map(|id| self.tasks[id].name())
                 .collect::<Vec<&str>>()
                 .join(" -> ")
         });
+        self.observers.notify(Event::DagStarted {
+            tasks: sequence.len(),
+        });
 
-        let handles = self
-            .exe_sequence
-            .iter()
-            .map(|id| (*id, self.execute_task(self.tasks[id].as_ref())))
-            .collect::<Vec<_>>();
+        // In the deterministic mode, a task is only spawned once the previous one finished.
+        let handles = match self.config.seed {
+            Some(seed) => self
+                .seeded_order(sequence, seed)
+                .into_iter()
+                .map(|id| (id, None))
+                .collect::<Vec<_>>(),
+            None => sequence
+                .iter()
+                .map(|id| (*id, Some(self.execute_task(self.tasks[id].as_ref()))))
+                .collect(),
+        };
 
         // Wait for the status of each task to execute. If there is an error in the execution of a task,
         // the engine will fail to execute and give up executing tasks that have not yet been executed.
-        for (tid, handle) in handles {
-            match handle.await {
-                Ok(succeed) => {
+        let mut handles = handles.into_iter();
+        let mut aborted = false;
+        for (tid, handle) in handles.by_ref() {
+            let mut handle = handle.unwrap_or_else(|| self.execute_task(self.tasks[&tid].as_ref()));
+            match self.join_or_expire(&mut handle).await {
+                Some(Ok(succeed)) => {
                     if !succeed {
                         self.handle_error(tid);
                     }
                 }
-                Err(err) => {
+                Some(Err(err)) => {
                     error!("Task execution encountered an unexpected error! {}", err);
+                    // The task ended before it could let its successors know.
+                    let out_degree = self.rely_graph.get_node_out_degree(&tid);
+                    self.state(tid).semaphore().add_permits(out_degree);
                     self.handle_error(tid);
                 }
+                None => {
+                    self.abort_task(tid, handle);
+                    aborted = true;
+                    break;
+                }
+            }
+        }
+        // The grace period of a shutdown expired: stop waiting for the remaining tasks.
+        for (tid, handle) in handles {
+            match handle {
+                Some(handle) => self.abort_task(tid, handle),
+                None => self.observers.notify(Event::TaskSkipped {
+                    id: tid,
+                    name: self.tasks[&tid].name().to_string(),
+                }),
             }
         }
 
-        if self.keep_going {
+        let success = if aborted {
+            false
+        } else if self.config.keep_going {
             // when keep_going is true, the task will continue to execute as much as possible.
             // So, the success is evaluated by keep_going_errored.
             !self.keep_going_errored.load(Ordering::Relaxed)
@@ -303,7 +813,436 @@ impl Dag {
             self.can_continue
                 .compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
                 .is_ok()
+        };
+        self.succeeded.store(success, Ordering::Release);
+        self.cancelled.store(
+            self.shutdown.cancelled.swap(false, Ordering::AcqRel),
+            Ordering::Release,
+        );
+        if let Some(run_dir) = self.run_dir() {
+            run_dir.finish(success);
+        }
+        self.observers.notify(Event::DagFinished { success });
+        let observers = self.observers.clone();
+        tokio::task::spawn_blocking(move || observers.flush())
+            .await
+            .ok();
+        #[cfg(feature = "notify")]
+        if !self.notifiers.is_empty() {
+            let report = self.report();
+            self.notifiers
+                .iter()
+                .for_each(|notifier| notifier.run_finished(&report));
+            // The run finishes once its messages are sent, without blocking a worker thread.
+            let notifiers = self.notifiers.clone();
+            let flush = move || notifiers.iter().for_each(|notifier| notifier.flush());
+            tokio::task::spawn_blocking(flush).await.ok();
+        }
+        success
+    }
+
+    /// An order of the given tasks in which every task comes after its predecessors, drawn
+    /// from the seed: each next task is picked at random among the ready ones, sorted by id,
+    /// so that the order does not depend on the order of the tasks in the dag.
+    fn seeded_order(&self, sequence: &[usize], seed: u64) -> Vec<usize> {
+        let mut waiting: HashMap<usize, usize> = sequence.iter().map(|id| (*id, 0)).collect();
+        let mut successors: HashMap<usize, Vec<usize>> = HashMap::new();
+        for id in sequence {
+            let precursors: HashSet<&usize> = self.tasks[id].precursors().iter().collect();
+            for precursor in precursors {
+                if waiting.contains_key(precursor) {
+                    *waiting.get_mut(id).unwrap() += 1;
+                    successors.entry(*precursor).or_default().push(*id);
+                }
+            }
+        }
+        let mut ready: Vec<usize> = sequence
+            .iter()
+            .filter(|id| waiting[id] == 0)
+            .copied()
+            .collect();
+        let mut rng = seed;
+        let mut order = Vec::with_capacity(sequence.len());
+        while !ready.is_empty() {
+            ready.sort_unstable();
+            let id = ready.remove((splitmix64(&mut rng) % ready.len() as u64) as usize);
+            order.push(id);
+            for successor in successors.remove(&id).unwrap_or_default() {
+                let count = waiting.get_mut(&successor).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(successor);
+                }
+            }
+        }
+        order
+    }
+
+    /// Wait for a task to finish, or for the grace period of a shutdown to expire, in which
+    /// case `None` is returned.
+    async fn join_or_expire(
+        &self,
+        handle: &mut JoinHandle<bool>,
+    ) -> Option<Result<bool, tokio::task::JoinError>> {
+        loop {
+            let mut expired = pin!(self.shutdown.notify.notified());
+            expired.as_mut().enable();
+            if self.shutdown.expired() {
+                return None;
+            }
+            let joined = poll_fn(|cx| {
+                if let Poll::Ready(joined) = Pin::new(&mut *handle).poll(cx) {
+                    return Poll::Ready(Some(joined));
+                }
+                expired.as_mut().poll(cx).map(|_| None)
+            })
+            .await;
+            // Otherwise, the grace period of a shutdown expired, maybe of a previous run.
+            if joined.is_some() {
+                return joined;
+            }
+        }
+    }
+
+    /// Stop waiting for a task after the grace period of a shutdown. A task that is running
+    /// fails, a task that did not start is skipped.
+    fn abort_task(&self, id: usize, handle: JoinHandle<bool>) {
+        if handle.is_finished() {
+            return;
+        }
+        handle.abort();
+        let name = self.tasks[&id].name().to_string();
+        let state = self.state(id);
+        if state.started_at().is_none() {
+            self.observers.notify(Event::TaskSkipped { id, name });
+            return;
+        }
+        warn!("Task [name: {}, id: {}] aborted by a shutdown", name, id);
+        state.mark_finished();
+        self.observers.notify(Event::TaskFailed {
+            id,
+            name: name.clone(),
+            error: ExecutionError::Aborted.to_string(),
+        });
+        self.errors.lock().unwrap().push(DagError::ExecutionFailed {
+            id,
+            name,
+            source: Box::new(ExecutionError::Aborted),
+        });
+    }
+
+    /// Run the tasks with the given ids again, together with every task that depends on them
+    /// directly or indirectly, for example after their inputs changed. The other tasks are not
+    /// run again; the affected tasks receive their outputs from the previous run. If the dag
+    /// has not been run yet, all tasks are run.
+    pub fn run_affected(&mut self, changed: &[usize]) -> Result<bool, DagError> {
+        if self.execute_states.is_empty() {
+            return self.start();
+        }
+
+        // The downstream closure of the changed tasks.
+        let mut affected = HashSet::new();
+        let mut stack = Vec::with_capacity(changed.len());
+        for id in changed {
+            if !self.tasks.contains_key(id) {
+                return Err(DagError::RelyTaskIllegal(id.to_string()));
+            }
+            stack.push(*id);
+        }
+        while let Some(id) = stack.pop() {
+            if affected.insert(id) {
+                stack.extend(
+                    self.rely_graph
+                        .get_node_successors(&id)
+                        .into_iter()
+                        .map(|index| self.rely_graph.find_id_by_index(index).unwrap()),
+                );
+            }
+        }
+
+        // Affected tasks start over, the others hand their previous outputs to the affected
+        // successors again.
+        let graph = &self.rely_graph;
+        self.execute_states = self
+            .execute_states
+            .restart(|index| affected.contains(&graph.find_id_by_index(index).unwrap()));
+        for id in self.tasks.keys() {
+            if !affected.contains(id) {
+                let permits = self
+                    .rely_graph
+                    .get_node_successors(id)
+                    .into_iter()
+                    .filter(|index| {
+                        affected.contains(&self.rely_graph.find_id_by_index(*index).unwrap())
+                    })
+                    .count();
+                self.state(*id).semaphore().add_permits(permits);
+            }
         }
+        self.can_continue.store(true, Ordering::Release);
+        self.keep_going_errored.store(false, Ordering::Release);
+
+        let sequence: Vec<usize> = self
+            .exe_sequence
+            .iter()
+            .filter(|id| affected.contains(*id))
+            .copied()
+            .collect();
+        Ok(block_on(self.run_sequence(&sequence)))
+    }
+
+    /// Run the dag once per set of environment variables, up to `max_concurrent` runs at a
+    /// time, and return the report of each run, in the order of the sets. Useful for
+    /// benchmarks, simulations and parameter searches.
+    ///
+    /// Each run happens in its own instance of the dag, with its own execution states, so the
+    /// runs do not see each other's outputs, and the dag itself is left as it is. The instances
+    /// share the tasks' actions, the execution settings, the observers and the notifiers of
+    /// the dag. Each set of variables replaces the environment of its instance, like
+    /// [`Dag::set_env`].
+    ///
+    /// Returns an error, without running anything, if the dag cannot be built.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{Dag, DefaultTask, EnvVar, Output};
+    ///
+    /// let task = DefaultTask::with_closure("square", |_input, env| {
+    ///     let x = env.get::<u64>("X").unwrap();
+    ///     Output::new(x * x)
+    /// });
+    /// let dag = Dag::with_tasks(vec![task]);
+    /// let params = (1..=4)
+    ///     .map(|x| {
+    ///         let mut env = EnvVar::new();
+    ///         env.set("X", x as u64);
+    ///         env
+    ///     })
+    ///     .collect();
+    /// let reports = dag.run_sweep(params, 2).unwrap();
+    /// assert_eq!(reports.len(), 4);
+    /// assert!(reports.iter().all(|report| report.success));
+    /// ```
+    pub fn run_sweep(
+        &self,
+        params: Vec<EnvVar>,
+        max_concurrent: usize,
+    ) -> Result<Vec<RunReport>, DagError> {
+        let mut instances = Vec::with_capacity(params.len());
+        for env in params {
+            let mut dag = self.instance();
+            dag.set_env(env);
+            dag.init()?;
+            instances.push(dag);
+        }
+        let count = instances.len();
+        let queue = Mutex::new(instances.into_iter().enumerate());
+        let reports = Mutex::new(vec![None; count]);
+        thread::scope(|scope| {
+            for _ in 0..max_concurrent.clamp(1, count.max(1)) {
+                scope.spawn(|| loop {
+                    let Some((index, mut dag)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    block_on(dag.run());
+                    reports.lock().unwrap()[index] = Some(dag.report());
+                });
+            }
+        });
+        Ok(reports
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// The part of the dag needed for the outputs of the given tasks: these tasks and the tasks
+    /// they depend on, directly or indirectly. It is a new dag with the same settings and
+    /// environment, that has not run yet, for example to build only some targets of a build
+    /// dag. The tasks keep their ids.
+    ///
+    /// Returns [`DagError::UnknownTask`] if a target is not in the dag.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{Dag, DefaultTask, Output, Task};
+    ///
+    /// let compile = DefaultTask::with_closure("compile", |_input, _env| Output::new(1));
+    /// let mut test = DefaultTask::with_closure("test", |_input, _env| Output::new(2));
+    /// test.set_predecessors(&[&compile]);
+    /// let mut docs = DefaultTask::with_closure("docs", |_input, _env| Output::new(3));
+    /// docs.set_predecessors(&[&compile]);
+    /// let test_id = test.id();
+    ///
+    /// let dag = Dag::with_tasks(vec![compile, test, docs]);
+    /// let mut tests = dag.subgraph_for(&[test_id]).unwrap();
+    /// assert!(tests.start().unwrap());
+    /// assert_eq!(tests.report().tasks.len(), 2);
+    /// ```
+    pub fn subgraph_for(&self, targets: &[usize]) -> Result<Dag, DagError> {
+        let mut needed = HashSet::new();
+        let mut stack = Vec::with_capacity(targets.len());
+        for id in targets {
+            if !self.tasks.contains_key(id) {
+                return Err(DagError::UnknownTask(id.to_string()));
+            }
+            stack.push(*id);
+        }
+        while let Some(id) = stack.pop() {
+            if let Some(task) = self.tasks.get(&id) {
+                if needed.insert(id) {
+                    stack.extend(task.precursors());
+                }
+            }
+        }
+        let mut dag = self.instance_of(needed.iter().map(|id| self.tasks[id].as_ref()));
+        dag.env = self.env.clone();
+        Ok(dag)
+    }
+
+    /// Compute the [`Fingerprint`] of every task, by id: what the task adds itself (see
+    /// [`Task::fingerprint`]), the variables of the environment listed in
+    /// [`DagConfig::fingerprint_env`], and the fingerprints of its predecessors, in order. A
+    /// task's fingerprint thus changes with anything that changes the tasks it depends on.
+    ///
+    /// Fails if the input file of a task cannot be read, and with
+    /// [`io::ErrorKind::InvalidInput`] if the tasks form a loop or depend on a missing task.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{CommandAction, Dag, DefaultTask, Task};
+    ///
+    /// let fetch = DefaultTask::with_action("fetch", CommandAction::new("curl -sf example.com"));
+    /// let mut count = DefaultTask::with_action("count", CommandAction::new("wc -c"));
+    /// count.set_predecessors(&[&fetch]);
+    /// let (fetch_id, count_id) = (fetch.id(), count.id());
+    ///
+    /// let dag = Dag::with_tasks(vec![fetch, count]);
+    /// let fingerprints = dag.fingerprints().unwrap();
+    /// assert_ne!(fingerprints[&fetch_id], fingerprints[&count_id]);
+    /// ```
+    pub fn fingerprints(&self) -> io::Result<HashMap<usize, Fingerprint>> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let mut fingerprints = HashMap::with_capacity(self.tasks.len());
+        let mut visiting = HashSet::new();
+        for root in self.tasks.keys() {
+            // Depth first, so that the predecessors of a task come before it. A task that is
+            // visited again before it is done is on the current path, which is then a loop.
+            let mut stack = vec![(*root, false)];
+            while let Some((id, ready)) = stack.pop() {
+                if fingerprints.contains_key(&id) {
+                    continue;
+                }
+                let task = self
+                    .tasks
+                    .get(&id)
+                    .ok_or_else(|| invalid(format!("Task[{}] does not exist.", id)))?;
+                if !ready {
+                    if !visiting.insert(id) {
+                        return Err(invalid(format!("Task[{}] is part of a loop.", task.name())));
+                    }
+                    stack.push((id, true));
+                    stack.extend(task.precursors().iter().map(|id| (*id, false)));
+                    continue;
+                }
+                let mut fingerprint = Fingerprinter::new();
+                task.fingerprint(&mut fingerprint)?;
+                fingerprint.env_vars(
+                    &self.env,
+                    self.config.fingerprint_env.iter().map(String::as_str),
+                );
+                for precursor in task.precursors() {
+                    fingerprint.upstream(&fingerprints[precursor]);
+                }
+                fingerprints.insert(id, fingerprint.finish());
+            }
+        }
+        Ok(fingerprints)
+    }
+
+    /// Run the tasks with the given names, and the tasks they depend on, like the targets of a
+    /// build, and return the output of each target by name. The other tasks do not run, and
+    /// the run happens in a new dag (see [`Dag::subgraph_for`]), so this dag is left as it is.
+    ///
+    /// Returns [`DagError::UnknownTask`] if no task has one of the names, the error of the
+    /// first task that failed if the run failed, and any error of building the dag.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{Dag, DefaultTask, Output};
+    ///
+    /// let compile = DefaultTask::with_closure("compile", |_input, _env| Output::new(1));
+    /// let mut test = DefaultTask::with_closure("test", |_input, _env| Output::new("passed"));
+    /// test.set_predecessors(&[&compile]);
+    /// let mut package = DefaultTask::with_closure("package", |_input, _env| Output::new(3));
+    /// package.set_predecessors(&[&compile]);
+    ///
+    /// let dag = Dag::with_tasks(vec![compile, test, package]);
+    /// let outputs = dag.run_targets(&["test"]).unwrap();
+    /// assert_eq!(outputs["test"].as_ref().unwrap().get::<&str>(), Some(&"passed"));
+    /// ```
+    pub fn run_targets(
+        &self,
+        targets: &[&str],
+    ) -> Result<HashMap<String, Option<Content>>, DagError> {
+        let ids = targets
+            .iter()
+            .map(|name| {
+                self.find_task_by_name(name)
+                    .ok_or_else(|| DagError::UnknownTask(name.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut dag = self.subgraph_for(&ids)?;
+        if !dag.start()? {
+            if let Some(error) = dag.take_errors().into_iter().next() {
+                return Err(error);
+            }
+        }
+        Ok(targets
+            .iter()
+            .zip(ids)
+            .map(|(name, id)| (name.to_string(), dag.state(id).get_output()))
+            .collect())
+    }
+
+    /// A new instance of the dag, with the same tasks and settings, that has not run yet.
+    fn instance(&self) -> Dag {
+        self.instance_of(self.tasks.values().map(|task| task.as_ref()))
+    }
+
+    /// A new dag of the given tasks of this dag, with the same settings, that has not run yet.
+    fn instance_of<'a>(&self, tasks: impl Iterator<Item = &'a dyn Task>) -> Dag {
+        let mut dag = Dag::with_tasks_dyn(
+            tasks
+                .map(|task| Box::new(TaskCopy::new(task)) as Box<dyn Task>)
+                .collect(),
+        );
+        dag.config = self.config.clone();
+        dag.params = self.params.clone();
+        dag.observers = self.observers.clone();
+        dag.capture_logs = self.capture_logs;
+        dag.grouped = self.grouped.clone();
+        dag.faults = self.faults.clone();
+        dag.cache = self.cache.clone();
+        dag.locks = self.locks.clone();
+        dag.pools = self.pools.clone();
+        dag.fair = self.fair.clone();
+        dag.artifacts = self.artifacts.next();
+        #[cfg(feature = "notify")]
+        {
+            dag.notifiers = self.notifiers.clone();
+        }
+        dag
+    }
+
+    /// The run directory of the last run.
+    fn run_dir(&self) -> Option<&ArtifactDir> {
+        self.run_dir.as_ref().and_then(|run_dir| run_dir.get())
     }
 
     /// Execute a given task asynchronously.
@@ -311,128 +1250,389 @@ impl Dag {
         let env = self.env.clone();
         let task_id = task.id();
         let task_name = task.name().to_string();
-        let execute_state = self.execute_states[&task_id].clone();
+        let execute_state = self.state_handle(task_id);
         let task_out_degree = self.rely_graph.get_node_out_degree(&task_id);
-        let wait_for_input: Vec<Arc<ExecState>> = task
+        let wait_for_input: Vec<(String, ExecStateRef)> = task
             .precursors()
             .iter()
-            .map(|id| self.execute_states[id].clone())
+            .map(|id| {
+                let name = self.tasks[id].name().to_string();
+                (name, self.state_handle(*id))
+            })
             .collect();
         let action = task.action();
+        let condition = task.condition();
+        let files = task.files().cloned();
+        // Sorted, so that tasks take the locks they share in the same order.
+        let locks: Vec<Arc<TaskLock<()>>> = {
+            let mut names: Vec<&String> = task.locks().iter().collect();
+            names.sort();
+            names.dedup();
+            let mut table = self.locks.lock().unwrap();
+            names
+                .into_iter()
+                .map(|name| table.entry(name.clone()).or_default().clone())
+                .collect()
+        };
+        let pool = task.pool().map(|TaskPool { name, size }| {
+            let mut pools = self.pools.lock().unwrap();
+            let size = (*size).max(1);
+            pools
+                .entry(name.clone())
+                .or_insert_with(|| (size, Arc::new(Semaphore::new(size))))
+                .1
+                .clone()
+        });
+        let fair = self.fair.clone();
         let can_continue = self.can_continue.clone();
+        let errors = self.errors.clone();
+        let observers = self.observers.clone();
+        let capture = self.capture_logs.then(|| execute_state.logs().clone());
+        let grouped = self.grouped.clone();
+        let config = task.config().resolve(&self.config);
+        let blocking_execution = config.execution == Some(Execution::Blocking);
+        let blocking_threads = self.blocking_threads.clone();
+        // Within a limit of the engine too, as the runtime must keep polling the tasks that
+        // wait.
+        let limited = fair.as_ref().is_some_and(FairShare::limited);
+        let kill_stalled = self.config.kill_stalled;
+        let faults = self.faults.clone();
+        let memory = self.memory.clone();
+        let cached = self
+            .cache
+            .clone()
+            .filter(|_| config.cache.unwrap_or_else(|| action.is_cacheable()))
+            .zip(self.fingerprints.get(&task_id).copied());
 
-        tokio::spawn(async move {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "task",
+            task.id = task_id,
+            task.name = %task_name,
+            run.id = self.run_id.as_deref().unwrap_or_default(),
+        );
+
+        #[cfg(feature = "bench")]
+        execute_state.mark(|t| &mut t.spawned);
+        let run = async move {
             // Wait for the execution result of the predecessor task
             let mut inputs = Vec::with_capacity(wait_for_input.len());
-            for wait_for in wait_for_input {
+            let mut input_names = Vec::with_capacity(wait_for_input.len());
+            let mut skip = false;
+            for (name, wait_for) in wait_for_input {
                 wait_for.semaphore().acquire().await.unwrap().forget();
                 // When the task execution result of the predecessor can be obtained, judge whether
                 // the continuation flag is set to false, if it is set to false, cancel the specific
                 // execution logic of the task and return immediately.
                 if !can_continue.load(Ordering::Acquire) || !wait_for.success() {
-                    return true;
+                    skip = true;
+                    break;
                 }
                 if let Some(content) = wait_for.get_output() {
+                    if faults
+                        .as_ref()
+                        .is_some_and(|faults| faults.drops(&name, &task_name))
+                    {
+                        warn!(
+                            "Injected the loss of the output of task '{}' on its way to task '{}'",
+                            name, task_name
+                        );
+                        continue;
+                    }
                     inputs.push(content);
+                    input_names.push(name);
+                }
+            }
+            // Wait for the start delay and the scheduling window of the task.
+            let mut missed_window = false;
+            if !skip {
+                if let Some(delay) = config.delay {
+                    tokio::time::sleep(delay).await;
+                }
+                match config.window_wait(std::time::SystemTime::now()) {
+                    // Even a sleep of zero waits for the next tick of the timer.
+                    Some(wait) if wait.is_zero() => {}
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => missed_window = true,
+                }
+            }
+            // A cancelled dag does not start any more tasks.
+            if skip || !can_continue.load(Ordering::Acquire) {
+                observers.notify(Event::TaskSkipped {
+                    id: task_id,
+                    name: task_name,
+                });
+                // Let the successors know that there is no output to wait for.
+                execute_state.semaphore().add_permits(task_out_degree);
+                return true;
+            }
+            // A task whose condition is false is skipped, and so are its successors.
+            if let Some(condition) = condition {
+                let input = Input::with_names(inputs.clone(), input_names.clone());
+                let check = if condition.is_blocking() {
+                    let env = env.clone();
+                    let check = move || condition.check(&input, &env);
+                    tokio::task::spawn_blocking(check).await.unwrap_or(false)
+                } else {
+                    condition.check(&input, &env)
+                };
+                if !check {
+                    debug!(
+                        "Condition of task [name: {}, id: {}] is false",
+                        task_name, task_id
+                    );
+                    observers.notify(Event::TaskSkipped {
+                        id: task_id,
+                        name: task_name,
+                    });
+                    execute_state.semaphore().add_permits(task_out_degree);
+                    return true;
+                }
+            }
+            // A task whose output files are up to date does not run, but its successors do.
+            if files.is_some_and(|files| files.up_to_date()) {
+                debug!("Task [name: {}, id: {}] is up to date", task_name, task_id);
+                observers.notify(Event::TaskFinished {
+                    id: task_id,
+                    name: task_name,
+                    output_size: None,
+                });
+                execute_state.mark_up_to_date();
+                execute_state.semaphore().add_permits(task_out_degree);
+                return true;
+            }
+            // A task whose output is in the cache does not run.
+            if let Some((cache, fingerprint)) = cached.clone() {
+                let lookup = tokio::task::spawn_blocking(move || cache.get(&fingerprint))
+                    .await
+                    .unwrap_or_else(|err| Err(io::Error::other(err)));
+                match lookup {
+                    Ok(Some(entry)) => match decode_output(entry) {
+                        Some(output) => {
+                            debug!(
+                                "Restored the output of task [name: {}, id: {}] from the cache",
+                                task_name, task_id
+                            );
+                            observers.notify(Event::TaskFinished {
+                                id: task_id,
+                                name: task_name,
+                                output_size: output.as_ref().and_then(|c| c.byte_size()),
+                            });
+                            execute_state.set_output(Output::Out(output));
+                            if let Some(memory) = &memory {
+                                memory.admit(task_id, &execute_state);
+                            }
+                            execute_state.exe_success();
+                            execute_state.semaphore().add_permits(task_out_degree);
+                            return true;
+                        }
+                        None => warn!("Invalid cache entry of task '{}'", task_name),
+                    },
+                    Ok(None) => {}
+                    Err(err) => warn!("Failed to read the cache of task '{}': {}", task_name, err),
                 }
             }
+            // Wait for the tasks sharing a lock with this one to finish.
+            let mut guards = Vec::with_capacity(locks.len());
+            for lock in locks {
+                guards.push(lock.lock_owned().await);
+            }
+            // Then for a free place in its pool.
+            let permit = match pool {
+                Some(pool) => pool.acquire_owned().await.ok(),
+                None => None,
+            };
+            // And for its turn among the dags of the engine.
+            let slot = match &fair {
+                Some(fair) => fair.acquire().await,
+                None => None,
+            };
+            if !can_continue.load(Ordering::Acquire) {
+                observers.notify(Event::TaskSkipped {
+                    id: task_id,
+                    name: task_name,
+                });
+                execute_state.semaphore().add_permits(task_out_degree);
+                return true;
+            }
+            #[cfg(feature = "bench")]
+            execute_state.mark(|t| &mut t.inputs_ready);
             debug!("Executing task [name: {}, id: {}]", task_name, task_id);
+            observers.notify(Event::TaskStarted {
+                id: task_id,
+                name: task_name.clone(),
+            });
             // Concrete logical behavior for performing tasks.
-            panic::catch_unwind(AssertUnwindSafe(|| action.run(Input::new(inputs), env)))
-                .map_or_else(
-                    |_| {
-                        error!("Execution failed [name: {}, id: {}]", task_name, task_id);
-                        false
-                    },
-                    |out| {
-                        // Store execution results
-                        if out.is_err() {
-                            error!(
-                                "Execution failed [name: {}, id: {}]\nerr: {}",
+            execute_state.mark_started();
+            let retries = config.retries.unwrap_or_default();
+            let backoff = config.backoff.unwrap_or_default();
+            // Asked once the task is ready to run, so that a lazy action is built only then.
+            let blocking_task = blocking_execution || action.is_blocking();
+            // Only the actions of blocking tasks count towards the blocking threads.
+            let blocking_threads = blocking_threads.filter(|_| blocking_task);
+            let blocking = blocking_task || limited;
+            let start = std::time::Instant::now();
+            let mut attempt = 0;
+            let mut abandoned = None;
+            let result = if missed_window {
+                Err(ExecutionError::MissedWindow)
+            } else {
+                loop {
+                    let watchdog = config.stall_timeout.map(|timeout| Watchdog {
+                        timeout,
+                        kill: kill_stalled,
+                        observers: observers.clone(),
+                    });
+                    let action = match &faults {
+                        Some(faults) => faults.wrap(action.clone(), &task_name, attempt),
+                        None => action.clone(),
+                    };
+                    let thread = match &blocking_threads {
+                        Some(threads) => threads.clone().acquire_owned().await.ok(),
+                        None => None,
+                    };
+                    // Boxed, so that tasks waiting for their predecessors take less memory.
+                    let result = Box::pin(run_action(
+                        action,
+                        Input::with_names(inputs.clone(), input_names.clone()),
+                        env.clone(),
+                        task_id,
+                        task_name.clone(),
+                        capture.clone(),
+                        config.timeout,
+                        watchdog,
+                        blocking,
+                        &mut abandoned,
+                    ))
+                    .await;
+                    drop(thread);
+                    match result {
+                        Ok(out) => break Ok(out),
+                        Err(err) if attempt < retries => {
+                            // An attempt that timed out or stalled ends before the next one
+                            // starts, so that the action never runs twice at the same time.
+                            if let Some(abandoned) = abandoned.take() {
+                                abandoned.await.ok();
+                            }
+                            attempt += 1;
+                            let Some(delay) = backoff.next_delay(attempt, start) else {
+                                break Err(err);
+                            };
+                            warn!(
+                                "Retrying task [name: {}, id: {}] ({}/{}) in {:?} after error: {}",
                                 task_name,
                                 task_id,
-                                out.get_err().unwrap_or("".to_string())
+                                attempt,
+                                retries,
+                                delay,
+                                env.redact(&err.to_string())
                             );
-                            false
-                        } else {
-                            execute_state.set_output(out);
-                            execute_state.exe_success();
-                            execute_state.semaphore().add_permits(task_out_degree);
-                            debug!("Execution succeed [name: {}, id: {}]", task_name, task_id);
-                            true
+                            tokio::time::sleep(delay).await;
                         }
-                    },
-                )
-        })
+                        Err(err) => break Err(err),
+                    }
+                }
+            };
+            execute_state.mark_finished();
+            drop((guards, permit, slot));
+            let result =
+                result.and_then(|out| limit_output(out, &config, &env, task_id, &task_name));
+            if let Some(grouped) = &grouped {
+                let logs = execute_state.logs().lock().unwrap().clone();
+                let (output, error) = match &result {
+                    Ok(out) => (out.get_out(), None),
+                    Err(err) => (None, Some(err.to_string())),
+                };
+                grouped.print(&task_name, &logs, output.as_ref(), error.as_deref(), &env);
+            }
+            let error = match result {
+                Ok(out) => {
+                    observers.notify(Event::TaskFinished {
+                        id: task_id,
+                        name: task_name.clone(),
+                        output_size: out.get_out().and_then(|c| c.byte_size()),
+                    });
+                    if let Some((cache, fingerprint)) = cached {
+                        if let Some(entry) = encode_output(out.get_out().as_ref()) {
+                            let put = move || cache.put(&fingerprint, &entry);
+                            if let Ok(Err(err)) = tokio::task::spawn_blocking(put).await {
+                                warn!(
+                                    "Failed to cache the output of task '{}': {}",
+                                    task_name, err
+                                );
+                            }
+                        }
+                    }
+                    // Store execution results
+                    execute_state.set_output(out);
+                    if let Some(memory) = &memory {
+                        memory.admit(task_id, &execute_state);
+                    }
+                    execute_state.exe_success();
+                    execute_state.semaphore().add_permits(task_out_degree);
+                    #[cfg(feature = "bench")]
+                    execute_state.mark(|t| &mut t.stored);
+                    debug!("Execution succeed [name: {}, id: {}]", task_name, task_id);
+                    return true;
+                }
+                Err(error) => redact_error(error, &env),
+            };
+            error!(
+                "Execution failed [name: {}, id: {}]\nerr: {}",
+                task_name, task_id, error
+            );
+            observers.notify(Event::TaskFailed {
+                id: task_id,
+                name: task_name.clone(),
+                error: error.to_string(),
+            });
+            errors.lock().unwrap().push(DagError::ExecutionFailed {
+                id: task_id,
+                name: task_name,
+                source: Box::new(error),
+            });
+            // Let the successors know that there is no output to wait for.
+            execute_state.semaphore().add_permits(task_out_degree);
+            false
+        };
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::instrument(run, span);
+        tokio::spawn(run)
     }
 
     /// error handling.
     /// When a task execution error occurs, the error handling logic is:
-    /// First, set the continuation status to false, and then release the semaphore of the
-    /// error task and the tasks after the error task, so that subsequent tasks can quickly
-    /// know that some tasks have errors and cannot continue to execute.
-    /// After that, the follow-up task finds that the flag that can continue to execute is set
-    /// to false, and the specific behavior of executing the task will be cancelled.
+    /// First, set the continuation status to false, so that the tasks that did not start yet
+    /// are cancelled. A task always releases its own semaphore when it ends, so the follow-up
+    /// tasks then find that the flag that can continue to execute is set to false, and skip
+    /// the specific behavior of executing the task.
     fn handle_error(&self, error_task_id: usize) {
-        if self.keep_going {
+        if self.config.keep_going {
             self.handle_errored_keep_going(error_task_id);
         } else {
-            self.handle_errored_stopping(error_task_id);
+            self.handle_errored_stopping();
         }
     }
 
     /// When the keep_going flag is set to false, the error handling logic is:
     /// - Set the continuation status to false
-    /// - Adding permits for all the subsequent tasks
-    fn handle_errored_stopping(&self, error_task_id: usize) {
-        if self
-            .can_continue
-            .compare_exchange(true, false, Ordering::SeqCst, Ordering::Relaxed)
-            .is_err()
-        {
-            return;
-        }
-
-        // Find the position of the faulty task in the execution sequence.
-        let index = self
-            .exe_sequence
-            .iter()
-            .position(|tid| *tid == error_task_id)
-            .unwrap();
-
-        // Add permits for all the subsequent tasks
-        for tid in self.exe_sequence.iter().skip(index) {
-            self.handle_errored_successor(tid, false);
-        }
+    fn handle_errored_stopping(&self) {
+        self.can_continue.store(false, Ordering::SeqCst);
     }
 
     /// When the keep_going flag is set to true, the error handling logic is:
     /// - Set the keep_going_errored flag to true
-    /// - Adding permits for all tasks that rely on the error task
-    /// - Setting them as failed
+    /// - Setting the tasks that rely on the error task as failed
     fn handle_errored_keep_going(&self, error_task_id: usize) {
         self.keep_going_errored.store(true, Ordering::SeqCst);
 
-        // Add permits for the tasks that rely on the error task
         for successor in self
             .rely_graph
             .get_node_successors(&error_task_id)
             .into_iter()
         {
             let tid = self.rely_graph.find_id_by_index(successor).unwrap();
-            self.handle_errored_successor(&tid, true);
-        }
-    }
-
-    /// Add permits for a task and mark the task as failed if necessary.
-    fn handle_errored_successor(&self, tid: &usize, exe_fail: bool) {
-        let out_degree = self.rely_graph.get_node_out_degree(tid);
-        let exec_state = &self.execute_states[tid];
-
-        exec_state.semaphore().add_permits(out_degree);
-
-        if exe_fail {
-            exec_state.exe_fail();
+            self.state(tid).exe_fail();
         }
     }
 
@@ -442,7 +1642,7 @@ impl Dag {
             None
         } else {
             let last_id = self.exe_sequence.last().unwrap();
-            if let Some(content) = self.execute_states[last_id].get_output() {
+            if let Some(content) = self.state(*last_id).get_output() {
                 content.into_inner()
             } else {
                 None
@@ -453,21 +1653,667 @@ impl Dag {
     /// Get the output of all tasks.
     pub fn get_results<T: Send + Sync + 'static>(&self) -> HashMap<usize, Option<Arc<T>>> {
         let hm = self
-            .execute_states
-            .iter()
-            .map(|(&id, state)| {
-                let output = match state.get_output() {
+            .tasks
+            .keys()
+            .filter_map(|&id| {
+                let output = match self.exec_state(id)?.get_output() {
                     Some(content) => content.into_inner(),
                     None => None,
                 };
-                (id, output)
+                Some((id, output))
             })
             .collect();
         hm
     }
 
+    /// Quantify the overhead the engine added to each task of the last execution.
+    /// See [`OverheadReport`](super::OverheadReport).
+    #[cfg(feature = "bench")]
+    pub fn overhead_report(&self) -> super::OverheadReport {
+        let mut tasks: Vec<_> = self
+            .exe_sequence
+            .iter()
+            .filter_map(|id| {
+                let timing = self.exec_state(*id)?.timing();
+                super::TaskOverhead::new(*id, self.tasks[id].name(), &timing)
+            })
+            .collect();
+        tasks.sort_by_key(|task| task.id);
+        super::OverheadReport { tasks }
+    }
+
+    /// Partition the tasks into blocks by their level in the dependency graph. The first block
+    /// contains the tasks without predecessors, and every other task is in the block after its
+    /// deepest predecessor, so tasks in the same block don't depend on each other and may run
+    /// together. Each block lists the `(id, name)` of its tasks, sorted by id.
+    ///
+    /// Returns an error if the dag cannot be built, for example because of a loop.
+    pub fn blocks(&self) -> Result<Vec<Vec<(usize, String)>>, DagError> {
+        let graph = self.build_graph()?;
+        if graph.topo_sort().is_none() {
+            return Err(DagError::LoopGraph);
+        }
+        let mut blocks: Vec<Vec<(usize, String)>> = Vec::new();
+        for (index, level) in graph.levels().into_iter().enumerate() {
+            if blocks.len() <= level {
+                blocks.resize(level + 1, Vec::new());
+            }
+            let id = graph.find_id_by_index(index).unwrap();
+            blocks[level].push((id, self.tasks[&id].name().to_string()));
+        }
+        blocks.iter_mut().for_each(|block| block.sort());
+        Ok(blocks)
+    }
+
+    /// Iterate over the tasks in a valid topological order, together with their level in the
+    /// dependency graph (see [`Dag::blocks`]). Tasks are yielded by ascending level, and by
+    /// ascending id within a level, so every task comes after all of its predecessors.
+    ///
+    /// Returns an error if the dag cannot be built, for example because of a loop.
+    ///
+    /// # Example
+    ///
+    /// Executing the tasks one after another on the current thread:
+    ///
+    /// ```rust
+    /// use dagrs::{Dag, DefaultTask, EnvVar, Input, Output, Task};
+    /// use std::{collections::HashMap, sync::Arc};
+    ///
+    /// let a = DefaultTask::with_closure("a", |_, _| Output::new(1usize));
+    /// let mut b = DefaultTask::with_closure("b", |input, _| {
+    ///     let sum: usize = input.get_iter().map(|c| c.get::<usize>().unwrap()).sum();
+    ///     Output::new(sum + 1)
+    /// });
+    /// b.set_predecessors(&[&a]);
+    ///
+    /// let dag = Dag::with_tasks(vec![a, b]);
+    /// let env = Arc::new(EnvVar::new());
+    /// let mut outputs = HashMap::new();
+    /// for (_level, task) in dag.topo_iter().unwrap() {
+    ///     let inputs = task.precursors().iter().filter_map(|id| outputs.get(id).cloned());
+    ///     let output = task.action().run(Input::new(inputs.collect()), env.clone());
+    ///     outputs.insert(task.id(), output.get_out().unwrap());
+    /// }
+    /// ```
+    pub fn topo_iter(&self) -> Result<impl Iterator<Item = (usize, &dyn Task)>, DagError> {
+        let mut order: Vec<(usize, usize)> = self
+            .blocks()?
+            .into_iter()
+            .enumerate()
+            .flat_map(|(level, block)| block.into_iter().map(move |(id, _)| (level, id)))
+            .collect();
+        order.sort();
+        Ok(order
+            .into_iter()
+            .map(|(level, id)| (level, self.tasks[&id].as_ref())))
+    }
+
+    /// Check the wiring of the dag without running real workloads.
+    ///
+    /// A copy of the dag, in which every action is replaced by a stub that immediately outputs
+    /// its task's id, is executed, and every task checks that it received the output of each
+    /// of its predecessors. The dag itself is left untouched and can be started afterwards.
+    /// Returns an error if the dag cannot be built, for example because of a loop.
+    pub fn simulate(&self) -> Result<Simulation, DagError> {
+        let missing = Arc::new(Mutex::new(Vec::new()));
+        let stubs = self
+            .tasks
+            .values()
+            .map(|task| Box::new(StubTask::new(task.as_ref(), missing.clone())) as Box<dyn Task>)
+            .collect();
+        let mut dag = Dag::with_tasks_dyn(stubs);
+        dag.start()?;
+
+        let name = |id: &usize| self.tasks[id].name().to_string();
+        let mut missing_inputs: Vec<(String, String)> = missing
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(task, pre)| (name(task), name(pre)))
+            .collect();
+        missing_inputs.sort();
+        let last = dag.exe_sequence.last();
+        let mut unconsumed_outputs: Vec<String> = dag
+            .exe_sequence
+            .iter()
+            .filter(|id| Some(*id) != last && dag.rely_graph.get_node_out_degree(id) == 0)
+            .map(name)
+            .collect();
+        unconsumed_outputs.sort();
+        Ok(Simulation {
+            missing_inputs,
+            unconsumed_outputs,
+        })
+    }
+
+    /// Record which tasks ran in the last execution, grouped by their level in the
+    /// dependency graph, and which did not. See [`ExecutionSnapshot`].
+    pub fn execution_snapshot(&self) -> ExecutionSnapshot {
+        let levels = self.rely_graph.levels();
+        let mut blocks: Vec<Vec<String>> = Vec::new();
+        let mut not_run = Vec::new();
+        for (id, task) in self.tasks.iter() {
+            let name = task.name().to_string();
+            let ran = self
+                .exec_state(*id)
+                .is_some_and(|state| state.started_at().is_some());
+            match self.rely_graph.find_index_by_id(id) {
+                Some(index) if ran => {
+                    let level = levels[index];
+                    if blocks.len() <= level {
+                        blocks.resize(level + 1, Vec::new());
+                    }
+                    blocks[level].push(name);
+                }
+                _ => not_run.push(name),
+            }
+        }
+        blocks.retain(|block| !block.is_empty());
+        blocks.iter_mut().for_each(|block| block.sort());
+        not_run.sort();
+        ExecutionSnapshot { blocks, not_run }
+    }
+
+    /// Summarize the last run of the dag: the status, duration, error and output of every task,
+    /// in execution order. The run succeeded if no task failed and it was not cancelled; tasks
+    /// skipped because of a false [`Condition`] do not fail it.
+    pub fn report(&self) -> RunReport {
+        let errors = self.errors.lock().unwrap();
+        let tasks: Vec<TaskReport> = self
+            .exe_sequence
+            .iter()
+            .map(|id| {
+                let state = self.state(*id);
+                let error = errors.iter().find_map(|err| match err {
+                    DagError::ExecutionFailed {
+                        id: failed, source, ..
+                    } if failed == id => Some(source.to_string()),
+                    _ => None,
+                });
+                let status = if state.up_to_date() {
+                    TaskStatus::UpToDate
+                } else if state.success() {
+                    TaskStatus::Succeeded
+                } else if state.started_at().is_some() {
+                    TaskStatus::Failed
+                } else {
+                    TaskStatus::Skipped
+                };
+                let mut report = TaskReport {
+                    id: *id,
+                    name: self.tasks[id].name().to_string(),
+                    status,
+                    duration: state
+                        .started_at()
+                        .zip(state.finished_at())
+                        .map(|(started, finished)| finished - started),
+                    error,
+                    output: None,
+                    stderr: None,
+                    stdout_file: None,
+                    stderr_file: None,
+                };
+                if let Some(content) = state.get_output() {
+                    report.set_output(&content);
+                }
+                if let Some(dir) = self.run_dir() {
+                    let file = |stream| {
+                        let path = dir.path().join(output_artifact(*id, &report.name, stream));
+                        (state.started_at().is_some() && path.exists()).then_some(path)
+                    };
+                    report.stdout_file = file("stdout");
+                    report.stderr_file = file("stderr");
+                }
+                report.output = report.output.map(|output| self.env.redact(&output));
+                report.stderr = report.stderr.map(|stderr| self.env.redact(&stderr));
+                report
+            })
+            .collect();
+        RunReport {
+            run_id: self.run_id.clone(),
+            success: self.succeeded.load(Ordering::Acquire),
+            cancelled: self.cancelled.load(Ordering::Acquire),
+            tasks,
+        }
+    }
+
+    /// Find the id of a task by its name.
+    pub(crate) fn find_task_by_name(&self, name: &str) -> Option<usize> {
+        self.tasks
+            .values()
+            .find(|task| task.name() == name)
+            .map(|task| task.id())
+    }
+
+    /// Get the execution state of a task.
+    pub(crate) fn exec_state(&self, id: usize) -> Option<&ExecState> {
+        self.rely_graph
+            .find_index_by_id(&id)
+            .and_then(|index| self.execute_states.get(index))
+    }
+
+    /// The execution state of a task of the initialized dag.
+    fn state(&self, id: usize) -> &ExecState {
+        self.exec_state(id)
+            .expect("the task has an execution state")
+    }
+
+    /// A handle on the execution state of a task of the initialized dag, for its run.
+    fn state_handle(&self, id: usize) -> ExecStateRef {
+        let index = self.rely_graph.find_index_by_id(&id).unwrap();
+        self.execute_states.handle(index)
+    }
+
+    /// Get the log lines captured for the given task. Only available if the dag was built
+    /// with [`Dag::capture_logs`].
+    pub fn get_logs(&self, id: usize) -> Option<Vec<String>> {
+        self.exec_state(id).map(|state| {
+            let logs = state.logs().lock().unwrap();
+            logs.iter().map(|line| self.env.redact(line)).collect()
+        })
+    }
+
+    /// Render the last run of the dag as a standalone HTML page: a picture of the dag with
+    /// each task colored by its status, then the duration, error, output and captured log
+    /// lines (see [`Dag::capture_logs`]) of every task.
+    #[cfg(feature = "report-html")]
+    pub fn html_report(&self) -> String {
+        let logs = self
+            .tasks
+            .keys()
+            .filter_map(|id| self.get_logs(*id).map(|logs| (*id, logs)))
+            .collect();
+        HtmlReport {
+            report: &self.report(),
+            blocks: &self.blocks().unwrap_or_default(),
+            edges: &self.export_structure().edges,
+            logs: &logs,
+        }
+        .render()
+    }
+
+    /// Write the [HTML report](Dag::html_report) of the last run to a file.
+    #[cfg(feature = "report-html")]
+    pub fn save_html_report(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.html_report())
+    }
+
+    /// The id of the current or last run of the dag, `None` if it has not run yet. Every run,
+    /// including each call of [`Dag::run_affected`], gets a new id, which is unique across
+    /// processes. It is given to the observers with every event (see
+    /// [`ExecutionObserver::on_run_event`]), is part of the [`RunReport`] and of notifications,
+    /// prefixes the log lines of actions (see [`ContextLogger`](crate::utils::ContextLogger)),
+    /// and is available to actions as the `DAGRS_RUN_ID` environment variable, a `String`.
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
+    }
+
+    /// Take the errors of the tasks that failed during the last execution, in the order
+    /// they occurred. Each error is a [`DagError::ExecutionFailed`].
+    pub fn take_errors(&self) -> Vec<DagError> {
+        std::mem::take(&mut *self.errors.lock().unwrap())
+    }
+
     /// Before the dag starts executing, set the dag's global environment variable.
-    pub fn set_env(&mut self, env: EnvVar) {
+    pub fn set_env(&mut self, mut env: EnvVar) {
+        if let Some(content) = self.env.get_content(CAPTURE_OUTPUT_KEY) {
+            env.set_content(CAPTURE_OUTPUT_KEY, content.clone());
+        }
+        for (key, value) in &self.params {
+            env.set(key, value.clone());
+        }
         self.env = Arc::new(env);
     }
 }
+
+/// A new run id: the time in milliseconds since the Unix epoch, the process id and a counter
+/// of the runs of the process, in hexadecimal.
+fn new_run_id() -> String {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    format!(
+        "{:x}-{:x}-{:x}",
+        millis,
+        std::process::id(),
+        RUNS.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Run a dag on a new runtime. Actions that timed out may still be running on blocking
+/// threads; they are not waited for.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let output = runtime.block_on(future);
+    runtime.shutdown_background();
+    output
+}
+
+/// Apply the output size limit of a task to its output, see [`DagConfig::max_output_size`].
+fn limit_output(
+    out: Output,
+    config: &TaskConfig,
+    env: &EnvVar,
+    id: usize,
+    name: &str,
+) -> Result<Output, ExecutionError> {
+    let (Some(limit), Some(content)) = (config.max_output_size, out.get_out()) else {
+        return Ok(out);
+    };
+    let Some(size) = content.byte_size().filter(|size| *size > limit) else {
+        return Ok(out);
+    };
+    let too_large = ExecutionError::OutputTooLarge { size, limit };
+    match config.output_overflow.unwrap_or_default() {
+        OverflowPolicy::Fail => Err(too_large),
+        OverflowPolicy::Truncate => {
+            let truncated = content.truncate(limit).ok_or(too_large)?;
+            warn!(
+                "Truncated the output of task [name: {}, id: {}] from {} to {} bytes",
+                name, id, size, limit
+            );
+            Ok(Output::Out(Some(truncated)))
+        }
+        OverflowPolicy::Spill => {
+            let (Some(payload), Some(dir)) = (content.payload(), ArtifactDir::from_env(env)) else {
+                return Err(too_large);
+            };
+            let spilled = dir
+                .create(&output_artifact(id, name, "out"))
+                .and_then(|artifact| std::fs::write(artifact.path(), payload).map(|()| artifact));
+            match spilled {
+                Ok(artifact) => {
+                    debug!(
+                        "Spilled the output of task [name: {}, id: {}] to '{}'",
+                        name,
+                        id,
+                        artifact.path().display()
+                    );
+                    Ok(Output::new(artifact))
+                }
+                Err(err) => Err(ExecutionError::Output(format!(
+                    "failed to spill the output of {} bytes: {}",
+                    size, err
+                ))),
+            }
+        }
+    }
+}
+
+/// Run an action once and catch its panics. An action with a timeout runs on a blocking
+/// thread; as actions cannot be interrupted, the thread is left behind if the action does not
+/// finish in time. So does a `blocking` action, so that it leaves the threads of the runtime to
+/// the tasks waiting for their turn.
+#[allow(clippy::too_many_arguments)]
+async fn run_action(
+    action: Action,
+    input: Input,
+    env: Arc<EnvVar>,
+    id: usize,
+    name: String,
+    capture: Option<LogBuffer>,
+    timeout: Option<Duration>,
+    watchdog: Option<Watchdog>,
+    blocking: bool,
+    abandoned: &mut Option<JoinHandle<std::thread::Result<Output>>>,
+) -> Result<Output, ExecutionError> {
+    let heartbeat = Heartbeat::new();
+    let beat = heartbeat.clone();
+    let task_name = name.clone();
+    let run_id = env.get::<String>(RUN_ID_KEY);
+    // Actions on blocking threads log within the span of their task too.
+    #[cfg(feature = "tracing")]
+    let span = tracing::Span::current();
+    let run = move || {
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            log_scope(id, &task_name, run_id, capture, beat, || {
+                action.run(input, env)
+            })
+        }))
+    };
+    if !blocking && timeout.is_none() && watchdog.is_none() {
+        return output_result(run());
+    }
+    // Run the action on a blocking thread, so that it can be given up on.
+    let mut handle = tokio::task::spawn_blocking(run);
+    let supervised = async {
+        let Some(watchdog) = watchdog else {
+            return Ok((&mut handle).await);
+        };
+        let mut stalled = false;
+        loop {
+            let check = (watchdog.timeout / 4).max(Duration::from_millis(10));
+            if let Ok(joined) = tokio::time::timeout(check, &mut handle).await {
+                return Ok(joined);
+            }
+            let silence = heartbeat.silence();
+            if silence < watchdog.timeout {
+                stalled = false;
+                continue;
+            }
+            if !stalled {
+                stalled = true;
+                warn!(
+                    "Task [name: {}, id: {}] has no heartbeat for {:?}",
+                    name, id, silence
+                );
+                watchdog.observers.notify(Event::TaskStalled {
+                    id,
+                    name: name.clone(),
+                });
+            }
+            if watchdog.kill {
+                return Err(ExecutionError::Stalled(silence));
+            }
+        }
+    };
+    let joined = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, supervised)
+            .await
+            .unwrap_or(Err(ExecutionError::Timeout(timeout))),
+        None => supervised.await,
+    };
+    match joined {
+        Ok(joined) => output_result(joined.map_err(|err| ExecutionError::Panic(err.to_string()))?),
+        // The action goes on running on its thread.
+        Err(err) => {
+            *abandoned = Some(handle);
+            Err(err)
+        }
+    }
+}
+
+/// The watchdog of a running action, see [`DagConfig::stall_timeout`].
+struct Watchdog {
+    timeout: Duration,
+    kill: bool,
+    observers: Observers,
+}
+
+/// Convert the result of an action, which may have panicked, into the result of its task.
+fn output_result(result: std::thread::Result<Output>) -> Result<Output, ExecutionError> {
+    match result {
+        Ok(out) if !out.is_err() => Ok(out),
+        Ok(out) => Err(execution_error(&out)),
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(ExecutionError::Panic(msg))
+        }
+    }
+}
+
+/// A copy of a task, for another instance of its dag, see [`Dag::run_sweep`].
+struct TaskCopy {
+    id: usize,
+    name: String,
+    precursors: Vec<usize>,
+    action: Action,
+    action_type: Option<String>,
+    action_args: Option<HashMap<String, String>>,
+    config: TaskConfig,
+    condition: Option<Arc<dyn Condition>>,
+    files: Option<TaskFiles>,
+    locks: Vec<String>,
+    pool: Option<TaskPool>,
+}
+
+impl TaskCopy {
+    fn new(task: &dyn Task) -> Self {
+        Self {
+            id: task.id(),
+            name: task.name().to_owned(),
+            precursors: task.precursors().to_vec(),
+            action: task.action(),
+            action_type: task.action_type().map(str::to_owned),
+            action_args: task.action_args().cloned(),
+            config: task.config(),
+            condition: task.condition(),
+            files: task.files().cloned(),
+            locks: task.locks().to_vec(),
+            pool: task.pool().cloned(),
+        }
+    }
+}
+
+impl Task for TaskCopy {
+    fn action(&self) -> Action {
+        self.action.clone()
+    }
+
+    fn precursors(&self) -> &[usize] {
+        &self.precursors
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn action_type(&self) -> Option<&str> {
+        self.action_type.as_deref()
+    }
+
+    fn action_args(&self) -> Option<&HashMap<String, String>> {
+        self.action_args.as_ref()
+    }
+
+    fn config(&self) -> TaskConfig {
+        self.config
+    }
+
+    fn condition(&self) -> Option<Arc<dyn Condition>> {
+        self.condition.clone()
+    }
+
+    fn files(&self) -> Option<&TaskFiles> {
+        self.files.as_ref()
+    }
+
+    fn locks(&self) -> &[String] {
+        &self.locks
+    }
+
+    fn pool(&self) -> Option<&TaskPool> {
+        self.pool.as_ref()
+    }
+}
+
+/// A task with predecessors added by [`Dag::add_edge`].
+struct LinkedTask {
+    task: Box<dyn Task>,
+    precursors: Vec<usize>,
+}
+
+impl Task for LinkedTask {
+    fn action(&self) -> Action {
+        self.task.action()
+    }
+
+    fn precursors(&self) -> &[usize] {
+        &self.precursors
+    }
+
+    fn id(&self) -> usize {
+        self.task.id()
+    }
+
+    fn name(&self) -> &str {
+        self.task.name()
+    }
+
+    fn action_type(&self) -> Option<&str> {
+        self.task.action_type()
+    }
+
+    fn action_args(&self) -> Option<&HashMap<String, String>> {
+        self.task.action_args()
+    }
+
+    fn config(&self) -> TaskConfig {
+        self.task.config()
+    }
+
+    fn condition(&self) -> Option<Arc<dyn Condition>> {
+        self.task.condition()
+    }
+
+    fn files(&self) -> Option<&TaskFiles> {
+        self.task.files()
+    }
+
+    fn locks(&self) -> &[String] {
+        self.task.locks()
+    }
+
+    fn pool(&self) -> Option<&TaskPool> {
+        self.task.pool()
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) -> io::Result<()> {
+        self.task.fingerprint(fingerprint)
+    }
+}
+
+/// Mask the secrets of the environment in the message of an error, before it is logged or
+/// stored.
+fn redact_error(error: ExecutionError, env: &EnvVar) -> ExecutionError {
+    match error {
+        ExecutionError::Output(msg) => ExecutionError::Output(env.redact(&msg)),
+        ExecutionError::ExitCode { code, msg } => ExecutionError::ExitCode {
+            code,
+            msg: env.redact(&msg),
+        },
+        ExecutionError::Panic(msg) => ExecutionError::Panic(env.redact(&msg)),
+        error => error,
+    }
+}
+
+/// Convert an error [`Output`] into the [`ExecutionError`] describing it.
+fn execution_error(out: &Output) -> ExecutionError {
+    match out {
+        Output::ErrWithExitCode(code, content) => {
+            let msg = content
+                .as_ref()
+                .and_then(|c| {
+                    c.get::<String>().cloned().or_else(|| {
+                        c.get::<(Vec<String>, Vec<String>)>()
+                            .map(|(_, stderr)| stderr.join("\n"))
+                    })
+                })
+                .unwrap_or_default();
+            ExecutionError::ExitCode { code: *code, msg }
+        }
+        _ => ExecutionError::Output(out.get_err().unwrap_or_default()),
+    }
+}
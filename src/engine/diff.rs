@@ -0,0 +1,158 @@
+//! Dag structure diff
+//!
+//! [`DagStructure::diff`] compares two versions of a workflow definition and reports which
+//! tasks and dependency edges were added or removed. Task ids are allocated anew every time a
+//! dag is built, so tasks are matched by name. A removed task and an added task with the same
+//! action type, predecessors and successors are reported as a rename.
+
+use super::DagStructure;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+};
+
+/// The changes between two [`DagStructure`]s. All lists are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StructureDiff {
+    /// Names of the tasks that only exist in the new structure.
+    pub added_tasks: Vec<String>,
+    /// Names of the tasks that only exist in the old structure.
+    pub removed_tasks: Vec<String>,
+    /// `(old name, new name)` pairs of renamed tasks.
+    pub renamed_tasks: Vec<(String, String)>,
+    /// `(predecessor, successor)` names of the edges that only exist in the new structure.
+    pub added_edges: Vec<(String, String)>,
+    /// `(predecessor, successor)` names of the edges that only exist in the old structure.
+    pub removed_edges: Vec<(String, String)>,
+}
+
+impl StructureDiff {
+    /// Whether the two structures are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.added_tasks.is_empty()
+            && self.removed_tasks.is_empty()
+            && self.renamed_tasks.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+impl Display for StructureDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for name in self.removed_tasks.iter() {
+            writeln!(f, "- task {}", name)?;
+        }
+        for name in self.added_tasks.iter() {
+            writeln!(f, "+ task {}", name)?;
+        }
+        for (old, new) in self.renamed_tasks.iter() {
+            writeln!(f, "~ task {} -> {}", old, new)?;
+        }
+        for (from, to) in self.removed_edges.iter() {
+            writeln!(f, "- edge {} -> {}", from, to)?;
+        }
+        for (from, to) in self.added_edges.iter() {
+            writeln!(f, "+ edge {} -> {}", from, to)?;
+        }
+        Ok(())
+    }
+}
+
+/// The tasks and edges of a structure by name.
+struct Named<'a> {
+    actions: HashMap<&'a str, Option<&'a str>>,
+    edges: BTreeSet<(&'a str, &'a str)>,
+}
+
+impl<'a> Named<'a> {
+    fn new(structure: &'a DagStructure) -> Self {
+        let names: HashMap<usize, &str> = structure
+            .tasks
+            .iter()
+            .map(|task| (task.id, task.name.as_str()))
+            .collect();
+        let actions = structure
+            .tasks
+            .iter()
+            .map(|task| (task.name.as_str(), task.action.as_deref()))
+            .collect();
+        let edges = structure
+            .edges
+            .iter()
+            .filter_map(|(from, to)| Some((*names.get(from)?, *names.get(to)?)))
+            .collect();
+        Self { actions, edges }
+    }
+
+    /// The names of the predecessors and successors of a task.
+    fn neighbours(&self, name: &str) -> (BTreeSet<&'a str>, BTreeSet<&'a str>) {
+        let pres = self
+            .edges
+            .iter()
+            .filter(|(_, to)| *to == name)
+            .map(|(from, _)| *from)
+            .collect();
+        let sucs = self
+            .edges
+            .iter()
+            .filter(|(from, _)| *from == name)
+            .map(|(_, to)| *to)
+            .collect();
+        (pres, sucs)
+    }
+}
+
+impl DagStructure {
+    /// Compute the changes from this structure to `new`.
+    pub fn diff(&self, new: &DagStructure) -> StructureDiff {
+        let old = Named::new(self);
+        let new = Named::new(new);
+
+        let mut removed: BTreeSet<&str> = old
+            .actions
+            .keys()
+            .filter(|name| !new.actions.contains_key(*name))
+            .copied()
+            .collect();
+        let mut added: BTreeSet<&str> = new
+            .actions
+            .keys()
+            .filter(|name| !old.actions.contains_key(*name))
+            .copied()
+            .collect();
+
+        let mut renamed = Vec::new();
+        for old_name in removed.clone() {
+            let found = added.iter().copied().find(|new_name| {
+                old.actions[old_name] == new.actions[new_name]
+                    && old.neighbours(old_name) == new.neighbours(new_name)
+            });
+            if let Some(new_name) = found {
+                removed.remove(old_name);
+                added.remove(new_name);
+                renamed.push((old_name, new_name));
+            }
+        }
+
+        let renames: HashMap<&str, &str> = renamed.iter().copied().collect();
+        let old_edges: BTreeSet<(&str, &str)> = old
+            .edges
+            .iter()
+            .map(|&(from, to)| {
+                (
+                    renames.get(from).copied().unwrap_or(from),
+                    renames.get(to).copied().unwrap_or(to),
+                )
+            })
+            .collect();
+
+        let to_owned = |(from, to): &(&str, &str)| (from.to_string(), to.to_string());
+        StructureDiff {
+            added_tasks: added.into_iter().map(str::to_string).collect(),
+            removed_tasks: removed.into_iter().map(str::to_string).collect(),
+            renamed_tasks: renamed.iter().map(to_owned).collect(),
+            added_edges: new.edges.difference(&old_edges).map(to_owned).collect(),
+            removed_edges: old_edges.difference(&new.edges).map(to_owned).collect(),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Execution events
+//!
+//! While a [`Dag`](super::Dag) runs, it reports the lifecycle of the dag and of each task
+//! as an [`Event`] to every registered [`ExecutionObserver`]. Observers can be used to
+//! collect statistics, write logs, or notify external systems.
+//!
+//! [`JsonEventLog`] is an observer that writes one JSON object per event to a file or any
+//! other writer, so that runs can be analyzed by external tooling and compared across
+//! executions.
+//!
+//! [`LogObserver`] forwards events to the `log` facade, and `TracingObserver` (with the
+//! `tracing` feature) emits them as `tracing` events with the task id and name as fields,
+//! so that dagrs output lands in the application's existing logging pipeline.
+//!
+//! Each run of a dag has a run id (see [`Dag::run_id`](super::Dag::run_id)), which observers
+//! receive with every event through [`ExecutionObserver::on_run_event`]. The observers of this
+//! module include it in their output, so that the events of concurrent runs of the same dag
+//! can be told apart.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{Dag, DefaultTask, Output, JsonEventLog};
+//!
+//! let task = DefaultTask::with_closure("Simple Task", |_input, _env| Output::new(1));
+//! let mut dag = Dag::with_tasks(vec![task]);
+//! dag.add_observer(JsonEventLog::new(std::io::stdout()));
+//! assert!(dag.start().unwrap());
+//! ```
+
+use serde::Serialize;
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Lifecycle events of a dag and its tasks.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The dag started executing.
+    DagStarted { tasks: usize },
+    /// The dag finished executing.
+    DagFinished { success: bool },
+    /// All predecessors of a task are done and its action starts running.
+    TaskStarted { id: usize, name: String },
+    /// A task finished successfully. `output_size` is the size in bytes of the output,
+    /// if the output is of a type whose size is known (see [`Content::byte_size`](crate::task::Content::byte_size)).
+    TaskFinished {
+        id: usize,
+        name: String,
+        output_size: Option<usize>,
+    },
+    /// A task failed.
+    TaskFailed {
+        id: usize,
+        name: String,
+        error: String,
+    },
+    /// A task was not executed because a task it depends on failed.
+    TaskSkipped { id: usize, name: String },
+    /// The action of a running task went without a heartbeat for longer than its stall
+    /// timeout, see [`DagConfig::stall_timeout`](super::DagConfig::stall_timeout).
+    TaskStalled { id: usize, name: String },
+}
+
+/// An observer of dag execution.
+///
+/// Observers are called synchronously from the threads that execute the tasks, so
+/// implementations should return quickly.
+pub trait ExecutionObserver: Send + Sync {
+    fn on_event(&self, event: &Event);
+
+    /// Called with each event of the run with the given id. Forwards the event to
+    /// [`on_event`](ExecutionObserver::on_event) by default.
+    fn on_run_event(&self, run_id: &str, event: &Event) {
+        let _ = run_id;
+        self.on_event(event);
+    }
+
+    /// Wait for the work the observer does in the background for the events so far, such as
+    /// posting messages. Called at the end of every run, on a blocking thread. Does nothing by
+    /// default.
+    fn flush(&self) {}
+}
+
+/// The observers registered on a dag.
+#[derive(Clone, Default)]
+pub(crate) struct Observers {
+    observers: Vec<Arc<dyn ExecutionObserver>>,
+    /// The id of the current run.
+    run_id: Option<Arc<str>>,
+}
+
+impl Observers {
+    pub(crate) fn push(&mut self, observer: Arc<dyn ExecutionObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub(crate) fn set_run_id(&mut self, run_id: &str) {
+        self.run_id = Some(run_id.into());
+    }
+
+    /// Report an event to every observer.
+    pub(crate) fn notify(&self, event: Event) {
+        self.observers
+            .iter()
+            .for_each(|observer| match &self.run_id {
+                Some(run_id) => observer.on_run_event(run_id, &event),
+                None => observer.on_event(&event),
+            });
+    }
+
+    /// Wait for the background work of every observer, see [`ExecutionObserver::flush`].
+    pub(crate) fn flush(&self) {
+        self.observers.iter().for_each(|observer| observer.flush());
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} observers", self.observers.len())
+    }
+}
+
+/// Writes each [`Event`] as a line of JSON, with a millisecond `timestamp` field and the
+/// `run_id` of the run added.
+///
+/// ```json
+/// {"event":"task_started","id":1,"name":"Compute A","run_id":"18bcfe56800-1f2a-0","timestamp":1700000000000}
+/// ```
+pub struct JsonEventLog<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonEventLog<W> {
+    /// Write events to the given writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl JsonEventLog<LineWriter<File>> {
+    /// Create (or truncate) the file at `path` and write events to it.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(LineWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write + Send> JsonEventLog<W> {
+    fn write(&self, run_id: Option<&str>, event: &Event) {
+        let mut value = serde_json::to_value(event).unwrap();
+        let object = value.as_object_mut().unwrap();
+        if let Some(run_id) = run_id {
+            object.insert("run_id".to_string(), run_id.into());
+        }
+        object.insert("timestamp".to_string(), timestamp_millis().into());
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(err) = writeln!(writer, "{}", value) {
+            log::error!("Failed to write event log: {}", err);
+        }
+    }
+}
+
+impl<W: Write + Send> ExecutionObserver for JsonEventLog<W> {
+    fn on_event(&self, event: &Event) {
+        self.write(None, event);
+    }
+
+    fn on_run_event(&self, run_id: &str, event: &Event) {
+        self.write(Some(run_id), event);
+    }
+}
+
+/// Forwards each [`Event`] to the `log` facade under the `dagrs` target. Task failures are
+/// logged at `error` level, everything else at `info` level. The run id leads the fields of
+/// each line.
+#[derive(Debug, Default)]
+pub struct LogObserver;
+
+impl LogObserver {
+    fn log(&self, run_id: Option<&str>, event: &Event) {
+        let run = run_id.map_or_else(String::new, |run_id| format!("run: {}, ", run_id));
+        match event {
+            Event::DagStarted { tasks } => {
+                log::info!(target: "dagrs", "Dag started [{}tasks: {}]", run, tasks)
+            }
+            Event::DagFinished { success } => {
+                log::info!(target: "dagrs", "Dag finished [{}success: {}]", run, success)
+            }
+            Event::TaskStarted { id, name } => {
+                log::info!(target: "dagrs", "Task started [{}name: {}, id: {}]", run, name, id)
+            }
+            Event::TaskFinished { id, name, .. } => {
+                log::info!(target: "dagrs", "Task finished [{}name: {}, id: {}]", run, name, id)
+            }
+            Event::TaskFailed { id, name, error } => log::error!(
+                target: "dagrs",
+                "Task failed [{}name: {}, id: {}]: {}",
+                run,
+                name,
+                id,
+                error
+            ),
+            Event::TaskSkipped { id, name } => {
+                log::info!(target: "dagrs", "Task skipped [{}name: {}, id: {}]", run, name, id)
+            }
+            Event::TaskStalled { id, name } => {
+                log::warn!(target: "dagrs", "Task stalled [{}name: {}, id: {}]", run, name, id)
+            }
+        }
+    }
+}
+
+impl ExecutionObserver for LogObserver {
+    fn on_event(&self, event: &Event) {
+        self.log(None, event);
+    }
+
+    fn on_run_event(&self, run_id: &str, event: &Event) {
+        self.log(Some(run_id), event);
+    }
+}
+
+/// Emits each [`Event`] as a `tracing` event under the `dagrs` target, with a `run.id` field,
+/// and `task.id` and `task.name` fields for task events. Task failures are emitted at `ERROR`
+/// level, stalled tasks at `WARN` level, everything else at `INFO` level.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default)]
+pub struct TracingObserver;
+
+#[cfg(feature = "tracing")]
+impl TracingObserver {
+    fn emit(&self, run_id: Option<&str>, event: &Event) {
+        use tracing::{event, Level};
+        match event {
+            Event::DagStarted { tasks } => {
+                event!(target: "dagrs", Level::INFO, run.id = run_id, tasks, "dag started")
+            }
+            Event::DagFinished { success } => {
+                event!(target: "dagrs", Level::INFO, run.id = run_id, success, "dag finished")
+            }
+            Event::TaskStarted { id, name } => event!(
+                target: "dagrs",
+                Level::INFO,
+                run.id = run_id,
+                task.id = id,
+                task.name = %name,
+                "task started"
+            ),
+            Event::TaskFinished {
+                id,
+                name,
+                output_size,
+            } => event!(
+                target: "dagrs",
+                Level::INFO,
+                run.id = run_id,
+                task.id = id,
+                task.name = %name,
+                output_size,
+                "task finished"
+            ),
+            Event::TaskFailed { id, name, error } => event!(
+                target: "dagrs",
+                Level::ERROR,
+                run.id = run_id,
+                task.id = id,
+                task.name = %name,
+                error = %error,
+                "task failed"
+            ),
+            Event::TaskSkipped { id, name } => event!(
+                target: "dagrs",
+                Level::INFO,
+                run.id = run_id,
+                task.id = id,
+                task.name = %name,
+                "task skipped"
+            ),
+            Event::TaskStalled { id, name } => event!(
+                target: "dagrs",
+                Level::WARN,
+                run.id = run_id,
+                task.id = id,
+                task.name = %name,
+                "task stalled"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ExecutionObserver for TracingObserver {
+    fn on_event(&self, event: &Event) {
+        self.emit(None, event);
+    }
+
+    fn on_run_event(&self, run_id: &str, event: &Event) {
+        self.emit(Some(run_id), event);
+    }
+}
+
+/// Milliseconds since the unix epoch.
+pub(crate) fn timestamp_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,258 @@
+//! Fair sharing of the tasks an [`Engine`](super::Engine) runs at the same time between its
+//! dags, see [`Engine::set_max_parallel`](super::Engine::set_max_parallel), and preemption
+//! of the dags by urgent ones, see [`Engine::set_urgent`](super::Engine::set_urgent).
+
+use super::CancelHandle;
+use log::info;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::oneshot;
+
+/// The tasks of a dag that are running or waiting for a place.
+#[derive(Debug)]
+struct Share {
+    /// The share of the dag relative to the other dags, see
+    /// [`Engine::set_priority`](super::Engine::set_priority).
+    weight: u32,
+    running: usize,
+    /// When the dag last got a place, to break ties between dags in turn.
+    served: u64,
+    waiting: VecDeque<oneshot::Sender<Slot>>,
+    /// Whether the runs of the dag hold back the tasks of the other dags.
+    urgent: bool,
+    /// Whether the runs of the dag are cancelled when an urgent run starts.
+    preemptible: bool,
+    /// The runs of the dag in progress, by number.
+    runs: Vec<(u64, CancelHandle)>,
+}
+
+impl Default for Share {
+    fn default() -> Self {
+        Self {
+            weight: 1,
+            running: 0,
+            served: 0,
+            waiting: VecDeque::new(),
+            urgent: false,
+            preemptible: false,
+            runs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    running: usize,
+    clock: u64,
+    dags: HashMap<Arc<str>, Share>,
+}
+
+impl State {
+    /// Whether an urgent run is in progress.
+    fn urgent_running(&self) -> bool {
+        self.dags
+            .values()
+            .any(|share| share.urgent && !share.runs.is_empty())
+    }
+}
+
+/// Hands out the places of the tasks that may run at the same time in all dags of an engine.
+/// When tasks of several dags wait, the next place goes to the dag that runs the fewest tasks
+/// for its weight, and between equals to the one that waited longest, so that a large dag
+/// does not starve the others.
+///
+/// While a run of an urgent dag is in progress, only urgent dags get places: the tasks of the
+/// other dags that are running finish, but no other one starts.
+#[derive(Debug)]
+pub(crate) struct FairScheduler {
+    capacity: AtomicUsize,
+    state: Mutex<State>,
+}
+
+impl FairScheduler {
+    /// A scheduler without a limit, until [`FairScheduler::set_capacity`].
+    pub(crate) fn new() -> Self {
+        Self {
+            capacity: AtomicUsize::new(usize::MAX),
+            state: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn set_capacity(self: &Arc<Self>, capacity: usize) {
+        self.capacity.store(capacity.max(1), Ordering::Release);
+        self.grant(&mut self.state.lock().unwrap());
+    }
+
+    pub(crate) fn set_weight(self: &Arc<Self>, dag: &str, weight: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.dags.entry(dag.into()).or_default().weight = weight.max(1);
+        self.grant(&mut state);
+    }
+
+    pub(crate) fn set_urgent(self: &Arc<Self>, dag: &str, urgent: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.dags.entry(dag.into()).or_default().urgent = urgent;
+        self.grant(&mut state);
+    }
+
+    pub(crate) fn set_preemptible(&self, dag: &str, preemptible: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.dags.entry(dag.into()).or_default().preemptible = preemptible;
+    }
+
+    /// Give the free places to the waiting tasks, in turn.
+    fn grant(self: &Arc<Self>, state: &mut State) {
+        let capacity = self.capacity.load(Ordering::Acquire);
+        let urgent_only = state.urgent_running();
+        while state.running < capacity {
+            // The fewest running tasks for the weight, compared without division.
+            let next = state
+                .dags
+                .iter()
+                .filter(|(_, share)| !share.waiting.is_empty())
+                .filter(|(_, share)| share.urgent || !urgent_only)
+                .min_by(|(_, a), (_, b)| {
+                    (a.running as u64 * b.weight as u64)
+                        .cmp(&(b.running as u64 * a.weight as u64))
+                        .then(a.served.cmp(&b.served))
+                })
+                .map(|(dag, _)| dag.clone());
+            let Some(dag) = next else {
+                return;
+            };
+            state.clock += 1;
+            let clock = state.clock;
+            let share = state.dags.get_mut(&dag).unwrap();
+            let waiter = share.waiting.pop_front().unwrap();
+            let slot = Slot {
+                place: Some((self.clone(), dag)),
+            };
+            // A task that stopped waiting does not take the place.
+            if let Err(mut slot) = waiter.send(slot) {
+                slot.place = None;
+                continue;
+            }
+            share.running += 1;
+            share.served = clock;
+            state.running += 1;
+        }
+    }
+
+    fn release(self: &Arc<Self>, dag: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.running -= 1;
+        if let Some(share) = state.dags.get_mut(dag) {
+            share.running -= 1;
+        }
+        self.grant(&mut state);
+    }
+}
+
+/// The share of a dag in a [`FairScheduler`].
+#[derive(Debug, Clone)]
+pub(crate) struct FairShare {
+    scheduler: Arc<FairScheduler>,
+    dag: Arc<str>,
+}
+
+impl FairShare {
+    pub(crate) fn new(scheduler: Arc<FairScheduler>, dag: &str) -> Self {
+        Self {
+            scheduler,
+            dag: dag.into(),
+        }
+    }
+
+    /// Whether the engine limits the tasks that run at the same time. Holding tasks back for
+    /// urgent dags is not a limit: the places are handed out at once outside of urgent runs.
+    pub(crate) fn limited(&self) -> bool {
+        self.scheduler.capacity.load(Ordering::Acquire) != usize::MAX
+    }
+
+    /// Record the start of a run of the dag, that can be cancelled with the given handle,
+    /// until the returned guard is dropped. An urgent run cancels the runs of the preemptible
+    /// dags in progress, and a run of a preemptible dag that starts during an urgent run is
+    /// cancelled at once.
+    pub(crate) fn start_run(&self, cancel: CancelHandle) -> RunGuard {
+        let mut state = self.scheduler.state.lock().unwrap();
+        state.clock += 1;
+        let number = state.clock;
+        let urgent_running = state.urgent_running();
+        let share = state.dags.entry(self.dag.clone()).or_default();
+        if share.preemptible && !share.urgent && urgent_running {
+            info!("Cancel the run of dag '{}' during an urgent run", self.dag);
+            cancel.cancel();
+        }
+        share.runs.push((number, cancel));
+        if share.urgent {
+            for (dag, share) in state
+                .dags
+                .iter()
+                .filter(|(_, s)| s.preemptible && !s.urgent)
+            {
+                for (_, cancel) in &share.runs {
+                    info!(
+                        "Cancel the run of dag '{}' for urgent dag '{}'",
+                        dag, self.dag
+                    );
+                    cancel.cancel();
+                }
+            }
+        }
+        RunGuard {
+            share: self.clone(),
+            number,
+        }
+    }
+
+    /// Wait for a place for a task of the dag.
+    pub(crate) async fn acquire(&self) -> Option<Slot> {
+        let receiver = {
+            let mut state = self.scheduler.state.lock().unwrap();
+            let (sender, receiver) = oneshot::channel();
+            let share = state.dags.entry(self.dag.clone()).or_default();
+            share.waiting.push_back(sender);
+            self.scheduler.grant(&mut state);
+            receiver
+        };
+        receiver.await.ok()
+    }
+}
+
+/// The place of a running task, given back when dropped.
+#[derive(Debug)]
+pub(crate) struct Slot {
+    place: Option<(Arc<FairScheduler>, Arc<str>)>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some((scheduler, dag)) = self.place.take() {
+            scheduler.release(&dag);
+        }
+    }
+}
+
+/// A run of a dag in progress, see [`FairShare::start_run`].
+#[derive(Debug)]
+pub(crate) struct RunGuard {
+    share: FairShare,
+    number: u64,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        let scheduler = &self.share.scheduler;
+        let mut state = scheduler.state.lock().unwrap();
+        if let Some(share) = state.dags.get_mut(&self.share.dag) {
+            share.runs.retain(|(number, _)| *number != self.number);
+        }
+        // The end of an urgent run lets the other dags go on.
+        scheduler.grant(&mut state);
+    }
+}
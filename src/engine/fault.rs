@@ -0,0 +1,193 @@
+//! Fault injection
+//!
+//! A [`FaultInjector`] makes selected tasks of a dag misbehave on purpose, so that the
+//! resilience settings of a dag can be tested: that its retries, timeouts, fallbacks and
+//! `keep_going` actually handle the failures they are meant for. It can
+//! - delay the action of a task,
+//! - make the action of a task fail,
+//! - drop the output of a task on its way to a successor, which then runs without it.
+//!
+//! Each fault happens with a probability. Whether it happens is drawn from the seed of the
+//! injector, the fault and the task (and for actions, the attempt), so that the same seed
+//! injects the same faults in every run, however the tasks are scheduled. Try other seeds to
+//! inject other faults.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{Dag, DefaultTask, FaultInjector, Output};
+//!
+//! let mut fetch = DefaultTask::with_closure("fetch", |_input, _env| Output::new(1));
+//! fetch.set_retries(3);
+//! let dag = Dag::with_tasks(vec![fetch]).with_fault_injector(
+//!     FaultInjector::new(42).fail("fetch", 0.5),
+//! );
+//! ```
+
+use crate::{
+    utils::{splitmix64, unit_f64},
+    Action, Output,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// A fault of a [`FaultRule`].
+#[derive(Debug, Clone, Copy)]
+enum Fault {
+    Delay(Duration),
+    Fail,
+    Drop,
+}
+
+/// A fault injected into a task, or into an edge from the task `from`.
+#[derive(Debug, Clone)]
+struct FaultRule {
+    /// The name of the task, `*` for all tasks.
+    task: String,
+    /// For dropped outputs, the name of the predecessor, `*` for all predecessors.
+    from: Option<String>,
+    fault: Fault,
+    probability: f64,
+}
+
+/// Injects faults into the tasks of a dag, see the [module documentation](self) and
+/// [`Dag::with_fault_injector`](super::Dag::with_fault_injector).
+///
+/// Tasks are selected by name; `*` selects all tasks.
+///
+/// Clones share the count of [`injected`](FaultInjector::injected) faults, so a test keeps a
+/// clone to check how many faults a run got.
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    seed: u64,
+    rules: Vec<FaultRule>,
+    injected: Arc<AtomicUsize>,
+}
+
+impl FaultInjector {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rules: Vec::new(),
+            injected: Arc::default(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// How many faults were injected so far.
+    pub fn injected(&self) -> usize {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    /// Delay each attempt of the action of the task by `delay`, with the given probability.
+    /// The delay counts towards the timeout of the task.
+    pub fn delay(self, task: &str, delay: Duration, probability: f64) -> Self {
+        self.rule(task, None, Fault::Delay(delay), probability)
+    }
+
+    /// Make each attempt of the action of the task fail, with the given probability, without
+    /// running it.
+    pub fn fail(self, task: &str, probability: f64) -> Self {
+        self.rule(task, None, Fault::Fail, probability)
+    }
+
+    /// Drop the output of the task `from` on its way to its successor `to`, with the given
+    /// probability. `to` runs without this input.
+    pub fn drop(self, from: &str, to: &str, probability: f64) -> Self {
+        self.rule(to, Some(from), Fault::Drop, probability)
+    }
+
+    fn rule(mut self, task: &str, from: Option<&str>, fault: Fault, probability: f64) -> Self {
+        self.rules.push(FaultRule {
+            task: task.to_owned(),
+            from: from.map(str::to_owned),
+            fault,
+            probability,
+        });
+        self
+    }
+
+    /// Wrap an attempt of the action of a task in the delays and failures injected into it.
+    pub(crate) fn wrap(&self, action: Action, task: &str, attempt: u32) -> Action {
+        let mut delay = Duration::ZERO;
+        let mut fail = false;
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.from.is_some() || !selects(&rule.task, task) {
+                continue;
+            }
+            if self.draw(index, &[task], attempt) >= rule.probability {
+                continue;
+            }
+            match rule.fault {
+                Fault::Delay(duration) => delay += duration,
+                Fault::Fail => fail = true,
+                Fault::Drop => {}
+            }
+        }
+        if delay.is_zero() && !fail {
+            return action;
+        }
+        let task = task.to_owned();
+        let injected = self.injected.clone();
+        Action::Closure(Arc::new(move |input, env| {
+            injected.fetch_add(
+                usize::from(!delay.is_zero()) + usize::from(fail),
+                Ordering::Relaxed,
+            );
+            if !delay.is_zero() {
+                log::warn!("Injected a delay of {:?} into task '{}'", delay, task);
+                std::thread::sleep(delay);
+            }
+            if fail {
+                log::warn!("Injected a failure into task '{}'", task);
+                return Output::error(format!("injected fault in task '{}'", task));
+            }
+            action.run(input, env)
+        }))
+    }
+
+    /// Whether the output of the task `from` is dropped on its way to the task `to`.
+    pub(crate) fn drops(&self, from: &str, to: &str) -> bool {
+        let dropped = self.rules.iter().enumerate().any(|(index, rule)| {
+            matches!(rule.fault, Fault::Drop)
+                && selects(&rule.task, to)
+                && rule
+                    .from
+                    .as_deref()
+                    .is_some_and(|rule_from| selects(rule_from, from))
+                && self.draw(index, &[from, to], 0) < rule.probability
+        });
+        if dropped {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+        }
+        dropped
+    }
+
+    /// A number in `[0, 1)` drawn from the seed, the rule, the tasks and the attempt.
+    fn draw(&self, rule: usize, tasks: &[&str], attempt: u32) -> f64 {
+        // FNV-1a of the key, mixed with the seed.
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        let key = tasks
+            .iter()
+            .flat_map(|task| task.bytes().chain([0]))
+            .chain((rule as u64).to_le_bytes())
+            .chain(attempt.to_le_bytes());
+        for byte in key {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3);
+        }
+        let mut state = self.seed ^ hash;
+        unit_f64(splitmix64(&mut state))
+    }
+}
+
+fn selects(pattern: &str, name: &str) -> bool {
+    pattern == "*" || pattern == name
+}
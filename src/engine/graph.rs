@@ -23,64 +23,88 @@ task1->task2->task3->task4->task5->task6->task7->task8->task9
 
 */
 
-use bimap::BiMap;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
 /// Graph Struct
+///
+/// Nodes are identified by their index, from 0 to the number of nodes. The adjacency of the
+/// graph is stored in compressed sparse row form: the successors of all nodes in one vector,
+/// those of each node being contiguous, which takes two allocations however large the graph is.
+#[derive(Debug, Clone, Default)]
 pub(crate) struct Graph {
-    size: usize,
-    /// Record node id and it's index <id,index>
-    nodes: BiMap<usize, usize>,
-    /// Adjacency list of graph (stored as a vector of vector of indices)
-    adj: Vec<Vec<usize>>,
+    /// The id of each node, by index.
+    ids: Vec<usize>,
+    /// The index of each node, by id.
+    indices: HashMap<usize, usize>,
+    /// The successors of node `v` are `targets[offsets[v]..offsets[v + 1]]`.
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
     /// Node's in_degree, used for topological sort
     in_degree: Vec<usize>,
 }
 
 impl Graph {
-    /// Allocate an empty graph
-    pub(crate) fn new() -> Graph {
+    /// A graph of the nodes with the given ids, without edges. Each node's index is the
+    /// position of its id.
+    pub(crate) fn new(ids: Vec<usize>) -> Graph {
+        let indices = ids
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| (id, index))
+            .collect();
+        let size = ids.len();
         Graph {
-            size: 0,
-            nodes: BiMap::new(),
-            adj: Vec::new(),
-            in_degree: Vec::new(),
+            ids,
+            indices,
+            offsets: vec![0; size + 1],
+            targets: Vec::new(),
+            in_degree: vec![0; size],
         }
     }
 
-    /// Set graph size, size is the number of tasks
-    pub(crate) fn set_graph_size(&mut self, size: usize) {
-        self.size = size;
-        self.adj.resize(size, Vec::new());
-        self.in_degree.resize(size, 0);
-        self.nodes.reserve(size);
+    /// Set the edges of the graph, as pairs of node indices.
+    /// An edge from node 0 to node 1 means task 0 shall be executed before task 1.
+    pub(crate) fn set_edges(&mut self, edges: &[(usize, usize)]) {
+        let size = self.ids.len();
+        // Count the successors of each node, then place each edge at its node's next slot.
+        let mut offsets = vec![0; size + 1];
+        let mut in_degree = vec![0; size];
+        for &(v, w) in edges {
+            offsets[v + 1] += 1;
+            in_degree[w] += 1;
+        }
+        for v in 0..size {
+            offsets[v + 1] += offsets[v];
+        }
+        let mut next = offsets.clone();
+        let mut targets = vec![0; edges.len()];
+        for &(v, w) in edges {
+            targets[next[v]] = w;
+            next[v] += 1;
+        }
+        self.offsets = offsets;
+        self.targets = targets;
+        self.in_degree = in_degree;
     }
 
-    /// Add a node into the graph
-    /// This operation will create a mapping between ID and its index.
-    /// **Note:** `id` won't get repeated in dagrs,
-    /// since yaml parser will overwrite its info if a task's ID repeats.
-    pub(crate) fn add_node(&mut self, id: usize) {
-        let index = self.nodes.len();
-        self.nodes.insert(id, index);
+    /// The number of nodes.
+    pub(crate) fn size(&self) -> usize {
+        self.ids.len()
     }
 
-    /// Add an edge into the graph.
-    /// Above operation adds a arrow from node 0 to node 1,
-    /// which means task 0 shall be executed before task 1.
-    pub(crate) fn add_edge(&mut self, v: usize, w: usize) {
-        self.adj[v].push(w);
-        self.in_degree[w] += 1;
+    /// The direct successors of a node, by index.
+    fn successors(&self, index: usize) -> &[usize] {
+        &self.targets[self.offsets[index]..self.offsets[index + 1]]
     }
 
     /// Find a task's index by its ID
     pub(crate) fn find_index_by_id(&self, id: &usize) -> Option<usize> {
-        self.nodes.get_by_left(id).copied()
+        self.indices.get(id).copied()
     }
 
     /// Find a task's ID by its index
     pub(crate) fn find_id_by_index(&self, index: usize) -> Option<usize> {
-        self.nodes.get_by_right(&index).copied()
+        self.ids.get(index).copied()
     }
 
     /// Do topo sort in graph, returns a possible execution sequence if DAG.
@@ -111,12 +135,12 @@ impl Graph {
 
         let mut in_degree = self.in_degree.clone();
 
-        let mut sequence = Vec::with_capacity(self.size);
+        let mut sequence = Vec::with_capacity(self.size());
 
         while let Some(v) = queue.pop() {
             sequence.push(v);
 
-            for &index in self.adj[v].iter() {
+            for &index in self.successors(v) {
                 in_degree[index] -= 1;
                 if in_degree[index] == 0 {
                     queue.push(index)
@@ -124,17 +148,32 @@ impl Graph {
             }
         }
 
-        if sequence.len() < self.size {
+        if sequence.len() < self.size() {
             None
         } else {
             Some(sequence)
         }
     }
 
+    /// Compute the level of every node, indexed by node index. Nodes without predecessors
+    /// are at level 0, and every other node is one level below its deepest predecessor, so
+    /// nodes at the same level don't depend on each other.
+    ///
+    /// **Note**: this function can only be called on an acyclic graph.
+    pub(crate) fn levels(&self) -> Vec<usize> {
+        let mut levels = vec![0; self.size()];
+        for v in self.topo_sort().unwrap_or_default() {
+            for &w in self.successors(v) {
+                levels[w] = levels[w].max(levels[v] + 1);
+            }
+        }
+        levels
+    }
+
     /// Get the out degree of a node.
     pub(crate) fn get_node_out_degree(&self, id: &usize) -> usize {
-        match self.nodes.get_by_left(id) {
-            Some(index) => self.adj[*index].len(),
+        match self.find_index_by_id(id) {
+            Some(index) => self.successors(index).len(),
             None => 0,
         }
     }
@@ -142,19 +181,19 @@ impl Graph {
     /// Get all the successors of a node (direct or indirect).
     /// This function will return a vector of indices of successors (including itself).
     pub(crate) fn get_node_successors(&self, id: &usize) -> Vec<usize> {
-        match self.nodes.get_by_left(id) {
+        match self.find_index_by_id(id) {
             Some(index) => {
                 // initialize a vector to store successors with max possible size
-                let mut successors = Vec::with_capacity(self.adj[*index].len());
+                let mut successors = Vec::with_capacity(self.successors(index).len());
 
                 // create a visited array to avoid visiting a node more than once
-                let mut visited = vec![false; self.size];
+                let mut visited = vec![false; self.size()];
 
                 // do BFS traversal starting from current node
 
                 // mark the current node as visited and enqueue it
-                visited[*index] = true;
-                successors.push(*index);
+                visited[index] = true;
+                successors.push(index);
 
                 // the index of the queue
                 let mut i_queue = 0;
@@ -163,7 +202,7 @@ impl Graph {
                 while i_queue < successors.len() {
                     let v = successors[i_queue];
 
-                    for &index in self.adj[v].iter() {
+                    for &index in self.successors(v) {
                         // if not visited, mark it as visited and collect it
                         if !visited[index] {
                             visited[index] = true;
@@ -179,14 +218,3 @@ impl Graph {
         }
     }
 }
-
-impl Default for Graph {
-    fn default() -> Self {
-        Graph {
-            size: 0,
-            nodes: BiMap::new(),
-            adj: Vec::new(),
-            in_degree: Vec::new(),
-        }
-    }
-}
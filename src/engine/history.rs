@@ -0,0 +1,190 @@
+//! Run history
+//!
+//! A [`RunHistory`] keeps a record of every run of the dags of an [`Engine`](super::Engine)
+//! (see [`Engine::set_history`](super::Engine::set_history)) or of the `dagrs` command line
+//! tool (see its `--history` option): when it started and finished, whether it succeeded and
+//! the outcome of each task. Records are appended as JSON lines to a file, so that past runs
+//! can be listed and inspected, with [`RunHistory::runs`] or `dagrs history`, long after the
+//! process that ran them exited.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{Dag, DefaultTask, Engine, Output, RunHistory};
+//!
+//! let path = std::env::temp_dir().join("dagrs_history_doc.jsonl");
+//! # std::fs::remove_file(&path).ok();
+//! let mut engine = Engine::default();
+//! engine.set_history(RunHistory::new(&path));
+//! let task = DefaultTask::with_closure("a", |_input, _env| Output::new(1));
+//! engine.append_dag("example", Dag::with_tasks(vec![task]));
+//! assert!(engine.run_dag("example"));
+//!
+//! let runs = engine.history().unwrap().runs().unwrap();
+//! assert_eq!(runs[0].dag, "example");
+//! assert!(runs[0].success);
+//! ```
+
+use super::{RunReport, TaskStatus};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The outcome of a task in a [`RunRecord`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub id: usize,
+    pub name: String,
+    pub status: TaskStatus,
+    /// How long the task's action ran, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// The error of a failed task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A run of a dag in a [`RunHistory`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// The number of the run in the history, starting at 1.
+    pub number: u64,
+    /// The name of the dag.
+    pub dag: String,
+    /// The id of the run, see [`Dag::run_id`](super::Dag::run_id).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// When the run started, in milliseconds since the Unix epoch.
+    pub started_at: u64,
+    /// When the run finished, in milliseconds since the Unix epoch.
+    pub finished_at: u64,
+    pub success: bool,
+    /// The tasks in execution order.
+    pub tasks: Vec<TaskOutcome>,
+}
+
+impl RunRecord {
+    /// How long the run took.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.finished_at.saturating_sub(self.started_at))
+    }
+}
+
+/// The records of past runs, stored as JSON lines in a file. See the
+/// [module documentation](self).
+///
+/// Records are appended to the file as runs finish, under a lock of the file, so that the
+/// threads and processes recording runs into the same file at the same time number them in
+/// turn. Lines that are not records, such as a line cut short by a crash, are skipped.
+#[derive(Debug, Clone)]
+pub struct RunHistory {
+    path: PathBuf,
+}
+
+impl RunHistory {
+    /// A history stored in the given file, which is created with the first record.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a run of the dag with the given name, and return its record.
+    pub fn record(
+        &self,
+        dag: &str,
+        started: SystemTime,
+        finished: SystemTime,
+        report: &RunReport,
+    ) -> io::Result<RunRecord> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        // Released when the file is closed.
+        file.lock()?;
+        let number = read_runs(&file, &self.path)?
+            .last()
+            .map_or(1, |last| last.number + 1);
+        let record = RunRecord {
+            number,
+            dag: dag.to_owned(),
+            run_id: report.run_id.clone(),
+            started_at: unix_millis(started),
+            finished_at: unix_millis(finished),
+            success: report.success,
+            tasks: report
+                .tasks
+                .iter()
+                .map(|task| TaskOutcome {
+                    id: task.id,
+                    name: task.name.clone(),
+                    status: task.status,
+                    duration_ms: task.duration.map(|duration| duration.as_millis() as u64),
+                    error: task.error.clone(),
+                })
+                .collect(),
+        };
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        Ok(record)
+    }
+
+    /// All runs, oldest first. An empty history if the file does not exist yet.
+    pub fn runs(&self) -> io::Result<Vec<RunRecord>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        read_runs(&file, &self.path)
+    }
+
+    /// The runs of the dag with the given name, oldest first.
+    pub fn runs_of(&self, dag: &str) -> io::Result<Vec<RunRecord>> {
+        let mut runs = self.runs()?;
+        runs.retain(|run| run.dag == dag);
+        Ok(runs)
+    }
+
+    /// The run with the given number.
+    pub fn get(&self, number: u64) -> io::Result<Option<RunRecord>> {
+        Ok(self.runs()?.into_iter().find(|run| run.number == number))
+    }
+}
+
+/// The runs recorded in the history file at `path`, skipping the lines that are not records.
+fn read_runs(file: &File, path: &Path) -> io::Result<Vec<RunRecord>> {
+    let mut runs = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(run) => runs.push(run),
+            Err(err) => warn!(
+                "Skipping line {} of history '{}': {}",
+                number + 1,
+                path.display(),
+                err
+            ),
+        }
+    }
+    Ok(runs)
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
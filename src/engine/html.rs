@@ -0,0 +1,171 @@
+//! HTML reports
+//!
+//! [`Dag::html_report`](super::Dag::html_report) renders the last run of a dag as a standalone
+//! HTML page, to share the result of a pipeline: a picture of the dag with each task colored
+//! by its status, then the duration, error, output and captured log lines of every task. The
+//! page has no external resources.
+
+use super::{RunReport, TaskStatus};
+use std::{collections::HashMap, fmt::Write};
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 40;
+const GAP_X: usize = 60;
+const GAP_Y: usize = 20;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse}\
+td,th{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+pre{margin:0;white-space:pre-wrap}\
+.succeeded{background:#c8f7c5}.failed{background:#f7c5c5}.skipped{background:#f7efc5}";
+
+/// The parts of a dag and its last run shown in an HTML report.
+pub(crate) struct HtmlReport<'a> {
+    pub(crate) report: &'a RunReport,
+    /// The `(id, name)` of the tasks by level, see [`Dag::blocks`](super::Dag::blocks).
+    pub(crate) blocks: &'a [Vec<(usize, String)>],
+    pub(crate) edges: &'a [(usize, usize)],
+    /// The captured log lines of the tasks that have some.
+    pub(crate) logs: &'a HashMap<usize, Vec<String>>,
+}
+
+impl HtmlReport<'_> {
+    pub(crate) fn render(&self) -> String {
+        let mut html = String::new();
+        let title = if self.report.success {
+            "Run succeeded"
+        } else {
+            "Run failed"
+        };
+        write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>dagrs: {}</title>\n\
+             <style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, STYLE, title
+        )
+        .unwrap();
+        if let Some(run_id) = &self.report.run_id {
+            writeln!(html, "<p>Run id: <code>{}</code></p>", escape(run_id)).unwrap();
+        }
+        self.render_graph(&mut html);
+        self.render_tasks(&mut html);
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// An SVG picture of the dag, with a column per level.
+    fn render_graph(&self, html: &mut String) {
+        let statuses: HashMap<usize, TaskStatus> = self
+            .report
+            .tasks
+            .iter()
+            .map(|task| (task.id, task.status))
+            .collect();
+        let mut positions = HashMap::new();
+        for (level, block) in self.blocks.iter().enumerate() {
+            for (row, (id, _)) in block.iter().enumerate() {
+                positions.insert(*id, (level * (WIDTH + GAP_X), row * (HEIGHT + GAP_Y)));
+            }
+        }
+        let rows = self.blocks.iter().map(Vec::len).max().unwrap_or(0);
+        writeln!(
+            html,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+            self.blocks.len() * (WIDTH + GAP_X),
+            rows * (HEIGHT + GAP_Y)
+        )
+        .unwrap();
+        for (from, to) in self.edges {
+            if let (Some((x1, y1)), Some((x2, y2))) = (positions.get(from), positions.get(to)) {
+                writeln!(
+                    html,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#888\"/>",
+                    x1 + WIDTH,
+                    y1 + HEIGHT / 2,
+                    x2,
+                    y2 + HEIGHT / 2
+                )
+                .unwrap();
+            }
+        }
+        for (id, name) in self.blocks.iter().flatten() {
+            let (x, y) = positions[id];
+            let color = match statuses.get(id) {
+                Some(TaskStatus::Succeeded) => "#c8f7c5",
+                Some(TaskStatus::Failed) => "#f7c5c5",
+                Some(TaskStatus::Skipped) => "#f7efc5",
+                Some(TaskStatus::UpToDate) => "#c5e3f7",
+                None => "#eeeeee",
+            };
+            writeln!(
+                html,
+                "<g><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\" fill=\"{}\" \
+                 stroke=\"#555\"/><text x=\"{}\" y=\"{}\" text-anchor=\"middle\" \
+                 dominant-baseline=\"middle\">{}</text></g>",
+                x,
+                y,
+                WIDTH,
+                HEIGHT,
+                color,
+                x + WIDTH / 2,
+                y + HEIGHT / 2,
+                escape(name)
+            )
+            .unwrap();
+        }
+        html.push_str("</svg>\n");
+    }
+
+    /// A table of the tasks in execution order.
+    fn render_tasks(&self, html: &mut String) {
+        html.push_str(
+            "<table>\n<tr><th>Task</th><th>Status</th><th>Duration</th><th>Error</th>\
+             <th>Output</th><th>Logs</th></tr>\n",
+        );
+        for task in &self.report.tasks {
+            let status = match task.status {
+                TaskStatus::Succeeded => "succeeded",
+                TaskStatus::Failed => "failed",
+                TaskStatus::Skipped => "skipped",
+                TaskStatus::UpToDate => "up to date",
+            };
+            let duration = task
+                .duration
+                .map(|duration| format!("{:.2?}", duration))
+                .unwrap_or_default();
+            let logs = self
+                .logs
+                .get(&task.id)
+                .filter(|logs| !logs.is_empty())
+                .map(|logs| {
+                    format!(
+                        "<details><summary>{} lines</summary><pre>{}</pre></details>",
+                        logs.len(),
+                        escape(&logs.join("\n"))
+                    )
+                })
+                .unwrap_or_default();
+            writeln!(
+                html,
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td><pre>{}</pre></td>\
+                 <td><pre>{}</pre></td><td>{}</td></tr>",
+                status,
+                escape(&task.name),
+                status,
+                duration,
+                escape(task.error.as_deref().unwrap_or_default()),
+                escape(task.output.as_deref().unwrap_or_default()),
+                logs
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n");
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
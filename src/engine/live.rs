@@ -0,0 +1,252 @@
+//! Live graph
+//!
+//! A [`LiveGraph`] observes the run of a dag and keeps a file up to date with the graph of the
+//! dag, each task colored by its state: pending, running, succeeded, failed or skipped. An
+//! external viewer watching the file shows the progress of the run. The file is written as
+//! DOT, Mermaid or JSON, see [`GraphFormat`].
+//!
+//! The file is replaced atomically, so a viewer never reads a partial graph.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{Dag, DefaultTask, GraphFormat, LiveGraph, Output};
+//!
+//! let a = DefaultTask::with_closure("a", |_input, _env| Output::new(1));
+//! let mut b = DefaultTask::with_closure("b", |_input, _env| Output::new(2));
+//! b.set_predecessors(&[&a]);
+//! let mut dag = Dag::with_tasks(vec![a, b]);
+//! let path = std::env::temp_dir().join("dagrs-live-graph-example.dot");
+//! dag.add_observer(LiveGraph::new(&dag.export_structure(), &path, GraphFormat::Dot));
+//! assert!(dag.start().unwrap());
+//! assert!(std::fs::read_to_string(&path).unwrap().contains("palegreen"));
+//! ```
+
+use super::{DagStructure, Event, ExecutionObserver};
+use serde::Serialize;
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The format of the file of a [`LiveGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// A Graphviz `digraph`, with the tasks filled with the color of their state.
+    Dot,
+    /// A Mermaid flowchart, with a class per state.
+    Mermaid,
+    /// The tasks with their states, and the edges, as JSON:
+    /// `{"tasks":[{"id":1,"name":"a","state":"running"}],"edges":[[1,2]]}`.
+    Json,
+}
+
+impl GraphFormat {
+    /// The format of a file by its extension: `.dot` and `.gv` are DOT, `.mmd` and `.mermaid`
+    /// are Mermaid, anything else is JSON.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("dot" | "gv") => Self::Dot,
+            Some("mmd" | "mermaid") => Self::Mermaid,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// The state of a task in a [`LiveGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum State {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl State {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Self::Pending => "lightgray",
+            Self::Running => "lightskyblue",
+            Self::Succeeded => "palegreen",
+            Self::Failed => "salmon",
+            Self::Skipped => "khaki",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Node {
+    id: usize,
+    name: String,
+    state: State,
+}
+
+struct Snapshot {
+    /// The tasks, sorted by id.
+    tasks: Vec<Node>,
+    last_write: Option<Instant>,
+}
+
+/// Writes the graph of a dag with the state of each task to a file as the dag runs, see the
+/// [module documentation](self).
+pub struct LiveGraph {
+    path: PathBuf,
+    format: GraphFormat,
+    min_interval: Duration,
+    edges: Vec<(usize, usize)>,
+    snapshot: Mutex<Snapshot>,
+}
+
+impl LiveGraph {
+    /// Write the graph of the dag described by `structure` to `path` on each event of its
+    /// runs. `structure` is usually [`Dag::export_structure`](super::Dag::export_structure).
+    pub fn new(structure: &DagStructure, path: impl Into<PathBuf>, format: GraphFormat) -> Self {
+        let tasks = structure
+            .tasks
+            .iter()
+            .map(|task| Node {
+                id: task.id,
+                name: task.name.clone(),
+                state: State::Pending,
+            })
+            .collect();
+        Self {
+            path: path.into(),
+            format,
+            min_interval: Duration::ZERO,
+            edges: structure.edges.clone(),
+            snapshot: Mutex::new(Snapshot {
+                tasks,
+                last_write: None,
+            }),
+        }
+    }
+
+    /// Write the file at most once per `interval` instead of on each event, for dags with
+    /// many short tasks. The file is always written when the run starts and when it finishes.
+    pub fn min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Render the graph with the current states.
+    fn render(&self, tasks: &[Node]) -> String {
+        let mut out = String::new();
+        match self.format {
+            GraphFormat::Dot => {
+                out.push_str("digraph dag {\n    node [style=filled];\n");
+                for task in tasks {
+                    writeln!(
+                        out,
+                        "    {} [label={:?}, fillcolor={}, tooltip={}];",
+                        task.id,
+                        task.name,
+                        task.state.color(),
+                        task.state.name()
+                    )
+                    .unwrap();
+                }
+                for (from, to) in &self.edges {
+                    writeln!(out, "    {} -> {};", from, to).unwrap();
+                }
+                out.push_str("}\n");
+            }
+            GraphFormat::Mermaid => {
+                out.push_str("flowchart TD\n");
+                for task in tasks {
+                    let name = task.name.replace('"', "#quot;");
+                    writeln!(
+                        out,
+                        "    t{}[\"{}\"]:::{}",
+                        task.id,
+                        name,
+                        task.state.name()
+                    )
+                    .unwrap();
+                }
+                for (from, to) in &self.edges {
+                    writeln!(out, "    t{} --> t{}", from, to).unwrap();
+                }
+                for state in [
+                    State::Pending,
+                    State::Running,
+                    State::Succeeded,
+                    State::Failed,
+                    State::Skipped,
+                ] {
+                    writeln!(out, "    classDef {} fill:{}", state.name(), state.color()).unwrap();
+                }
+            }
+            GraphFormat::Json => {
+                let graph = serde_json::json!({ "tasks": tasks, "edges": self.edges });
+                out = serde_json::to_string(&graph).unwrap();
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Write the file through a temporary file in the same directory, renamed over it.
+    fn write(&self, content: &str) -> io::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+impl ExecutionObserver for LiveGraph {
+    fn on_event(&self, event: &Event) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        let update = match event {
+            Event::DagStarted { .. } => {
+                snapshot
+                    .tasks
+                    .iter_mut()
+                    .for_each(|task| task.state = State::Pending);
+                None
+            }
+            Event::DagFinished { .. } => None,
+            Event::TaskStarted { id, .. } => Some((*id, State::Running)),
+            Event::TaskFinished { id, .. } => Some((*id, State::Succeeded)),
+            Event::TaskFailed { id, .. } => Some((*id, State::Failed)),
+            Event::TaskSkipped { id, .. } => Some((*id, State::Skipped)),
+            Event::TaskStalled { .. } => return,
+        };
+        if let Some((id, state)) = update {
+            if let Ok(index) = snapshot.tasks.binary_search_by_key(&id, |task| task.id) {
+                snapshot.tasks[index].state = state;
+            }
+        }
+        let boundary = matches!(event, Event::DagStarted { .. } | Event::DagFinished { .. });
+        let due = snapshot
+            .last_write
+            .is_none_or(|last| last.elapsed() >= self.min_interval);
+        if !boundary && !due {
+            return;
+        }
+        snapshot.last_write = Some(Instant::now());
+        if let Err(err) = self.write(&self.render(&snapshot.tasks)) {
+            log::error!(
+                "Failed to write the live graph to '{}': {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
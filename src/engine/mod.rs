@@ -9,15 +9,71 @@
 //! can specify which task to execute by giving the name of the Dag, or follow the order in which
 //! the Dags are added to the Engine , executing each Dag in turn.
 
-pub use dag::Dag;
+#[cfg(feature = "cache-http")]
+pub use cache::HttpCache;
+#[cfg(feature = "s3")]
+pub use cache::S3Cache;
+pub use cache::{CacheBackend, DiskCache};
+#[cfg(feature = "notify-chat")]
+pub use chat::{ChatObserver, ChatService};
+pub use config::{DagConfig, Execution, OverflowPolicy, TaskConfig, TimeOfDay};
+pub use console::{GroupStyle, GroupedOutput};
+pub use dag::{CancelHandle, Dag};
+pub use diff::StructureDiff;
+pub(crate) use event::Observers;
+#[cfg(feature = "tracing")]
+pub use event::TracingObserver;
+pub use event::{Event, ExecutionObserver, JsonEventLog, LogObserver};
+pub use fault::FaultInjector;
+pub use history::{RunHistory, RunRecord, TaskOutcome};
+pub use live::{GraphFormat, LiveGraph};
 use log::error;
+#[cfg(feature = "notify")]
+pub(crate) use notify::TaskFailureNotifier;
+#[cfg(feature = "notify")]
+pub use notify::{EmailConfig, Notifier, NotifyChannel, NotifyOn};
+#[cfg(feature = "bench")]
+pub use overhead::{OverheadReport, TaskOverhead};
+pub use report::{RunReport, TaskReport, TaskStatus};
+#[cfg(feature = "scheduler")]
+use scheduler::ScheduledDag;
+#[cfg(feature = "scheduler")]
+pub use scheduler::{DagFactory, OverlapPolicy};
+pub use simulation::Simulation;
+pub use snapshot::ExecutionSnapshot;
+pub use structure::{DagStructure, TaskStructure};
 use thiserror::Error;
 
+mod cache;
+#[cfg(feature = "notify-chat")]
+mod chat;
+mod config;
+mod console;
 mod dag;
+mod diff;
+mod event;
+mod fair;
+mod fault;
 mod graph;
+mod history;
+#[cfg(feature = "report-html")]
+mod html;
+mod live;
+#[cfg(feature = "notify")]
+mod notify;
+#[cfg(feature = "bench")]
+mod overhead;
+mod report;
+#[cfg(feature = "scheduler")]
+mod scheduler;
+mod simulation;
+mod snapshot;
+mod spill;
+mod structure;
 
 use crate::ParseError;
-use std::{collections::HashMap, sync::Arc};
+use fair::{FairScheduler, FairShare};
+use std::{collections::HashMap, sync::Arc, thread, time::SystemTime};
 use tokio::runtime::Runtime;
 
 /// The Engine. Manage multiple Dags.
@@ -29,6 +85,16 @@ pub struct Engine {
     /// A tokio runtime.
     /// In order to save computer resources, multiple Dags share one runtime.
     runtime: Runtime,
+    /// Dags run on schedule by the daemon.
+    #[cfg(feature = "scheduler")]
+    schedules: Vec<ScheduledDag>,
+    /// Notifiers registered on every dag of the engine.
+    #[cfg(feature = "notify")]
+    notifiers: Vec<Notifier>,
+    /// Where the runs of the dags are recorded.
+    history: Option<RunHistory>,
+    /// Shares the places of the tasks between the dags.
+    fair: Arc<FairScheduler>,
 }
 
 /// Errors that may be raised by building and running dag jobs.
@@ -47,6 +113,64 @@ pub enum DagError {
     /// There are no tasks in the job.
     #[error("There are no tasks in the job.")]
     EmptyJob,
+    /// A task asked for by id or name is not in the dag.
+    #[error("Task[{0}] does not exist.")]
+    UnknownTask(String),
+    /// A task failed during execution. `source` carries the concrete cause, usually an
+    /// [`ExecutionError`].
+    #[error("Task[{name}] (id: {id}) execution failed: {source}")]
+    ExecutionFailed {
+        id: usize,
+        name: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+/// Errors of adding an edge to a dag, see [`Dag::add_edge`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GraphError {
+    /// The task the edge starts from is not in the dag.
+    #[error("Task {0} the edge starts from does not exist.")]
+    UnknownFrom(usize),
+    /// The task the edge leads to is not in the dag.
+    #[error("Task {0} the edge leads to does not exist.")]
+    UnknownTo(usize),
+    /// The edge already exists.
+    #[error("Edge from task {0} to task {1} already exists.")]
+    DuplicateEdge(usize, usize),
+}
+
+/// The cause of a task execution failure.
+#[derive(Debug, Error)]
+pub enum ExecutionError {
+    /// The task returned [`Output::Err`](crate::Output::Err).
+    #[error("{0}")]
+    Output(String),
+    /// The task returned [`Output::ErrWithExitCode`](crate::Output::ErrWithExitCode).
+    #[error("exit code {code:?}: {msg}")]
+    ExitCode { code: Option<i32>, msg: String },
+    /// The task panicked while running.
+    #[error("task panicked: {0}")]
+    Panic(String),
+    /// The task did not finish within its timeout.
+    #[error("task timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    /// The task was still running when the grace period of a shutdown expired, see
+    /// [`CancelHandle::shutdown`](crate::CancelHandle::shutdown).
+    #[error("task aborted by a shutdown")]
+    Aborted,
+    /// The task was ready after the end of its scheduling window, see
+    /// [`TaskConfig::not_after`].
+    #[error("task missed its scheduling window")]
+    MissedWindow,
+    /// The action went without a heartbeat for longer than its stall timeout, see
+    /// [`DagConfig::kill_stalled`].
+    #[error("task stalled: no heartbeat for {0:?}")]
+    Stalled(std::time::Duration),
+    /// The output of the task was larger than its limit, see [`DagConfig::max_output_size`].
+    #[error("task output of {size} bytes exceeds the limit of {limit} bytes")]
+    OutputTooLarge { size: usize, limit: usize },
 }
 
 impl Engine {
@@ -56,6 +180,11 @@ impl Engine {
         if !self.dags.contains_key(name) {
             match dag.init() {
                 Ok(()) => {
+                    #[cfg(feature = "notify")]
+                    self.notifiers
+                        .iter()
+                        .for_each(|notifier| dag.add_notifier(notifier.clone()));
+                    dag.set_fair_share(FairShare::new(self.fair.clone(), name));
                     self.dags.insert(name.to_string(), dag);
                     let len = self.sequence.len();
                     self.sequence.insert(len + 1, name.to_string());
@@ -70,8 +199,11 @@ impl Engine {
     /// Given a Dag name, execute this Dag.
     /// Returns true if the given Dag executes successfully, otherwise false.
     pub fn run_dag(&mut self, name: &str) -> bool {
-        if let Some(dag) = self.dags.get(name) {
-            self.runtime.block_on(dag.run())
+        if let Some(dag) = self.dags.get_mut(name) {
+            let started = SystemTime::now();
+            let success = self.runtime.block_on(dag.run());
+            record(self.history.as_ref(), name, started, dag);
+            success
         } else {
             error!("No job named '{}'", name);
             false
@@ -89,18 +221,180 @@ impl Engine {
         res
     }
 
+    /// Execute all the Dags in the Engine at the same time. The return value is the execution
+    /// status of each Dag, in the order of their sequence numbers. With
+    /// [`Engine::set_max_parallel`], the dags take turns to run their ready tasks.
+    pub fn run_concurrent(&mut self) -> Vec<bool> {
+        let order: HashMap<&str, usize> = self
+            .sequence
+            .iter()
+            .map(|(seq, name)| (name.as_str(), *seq))
+            .collect();
+        let mut dags: Vec<(&String, &mut Dag)> = self.dags.iter_mut().collect();
+        dags.sort_by_key(|(name, _)| order[name.as_str()]);
+        let (runtime, history) = (&self.runtime, self.history.as_ref());
+        thread::scope(|scope| {
+            let runs: Vec<_> = dags
+                .into_iter()
+                .map(|(name, dag)| {
+                    scope.spawn(move || {
+                        let started = SystemTime::now();
+                        let success = runtime.block_on(dag.run());
+                        record(history, name, started, dag);
+                        success
+                    })
+                })
+                .collect();
+            runs.into_iter()
+                .map(|run| run.join().unwrap_or(false))
+                .collect()
+        })
+    }
+
+    /// Limit the tasks that run at the same time in all dags of the engine. The places are
+    /// shared fairly between the dags that have ready tasks, according to their priorities
+    /// (see [`Engine::set_priority`]), so that a large dag does not starve the others. There
+    /// is no limit by default. With a limit, all actions run on blocking threads, as with
+    /// [`Execution::Blocking`], so that the runtime keeps handing out the places while they run.
+    pub fn set_max_parallel(&mut self, max_parallel: usize) {
+        self.fair.set_capacity(max_parallel);
+    }
+
+    /// Give the dag with the given name a priority, 1 by default: with
+    /// [`Engine::set_max_parallel`], a dag of priority 2 gets twice as many places as a dag of
+    /// priority 1 while both have ready tasks.
+    pub fn set_priority(&mut self, name: &str, priority: u32) {
+        self.fair.set_weight(name, priority);
+    }
+
+    /// Make the dag with the given name urgent, or not: while it runs, the tasks of the other
+    /// dags of the engine that are not urgent do not start, until the urgent run finishes.
+    /// The runs of preemptible dags (see [`Engine::set_preemptible`]) are cancelled instead.
+    /// Unlike [`Engine::set_max_parallel`], it does not change where the actions run.
+    pub fn set_urgent(&mut self, name: &str, urgent: bool) {
+        self.fair.set_urgent(name, urgent);
+    }
+
+    /// Make the dag with the given name preemptible, or not: its runs in progress are
+    /// cancelled when a run of an urgent dag starts, and so are its runs that start while an
+    /// urgent run is in progress, see [`CancelHandle::cancel`].
+    pub fn set_preemptible(&mut self, name: &str, preemptible: bool) {
+        self.fair.set_preemptible(name, preemptible);
+    }
+
+    /// Register a notifier on every dag appended to the engine, before or after this call.
+    #[cfg(feature = "notify")]
+    pub fn add_notifier(&mut self, notifier: Notifier) {
+        self.dags
+            .values_mut()
+            .for_each(|dag| dag.add_notifier(notifier.clone()));
+        self.notifiers.push(notifier);
+    }
+
+    /// Register a dag to be run on the schedule given by a cron expression when the engine
+    /// runs as a daemon. `factory` builds a new dag for every run. `policy` decides what
+    /// happens when a run is due while the previous one is still in progress.
+    #[cfg(feature = "scheduler")]
+    pub fn schedule_dag(
+        &mut self,
+        name: &str,
+        cron: &str,
+        policy: OverlapPolicy,
+        factory: impl Fn() -> Result<Dag, DagError> + Send + Sync + 'static,
+    ) -> Result<(), DagError> {
+        let scheduled = ScheduledDag::new(name, cron, policy, Arc::new(factory))?;
+        self.schedules.push(scheduled);
+        Ok(())
+    }
+
+    /// Run the scheduled dags on schedule, forever.
+    #[cfg(feature = "scheduler")]
+    pub fn run_daemon(&mut self) {
+        self.run_daemon_until(|| false)
+    }
+
+    /// Run the scheduled dags on schedule until `stop` returns true, then wait for the runs in
+    /// progress to finish.
+    #[cfg(feature = "scheduler")]
+    pub fn run_daemon_until(&mut self, stop: impl Fn() -> bool) {
+        use std::time::Duration;
+
+        // How often `stop` is checked.
+        const TICK: Duration = Duration::from_millis(100);
+
+        let mut runs = Vec::new();
+        while !stop() {
+            let now = chrono::Utc::now();
+            runs.extend(
+                self.schedules
+                    .iter_mut()
+                    .filter_map(|s| s.fire(now, &self.fair, self.history.as_ref())),
+            );
+            runs.retain(|run: &std::thread::JoinHandle<()>| !run.is_finished());
+
+            let sleep = self
+                .schedules
+                .iter()
+                .filter_map(ScheduledDag::next)
+                .min()
+                .and_then(|next| (next - chrono::Utc::now()).to_std().ok())
+                .map_or(TICK, |until| until.min(TICK));
+            std::thread::sleep(sleep);
+        }
+        for run in runs {
+            run.join().ok();
+        }
+    }
+
+    /// Record the runs of the dags of the engine, scheduled ones included, in the given history.
+    pub fn set_history(&mut self, history: RunHistory) {
+        self.history = Some(history);
+    }
+
+    /// The history the runs of the dags are recorded in, see [`Engine::set_history`].
+    pub fn history(&self) -> Option<&RunHistory> {
+        self.history.as_ref()
+    }
+
+    /// Give a dag that runs outside of the engine, such as a run submitted to a
+    /// [`Server`](crate::server::Server), the notifiers of the engine and a share of its
+    /// places under the given name. Record its runs with [`record`].
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    pub(crate) fn adopt(&self, name: &str, dag: &mut Dag) {
+        #[cfg(feature = "notify")]
+        self.notifiers
+            .iter()
+            .for_each(|notifier| dag.add_notifier(notifier.clone()));
+        dag.set_fair_share(FairShare::new(self.fair.clone(), name));
+    }
+
     /// Given the name of the Dag, get the execution result of the specified Dag.
     pub fn get_dag_result<T: Send + Sync + Clone + 'static>(&self, name: &str) -> Option<Arc<T>> {
         self.dags.get(name).and_then(|dag| dag.get_result())
     }
 }
 
+/// Record the run of a dag in the history, if any.
+pub(crate) fn record(history: Option<&RunHistory>, name: &str, started: SystemTime, dag: &Dag) {
+    if let Some(history) = history {
+        if let Err(err) = history.record(name, started, SystemTime::now(), &dag.report()) {
+            error!("Failed to record the run of '{}': {}", name, err);
+        }
+    }
+}
+
 impl Default for Engine {
     fn default() -> Self {
         Self {
             dags: HashMap::new(),
             runtime: Runtime::new().unwrap(),
             sequence: HashMap::new(),
+            #[cfg(feature = "scheduler")]
+            schedules: Vec::new(),
+            #[cfg(feature = "notify")]
+            notifiers: Vec::new(),
+            history: None,
+            fair: Arc::new(FairScheduler::new()),
         }
     }
 }
@@ -0,0 +1,92 @@
+//! Engine overhead report
+//!
+//! With the `bench` feature, the engine records when each task is spawned, when the outputs
+//! of its predecessors become available, when its action starts and finishes, and when its
+//! output has been stored for its successors. [`Dag::overhead_report`](super::Dag::overhead_report)
+//! turns these points in time into an [`OverheadReport`], which quantifies how much time the
+//! engine itself spends per task, so that performance regressions in the engine are visible.
+
+use crate::task::Timing;
+use std::{fmt::Display, time::Duration};
+
+/// The time spent in each phase of a task's execution.
+#[derive(Debug, Clone)]
+pub struct TaskOverhead {
+    pub id: usize,
+    pub name: String,
+    /// Waiting for the outputs of the predecessors, from the moment the task was spawned.
+    pub wait: Duration,
+    /// Dispatching the action once the inputs were available: collecting inputs, notifying
+    /// observers and setting up the log context.
+    pub dispatch: Duration,
+    /// Running the action.
+    pub action: Duration,
+    /// Storing the output and waking up the successors.
+    pub completion: Duration,
+}
+
+impl TaskOverhead {
+    /// Returns `None` for tasks whose action did not complete successfully.
+    pub(crate) fn new(id: usize, name: &str, timing: &Timing) -> Option<Self> {
+        let spawned = timing.spawned?;
+        let inputs_ready = timing.inputs_ready?;
+        let started = timing.started?;
+        let finished = timing.finished?;
+        let stored = timing.stored?;
+        Some(Self {
+            id,
+            name: name.to_string(),
+            wait: inputs_ready - spawned,
+            dispatch: started - inputs_ready,
+            action: finished - started,
+            completion: stored - finished,
+        })
+    }
+
+    /// The time spent by the engine rather than by the action or waiting for predecessors.
+    pub fn overhead(&self) -> Duration {
+        self.dispatch + self.completion
+    }
+}
+
+/// The overhead of the engine for every successfully executed task of a dag, sorted by
+/// task id.
+#[derive(Debug, Clone, Default)]
+pub struct OverheadReport {
+    pub tasks: Vec<TaskOverhead>,
+}
+
+impl OverheadReport {
+    /// The total overhead of the engine over all tasks.
+    pub fn total_overhead(&self) -> Duration {
+        self.tasks.iter().map(TaskOverhead::overhead).sum()
+    }
+
+    /// The total time spent running actions over all tasks.
+    pub fn total_action(&self) -> Duration {
+        self.tasks.iter().map(|task| task.action).sum()
+    }
+}
+
+impl Display for OverheadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>12} {:>12} {:>12}",
+            "task", "wait", "dispatch", "action", "completion"
+        )?;
+        for task in self.tasks.iter() {
+            writeln!(
+                f,
+                "{:<24} {:>12?} {:>12?} {:>12?} {:>12?}",
+                task.name, task.wait, task.dispatch, task.action, task.completion
+            )?;
+        }
+        write!(
+            f,
+            "total overhead: {:?}, total action: {:?}",
+            self.total_overhead(),
+            self.total_action()
+        )
+    }
+}
@@ -0,0 +1,157 @@
+//! Run reports
+//!
+//! A [`RunReport`] summarizes a finished run of a dag (see [`Dag::report`](super::Dag::report)):
+//! the status and duration of every task, the error of failed tasks, and the outputs that can
+//! be shown as text. It serializes to JSON, so that programs and CI scripts can consume the
+//! result of a run.
+
+use crate::task::Content;
+use serde::{Deserialize, Serialize, Serializer};
+use std::{path::PathBuf, time::Duration};
+
+/// The status of a task after a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Succeeded,
+    Failed,
+    /// The task's action did not run, because a predecessor failed, the run was cancelled, or
+    /// the [`Condition`](crate::Condition) of the task or of a predecessor was false.
+    Skipped,
+    /// The task's action did not run, because the output files of the task were newer than
+    /// its input files, see [`TaskFiles`](crate::TaskFiles).
+    UpToDate,
+}
+
+/// The result of a task in a [`RunReport`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskReport {
+    pub id: usize,
+    pub name: String,
+    pub status: TaskStatus,
+    /// How long the task's action ran.
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Option<Duration>,
+    /// The error of a failed task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The output of the task, if it is text. Tasks running a
+    /// [`CommandAction`](crate::CommandAction) report their stdout here.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// The stderr of a task running a [`CommandAction`](crate::CommandAction).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+    /// The file the stdout of the command of the task was captured to, see
+    /// [`Dag::capture_command_output`](super::Dag::capture_command_output).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout_file: Option<PathBuf>,
+    /// The file the stderr of the command of the task was captured to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr_file: Option<PathBuf>,
+}
+
+impl TaskReport {
+    /// Set the `output` and `stderr` of the report from the task's output.
+    pub(crate) fn set_output(&mut self, content: &Content) {
+        self.output = content.as_text();
+        // `CommandAction` wraps its output in a second `Content`.
+        let content = content.get::<Content>().unwrap_or(content);
+        if let Some((_, stderr)) = content.get::<(Vec<String>, Vec<String>)>() {
+            self.stderr = Some(stderr.join("\n"));
+        }
+    }
+}
+
+/// The result of a run of a dag.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// The id of the run, see [`Dag::run_id`](super::Dag::run_id).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub success: bool,
+    /// Whether the run was cancelled through a [`CancelHandle`](super::CancelHandle), rather
+    /// than stopped by a failed task.
+    pub cancelled: bool,
+    /// The tasks in execution order.
+    pub tasks: Vec<TaskReport>,
+}
+
+impl RunReport {
+    /// Serialize the report to pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Render the report as JUnit XML, which CI systems display natively: a test suite with
+    /// the given name, in which each task is a test case with its duration. A failed task is a
+    /// failure with its error as message, a skipped task is skipped, and the output and stderr
+    /// of a task are its `system-out` and `system-err`.
+    pub fn to_junit(&self, suite: &str) -> String {
+        let count = |status| self.tasks.iter().filter(|t| t.status == status).count();
+        let (failures, skipped) = (count(TaskStatus::Failed), count(TaskStatus::Skipped));
+        let time: f64 = self
+            .tasks
+            .iter()
+            .filter_map(|task| task.duration)
+            .map(|duration| duration.as_secs_f64())
+            .sum();
+        let suite = xml_escape(suite);
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let counts = format!(
+            "tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{:.3}\"",
+            self.tasks.len(),
+            failures,
+            skipped,
+            time
+        );
+        xml += &format!("<testsuites name=\"dagrs\" {}>\n", counts);
+        xml += &format!("  <testsuite name=\"{}\" {}", suite, counts);
+        if let Some(run_id) = &self.run_id {
+            xml += &format!(" id=\"{}\"", xml_escape(run_id));
+        }
+        xml += ">\n";
+        for task in &self.tasks {
+            xml += &format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(&task.name),
+                suite,
+                task.duration.map_or(0.0, |duration| duration.as_secs_f64())
+            );
+            match task.status {
+                TaskStatus::Succeeded | TaskStatus::UpToDate => {}
+                TaskStatus::Failed => {
+                    let error = xml_escape(task.error.as_deref().unwrap_or("failed"));
+                    xml += &format!("      <failure message=\"{}\">{}</failure>\n", error, error);
+                }
+                TaskStatus::Skipped => xml += "      <skipped/>\n",
+            }
+            if let Some(output) = &task.output {
+                xml += &format!("      <system-out>{}</system-out>\n", xml_escape(output));
+            }
+            if let Some(stderr) = task.stderr.as_deref().filter(|stderr| !stderr.is_empty()) {
+                xml += &format!("      <system-err>{}</system-err>\n", xml_escape(stderr));
+            }
+            xml += "    </testcase>\n";
+        }
+        xml + "  </testsuite>\n</testsuites>\n"
+    }
+}
+
+/// Escape text for XML content and attribute values.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn serialize_millis<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&(duration.as_secs_f64() * 1000.0)),
+        None => serializer.serialize_none(),
+    }
+}
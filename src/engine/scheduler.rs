@@ -0,0 +1,151 @@
+//! Cron-style scheduling of dags
+//!
+//! With the `scheduler` feature, dags can be registered on an [`Engine`](super::Engine) together
+//! with a cron expression (see [`Engine::schedule_dag`](super::Engine::schedule_dag)), and
+//! [`Engine::run_daemon`](super::Engine::run_daemon) runs them automatically on schedule.
+//!
+//! A scheduled dag is registered as a factory that builds a new [`Dag`] for every run, for
+//! example by reading a yaml configuration file again, so that runs can overlap and pick up
+//! changes to the configuration.
+//!
+//! Cron expressions have six or seven fields, starting with the seconds:
+//! `sec min hour day-of-month month day-of-week [year]`. For example `0 */5 * * * *` runs
+//! every five minutes. Times are in UTC.
+
+use super::{
+    fair::{FairScheduler, FairShare},
+    record, Dag, DagError, RunHistory,
+};
+use crate::ParseError;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use log::{error, info};
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::SystemTime,
+};
+
+/// What to do when a scheduled dag is due while a previous run of it is still in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// Do not start a new run.
+    #[default]
+    Skip,
+    /// Start a new run once the running one finished. Several due runs queue up.
+    Queue,
+    /// Start a new run alongside the running one.
+    Parallel,
+}
+
+/// Builds a new dag for every scheduled run.
+pub type DagFactory = dyn Fn() -> Result<Dag, DagError> + Send + Sync;
+
+#[derive(Default)]
+struct RunState {
+    running: usize,
+    queued: usize,
+}
+
+/// A dag registered on the engine with a schedule.
+pub(crate) struct ScheduledDag {
+    name: String,
+    schedule: Schedule,
+    policy: OverlapPolicy,
+    factory: Arc<DagFactory>,
+    state: Arc<Mutex<RunState>>,
+    next: Option<DateTime<Utc>>,
+}
+
+impl ScheduledDag {
+    pub(crate) fn new(
+        name: &str,
+        cron: &str,
+        policy: OverlapPolicy,
+        factory: Arc<DagFactory>,
+    ) -> Result<Self, DagError> {
+        let schedule = Schedule::from_str(cron)
+            .map_err(|err| ParseError(format!("Invalid cron expression '{}': {}", cron, err)))?;
+        let next = schedule.upcoming(Utc).next();
+        Ok(Self {
+            name: name.to_owned(),
+            schedule,
+            policy,
+            factory,
+            state: Arc::default(),
+            next,
+        })
+    }
+
+    /// When the dag is due next, if ever.
+    pub(crate) fn next(&self) -> Option<DateTime<Utc>> {
+        self.next
+    }
+
+    /// Start a run if the dag is due at `now`, according to the overlap policy. Its tasks take
+    /// their turns with those of the other dags of the engine in `fair`, and the run is
+    /// recorded in `history`, if any.
+    pub(crate) fn fire(
+        &mut self,
+        now: DateTime<Utc>,
+        fair: &Arc<FairScheduler>,
+        history: Option<&RunHistory>,
+    ) -> Option<JoinHandle<()>> {
+        match self.next {
+            Some(next) if next <= now => self.next = self.schedule.after(&now).next(),
+            _ => return None,
+        }
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.running > 0 {
+                match self.policy {
+                    OverlapPolicy::Skip => {
+                        info!("Skip scheduled run of dag '{}': still running", self.name);
+                        return None;
+                    }
+                    OverlapPolicy::Queue => {
+                        state.queued += 1;
+                        return None;
+                    }
+                    OverlapPolicy::Parallel => {}
+                }
+            }
+            state.running += 1;
+        }
+
+        let name = self.name.clone();
+        let factory = self.factory.clone();
+        let state = self.state.clone();
+        let fair = FairShare::new(fair.clone(), &name);
+        let history = history.cloned();
+        Some(thread::spawn(move || loop {
+            run_once(&name, factory.as_ref(), &fair, history.as_ref());
+            let mut state = state.lock().unwrap();
+            if state.queued > 0 {
+                state.queued -= 1;
+            } else {
+                state.running -= 1;
+                break;
+            }
+        }))
+    }
+}
+
+fn run_once(name: &str, factory: &DagFactory, fair: &FairShare, history: Option<&RunHistory>) {
+    info!("Start scheduled run of dag '{}'", name);
+    let started = SystemTime::now();
+    let run = factory().and_then(|mut dag| {
+        dag.set_fair_share(fair.clone());
+        let run = dag.start();
+        if run.is_ok() {
+            record(history, name, started, &dag);
+        }
+        run
+    });
+    match run {
+        Ok(true) => info!("Scheduled run of dag '{}' succeeded", name),
+        Ok(false) => error!("Scheduled run of dag '{}' failed", name),
+        Err(err) => error!("Scheduled run of dag '{}' failed: {}", name, err),
+    }
+}
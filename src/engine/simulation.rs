@@ -0,0 +1,79 @@
+//! Wiring-only simulation
+//!
+//! [`Dag::simulate`](super::Dag::simulate) runs a copy of a dag in which every action is
+//! replaced by a stub that immediately outputs its task's id, and checks that every task
+//! receives the output of each of its predecessors. This catches miswired dags (missing
+//! dependencies, loops, lost outputs) without running real workloads.
+
+use crate::{Action, Input, Output, Task};
+use std::sync::{Arc, Mutex};
+
+/// The result of simulating a dag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Simulation {
+    /// Pairs of `(task, predecessor)` names where the task did not receive the output of the
+    /// predecessor.
+    pub missing_inputs: Vec<(String, String)>,
+    /// Names of the tasks, other than the last one, whose output is not consumed by any other
+    /// task. Their output can only be read through [`Dag::get_results`](super::Dag::get_results).
+    pub unconsumed_outputs: Vec<String>,
+}
+
+impl Simulation {
+    /// Whether every task received the outputs of all of its predecessors.
+    pub fn is_ok(&self) -> bool {
+        self.missing_inputs.is_empty()
+    }
+}
+
+/// A task with the same id, name and predecessors as a real task, whose action outputs its
+/// id and records the predecessors whose outputs it did not receive.
+pub(crate) struct StubTask {
+    id: usize,
+    name: String,
+    precursors: Vec<usize>,
+    action: Action,
+}
+
+impl StubTask {
+    pub(crate) fn new(task: &dyn Task, missing: Arc<Mutex<Vec<(usize, usize)>>>) -> Self {
+        let id = task.id();
+        let precursors = task.precursors().to_vec();
+        let expected = precursors.clone();
+        let action = move |input: Input, _| {
+            let received: Vec<usize> = input
+                .get_iter()
+                .filter_map(|content| content.get::<usize>().copied())
+                .collect();
+            expected
+                .iter()
+                .filter(|pre| !received.contains(pre))
+                .for_each(|pre| missing.lock().unwrap().push((id, *pre)));
+            Output::new(id)
+        };
+        Self {
+            id,
+            name: task.name().to_string(),
+            precursors,
+            action: Action::Closure(Arc::new(action)),
+        }
+    }
+}
+
+impl Task for StubTask {
+    fn action(&self) -> Action {
+        self.action.clone()
+    }
+
+    fn precursors(&self) -> &[usize] {
+        &self.precursors
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,39 @@
+//! Execution snapshots
+//!
+//! An [`ExecutionSnapshot`] records which tasks of a dag ran, grouped into blocks by their
+//! level in the dependency graph (see [`Dag::execution_snapshot`](super::Dag::execution_snapshot)),
+//! and which tasks did not run. Unlike the realized timing of concurrent tasks, the snapshot
+//! of a run is deterministic, so it can be compared with the snapshot of a previous run to
+//! detect unintended scheduling changes in complex dags.
+//!
+//! Snapshots can be saved to and loaded from JSON files. The
+//! [`assert_snapshot`](crate::testing::assert_snapshot) test helper compares a run with a
+//! snapshot file.
+
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+/// The realized execution of a dag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionSnapshot {
+    /// Names of the tasks whose action ran, by level in the dependency graph. The names in a
+    /// block are sorted.
+    pub blocks: Vec<Vec<String>>,
+    /// Names of the tasks whose action did not run, sorted.
+    pub not_run: Vec<String>,
+}
+
+impl ExecutionSnapshot {
+    /// Load a snapshot from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Save the snapshot to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content + "\n")
+    }
+}
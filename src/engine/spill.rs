@@ -0,0 +1,63 @@
+//! Spilling of the outputs of a run to disk beyond its memory budget, see
+//! [`DagConfig::memory_budget`](super::DagConfig::memory_budget).
+
+use crate::task::{ArtifactDir, Content, ExecStateRef};
+use log::{debug, warn};
+use std::{collections::VecDeque, sync::Mutex};
+
+/// The stored outputs of a run, oldest first, and their total size.
+#[derive(Debug, Default)]
+struct Resident {
+    outputs: VecDeque<(usize, ExecStateRef, usize)>,
+    size: usize,
+}
+
+/// Spills the oldest stored outputs of a run to disk once they exceed the budget.
+#[derive(Debug)]
+pub(crate) struct MemoryBudget {
+    limit: usize,
+    resident: Mutex<Resident>,
+    /// The [`ArtifactDir`] of the run, which the outputs are spilled to.
+    run_dir: Content,
+}
+
+impl MemoryBudget {
+    pub(crate) fn new(limit: usize, run_dir: Content) -> Self {
+        Self {
+            limit,
+            resident: Mutex::default(),
+            run_dir,
+        }
+    }
+
+    /// Account for the output just stored by the task with the given id, and spill the oldest
+    /// outputs, this one included, until the stored outputs fit in the budget.
+    pub(crate) fn admit(&self, id: usize, state: &ExecStateRef) {
+        let (Some(size), Some(dir)) = (state.spillable_size(), self.run_dir.get::<ArtifactDir>())
+        else {
+            return;
+        };
+        let mut evicted = Vec::new();
+        {
+            let mut resident = self.resident.lock().unwrap();
+            resident.outputs.push_back((id, state.clone(), size));
+            resident.size += size;
+            while resident.size > self.limit {
+                let Some((id, state, size)) = resident.outputs.pop_front() else {
+                    break;
+                };
+                resident.size -= size;
+                evicted.push((id, state, size));
+            }
+        }
+        for (id, state, size) in evicted {
+            let spilled = dir
+                .create(&format!("spill/{}", id))
+                .and_then(|artifact| state.spill(artifact.path()));
+            match spilled {
+                Ok(()) => debug!("Spilled the output of task {} ({} bytes) to disk", id, size),
+                Err(err) => warn!("Failed to spill the output of task {}: {}", id, err),
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Dag structure
+//!
+//! A [`DagStructure`] describes a dag as data: its tasks (ids, names and action type names)
+//! and the dependency edges between them. It can be serialized to and from JSON, so that
+//! external tools can store, diff and regenerate workflow definitions.
+//!
+//! [`Dag::export_structure`](super::Dag::export_structure) describes an existing dag, and
+//! [`Dag::import_structure`](super::Dag::import_structure) rebuilds a dag from a description,
+//! creating each task's action through an [`ActionRegistry`](crate::ActionRegistry).
+//!
+//! ```json
+//! {
+//!   "tasks": [
+//!     { "id": 1, "name": "fetch", "action": "http", "args": { "url": "https://example.com" } },
+//!     { "id": 2, "name": "count", "action": "wc" }
+//!   ],
+//!   "edges": [[1, 2]]
+//! }
+//! ```
+
+use crate::ParseError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A task of a [`DagStructure`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStructure {
+    /// The id of the task. Ids only need to be unique within the structure; importing a
+    /// structure allocates new ids.
+    pub id: usize,
+    pub name: String,
+    /// The name of the action type, used to look up the action factory when importing.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Arguments passed to the action factory when importing.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub args: HashMap<String, String>,
+}
+
+/// The tasks of a dag and the dependencies between them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DagStructure {
+    /// The tasks, sorted by id.
+    pub tasks: Vec<TaskStructure>,
+    /// The dependency edges as `(predecessor id, successor id)` pairs, sorted.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl DagStructure {
+    /// Serialize the structure to pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Deserialize a structure from JSON.
+    pub fn from_json(json: &str) -> Result<Self, ParseError> {
+        serde_json::from_str(json).map_err(|err| ParseError(err.to_string()))
+    }
+}
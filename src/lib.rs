@@ -1,4 +1,3 @@
-extern crate bimap;
 extern crate clap;
 #[cfg(feature = "derive")]
 extern crate derive;
@@ -8,16 +7,41 @@ extern crate yaml_rust;
 
 #[cfg(feature = "derive")]
 pub use derive::*;
-pub use engine::{Dag, DagError, Engine};
+#[cfg(feature = "scheduler")]
+pub use engine::OverlapPolicy;
+#[cfg(feature = "tracing")]
+pub use engine::TracingObserver;
+pub use engine::{
+    CancelHandle, Dag, DagError, DagStructure, Engine, Event, ExecutionError, ExecutionObserver,
+    ExecutionSnapshot, FaultInjector, GraphError, GraphFormat, GroupStyle, GroupedOutput,
+    JsonEventLog, LiveGraph, LogObserver, RunHistory, RunReport, Simulation, StructureDiff,
+};
+#[cfg(feature = "notify-chat")]
+pub use engine::{ChatObserver, ChatService};
+#[cfg(feature = "notify")]
+pub use engine::{EmailConfig, Notifier, NotifyChannel, NotifyOn};
+#[cfg(feature = "rhai")]
+pub use task::RhaiAction;
 pub use task::{
-    alloc_id, Action, CommandAction, Complex, DefaultTask, Input, Output, Simple, Task,
+    alloc_id, Action, ActionExt, ActionLayer, ActionRegistry, Artifact, ArtifactDir, AsyncClosure,
+    AsyncCondition, Blocking, CircuitBreaker, CircuitState, CleanupPolicy, CommandAction,
+    CommandCondition, Complex, Condition, DefaultTask, Fallback, Fingerprint, Fingerprinter,
+    ImportMode, Input, Lazy, LogLayer, Output, Par, Retry, Seq, Shell, Simple, SyncAction, Task,
+    TaskBuilder, TaskFiles, TaskPool, Timeout, TypedAction, TypedTask,
 };
+#[cfg(feature = "s3")]
+pub use task::{S3DownloadAction, S3UploadAction};
+#[cfg(feature = "sql")]
+pub use task::{SqlAction, SqlRows, SqlValue};
 pub use utils::{EnvVar, ParseError, Parser};
 #[cfg(feature = "yaml")]
 pub use yaml::{FileContentError, FileNotFound, YamlParser, YamlTask, YamlTaskError};
 
 pub mod engine;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod task;
+pub mod testing;
 pub mod utils;
 #[cfg(feature = "yaml")]
 pub mod yaml;
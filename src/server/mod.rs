@@ -0,0 +1,345 @@
+//! HTTP control API
+//!
+//! With the `server` feature, a [`Server`] lets dagrs operate as a self-hosted workflow
+//! service. Dags are submitted as yaml configurations and run in the background by an
+//! [`Engine`], with its notifiers, its history and its limits; their progress can be queried
+//! and they can be cancelled over a small REST API:
+//!
+//! | Request                   | Response                                                   |
+//! |---------------------------|------------------------------------------------------------|
+//! | `POST /runs`              | Run the yaml in the body, at most 1 MiB. `{"id": 1}`       |
+//! | `GET /runs`               | All runs. `[{"id": 1, "status": "running"}]`               |
+//! | `GET /runs/<id>`          | A run and the status of its tasks.                         |
+//! | `GET /runs/<id>/log`      | The log of a run, as plain text.                           |
+//! | `POST /runs/<id>/cancel`  | Cancel a run. Running tasks are completed.                 |
+//! | `DELETE /runs/<id>`       | Forget a run that finished.                                |
+//!
+//! The server keeps the last 100 runs that finished by default, see [`Server::keep_runs`].
+//!
+//! # Security
+//!
+//! **The server runs the commands of the yaml it is sent**, with the rights of its process.
+//! Anyone who can reach it can run anything. Bind it to a local address, or require a token
+//! with [`Server::with_token`], which every request must then give in an
+//! `Authorization: Bearer <token>` header, and serve it behind a TLS proxy.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dagrs::{server::Server, Engine};
+//!
+//! let mut engine = Engine::default();
+//! engine.set_max_parallel(4);
+//! let server = Server::bind("127.0.0.1:8080")
+//!     .unwrap()
+//!     .with_engine(engine)
+//!     .with_token(&std::env::var("DAGRS_TOKEN").unwrap());
+//! server.serve();
+//! ```
+
+use crate::{engine::record, CancelHandle, Dag, Engine, Event, ExecutionObserver};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Read},
+    net::{SocketAddr, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+    time::SystemTime,
+};
+use tiny_http::{Header, Method, Request, Response};
+
+/// The largest yaml configuration that can be submitted, in bytes.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// The status of a submitted run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// A submitted run.
+struct Run {
+    status: RunStatus,
+    /// The status of each task by name.
+    tasks: BTreeMap<String, &'static str>,
+    log: Vec<String>,
+    cancel: CancelHandle,
+}
+
+type Runs = Arc<Mutex<HashMap<usize, Run>>>;
+
+/// Records the events of a run into its log and task statuses.
+struct RunObserver {
+    runs: Runs,
+    id: usize,
+}
+
+impl ExecutionObserver for RunObserver {
+    fn on_event(&self, event: &Event) {
+        let mut runs = self.runs.lock().unwrap();
+        let Some(run) = runs.get_mut(&self.id) else {
+            return;
+        };
+        let task = match event {
+            Event::TaskStarted { name, .. } => Some((name, "running")),
+            Event::TaskFinished { name, .. } => Some((name, "succeeded")),
+            Event::TaskFailed { name, .. } => Some((name, "failed")),
+            Event::TaskSkipped { name, .. } => Some((name, "skipped")),
+            Event::DagStarted { .. } | Event::DagFinished { .. } | Event::TaskStalled { .. } => {
+                None
+            }
+        };
+        if let Some((name, status)) = task {
+            run.tasks.insert(name.clone(), status);
+        }
+        run.log.push(serde_json::to_string(event).unwrap());
+    }
+}
+
+/// An HTTP server that runs submitted dags. See the [module documentation](self).
+pub struct Server {
+    http: tiny_http::Server,
+    runs: Runs,
+    next_id: Mutex<usize>,
+    engine: Arc<Mutex<Engine>>,
+    /// The token every request must give, if any.
+    token: Option<String>,
+    /// How many finished runs are kept.
+    keep: usize,
+}
+
+impl Server {
+    /// Listen on the given address. The runs get a default [`Engine`], and no token is
+    /// required.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let http = tiny_http::Server::http(addr).map_err(io::Error::other)?;
+        Ok(Self {
+            http,
+            runs: Runs::default(),
+            next_id: Mutex::new(1),
+            engine: Arc::default(),
+            token: None,
+            keep: 100,
+        })
+    }
+
+    /// Run the submitted dags with the notifiers, the history and the limits of the given
+    /// engine, such as [`Engine::set_max_parallel`]. Each run is named `run <id>` in the
+    /// engine.
+    pub fn with_engine(mut self, engine: Engine) -> Self {
+        self.engine = Arc::new(Mutex::new(engine));
+        self
+    }
+
+    /// Answer only the requests that give this token, in an `Authorization: Bearer <token>`
+    /// header, and the others with `401 Unauthorized`.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_owned());
+        self
+    }
+
+    /// Keep the given number of runs that finished, and forget the older ones. 100 by
+    /// default.
+    pub fn keep_runs(mut self, count: usize) -> Self {
+        self.keep = count;
+        self
+    }
+
+    /// The address the server listens on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// Handle requests until [`stop`](Server::stop) is called.
+    pub fn serve(&self) {
+        for request in self.http.incoming_requests() {
+            self.handle(request);
+        }
+    }
+
+    /// Make [`serve`](Server::serve) return. Runs in progress are not cancelled.
+    pub fn stop(&self) {
+        self.http.unblock();
+    }
+
+    /// Whether the request gives the token of the server, if it has one.
+    fn authorized(&self, request: &Request) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        let expected = format!("Bearer {}", token);
+        request
+            .headers()
+            .iter()
+            .filter(|header| header.field.equiv("Authorization"))
+            .any(|header| same(header.value.as_str().as_bytes(), expected.as_bytes()))
+    }
+
+    fn handle(&self, mut request: Request) {
+        if !self.authorized(&request) {
+            let response = error(401, "unauthorized")
+                .with_header(Header::from_bytes("WWW-Authenticate", "Bearer").unwrap());
+            request.respond(response).ok();
+            return;
+        }
+        let url = request.url();
+        let path = url.split_once('?').map_or(url, |(path, _)| path);
+        let url = path.trim_end_matches('/').to_string();
+        let segments: Vec<&str> = url.split('/').skip(1).collect();
+        let response = match (request.method(), segments.as_slice()) {
+            (Method::Post, ["runs"]) => {
+                let mut body = String::new();
+                let mut reader = request.as_reader().take(MAX_BODY_SIZE + 1);
+                match reader.read_to_string(&mut body) {
+                    Ok(size) if size as u64 > MAX_BODY_SIZE => error(413, "body too large"),
+                    Ok(_) => self.submit(&body),
+                    Err(err) => error(400, &err.to_string()),
+                }
+            }
+            (Method::Get, ["runs"]) => {
+                let runs = self.runs.lock().unwrap();
+                let mut list: Vec<_> = runs
+                    .iter()
+                    .map(|(id, run)| json!({ "id": id, "status": run.status }))
+                    .collect();
+                list.sort_by_key(|run| run["id"].as_u64());
+                ok(json!(list))
+            }
+            (method, ["runs", id, rest @ ..]) => match id.parse::<usize>() {
+                Ok(id) => self.handle_run(method, id, rest),
+                Err(_) => error(404, "no such run"),
+            },
+            _ => error(404, "not found"),
+        };
+        request.respond(response).ok();
+    }
+
+    fn handle_run(
+        &self,
+        method: &Method,
+        id: usize,
+        rest: &[&str],
+    ) -> Response<io::Cursor<Vec<u8>>> {
+        let mut runs = self.runs.lock().unwrap();
+        let Some(run) = runs.get_mut(&id) else {
+            return error(404, "no such run");
+        };
+        match (method, rest) {
+            (Method::Get, []) => ok(json!({
+                "id": id,
+                "status": run.status,
+                "tasks": run.tasks,
+            })),
+            (Method::Get, ["log"]) => Response::from_string(run.log.join("\n") + "\n")
+                .with_header(content_type("text/plain")),
+            (Method::Post, ["cancel"]) => {
+                if run.status != RunStatus::Running {
+                    return error(409, "run is not running");
+                }
+                run.cancel.cancel();
+                ok(json!({ "id": id }))
+            }
+            (Method::Delete, []) => {
+                if run.status == RunStatus::Running {
+                    return error(409, "run is running");
+                }
+                runs.remove(&id);
+                ok(json!({ "id": id }))
+            }
+            _ => error(404, "not found"),
+        }
+    }
+
+    /// Start running a yaml configuration in the background.
+    fn submit(&self, yaml: &str) -> Response<io::Cursor<Vec<u8>>> {
+        let mut dag = match Dag::with_yaml_str(yaml, HashMap::new()) {
+            Ok(dag) => dag.capture_logs(),
+            Err(err) => return error(400, &err.to_string()),
+        };
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id - 1
+        };
+        dag.add_observer(RunObserver {
+            runs: self.runs.clone(),
+            id,
+        });
+        let name = format!("run {}", id);
+        self.engine.lock().unwrap().adopt(&name, &mut dag);
+        self.runs.lock().unwrap().insert(
+            id,
+            Run {
+                status: RunStatus::Running,
+                tasks: BTreeMap::new(),
+                log: Vec::new(),
+                cancel: dag.cancel_handle(),
+            },
+        );
+
+        let runs = self.runs.clone();
+        let engine = self.engine.clone();
+        let keep = self.keep;
+        thread::spawn(move || {
+            let started = SystemTime::now();
+            let success = matches!(dag.start(), Ok(true));
+            record(engine.lock().unwrap().history(), &name, started, &dag);
+            let mut runs = runs.lock().unwrap();
+            let run = runs.get_mut(&id).unwrap();
+            for task in dag.export_structure().tasks {
+                for line in dag.get_logs(task.id).unwrap_or_default() {
+                    run.log.push(format!("[{}] {}", task.name, line));
+                }
+            }
+            for err in dag.take_errors() {
+                run.log.push(err.to_string());
+            }
+            run.status = match (success, dag.report().cancelled) {
+                (true, _) => RunStatus::Succeeded,
+                (false, true) => RunStatus::Cancelled,
+                (false, false) => RunStatus::Failed,
+            };
+            forget_old_runs(&mut runs, keep);
+        });
+
+        ok(json!({ "id": id })).with_status_code(201)
+    }
+}
+
+/// Forget the oldest runs that finished beyond the given number.
+fn forget_old_runs(runs: &mut HashMap<usize, Run>, keep: usize) {
+    let mut finished: Vec<usize> = runs
+        .iter()
+        .filter(|(_, run)| run.status != RunStatus::Running)
+        .map(|(id, _)| *id)
+        .collect();
+    if finished.len() > keep {
+        finished.sort_unstable();
+        for id in &finished[..finished.len() - keep] {
+            runs.remove(id);
+        }
+    }
+}
+
+/// Compare a secret in a time that does not depend on where it differs.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).unwrap()
+}
+
+fn ok(body: serde_json::Value) -> Response<io::Cursor<Vec<u8>>> {
+    Response::from_string(body.to_string()).with_header(content_type("application/json"))
+}
+
+fn error(code: u16, msg: &str) -> Response<io::Cursor<Vec<u8>>> {
+    ok(json!({ "error": msg })).with_status_code(code)
+}
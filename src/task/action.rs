@@ -1,5 +1,6 @@
+use super::{block_on_thread, Fingerprinter};
 use crate::{EnvVar, Input, Output};
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 
 /// The type of closure that performs logic.
 /// # [`Simple`]
@@ -52,6 +53,89 @@ pub type Simple = dyn Fn(Input, Arc<EnvVar>) -> Output + Send + Sync;
 /// ```
 pub trait Complex {
     fn run(&self, input: Input, env: Arc<EnvVar>) -> Output;
+
+    /// Add what determines the output of this action, such as the command it runs, to the
+    /// fingerprint of its task, see [`Fingerprint`](super::Fingerprint). Adds nothing by
+    /// default, so that the task is told apart by its name.
+    fn fingerprint(&self, _fingerprint: &mut Fingerprinter) {}
+
+    /// Whether the action blocks the thread it runs on for long, for example because it is
+    /// CPU-bound. The task of a blocking action runs it on a blocking thread, as with
+    /// [`Execution::Blocking`](crate::engine::Execution::Blocking), whatever the execution of
+    /// the task. `false` by default; see [`ActionExt::blocking`](super::ActionExt::blocking)
+    /// and [`SyncAction`].
+    fn is_blocking(&self) -> bool {
+        false
+    }
+
+    /// Whether the output of the action may be looked up in and stored into the cache of the
+    /// dag, see [`Dag::with_cache`](crate::Dag::with_cache). An action whose fingerprint cannot
+    /// tell apart what it does, such as an [`AsyncClosure`], is not, unless its task sets
+    /// [`TaskConfig::cache`](crate::engine::TaskConfig::cache). `true` by default.
+    fn is_cacheable(&self) -> bool {
+        true
+    }
+}
+
+/// Plain synchronous logic, such as a CPU-bound computation. Turned into an [`Action`] with
+/// [`Action::from_sync`], it always runs on a blocking thread, so that it never holds up the
+/// runtime of the dag, and it needs no [`Complex::is_blocking`] nor
+/// [`ActionExt::blocking`](super::ActionExt::blocking).
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Action, DefaultTask, EnvVar, Input, Output, SyncAction};
+/// use std::sync::Arc;
+///
+/// struct Checksum {
+///     count: u64,
+/// }
+///
+/// impl SyncAction for Checksum {
+///     fn run(&self, _input: Input, _env: Arc<EnvVar>) -> Output {
+///         Output::new((0..self.count).sum::<u64>())
+///     }
+/// }
+///
+/// let action = Action::from_sync(Checksum { count: 1_000_000 });
+/// assert!(action.is_blocking());
+/// let task = DefaultTask::with_action("checksum", action);
+/// ```
+pub trait SyncAction: Send + Sync {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output;
+
+    /// See [`Complex::fingerprint`].
+    fn fingerprint(&self, _fingerprint: &mut Fingerprinter) {}
+}
+
+/// The [`Complex`] action of a [`SyncAction`], which is blocking.
+struct SyncAdapter<A>(A);
+
+impl<A: SyncAction> Complex for SyncAdapter<A> {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        self.0.run(input, env)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.0.fingerprint(fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+}
+
+/// An action that transforms a single input of type [`In`](TypedAction::In) into an output of
+/// type [`Out`](TypedAction::Out).
+///
+/// The `#[action]` attribute of the `derive` feature implements it, together with [`Complex`],
+/// for functions like `async fn double(x: u32) -> u64`: the action takes the first input of its
+/// task holding an `In`, and fails if there is none. [`TypedTask`](crate::TypedTask) uses the
+/// types to check the connections between tasks at compile time.
+pub trait TypedAction: Complex {
+    type In: Send + Sync + 'static;
+    type Out: Send + Sync + 'static;
 }
 
 /// Task specific behavior
@@ -69,10 +153,162 @@ pub enum Action {
 }
 
 impl Action {
+    /// The action of a plain function or closure. A closure can also be converted with
+    /// `into()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{Action, DefaultTask, EnvVar, Input, Output};
+    /// use std::sync::Arc;
+    ///
+    /// fn answer(_input: Input, _env: Arc<EnvVar>) -> Output {
+    ///     Output::new(42)
+    /// }
+    ///
+    /// let mut task = DefaultTask::new("answer");
+    /// task.set_action_dyn(Action::from_fn(answer));
+    /// task.set_action_dyn((|_, _| Output::new(43)).into());
+    /// ```
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: Fn(Input, Arc<EnvVar>) -> Output + Send + Sync + 'static,
+    {
+        Self::Closure(Arc::new(f))
+    }
+
+    /// The action of an async function or closure, run as an [`AsyncClosure`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use dagrs::{Action, EnvVar, Input, Output};
+    /// use std::sync::Arc;
+    ///
+    /// async fn fetch(_input: Input, _env: Arc<EnvVar>) -> Output {
+    ///     Output::new("<html></html>".to_string())
+    /// }
+    ///
+    /// let action = Action::from_async_fn(fetch);
+    /// let output = action.run(Input::new(vec![]), Arc::new(EnvVar::new()));
+    /// assert!(!output.is_err());
+    /// ```
+    pub fn from_async_fn<F, Fut>(f: F) -> Self
+    where
+        F: Fn(Input, Arc<EnvVar>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Output> + Send,
+    {
+        Self::Structure(Arc::new(AsyncClosure::new(f)))
+    }
+
+    /// The action of a [`SyncAction`], which its task runs on a blocking thread.
+    pub fn from_sync(action: impl SyncAction + 'static) -> Self {
+        Self::Structure(Arc::new(SyncAdapter(action)))
+    }
+
     pub fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
         match self {
             Self::Closure(closure) => closure(input, env),
             Self::Structure(structure) => structure.run(input, env),
         }
     }
+
+    /// See [`Complex::fingerprint`]. A closure adds nothing.
+    pub fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        if let Self::Structure(structure) = self {
+            structure.fingerprint(fingerprint);
+        }
+    }
+
+    /// See [`Complex::is_blocking`]. A closure is not blocking.
+    pub fn is_blocking(&self) -> bool {
+        match self {
+            Self::Closure(_) => false,
+            Self::Structure(structure) => structure.is_blocking(),
+        }
+    }
+
+    /// See [`Complex::is_cacheable`]. A closure is cacheable, under the name of its task.
+    pub fn is_cacheable(&self) -> bool {
+        match self {
+            Self::Closure(_) => true,
+            Self::Structure(structure) => structure.is_cacheable(),
+        }
+    }
+}
+
+impl<F> From<F> for Action
+where
+    F: Fn(Input, Arc<EnvVar>) -> Output + Send + Sync + 'static,
+{
+    fn from(f: F) -> Self {
+        Self::from_fn(f)
+    }
+}
+
+/// [`AsyncClosure`] turns an async closure into a [`Complex`] action, for tasks that call
+/// async libraries. The future runs on a runtime of its own, in a new thread, until it
+/// completes. See [`DefaultTask::with_async_closure`](crate::DefaultTask::with_async_closure).
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{AsyncClosure, Complex, EnvVar, Input, Output};
+/// use std::{sync::Arc, time::Duration};
+///
+/// let action = AsyncClosure::new(|_input, _env| async {
+///     tokio::time::sleep(Duration::from_millis(10)).await;
+///     Output::new(1)
+/// });
+/// let output = action.run(Input::new(vec![]), Arc::new(EnvVar::new()));
+/// assert_eq!(output.get_out().unwrap().get::<i32>(), Some(&1));
+/// ```
+pub struct AsyncClosure<F>(F);
+
+impl<F, Fut> AsyncClosure<F>
+where
+    F: Fn(Input, Arc<EnvVar>) -> Fut,
+    Fut: Future<Output = Output> + Send,
+{
+    pub fn new(closure: F) -> Self {
+        Self(closure)
+    }
+}
+
+impl<F, Fut> Complex for AsyncClosure<F>
+where
+    F: Fn(Input, Arc<EnvVar>) -> Fut,
+    Fut: Future<Output = Output> + Send,
+{
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        match block_on_thread((self.0)(input, env)) {
+            Ok(output) => output,
+            Err(err) => Output::error(format!("Failed to start a runtime: {}", err)),
+        }
+    }
+
+    /// Nothing tells apart what the closure does, so its output is not cached by default.
+    fn is_cacheable(&self) -> bool {
+        false
+    }
+}
+
+/// An [`Action`] is itself [`Complex`], so that closures can be given to the wrappers of
+/// actions, such as [`CircuitBreaker`](crate::CircuitBreaker).
+impl Complex for Action {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        Action::run(self, input, env)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        Action::fingerprint(self, fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        Action::is_blocking(self)
+    }
+
+    fn is_cacheable(&self) -> bool {
+        Action::is_cacheable(self)
+    }
 }
@@ -0,0 +1,198 @@
+//! File artifacts
+//!
+//! Tasks that exchange large files should not pass their bytes as outputs. Instead, a task
+//! writes the file into the run directory of the dag and outputs an [`Artifact`], a reference
+//! to the file, which its successors read from the disk.
+//!
+//! Every run of a dag has an [`ArtifactDir`] of its own, which its actions find in their
+//! [`EnvVar`] with [`ArtifactDir::from_env`]. The directory is created when the first artifact
+//! is, and removed according to its [`CleanupPolicy`] when the next run starts or the dag is
+//! dropped. By default it is a new directory in the temporary directory of the system, removed
+//! in any case; see [`Dag::with_artifacts`](crate::Dag::with_artifacts) to change this. A run
+//! of [`Dag::run_affected`](crate::Dag::run_affected) keeps the directory of the run before,
+//! whose artifacts the tasks that are not run again output.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{Artifact, ArtifactDir, Dag, DefaultTask, Output};
+//!
+//! let write = DefaultTask::with_closure("write", |_input, env| {
+//!     let artifact = ArtifactDir::from_env(&env).unwrap().create("data.csv").unwrap();
+//!     std::fs::write(artifact.path(), "a,b\n1,2\n").unwrap();
+//!     Output::new(artifact)
+//! });
+//! let mut read = DefaultTask::with_closure("read", |input, _env| {
+//!     let artifact = input.get_iter().next().unwrap().get::<Artifact>().unwrap();
+//!     Output::new(artifact.read_to_string().unwrap().lines().count())
+//! });
+//! read.set_predecessors(&[&write]);
+//! let mut dag = Dag::with_tasks(vec![write, read]);
+//! assert!(dag.start().unwrap());
+//! assert_eq!(*dag.get_result::<usize>().unwrap(), 2);
+//! ```
+
+use crate::EnvVar;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The key of the [`ArtifactDir`] of a dag in its [`EnvVar`].
+pub(crate) const ARTIFACT_DIR_KEY: &str = "__dagrs_artifact_dir";
+
+/// A file in the [`ArtifactDir`] of a dag, output by a task. Its text form, used for example
+/// by the placeholders of a [`CommandAction`](crate::CommandAction), is its path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    path: PathBuf,
+}
+
+impl Artifact {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        fs::read(&self.path)
+    }
+
+    pub fn read_to_string(&self) -> io::Result<String> {
+        fs::read_to_string(&self.path)
+    }
+
+    /// Copy the file to the given path, for example to keep it after the run directory is
+    /// removed.
+    pub fn copy_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::copy(&self.path, path).map(|_| ())
+    }
+
+    /// Make the file available at the given path, as a hard link, or as a copy if the path is
+    /// on another file system.
+    pub fn link_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        link_or_copy(&self.path, path.as_ref())
+    }
+}
+
+/// When the [`ArtifactDir`] of a run is removed, once the next run starts or the dag is
+/// dropped, or the scratch directory of a [`CommandAction`](crate::CommandAction), once its
+/// command finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CleanupPolicy {
+    #[default]
+    Always,
+    /// Keep the artifacts of a failed run for debugging.
+    OnSuccess,
+    Never,
+}
+
+/// How [`ArtifactDir::import`] brings a file into the directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Copy,
+    /// A hard link, or a copy if the file is on another file system.
+    Link,
+}
+
+/// The run directory holding the [`Artifact`]s of a dag. See the [module documentation](self).
+#[derive(Debug)]
+pub struct ArtifactDir {
+    path: PathBuf,
+    cleanup: CleanupPolicy,
+    /// Whether the run succeeded.
+    succeeded: AtomicBool,
+}
+
+impl ArtifactDir {
+    /// A run directory that will be created in the given parent directory.
+    pub fn new(parent: impl AsRef<Path>, cleanup: CleanupPolicy) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "dagrs-run-{}-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis())
+                .unwrap_or_default(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        Self {
+            path: parent.as_ref().join(name),
+            cleanup,
+            succeeded: AtomicBool::new(false),
+        }
+    }
+
+    /// The run directory of the run that provides the given environment to its actions.
+    pub fn from_env(env: &EnvVar) -> Option<&ArtifactDir> {
+        env.get_ref(ARTIFACT_DIR_KEY)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create an artifact with the given name, which may contain directories, and return it.
+    /// The caller writes the file at [`Artifact::path`].
+    pub fn create(&self, name: &str) -> io::Result<Artifact> {
+        let path = self.path.join(name);
+        fs::create_dir_all(path.parent().unwrap_or(&self.path))?;
+        Ok(Artifact { path })
+    }
+
+    /// Bring an existing file into the directory as an artifact with the given name.
+    pub fn import(
+        &self,
+        file: impl AsRef<Path>,
+        name: &str,
+        mode: ImportMode,
+    ) -> io::Result<Artifact> {
+        let artifact = self.create(name)?;
+        match mode {
+            ImportMode::Copy => fs::copy(file, &artifact.path).map(|_| ())?,
+            ImportMode::Link => link_or_copy(file.as_ref(), &artifact.path)?,
+        }
+        Ok(artifact)
+    }
+
+    /// A new run directory in the same parent directory, with the same cleanup policy.
+    pub(crate) fn next(&self) -> ArtifactDir {
+        ArtifactDir::new(
+            self.path.parent().unwrap_or_else(|| Path::new("")),
+            self.cleanup,
+        )
+    }
+
+    /// Record the result of the run, which decides whether the directory is removed.
+    pub(crate) fn finish(&self, success: bool) {
+        self.succeeded.store(success, Ordering::Relaxed);
+    }
+}
+
+impl Default for ArtifactDir {
+    /// A run directory in the temporary directory of the system, always removed.
+    fn default() -> Self {
+        Self::new(std::env::temp_dir(), CleanupPolicy::Always)
+    }
+}
+
+impl Drop for ArtifactDir {
+    fn drop(&mut self) {
+        let remove = match self.cleanup {
+            CleanupPolicy::Always => true,
+            CleanupPolicy::OnSuccess => self.succeeded.load(Ordering::Relaxed),
+            CleanupPolicy::Never => false,
+        };
+        if remove && self.path.exists() {
+            if let Err(err) = fs::remove_dir_all(&self.path) {
+                log::warn!("Failed to remove {}: {}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+fn link_or_copy(from: &Path, to: &Path) -> io::Result<()> {
+    fs::hard_link(from, to).or_else(|_| fs::copy(from, to).map(|_| ()))
+}
@@ -0,0 +1,179 @@
+use super::{layer, Action, ActionLayer, AsyncClosure, Complex, Condition, DefaultTask, Task};
+use crate::{
+    engine::{Execution, OverflowPolicy, TimeOfDay},
+    utils::Backoff,
+    EnvVar, Input, Output,
+};
+use std::{future::Future, path::PathBuf, sync::Arc, time::Duration};
+
+/// [`TaskBuilder`] creates a [`DefaultTask`] with all of its settings in one expression:
+/// its action and the layers around it, its predecessors, its execution settings and its
+/// condition. See [`DefaultTask::builder`].
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{CommandAction, CommandCondition, DefaultTask, LogLayer, Output};
+/// use dagrs::utils::Backoff;
+/// use std::time::Duration;
+///
+/// let build = DefaultTask::with_closure("build", |_input, _env| Output::empty());
+/// let deploy = DefaultTask::builder("deploy")
+///     .action(CommandAction::new("./deploy.sh"))
+///     .layer(LogLayer)
+///     .after(&[&build])
+///     .timeout(Duration::from_secs(300))
+///     .retries(2)
+///     .backoff(Backoff::exponential(Duration::from_secs(1), 2.0))
+///     .condition(CommandCondition::new("test \"$DEPLOY\" = yes"))
+///     .build();
+/// ```
+pub struct TaskBuilder {
+    task: DefaultTask,
+    layers: Vec<Box<dyn ActionLayer>>,
+}
+
+impl TaskBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            task: DefaultTask::new(name),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Provide a concrete type that implements the [`Complex`] trait as the action of the task.
+    pub fn action(mut self, action: impl Complex + Send + Sync + 'static) -> Self {
+        self.task.set_action(action);
+        self
+    }
+
+    /// Provide a closure as the action of the task.
+    pub fn closure(
+        mut self,
+        action: impl Fn(Input, Arc<EnvVar>) -> Output + Send + Sync + 'static,
+    ) -> Self {
+        self.task.set_closure(action);
+        self
+    }
+
+    /// Provide an async closure as the action of the task, see [`AsyncClosure`].
+    pub fn async_closure<F, Fut>(mut self, action: F) -> Self
+    where
+        F: Fn(Input, Arc<EnvVar>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Output> + Send,
+    {
+        self.task.set_action(AsyncClosure::new(action));
+        self
+    }
+
+    /// Wrap the action in a layer. The first layer is the outermost one.
+    pub fn layer(mut self, layer: impl ActionLayer + 'static) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Tasks that shall be executed before this one.
+    pub fn after<'a>(
+        mut self,
+        predecessors: impl IntoIterator<Item = &'a &'a DefaultTask>,
+    ) -> Self {
+        self.task.set_predecessors(predecessors);
+        self
+    }
+
+    /// Tasks that shall be executed before this one, by id.
+    pub fn after_ids(mut self, predecessors: impl IntoIterator<Item = usize>) -> Self {
+        self.task.set_predecessors_by_id(predecessors);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.task.set_timeout(timeout);
+        self
+    }
+
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.task.set_retries(retries);
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.task.set_backoff(backoff);
+        self
+    }
+
+    /// See [`DefaultTask::set_stall_timeout`].
+    pub fn stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.task.set_stall_timeout(stall_timeout);
+        self
+    }
+
+    /// See [`DefaultTask::set_max_output_size`].
+    pub fn max_output_size(mut self, max_output_size: usize, overflow: OverflowPolicy) -> Self {
+        self.task.set_max_output_size(max_output_size, overflow);
+        self
+    }
+
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.task.set_cache(cache);
+        self
+    }
+
+    pub fn execution(mut self, execution: Execution) -> Self {
+        self.task.set_execution(execution);
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.task.set_delay(delay);
+        self
+    }
+
+    /// Run the task only between the given times of day, see [`DefaultTask::set_window`].
+    pub fn window(mut self, not_before: Option<TimeOfDay>, not_after: Option<TimeOfDay>) -> Self {
+        self.task.set_window(not_before, not_after);
+        self
+    }
+
+    /// Run the task only if the given condition is true, see [`Condition`].
+    pub fn condition(mut self, condition: impl Condition + 'static) -> Self {
+        self.task.set_condition(condition);
+        self
+    }
+
+    /// Declare the files the task reads and writes, see [`TaskFiles`](super::TaskFiles).
+    pub fn files(
+        mut self,
+        inputs: impl IntoIterator<Item = impl Into<PathBuf>>,
+        outputs: impl IntoIterator<Item = impl Into<PathBuf>>,
+    ) -> Self {
+        self.task.set_files(inputs, outputs);
+        self
+    }
+
+    /// Hold the lock with the given name while the task runs, see [`Task::locks`].
+    pub fn lock(mut self, lock: &str) -> Self {
+        self.task.add_lock(lock);
+        self
+    }
+
+    /// Put the task in the pool with the given name and size, see [`TaskPool`](super::TaskPool).
+    pub fn pool(mut self, name: &str, size: usize) -> Self {
+        self.task.set_pool(name, size);
+        self
+    }
+
+    /// Give the action a type name, see [`Task::action_type`].
+    pub fn action_type(mut self, action_type: &str) -> Self {
+        self.task.set_action_type(action_type);
+        self
+    }
+
+    pub fn build(mut self) -> DefaultTask {
+        if !self.layers.is_empty() {
+            let action: Action = layer::apply_layers(self.task.action(), self.layers);
+            self.task.set_action_dyn(action);
+        }
+        self.task
+    }
+}
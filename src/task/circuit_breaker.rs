@@ -0,0 +1,134 @@
+use crate::{task::Content, Complex, EnvVar, Fingerprinter, Input, Output};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// The inner action runs. Counts the consecutive failures.
+    Closed(u32),
+    /// The inner action does not run, since the given instant.
+    Open(Instant),
+    /// One run of the inner action probes whether it recovered.
+    HalfOpen,
+}
+
+/// [`CircuitBreaker`] wraps an action that calls a flaky external system, such as a
+/// [`CommandAction`](crate::CommandAction) or an HTTP call, and stops calling it while it
+/// keeps failing.
+///
+/// The breaker is closed at first, and the inner action runs. After `threshold` consecutive
+/// failures, the breaker opens: runs fail immediately with an error, or return the last
+/// successful output if [`CircuitBreaker::fallback_to_last_output`] is set. After the
+/// `reset_timeout`, the breaker is half-open: the next run of the inner action is a probe,
+/// which closes the breaker if it succeeds and opens it again if it fails. Other runs during
+/// the probe fail immediately.
+///
+/// The state is kept across runs of the task and across dags, as long as the same breaker is
+/// used, for example through a clone of its [`Action`](crate::Action).
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{CircuitBreaker, CommandAction, DefaultTask};
+/// use std::time::Duration;
+///
+/// let fetch = CommandAction::new("curl -sf https://example.com/data.json");
+/// let breaker = CircuitBreaker::new(fetch, 3, Duration::from_secs(60));
+/// let task = DefaultTask::with_action("fetch", breaker);
+/// ```
+pub struct CircuitBreaker<A: Complex> {
+    inner: A,
+    threshold: u32,
+    reset_timeout: Duration,
+    fallback: bool,
+    state: Mutex<CircuitState>,
+    /// The output of the last successful run, if `fallback` is set.
+    last_output: Mutex<Option<Option<Content>>>,
+}
+
+impl<A: Complex> CircuitBreaker<A> {
+    pub fn new(inner: A, threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            inner,
+            threshold: threshold.max(1),
+            reset_timeout,
+            fallback: false,
+            state: Mutex::new(CircuitState::Closed(0)),
+            last_output: Mutex::new(None),
+        }
+    }
+
+    /// While the breaker is open, return the output of the last successful run instead of an
+    /// error, if there was one.
+    pub fn fallback_to_last_output(mut self) -> Self {
+        self.fallback = true;
+        self
+    }
+
+    pub fn state(&self) -> CircuitState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Whether the inner action may run now. Turns an expired open state into a probe.
+    fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            CircuitState::Closed(_) => true,
+            CircuitState::Open(since) if since.elapsed() >= self.reset_timeout => {
+                *state = CircuitState::HalfOpen;
+                true
+            }
+            CircuitState::Open(_) | CircuitState::HalfOpen => false,
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        *state = match (*state, success) {
+            (_, true) => CircuitState::Closed(0),
+            (CircuitState::Closed(failures), false) if failures + 1 < self.threshold => {
+                CircuitState::Closed(failures + 1)
+            }
+            (_, false) => CircuitState::Open(Instant::now()),
+        };
+    }
+}
+
+impl<A: Complex> Complex for CircuitBreaker<A> {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        if !self.allow() {
+            return match self.last_output.lock().unwrap().clone() {
+                Some(output) => Output::Out(output),
+                None => Output::error("Circuit breaker is open".to_owned()),
+            };
+        }
+        let output = match panic::catch_unwind(AssertUnwindSafe(|| self.inner.run(input, env))) {
+            Ok(output) => output,
+            Err(panic) => {
+                self.record(false);
+                panic::resume_unwind(panic)
+            }
+        };
+        self.record(!output.is_err());
+        if self.fallback && !output.is_err() {
+            *self.last_output.lock().unwrap() = Some(output.get_out());
+        }
+        output
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.inner.fingerprint(fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.inner.is_blocking()
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.inner.is_cacheable()
+    }
+}
@@ -1,12 +1,176 @@
-use crate::{Complex, EnvVar, Input, Output};
+use crate::{
+    utils::current_task, ArtifactDir, CleanupPolicy, Complex, EnvVar, Fingerprinter, Input, Output,
+};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::task::Content;
 
+/// The key of the setting of [`Dag::capture_command_output`](crate::Dag::capture_command_output)
+/// in the [`EnvVar`] of a dag: whether the captured output is also written to the console.
+pub(crate) const CAPTURE_OUTPUT_KEY: &str = "__dagrs_capture_output";
+
+/// The environment variable holding the scratch directory of a command.
+const SCRATCH_DIR_VAR: &str = "DAGRS_SCRATCH_DIR";
+
+/// The name of the artifact holding the stdout or stderr of the command of a task.
+pub(crate) fn output_artifact(id: usize, name: &str, stream: &str) -> String {
+    format!("output/{}.{}", file_name(id, name), stream)
+}
+
+/// A file name for the task with the given id and name.
+fn file_name(id: usize, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}-{}", id, name)
+}
+
+/// The scratch directory of the current task in the run directory of its dag, if it runs in
+/// a dag.
+fn scratch_dir(env: &EnvVar) -> Option<PathBuf> {
+    let (id, name) = current_task()?;
+    let dir = ArtifactDir::from_env(env)?;
+    Some(dir.path().join("scratch").join(file_name(id, &name)))
+}
+
+/// Create the scratch directory, empty, for a new run of the command.
+fn create_scratch_dir(dir: &Path) -> std::io::Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(dir)
+}
+
+/// Write the stdout and stderr of the command of the current task to artifacts, and to the
+/// console if `tee` is set.
+fn capture_output(dir: &ArtifactDir, stdout: &[u8], stderr: &[u8], tee: bool) {
+    let Some((id, name)) = current_task() else {
+        return;
+    };
+    for (stream, bytes) in [("stdout", stdout), ("stderr", stderr)] {
+        let written = dir
+            .create(&output_artifact(id, &name, stream))
+            .and_then(|artifact| std::fs::write(artifact.path(), bytes));
+        if let Err(err) = written {
+            log::warn!(
+                "Failed to capture the {} of task '{}': {}",
+                stream,
+                name,
+                err
+            );
+        }
+    }
+    if tee {
+        std::io::stdout().lock().write_all(stdout).ok();
+        std::io::stderr().lock().write_all(stderr).ok();
+    }
+}
+
+/// The shell that runs the command of a [`CommandAction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Sh,
+    Bash,
+    Zsh,
+    PowerShell,
+    Cmd,
+}
+
+impl Shell {
+    /// The program and the argument that makes it run a command.
+    pub(crate) fn program(&self) -> (&'static str, &'static str) {
+        match self {
+            Shell::Sh => ("sh", "-c"),
+            Shell::Bash => ("bash", "-c"),
+            Shell::Zsh => ("zsh", "-c"),
+            Shell::PowerShell => ("powershell", "-Command"),
+            Shell::Cmd => ("cmd", "/C"),
+        }
+    }
+}
+
+/// `powershell` on Windows, `sh` elsewhere.
+impl Default for Shell {
+    fn default() -> Self {
+        if cfg!(target_os = "windows") {
+            Shell::PowerShell
+        } else {
+            Shell::Sh
+        }
+    }
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sh" => Ok(Shell::Sh),
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "powershell" => Ok(Shell::PowerShell),
+            "cmd" => Ok(Shell::Cmd),
+            _ => Err(format!("Unknown shell '{}'.", s)),
+        }
+    }
+}
+
 /// [`CommandAction`] is a specific implementation of [`Complex`], used to execute operating system commands.
+///
+/// The command may contain placeholders that are replaced before it runs:
+/// - `{{ inputs.<name> }}`: the output of the predecessor task with the given name, which must
+///   be text, or the stdout of a predecessor that runs a command.
+/// - `{{ env.<key> }}`: the environment variable set for the command with
+///   [`CommandAction::with_env`], or else the text value of the variable in the dag's
+///   [`EnvVar`], or else the environment variable of the process.
+/// - `{{ scratch }}`: the scratch directory of the task.
+///
+/// The values are quoted for the shell, so that each one is read as a single piece of text
+/// whether the placeholder is outside or inside quotes: `cat {{ inputs.a }}` and
+/// `echo "v{{ inputs.a }}"` both read the output of `a` as is, even if it holds spaces, quotes
+/// or `$(…)`. The command fails if a placeholder cannot be replaced, and other `{{ … }}`,
+/// such as the Go templates of `docker ps --format '{{.Names}}'`, are kept as they are.
+///
+/// # Scratch directories
+///
+/// Each task running a command in a dag gets a scratch directory of its own, `scratch/<id>-<name>`
+/// in the run directory of the dag (see [`ArtifactDir`]), so that commands that run
+/// concurrently do not overwrite each other's temporary files. The directory is emptied each
+/// time the command starts, and is given to the command as the `DAGRS_SCRATCH_DIR`
+/// environment variable and the `{{ scratch }}` placeholder. It is removed with the run
+/// directory, or when the command finishes with [`CommandAction::with_scratch_cleanup`].
+/// [`CommandAction::in_scratch_dir`] runs the command in it.
+///
+/// ```rust
+/// use dagrs::{CleanupPolicy, CommandAction};
+///
+/// let build = CommandAction::new("tar xf {{ inputs.fetch }} && make")
+///     .in_scratch_dir()
+///     .with_scratch_cleanup(CleanupPolicy::OnSuccess);
+/// ```
 pub struct CommandAction {
     command: String,
+    /// The shell that runs the command.
+    shell: Shell,
+    /// Environment variables set for the command.
+    envs: Vec<(String, String)>,
+    /// The working directory of the command, the one of the process if unset.
+    dir: Option<PathBuf>,
+    /// Run the command in its scratch directory.
+    in_scratch: bool,
+    /// When the scratch directory is removed once the command finished.
+    scratch_cleanup: CleanupPolicy,
 }
 
 impl CommandAction {
@@ -14,30 +178,81 @@ impl CommandAction {
     pub fn new(cmd: &str) -> Self {
         Self {
             command: cmd.to_owned(),
+            shell: Shell::default(),
+            envs: Vec::new(),
+            dir: None,
+            in_scratch: false,
+            scratch_cleanup: CleanupPolicy::Never,
         }
     }
+
+    /// Run the command in the given shell instead of the platform's default shell.
+    pub fn with_shell(mut self, shell: Shell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Set an environment variable for the command.
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.envs.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Run the command in the given working directory, against which its relative paths are
+    /// resolved.
+    pub fn with_current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Run the command in its scratch directory, instead of its current directory, which then
+    /// only resolves the paths given to the command by its placeholders.
+    pub fn in_scratch_dir(mut self) -> Self {
+        self.in_scratch = true;
+        self
+    }
+
+    /// Remove the scratch directory when the command finishes, always or only if it succeeds,
+    /// instead of with the run directory of the dag.
+    pub fn with_scratch_cleanup(mut self, cleanup: CleanupPolicy) -> Self {
+        self.scratch_cleanup = cleanup;
+        self
+    }
 }
 
 impl Complex for CommandAction {
-    fn run(&self, input: Input, _env: Arc<EnvVar>) -> Output {
-        let mut args = Vec::new();
-        let mut cmd = if cfg!(target_os = "windows") {
-            args.push("-Command");
-            Command::new("powershell")
-        } else {
-            args.push("-c");
-            Command::new("sh")
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let command = match render_command(&self.command, self.shell, &self.envs, &input, &env) {
+            Ok(command) => command,
+            Err(err) => return Output::error(err),
         };
-        args.push(&self.command);
-
-        input.get_iter().for_each(|input| {
-            if let Some(inp) = input.get::<String>() {
-                args.push(inp)
+        let (program, arg) = self.shell.program();
+        let mut cmd = Command::new(program);
+        if let Some(dir) = &self.dir {
+            cmd.current_dir(dir);
+        }
+        let scratch = scratch_dir(&env);
+        if let Some(scratch) = &scratch {
+            if let Err(err) = create_scratch_dir(scratch) {
+                return Output::error(format!(
+                    "Failed to create the scratch directory {}: {}",
+                    scratch.display(),
+                    err
+                ));
+            }
+            cmd.env(SCRATCH_DIR_VAR, scratch);
+            if self.in_scratch {
+                cmd.current_dir(scratch);
             }
-        });
+        }
+        let args = [arg, &command];
 
-        log::debug!("cmd: {:?}, args: {:?}", cmd.get_program(), args);
-        let (code, out) = match cmd.args(args).output() {
+        log::debug!(
+            "cmd: {:?}, args: {:?}",
+            cmd.get_program(),
+            [arg, &env.redact(&command)]
+        );
+        let (code, out) = match cmd.args(args).envs(self.envs.iter().cloned()).output() {
             Ok(o) => (0, o),
             Err(e) => {
                 return Output::error_with_exit_code(
@@ -46,6 +261,12 @@ impl Complex for CommandAction {
                 )
             }
         };
+        if let (Some(tee), Some(dir)) = (
+            env.get::<bool>(CAPTURE_OUTPUT_KEY),
+            ArtifactDir::from_env(&env),
+        ) {
+            capture_output(dir, &out.stdout, &out.stderr, tee);
+        }
         let stdout: Vec<String> = {
             let out = String::from_utf8(out.stdout).unwrap_or("".to_string());
             if cfg!(target_os = "windows") {
@@ -63,10 +284,179 @@ impl Complex for CommandAction {
             }
         };
         let output = Content::new((stdout, stderr));
+        let remove_scratch = match self.scratch_cleanup {
+            CleanupPolicy::Always => true,
+            CleanupPolicy::OnSuccess => out.status.success(),
+            CleanupPolicy::Never => false,
+        };
+        if let Some(scratch) = scratch.filter(|_| remove_scratch) {
+            if let Err(err) = std::fs::remove_dir_all(&scratch) {
+                log::warn!("Failed to remove {}: {}", scratch.display(), err);
+            }
+        }
         if out.status.success() {
             Output::new(output)
         } else {
             Output::error_with_exit_code(Some(code), Some(output))
         }
     }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        fingerprint
+            .command(&self.command)
+            .bytes("shell", self.shell.program().0.as_bytes());
+        for (key, value) in &self.envs {
+            fingerprint.env(key, Some(value));
+        }
+        if let Some(dir) = &self.dir {
+            fingerprint.bytes("dir", dir.to_string_lossy().as_bytes());
+        }
+        if self.in_scratch {
+            fingerprint.bytes("in_scratch", &[]);
+        }
+    }
+}
+
+/// Replace the `{{ inputs.<name> }}`, `{{ env.<key> }}` and `{{ scratch }}` placeholders of a
+/// template given to an action. Other placeholders are kept as they are.
+#[cfg(feature = "s3")]
+pub(crate) fn render(template: &str, input: &Input, env: &EnvVar) -> Result<String, String> {
+    render_with(template, &[], input, env, |_, value| Ok(value.to_owned()))
+}
+
+/// Replace the placeholders of a command like [`render`], quoting their values so that the
+/// shell reads each one as text, whether the placeholder is outside or inside quotes.
+/// The `{{ env.<key> }}` placeholders take first the variables set for the command.
+pub(crate) fn render_command(
+    command: &str,
+    shell: Shell,
+    envs: &[(String, String)],
+    input: &Input,
+    env: &EnvVar,
+) -> Result<String, String> {
+    let mut quotes = Quotes::None;
+    render_with(command, envs, input, env, |literal, value| {
+        quotes = quotes.after(shell, literal);
+        quotes.quote(shell, value)
+    })
+}
+
+/// Replace the known placeholders of a template by their values, as given by `quote` from the
+/// text of the template since the previous placeholder and the value.
+fn render_with(
+    template: &str,
+    envs: &[(String, String)],
+    input: &Input,
+    env: &EnvVar,
+    mut quote: impl FnMut(&str, &str) -> Result<String, String>,
+) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut literal = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let placeholder = rest[start + 2..start + end].trim();
+        let value = if let Some(name) = placeholder.strip_prefix("inputs.") {
+            input
+                .get_by_name(name)
+                .and_then(Content::as_text)
+                .ok_or_else(|| format!("No text input from task '{}'", name))?
+        } else if placeholder == "scratch" {
+            scratch_dir(env)
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .ok_or_else(|| "No scratch directory outside of a dag".to_owned())?
+        } else if let Some(key) = placeholder.strip_prefix("env.") {
+            // The last one set wins, as for the process.
+            envs.iter()
+                .rev()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+                .or_else(|| env.get_content(key).and_then(Content::as_text))
+                .or_else(|| std::env::var(key).ok())
+                .ok_or_else(|| format!("No environment variable '{}'", key))?
+        } else {
+            // Not ours, such as the `{{ .Names }}` of a Go template given to a program.
+            rendered.push_str(&rest[..start + end + 2]);
+            rest = &rest[start + end + 2..];
+            continue;
+        };
+        rendered.push_str(&rest[..start]);
+        let value = quote(&rendered[literal..], &value)?;
+        rendered.push_str(&value);
+        literal = rendered.len();
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// The quotes a shell is in at some point of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quotes {
+    None,
+    Single,
+    Double,
+}
+
+impl Quotes {
+    /// The quotes the shell is in after the given text of the command.
+    fn after(mut self, shell: Shell, text: &str) -> Self {
+        let escape = match shell {
+            Shell::Sh | Shell::Bash | Shell::Zsh => '\\',
+            Shell::PowerShell => '`',
+            Shell::Cmd => '^',
+        };
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            self = match (self, c) {
+                // cmd does not escape inside quotes.
+                (Quotes::None, c) | (Quotes::Double, c)
+                    if c == escape && (self == Quotes::None || shell != Shell::Cmd) =>
+                {
+                    chars.next();
+                    self
+                }
+                (Quotes::None, '\'') if shell != Shell::Cmd => Quotes::Single,
+                (Quotes::None, '"') => Quotes::Double,
+                (Quotes::Single, '\'') => Quotes::None,
+                (Quotes::Double, '"') => Quotes::None,
+                _ => self,
+            };
+        }
+        self
+    }
+
+    /// The value quoted to be read as text by the shell at this point of a command.
+    fn quote(self, shell: Shell, value: &str) -> Result<String, String> {
+        Ok(match (shell, self) {
+            (Shell::Sh | Shell::Bash | Shell::Zsh, Quotes::None) => {
+                format!("'{}'", value.replace('\'', r"'\''"))
+            }
+            (Shell::Sh | Shell::Bash | Shell::Zsh, Quotes::Single) => value.replace('\'', r"'\''"),
+            (Shell::Sh | Shell::Bash | Shell::Zsh, Quotes::Double) => escape(value, "\\$`\"", '\\'),
+            (Shell::PowerShell, Quotes::None) => format!("'{}'", value.replace('\'', "''")),
+            (Shell::PowerShell, Quotes::Single) => value.replace('\'', "''"),
+            (Shell::PowerShell, Quotes::Double) => escape(value, "`$\"", '`'),
+            // cmd has no way to escape a quote inside quotes, nor `%` anywhere.
+            (Shell::Cmd, _) if value.contains(['"', '%', '\n', '\r']) => {
+                return Err(format!("Cannot quote '{}' for cmd", value))
+            }
+            (Shell::Cmd, Quotes::Double) => value.to_owned(),
+            (Shell::Cmd, _) => format!("\"{}\"", value),
+        })
+    }
+}
+
+/// The value with the given characters prefixed by the escape character.
+fn escape(value: &str, special: &str, escape: char) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(c) {
+            escaped.push(escape);
+        }
+        escaped.push(c);
+    }
+    escaped
 }
@@ -0,0 +1,437 @@
+//! Combinators of actions
+//!
+//! Combinators build an action out of other actions, so that common patterns are expressed
+//! within a single task:
+//! - [`Fallback`] runs a second action if the first one fails.
+//! - [`Timeout`] and [`Retry`], built with the methods of [`ActionExt`], limit the running time
+//!   of an action and retry it, independently of the configuration of its task.
+//! - [`Seq`] and [`Par`] run several small steps in order or concurrently, without a task for
+//!   each of them.
+//! - [`Lazy`] builds an action only when its task is about to run.
+
+use crate::{
+    task::Content,
+    utils::{Backoff, LogContext},
+    Action, Complex, EnvVar, Fingerprinter, Input, Output,
+};
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+/// [`Fallback`] runs a primary action and, if it fails or times out, a secondary action with
+/// the same inputs: try the fast path, fall back to the slow path.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{CommandAction, DefaultTask, Fallback};
+/// use std::time::Duration;
+///
+/// let fetch = Fallback::new(
+///     CommandAction::new("curl -sf https://cache.example.com/data.json"),
+///     CommandAction::new("curl -sf https://origin.example.com/data.json"),
+/// )
+/// .primary_timeout(Duration::from_secs(2));
+/// let task = DefaultTask::with_action("fetch", fetch);
+/// ```
+pub struct Fallback<P, S> {
+    primary: Arc<P>,
+    secondary: S,
+    timeout: Option<Duration>,
+}
+
+impl<P, S> Fallback<P, S>
+where
+    P: Complex + Send + Sync + 'static,
+    S: Complex,
+{
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary: Arc::new(primary),
+            secondary,
+            timeout: None,
+        }
+    }
+
+    /// Run the secondary action if the primary does not finish within the given time. The
+    /// primary then keeps running in the background, and its output is discarded.
+    pub fn primary_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<P, S> Complex for Fallback<P, S>
+where
+    P: Complex + Send + Sync + 'static,
+    S: Complex,
+{
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let output = match self.timeout {
+            Some(timeout) => {
+                run_with_timeout(self.primary.clone(), input.clone(), env.clone(), timeout)
+            }
+            None => self.primary.run(input.clone(), env.clone()),
+        };
+        if !output.is_err() {
+            return output;
+        }
+        log::warn!(
+            "Primary action failed, running the fallback: {}",
+            output.get_err().unwrap_or_default()
+        );
+        self.secondary.run(input, env)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.primary.fingerprint(fingerprint.bytes("fallback", &[]));
+        self.secondary.fingerprint(fingerprint);
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.primary.is_blocking() || self.secondary.is_blocking()
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.primary.is_cacheable() && self.secondary.is_cacheable()
+    }
+}
+
+/// Extension methods that wrap an action, available on every [`Complex`] type, including
+/// [`Action`](crate::Action).
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{ActionExt, CommandAction, DefaultTask};
+/// use dagrs::utils::Backoff;
+/// use std::time::Duration;
+///
+/// let fetch = CommandAction::new("curl -sf https://example.com/data.json")
+///     .with_timeout(Duration::from_secs(5))
+///     .with_retries(3, Backoff::exponential(Duration::from_millis(100), 2.0));
+/// let task = DefaultTask::with_action("fetch", fetch);
+/// ```
+pub trait ActionExt: Complex + Sized {
+    /// Fail with an error if the action does not finish within the given time. The action then
+    /// keeps running in the background, and its output is discarded.
+    fn with_timeout(self, timeout: Duration) -> Timeout<Self> {
+        Timeout {
+            inner: Arc::new(self),
+            timeout,
+        }
+    }
+
+    /// Run the action up to `retries` more times while it fails, with the same inputs, waiting
+    /// for the delays of the given backoff in between. As the waits block the thread, an
+    /// action with retries and delays is blocking, see [`Complex::is_blocking`].
+    fn with_retries(self, retries: u32, backoff: Backoff) -> Retry<Self> {
+        Retry {
+            inner: self,
+            retries,
+            backoff,
+        }
+    }
+
+    /// Mark the action as blocking, so that its task runs it on a blocking thread, see
+    /// [`Complex::is_blocking`]. Plain synchronous logic, such as a CPU-bound computation,
+    /// then does not hold up the runtime of the dag.
+    fn blocking(self) -> Blocking<Self> {
+        Blocking(self)
+    }
+}
+
+impl<A: Complex> ActionExt for A {}
+
+/// An action that runs on a blocking thread. See [`ActionExt::blocking`].
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Action, ActionExt, DefaultTask, Output};
+/// use std::sync::Arc;
+///
+/// let checksum = Action::Closure(Arc::new(|_, _| {
+///     let sum: u64 = (0..10_000_000u64).sum();
+///     Output::new(sum)
+/// }));
+/// let task = DefaultTask::with_action("checksum", checksum.blocking());
+/// ```
+pub struct Blocking<A>(A);
+
+impl<A: Complex> Complex for Blocking<A> {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        self.0.run(input, env)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.0.fingerprint(fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.0.is_cacheable()
+    }
+}
+
+/// An action that fails if its inner action does not finish in time. See
+/// [`ActionExt::with_timeout`].
+pub struct Timeout<A> {
+    inner: Arc<A>,
+    timeout: Duration,
+}
+
+impl<A: Complex + Send + Sync + 'static> Complex for Timeout<A> {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        run_with_timeout(self.inner.clone(), input, env, self.timeout)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.inner.fingerprint(fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.inner.is_blocking()
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.inner.is_cacheable()
+    }
+}
+
+/// An action that retries its inner action while it fails. See [`ActionExt::with_retries`].
+pub struct Retry<A> {
+    inner: A,
+    retries: u32,
+    backoff: Backoff,
+}
+
+impl<A: Complex> Complex for Retry<A> {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let mut attempt = 0;
+        let result = self.backoff.retry(self.retries, || {
+            if attempt > 0 {
+                log::warn!("Retrying action, attempt {}/{}", attempt, self.retries);
+            }
+            attempt += 1;
+            let output = self.inner.run(input.clone(), env.clone());
+            if output.is_err() {
+                Err(output)
+            } else {
+                Ok(output)
+            }
+        });
+        result.unwrap_or_else(|output| output)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.inner.fingerprint(fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        let waits = self.retries > 0 && self.backoff != Backoff::default();
+        waits || self.inner.is_blocking()
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.inner.is_cacheable()
+    }
+}
+
+/// [`Seq`] runs its actions one after another. The first action receives the inputs of the
+/// task, and each next action receives the output of the previous one as its only input, or no
+/// input if it was empty. The output is the one of the last action; the sequence stops at the
+/// first action that fails, with its error.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Action, Complex, EnvVar, Input, Output, Seq};
+/// use std::sync::Arc;
+///
+/// let seq = Seq(vec![
+///     Action::Closure(Arc::new(|_input, _env| Output::new(2))),
+///     Action::Closure(Arc::new(|input: Input, _env| {
+///         let value = input.get_iter().next().unwrap().get::<i32>().unwrap();
+///         Output::new(value * 10)
+///     })),
+/// ]);
+/// let output = seq.run(Input::new(vec![]), Arc::new(EnvVar::new()));
+/// assert_eq!(output.get_out().unwrap().get::<i32>(), Some(&20));
+/// ```
+#[derive(Clone)]
+pub struct Seq(pub Vec<Action>);
+
+impl Complex for Seq {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let mut input = input;
+        let mut output = Output::empty();
+        for action in &self.0 {
+            output = action.run(input, env.clone());
+            if output.is_err() {
+                return output;
+            }
+            input = Input::new(output.get_out().into_iter().collect());
+        }
+        output
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        fingerprint.bytes("seq", &[]);
+        self.0
+            .iter()
+            .for_each(|action| action.fingerprint(fingerprint));
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.0.iter().any(Action::is_blocking)
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.0.iter().all(Action::is_cacheable)
+    }
+}
+
+/// [`Lazy`] builds its action with a factory the first time it runs, that is when its task is
+/// ready to run, and keeps it for the next runs. Asking whether it is blocking builds the action
+/// too, which its task does once it is ready to run, and so does its fingerprint, which a dag
+/// with a cache computes for all of its tasks when a run starts. In a large dag, the tasks that never run, for
+/// example because of a false condition or a failed predecessor, do not pay for building their
+/// actions. See [`DefaultTask::lazy`](crate::DefaultTask::lazy).
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Action, Complex, EnvVar, Input, Lazy, Output};
+/// use std::sync::Arc;
+///
+/// let action = Lazy::new(|| {
+///     let table: Vec<u64> = (0..1000).map(|i| i * i).collect();
+///     Action::Closure(Arc::new(move |_, _| Output::new(table[12])))
+/// });
+/// let output = action.run(Input::new(vec![]), Arc::new(EnvVar::new()));
+/// assert_eq!(output.get_out().unwrap().get::<u64>(), Some(&144));
+/// ```
+pub struct Lazy<A, F> {
+    factory: F,
+    action: OnceLock<A>,
+}
+
+impl<A, F> Lazy<A, F>
+where
+    A: Complex,
+    F: Fn() -> A,
+{
+    pub fn new(factory: F) -> Self {
+        Self {
+            factory,
+            action: OnceLock::new(),
+        }
+    }
+}
+
+impl<A, F> Complex for Lazy<A, F>
+where
+    A: Complex,
+    F: Fn() -> A,
+{
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        self.action.get_or_init(&self.factory).run(input, env)
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.action
+            .get_or_init(&self.factory)
+            .fingerprint(fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.action.get_or_init(&self.factory).is_blocking()
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.action.get_or_init(&self.factory).is_cacheable()
+    }
+}
+
+/// [`Par`] runs its actions concurrently, each on its own thread, with the inputs of the task.
+/// Its output is a `Vec<Option<Content>>` of the outputs of the actions, in their order. If an
+/// action fails, the first error in that order is the output.
+#[derive(Clone)]
+pub struct Par(pub Vec<Action>);
+
+impl Complex for Par {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let mut outputs: Vec<Output> = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .0
+                .iter()
+                .map(|action| {
+                    let (input, env) = (input.clone(), env.clone());
+                    let context = LogContext::current();
+                    scope.spawn(move || context.scope(|| action.run(input, env)))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Output::error("action panicked".to_owned()))
+                })
+                .collect()
+        });
+        if let Some(index) = outputs.iter().position(Output::is_err) {
+            return outputs.swap_remove(index);
+        }
+        Output::new(
+            outputs
+                .iter()
+                .map(Output::get_out)
+                .collect::<Vec<Option<Content>>>(),
+        )
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        fingerprint.bytes("par", &[]);
+        self.0
+            .iter()
+            .for_each(|action| action.fingerprint(fingerprint));
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.0.iter().any(Action::is_blocking)
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.0.iter().all(Action::is_cacheable)
+    }
+}
+
+/// Run an action in a new thread and wait at most `timeout` for its output. If the action
+/// does not finish in time, it keeps running in the background and an error is returned.
+pub(crate) fn run_with_timeout<A: Complex + Send + Sync + 'static>(
+    action: Arc<A>,
+    input: Input,
+    env: Arc<EnvVar>,
+    timeout: Duration,
+) -> Output {
+    let (sender, receiver) = mpsc::channel();
+    let context = LogContext::current();
+    thread::spawn(move || {
+        let _ = sender.send(context.scope(|| action.run(input, env)));
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(output) => output,
+        Err(RecvTimeoutError::Timeout) => Output::error(format!("timed out after {:?}", timeout)),
+        Err(RecvTimeoutError::Disconnected) => Output::error("action panicked".to_owned()),
+    }
+}
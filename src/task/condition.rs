@@ -0,0 +1,173 @@
+use super::{block_on_thread, cmd::render_command, Shell};
+use crate::{EnvVar, Input};
+use std::{
+    future::Future,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// A [`Condition`] decides whether a task runs. It is checked once the predecessors of the
+/// task finished, with their outputs. If it is false, the task is skipped, and so are the tasks
+/// that depend on it, without failing the run.
+///
+/// A closure from the inputs and the environment to a `bool` is a condition, and
+/// [`AsyncCondition`] turns an async closure into a [`Condition`], like an
+/// [`AsyncClosure`](crate::AsyncClosure) does for actions. The closure gets copies of the inputs
+/// and the environment, and its future runs on a runtime of its own. The condition is false if
+/// the runtime cannot be started.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{AsyncCondition, Condition, EnvVar, Input};
+///
+/// let condition = AsyncCondition::new(|_input, env: EnvVar| async move {
+///     env.get::<bool>("DEPLOY") == Some(true)
+/// });
+/// assert!(!condition.check(&Input::new(vec![]), &EnvVar::new()));
+/// ```
+pub struct AsyncCondition<F>(F);
+
+impl<F, Fut> AsyncCondition<F>
+where
+    F: Fn(Input, EnvVar) -> Fut + Send + Sync,
+    Fut: Future<Output = bool> + Send,
+{
+    pub fn new(closure: F) -> Self {
+        Self(closure)
+    }
+}
+
+impl<F, Fut> Condition for AsyncCondition<F>
+where
+    F: Fn(Input, EnvVar) -> Fut + Send + Sync,
+    Fut: Future<Output = bool> + Send,
+{
+    fn check(&self, input: &Input, env: &EnvVar) -> bool {
+        match block_on_thread((self.0)(input.clone(), env.clone())) {
+            Ok(check) => check,
+            Err(err) => {
+                log::warn!("Condition is false: failed to start a runtime: {}", err);
+                false
+            }
+        }
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+}
+
+/// [`CommandCondition`] runs a command. Give a condition to a task with
+/// [`DefaultTask::set_condition`](crate::DefaultTask::set_condition), or with the `if` attribute
+/// of a task in yaml.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Dag, DefaultTask, EnvVar, Input, Output};
+///
+/// let mut deploy = DefaultTask::with_closure("deploy", |_input, _env| Output::new(1));
+/// deploy.set_condition(|_input: &Input, env: &EnvVar| env.get::<bool>("DEPLOY") == Some(true));
+/// let mut dag = Dag::with_tasks(vec![deploy]);
+/// assert!(dag.start().unwrap());
+/// assert!(dag.get_result::<i32>().is_none());
+/// ```
+pub trait Condition: Send + Sync {
+    fn check(&self, input: &Input, env: &EnvVar) -> bool;
+
+    /// Whether the check blocks, for example on a process or on I/O, so that it is run on a
+    /// blocking thread instead of holding up the runtime of the dag. Not blocking by default.
+    fn is_blocking(&self) -> bool {
+        false
+    }
+}
+
+impl<F> Condition for F
+where
+    F: Fn(&Input, &EnvVar) -> bool + Send + Sync,
+{
+    fn check(&self, input: &Input, env: &EnvVar) -> bool {
+        self(input, env)
+    }
+}
+
+/// [`CommandCondition`] runs a command, and is true if it exits with code 0, like the `if` of a
+/// shell. The command may contain the placeholders of a [`CommandAction`](crate::CommandAction);
+/// it is false if they cannot be replaced or if it cannot be started.
+pub struct CommandCondition {
+    command: String,
+    shell: Shell,
+    /// Environment variables set for the command.
+    envs: Vec<(String, String)>,
+    /// The working directory of the command, the one of the process if unset.
+    dir: Option<PathBuf>,
+}
+
+impl CommandCondition {
+    pub fn new(cmd: &str) -> Self {
+        Self {
+            command: cmd.to_owned(),
+            shell: Shell::default(),
+            envs: Vec::new(),
+            dir: None,
+        }
+    }
+
+    /// Run the command in the given shell instead of the platform's default shell.
+    pub fn with_shell(mut self, shell: Shell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Set an environment variable for the command.
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.envs.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Run the command in the given working directory.
+    pub fn with_current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+}
+
+impl Condition for CommandCondition {
+    fn check(&self, input: &Input, env: &EnvVar) -> bool {
+        let command = match render_command(&self.command, self.shell, &self.envs, input, env) {
+            Ok(command) => command,
+            Err(err) => {
+                log::warn!("Condition is false: {}", err);
+                return false;
+            }
+        };
+        let (program, arg) = self.shell.program();
+        let mut cmd = Command::new(program);
+        if let Some(dir) = &self.dir {
+            cmd.current_dir(dir);
+        }
+        let status = cmd
+            .args([arg, &command])
+            .envs(self.envs.iter().cloned())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match status {
+            Ok(status) => status.success(),
+            Err(err) => {
+                log::warn!(
+                    "Condition '{}' could not run: {}",
+                    env.redact(&command),
+                    err
+                );
+                false
+            }
+        }
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+}
@@ -1,6 +1,13 @@
-use super::{Action, Complex, Task, ID_ALLOCATOR};
-use crate::{EnvVar, Input, Output};
-use std::sync::Arc;
+use super::{
+    layer, Action, ActionLayer, AsyncClosure, Complex, Condition, Lazy, Task, TaskBuilder,
+    TaskFiles, TaskPool, ID_ALLOCATOR,
+};
+use crate::{
+    engine::{Execution, OverflowPolicy, TaskConfig, TimeOfDay},
+    utils::Backoff,
+    EnvVar, Input, Output,
+};
+use std::{collections::HashMap, future::Future, path::PathBuf, sync::Arc, time::Duration};
 
 /// Common task types
 ///
@@ -68,6 +75,20 @@ pub struct DefaultTask {
     precursors: Vec<usize>,
     /// Perform specific actions.
     action: Action,
+    /// The name of the action type, see [`Task::action_type`].
+    action_type: Option<String>,
+    /// The arguments of the action type, see [`Task::action_args`].
+    action_args: HashMap<String, String>,
+    /// Execution settings that override the defaults of the dag.
+    config: TaskConfig,
+    /// Whether the task runs, see [`Task::condition`].
+    condition: Option<Arc<dyn Condition>>,
+    /// The files the task reads and writes, see [`Task::files`].
+    files: Option<TaskFiles>,
+    /// The locks the task holds while it runs, see [`Task::locks`].
+    locks: Vec<String>,
+    /// The pool the task belongs to, see [`Task::pool`].
+    pool: Option<TaskPool>,
 }
 
 impl DefaultTask {
@@ -79,6 +100,13 @@ impl DefaultTask {
             action: Action::Closure(Arc::new(action)),
             name: name.to_owned(),
             precursors: Vec::new(),
+            action_type: None,
+            action_args: HashMap::new(),
+            config: TaskConfig::default(),
+            condition: None,
+            files: None,
+            locks: Vec::new(),
+            pool: None,
         }
     }
     /// Create a task, give the task name, and provide a specific type that implements the [`Complex`] trait as the specific
@@ -95,9 +123,28 @@ impl DefaultTask {
             action: Action::Structure(action),
             name: name.to_owned(),
             precursors: Vec::new(),
+            action_type: None,
+            action_args: HashMap::new(),
+            config: TaskConfig::default(),
+            condition: None,
+            files: None,
+            locks: Vec::new(),
+            pool: None,
         }
     }
 
+    /// Create a task, give the task name, and provide a specific type that implements the [`Complex`] trait as the specific
+    /// execution logic of the task, wrapped in the given layers. The first layer is the outermost one.
+    pub fn with_layers(
+        name: &str,
+        action: impl Complex + Send + Sync + 'static,
+        layers: Vec<Box<dyn ActionLayer>>,
+    ) -> Self {
+        let mut task = Self::new(name);
+        task.action = layer::apply_layers(Action::Structure(Arc::new(action)), layers);
+        task
+    }
+
     /// Create a task, give the task name, and provide a closure as the specific execution logic of the task.
     pub fn with_closure(
         name: &str,
@@ -106,6 +153,22 @@ impl DefaultTask {
         Self::with_closure_dyn(name, Arc::new(action))
     }
 
+    /// Create a task, give the task name, and provide an async closure as the specific execution logic of the task.
+    /// See [`AsyncClosure`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::{DefaultTask, Output};
+    /// let task = DefaultTask::with_async_closure("fetch", |_input, _env| async { Output::new(1) });
+    /// ```
+    pub fn with_async_closure<F, Fut>(name: &str, action: F) -> Self
+    where
+        F: Fn(Input, Arc<EnvVar>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Output> + Send,
+    {
+        Self::with_action(name, AsyncClosure::new(action))
+    }
+
     /// Create a task, give the task name, and provide a closure as the specific execution logic of the task.
     pub fn with_closure_dyn(
         name: &str,
@@ -116,9 +179,42 @@ impl DefaultTask {
             action: Action::Closure(action),
             name: name.to_owned(),
             precursors: Vec::new(),
+            action_type: None,
+            action_args: HashMap::new(),
+            config: TaskConfig::default(),
+            condition: None,
+            files: None,
+            locks: Vec::new(),
+            pool: None,
         }
     }
 
+    /// Create a task whose action is built by `factory` only when the task is ready to run,
+    /// see [`Lazy`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::{CommandAction, DefaultTask};
+    ///
+    /// let tasks: Vec<DefaultTask> = (0..1000)
+    ///     .map(|i| DefaultTask::lazy(&format!("shard-{}", i), move || {
+    ///         CommandAction::new(&format!("echo {}", i))
+    ///     }))
+    ///     .collect();
+    /// ```
+    pub fn lazy<A, F>(name: &str, factory: F) -> Self
+    where
+        A: Complex + Send + Sync + 'static,
+        F: Fn() -> A + Send + Sync + 'static,
+    {
+        Self::with_action(name, Lazy::new(factory))
+    }
+
+    /// Start building a task with the given name, see [`TaskBuilder`].
+    pub fn builder(name: &str) -> TaskBuilder {
+        TaskBuilder::new(name)
+    }
+
     /// Give the task a name.
     pub fn set_name(&mut self, name: &str) {
         self.name = name.to_string();
@@ -160,6 +256,105 @@ impl DefaultTask {
     pub fn set_action(&mut self, action: impl Complex + Send + Sync + 'static) {
         self.action = Action::Structure(Arc::new(action))
     }
+
+    /// Provide an [`Action`] to specify execution logic for the task.
+    pub fn set_action_dyn(&mut self, action: Action) {
+        self.action = action;
+    }
+
+    /// Fail the task if its action runs longer than `timeout`, instead of using the default
+    /// timeout of the dag.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.config.timeout = Some(timeout);
+    }
+
+    /// Run the task's action up to `retries` more times if it fails, instead of using the
+    /// default number of retries of the dag.
+    pub fn set_retries(&mut self, retries: u32) {
+        self.config.retries = Some(retries);
+    }
+
+    /// Wait between the runs of the task's action with the given backoff, instead of the
+    /// default backoff of the dag.
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.config.backoff = Some(backoff);
+    }
+
+    /// Report the task as stalled if its action goes without a heartbeat for longer than
+    /// `stall_timeout`, instead of using the default of the dag, see
+    /// [`DagConfig::stall_timeout`](crate::engine::DagConfig::stall_timeout).
+    pub fn set_stall_timeout(&mut self, stall_timeout: Duration) {
+        self.config.stall_timeout = Some(stall_timeout);
+    }
+
+    /// Limit the size of the output of the task, instead of using the default limit of the
+    /// dag, see [`DagConfig::max_output_size`](crate::engine::DagConfig::max_output_size).
+    pub fn set_max_output_size(&mut self, max_output_size: usize, overflow: OverflowPolicy) {
+        self.config.max_output_size = Some(max_output_size);
+        self.config.output_overflow = Some(overflow);
+    }
+
+    /// Look up the output of the task in the cache of the dag, and store it there, or not,
+    /// see [`Dag::with_cache`](crate::Dag::with_cache). Tasks are cached by default.
+    pub fn set_cache(&mut self, cache: bool) {
+        self.config.cache = Some(cache);
+    }
+
+    /// Run the action of the task inline or on a blocking thread, see [`Execution`].
+    pub fn set_execution(&mut self, execution: Execution) {
+        self.config.execution = Some(execution);
+    }
+
+    /// Wait for the given time once the predecessors of the task finished, before running it.
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.config.delay = Some(delay);
+    }
+
+    /// Run the task only between the given times of day, see [`TaskConfig::not_before`] and
+    /// [`TaskConfig::not_after`].
+    pub fn set_window(&mut self, not_before: Option<TimeOfDay>, not_after: Option<TimeOfDay>) {
+        self.config.not_before = not_before;
+        self.config.not_after = not_after;
+    }
+
+    /// Run the task only if the given condition is true, see [`Condition`].
+    pub fn set_condition(&mut self, condition: impl Condition + 'static) {
+        self.condition = Some(Arc::new(condition));
+    }
+
+    /// Declare the files the task reads and writes. The task does not run if its outputs are
+    /// up to date with its inputs, see [`TaskFiles`].
+    pub fn set_files(
+        &mut self,
+        inputs: impl IntoIterator<Item = impl Into<PathBuf>>,
+        outputs: impl IntoIterator<Item = impl Into<PathBuf>>,
+    ) {
+        self.files = Some(TaskFiles::new(inputs, outputs));
+    }
+
+    /// Hold the lock with the given name while the task runs, so that it never runs at the
+    /// same time as other tasks holding it, see [`Task::locks`].
+    pub fn add_lock(&mut self, lock: &str) {
+        self.locks.push(lock.to_owned());
+    }
+
+    /// Put the task in the pool with the given name and size, so that at most `size` tasks of
+    /// the pool run at the same time, see [`TaskPool`].
+    pub fn set_pool(&mut self, name: &str, size: usize) {
+        self.pool = Some(TaskPool::new(name, size));
+    }
+
+    /// Give the task's action a type name, under which it can be found in an
+    /// [`ActionRegistry`](super::ActionRegistry). See [`Task::action_type`].
+    pub fn set_action_type(&mut self, action_type: &str) {
+        self.action_type = Some(action_type.to_owned());
+    }
+
+    /// Record the arguments the action was created with from its action type. See
+    /// [`Task::action_args`].
+    pub fn set_action_args(&mut self, args: HashMap<String, String>) {
+        self.action_args = args;
+    }
 }
 
 impl Task for DefaultTask {
@@ -178,6 +373,32 @@ impl Task for DefaultTask {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn action_type(&self) -> Option<&str> {
+        self.action_type.as_deref()
+    }
+    fn action_args(&self) -> Option<&HashMap<String, String>> {
+        (!self.action_args.is_empty()).then_some(&self.action_args)
+    }
+    fn config(&self) -> TaskConfig {
+        self.config
+    }
+
+    fn condition(&self) -> Option<Arc<dyn Condition>> {
+        self.condition.clone()
+    }
+
+    fn files(&self) -> Option<&TaskFiles> {
+        self.files.as_ref()
+    }
+
+    fn locks(&self) -> &[String] {
+        &self.locks
+    }
+
+    fn pool(&self) -> Option<&TaskPool> {
+        self.pool.as_ref()
+    }
 }
 
 impl Default for DefaultTask {
@@ -190,6 +411,13 @@ impl Default for DefaultTask {
             name,
             precursors: Vec::new(),
             action: Action::Closure(Arc::new(action)),
+            action_type: None,
+            action_args: HashMap::new(),
+            config: TaskConfig::default(),
+            condition: None,
+            files: None,
+            locks: Vec::new(),
+            pool: None,
         }
     }
 }
@@ -0,0 +1,62 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The files a task reads and writes. A task that declares them is up to date, and does not
+/// run, if all its outputs exist and none of them is older than any of its inputs, like a
+/// target of `make`. An up to date task counts as succeeded for its successors, with an empty
+/// output, and is reported as [`TaskStatus::UpToDate`](crate::engine::TaskStatus::UpToDate).
+///
+/// A task without outputs always runs, and so does a task with a missing input, so that its
+/// action reports the missing file.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{CommandAction, DefaultTask};
+///
+/// let mut task = DefaultTask::with_action("compile", CommandAction::new("cc -c main.c"));
+/// task.set_files(["main.c", "main.h"], ["main.o"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskFiles {
+    pub inputs: Vec<PathBuf>,
+    pub outputs: Vec<PathBuf>,
+}
+
+impl TaskFiles {
+    pub fn new(
+        inputs: impl IntoIterator<Item = impl Into<PathBuf>>,
+        outputs: impl IntoIterator<Item = impl Into<PathBuf>>,
+    ) -> Self {
+        Self {
+            inputs: inputs.into_iter().map(Into::into).collect(),
+            outputs: outputs.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Whether all outputs exist and none of them is older than any of the inputs.
+    pub fn up_to_date(&self) -> bool {
+        let Some(inputs) = self
+            .inputs
+            .iter()
+            .map(|path| modified(path))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return false;
+        };
+        // A missing output is `None`, which is less than any time.
+        match self.outputs.iter().map(|path| modified(path)).min() {
+            Some(Some(oldest)) => inputs.iter().all(|input| *input <= oldest),
+            _ => false,
+        }
+    }
+}
+
+/// The time the file was last modified, `None` if it does not exist.
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
@@ -0,0 +1,171 @@
+use crate::EnvVar;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io,
+    path::Path,
+    str::FromStr,
+};
+
+/// The version of the encoding of the items of a fingerprint. It only changes with the
+/// encoding, so that fingerprints stored by one version of dagrs stay valid in the next ones.
+const VERSION: &[u8] = b"dagrs-fingerprint-v1";
+
+/// A [`Fingerprint`] identifies everything that determines the output of a task: the command it
+/// runs, the contents of the files it reads, the environment variables it uses and the
+/// fingerprints of the tasks it depends on. Two runs of a task with the same fingerprint
+/// produce the same output, so that the fingerprint is the key of the task's output in a cache.
+///
+/// A fingerprint is a SHA-256 hash, written as 64 lowercase hexadecimal digits in its
+/// serialized and displayed forms. It is computed with a [`Fingerprinter`], or for all tasks of
+/// a dag with [`Dag::fingerprints`](crate::Dag::fingerprints).
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Fingerprint, Fingerprinter};
+///
+/// let fetch = Fingerprinter::new().command("curl -sf https://example.com").finish();
+/// let parse = Fingerprinter::new()
+///     .command("jq .items")
+///     .upstream(&fetch)
+///     .finish();
+/// assert_ne!(fetch, parse);
+/// assert_eq!(parse.to_string().parse::<Fingerprint>().unwrap(), parse);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fingerprint({})", self)
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid fingerprint '{}'.", s);
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(invalid());
+        }
+        let mut bytes = [0; 32];
+        for (byte, digits) in bytes.iter_mut().zip(s.as_bytes().chunks(2)) {
+            let digits = std::str::from_utf8(digits).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for Fingerprint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fingerprint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Computes a [`Fingerprint`] from the items given to it, in order. Each item is hashed with
+/// its kind and its length, so that different items never run together into the same bytes.
+#[derive(Clone)]
+pub struct Fingerprinter {
+    hasher: Sha256,
+}
+
+impl Default for Fingerprinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fingerprinter {
+    pub fn new() -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(VERSION);
+        Self { hasher }
+    }
+
+    /// Add the command the task runs.
+    pub fn command(&mut self, command: &str) -> &mut Self {
+        self.item(b'c', command.as_bytes())
+    }
+
+    /// Add the path and the contents of a file the task reads. Fails if the file cannot be read.
+    pub fn file(&mut self, path: impl AsRef<Path>) -> io::Result<&mut Self> {
+        let path = path.as_ref();
+        let mut contents = Sha256::new();
+        io::copy(&mut File::open(path)?, &mut contents)?;
+        self.item(b'f', path.to_string_lossy().as_bytes());
+        Ok(self.item(b'h', &contents.finalize()))
+    }
+
+    /// Add an environment variable of the task, with its value, or without one if it is unset.
+    pub fn env(&mut self, name: &str, value: Option<&str>) -> &mut Self {
+        self.item(b'e', name.as_bytes());
+        match value {
+            Some(value) => self.item(b'v', value.as_bytes()),
+            None => self.item(b'n', &[]),
+        }
+    }
+
+    /// Add variables of the environment of a dag. Variables that are not strings or bytes
+    /// count as unset.
+    pub fn env_vars<'a>(
+        &mut self,
+        env: &EnvVar,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> &mut Self {
+        for name in names {
+            self.item(b'e', name.as_bytes());
+            match env.get_content(name).and_then(|content| content.payload()) {
+                Some(value) => self.item(b'v', value),
+                None => self.item(b'n', &[]),
+            };
+        }
+        self
+    }
+
+    /// Add the fingerprint of a task this one depends on.
+    pub fn upstream(&mut self, fingerprint: &Fingerprint) -> &mut Self {
+        self.item(b'u', fingerprint.as_bytes())
+    }
+
+    /// Add anything else that determines the output of the task, under a label of its own.
+    pub fn bytes(&mut self, label: &str, bytes: &[u8]) -> &mut Self {
+        self.item(b'l', label.as_bytes());
+        self.item(b'b', bytes)
+    }
+
+    pub fn finish(&self) -> Fingerprint {
+        Fingerprint(self.hasher.clone().finalize().into())
+    }
+
+    fn item(&mut self, kind: u8, bytes: &[u8]) -> &mut Self {
+        self.hasher.update([kind]);
+        self.hasher.update((bytes.len() as u64).to_le_bytes());
+        self.hasher.update(bytes);
+        self
+    }
+}
@@ -0,0 +1,94 @@
+//! Layers of actions
+//!
+//! An [`ActionLayer`] wraps an [`Action`] into another one that adds some behavior around it,
+//! such as logging, timing, retries or timeouts, like the layers of `tower` wrap services.
+//! Layers are stacked around the action of a task with [`DefaultTask::with_layers`], so that
+//! cross-cutting behavior is written once and applied to any action.
+//!
+//! A closure from [`Action`] to [`Action`] is a layer.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{Action, ActionLayer, DefaultTask, LogLayer, Output};
+//! use std::sync::Arc;
+//!
+//! // A layer that turns empty outputs into errors.
+//! let non_empty = |inner: Action| {
+//!     Action::Closure(Arc::new(move |input, env| match inner.run(input, env) {
+//!         Output::Out(None) => Output::error("empty output".to_string()),
+//!         output => output,
+//!     }))
+//! };
+//! let task = DefaultTask::with_layers(
+//!     "fetch",
+//!     Action::Closure(Arc::new(|_input, _env| Output::new(1))),
+//!     vec![Box::new(LogLayer), Box::new(non_empty)],
+//! );
+//! ```
+
+use crate::{Action, Complex, EnvVar, Fingerprinter, Input, Output};
+use std::{sync::Arc, time::Instant};
+
+/// Wraps an [`Action`] into another one. See the [module documentation](self).
+pub trait ActionLayer: Send + Sync {
+    fn layer(&self, inner: Action) -> Action;
+}
+
+impl<F> ActionLayer for F
+where
+    F: Fn(Action) -> Action + Send + Sync,
+{
+    fn layer(&self, inner: Action) -> Action {
+        self(inner)
+    }
+}
+
+/// Wrap an action in the given layers. The first layer is the outermost one: it runs first,
+/// and sees the output of all the others.
+pub(crate) fn apply_layers(action: Action, layers: Vec<Box<dyn ActionLayer>>) -> Action {
+    layers
+        .iter()
+        .rev()
+        .fold(action, |action, layer| layer.layer(action))
+}
+
+/// [`LogLayer`] logs when the action starts and finishes, with its duration and its error if
+/// it failed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogLayer;
+
+impl ActionLayer for LogLayer {
+    fn layer(&self, inner: Action) -> Action {
+        Action::Structure(Arc::new(Logged { inner }))
+    }
+}
+
+struct Logged {
+    inner: Action,
+}
+
+impl Complex for Logged {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        log::info!("Action started");
+        let start = Instant::now();
+        let output = self.inner.run(input, env);
+        match output.get_err() {
+            Some(err) => log::error!("Action failed after {:.2?}: {}", start.elapsed(), err),
+            None => log::info!("Action finished in {:.2?}", start.elapsed()),
+        }
+        output
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        self.inner.fingerprint(fingerprint)
+    }
+
+    fn is_blocking(&self) -> bool {
+        self.inner.is_blocking()
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.inner.is_cacheable()
+    }
+}
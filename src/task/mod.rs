@@ -24,20 +24,65 @@
 //! Each task may produce output and may require the output of its predecessor task as its input.
 //! [`Output`] is used to construct and store the output obtained by task execution. [`Input`] is used as a tool
 //! to provide users with the output of the predecessor task.
+use crate::engine::TaskConfig;
+use crate::utils::LogContext;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::io;
 use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 
-pub use self::action::{Action, Complex, Simple};
-pub use self::cmd::CommandAction;
+pub use self::action::{Action, AsyncClosure, Complex, Simple, SyncAction, TypedAction};
+pub(crate) use self::artifact::ARTIFACT_DIR_KEY;
+pub use self::artifact::{Artifact, ArtifactDir, CleanupPolicy, ImportMode};
+pub use self::builder::TaskBuilder;
+pub use self::circuit_breaker::{CircuitBreaker, CircuitState};
+pub(crate) use self::cmd::{output_artifact, CAPTURE_OUTPUT_KEY};
+pub use self::cmd::{CommandAction, Shell};
+pub use self::combinator::{ActionExt, Blocking, Fallback, Lazy, Par, Retry, Seq, Timeout};
+pub use self::condition::{AsyncCondition, CommandCondition, Condition};
 pub use self::default_task::DefaultTask;
+pub use self::files::TaskFiles;
+pub use self::fingerprint::{Fingerprint, Fingerprinter};
+pub use self::layer::{ActionLayer, LogLayer};
+pub use self::pool::TaskPool;
+pub use self::registry::{ActionFactory, ActionRegistry};
+#[cfg(feature = "rhai")]
+pub use self::rhai::RhaiAction;
+#[cfg(feature = "s3")]
+pub(crate) use self::s3::client as s3_client;
+#[cfg(feature = "s3")]
+pub use self::s3::{S3DownloadAction, S3UploadAction};
+#[cfg(feature = "sql")]
+pub use self::sql::{SqlAction, SqlRows, SqlValue};
 pub use self::state::Content;
-pub(crate) use self::state::ExecState;
+#[cfg(feature = "bench")]
+pub(crate) use self::state::Timing;
+pub(crate) use self::state::{ExecState, ExecStateRef, ExecStates};
 pub use self::state::{Input, Output};
+pub use self::typed::TypedTask;
 
 mod action;
+mod artifact;
+mod builder;
+mod circuit_breaker;
 mod cmd;
+mod combinator;
+mod condition;
 mod default_task;
+mod files;
+mod fingerprint;
+mod layer;
+mod pool;
+mod registry;
+#[cfg(feature = "rhai")]
+mod rhai;
+#[cfg(feature = "s3")]
+mod s3;
+#[cfg(feature = "sql")]
+mod sql;
 mod state;
+mod typed;
 /// The Task trait
 ///
 /// Tasks can have many attributes, among which `id`, `name`, `predecessor_tasks`, and
@@ -56,6 +101,65 @@ pub trait Task: Send + Sync {
     fn id(&self) -> usize;
     /// Get the name of this task.
     fn name(&self) -> &str;
+    /// Get the name of this task's action type, if it has one. The name is used to look up
+    /// the action in an [`ActionRegistry`] when a dag is rebuilt from its
+    /// [`DagStructure`](crate::engine::DagStructure).
+    fn action_type(&self) -> Option<&str> {
+        None
+    }
+    /// Get the arguments the action was created with from its action type, if any. They are
+    /// passed to the action factory again when a dag is rebuilt from its
+    /// [`DagStructure`](crate::engine::DagStructure).
+    fn action_args(&self) -> Option<&HashMap<String, String>> {
+        None
+    }
+    /// Get the execution settings of this task. Settings that are not specified are inherited
+    /// from the [`DagConfig`](crate::engine::DagConfig) of the dag.
+    fn config(&self) -> TaskConfig {
+        TaskConfig::default()
+    }
+    /// Get the condition that decides whether this task runs, if it has one. See [`Condition`].
+    fn condition(&self) -> Option<Arc<dyn Condition>> {
+        None
+    }
+    /// Get the files this task reads and writes, if it declares them. A task whose outputs are
+    /// up to date with its inputs does not run, see [`TaskFiles`].
+    fn files(&self) -> Option<&TaskFiles> {
+        None
+    }
+    /// Get the names of the locks this task holds while it runs. Tasks that share a lock never
+    /// run at the same time, in any order, without an edge between them.
+    fn locks(&self) -> &[String] {
+        &[]
+    }
+    /// Get the pool this task belongs to, if any. At most as many tasks of a pool as its size
+    /// run at the same time.
+    fn pool(&self) -> Option<&TaskPool> {
+        None
+    }
+    /// Add what determines the output of this task, apart from the tasks it depends on, to its
+    /// [`Fingerprint`]: by default its name, its action type, what its action adds (see
+    /// [`Complex::fingerprint`]) and the contents of its input files (see [`Task::files`]).
+    /// Fails if an input file cannot be read.
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) -> io::Result<()> {
+        fingerprint.bytes("name", self.name().as_bytes());
+        if let Some(action_type) = self.action_type() {
+            fingerprint.bytes("type", action_type.as_bytes());
+        }
+        if let Some(args) = self.action_args() {
+            let mut args: Vec<_> = args.iter().collect();
+            args.sort();
+            for (key, value) in args {
+                fingerprint.bytes("arg", key.as_bytes());
+                fingerprint.bytes("value", value.as_bytes());
+            }
+        }
+        self.action().fingerprint(fingerprint);
+        for path in self.files().map_or(&[][..], |files| &files.inputs) {
+            fingerprint.file(path)?;
+        }
+        Ok(())
+    }
 }
 
 /// IDAllocator for DefaultTask
@@ -95,3 +199,27 @@ static ID_ALLOCATOR: IDAllocator = IDAllocator {
 pub fn alloc_id() -> usize {
     ID_ALLOCATOR.alloc()
 }
+
+/// Run a future to completion on a runtime of its own. Actions run inside the runtime of the
+/// dag, which cannot be blocked on, so the runtime runs in a new thread, in the log context of
+/// the current task.
+pub(crate) fn block_on_thread<F>(future: F) -> std::io::Result<F::Output>
+where
+    F: std::future::Future + Send,
+    F::Output: Send,
+{
+    let context = LogContext::current();
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                context.scope(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?;
+                    Ok(runtime.block_on(future))
+                })
+            })
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
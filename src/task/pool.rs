@@ -0,0 +1,28 @@
+/// A pool of tasks, such as all tasks that download files, that run at most `size` at a time,
+/// whatever else the dag runs at the same time. Tasks belong to a pool by its name, see
+/// [`Task::pool`](super::Task::pool). If the tasks of a dag give different sizes for the same
+/// pool, the smallest one applies.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{CommandAction, DefaultTask};
+///
+/// let mut task = DefaultTask::with_action("fetch", CommandAction::new("curl -O https://example.com/a"));
+/// task.set_pool("downloads", 4);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskPool {
+    pub name: String,
+    /// How many tasks of the pool may run at the same time. A size of 0 counts as 1.
+    pub size: usize,
+}
+
+impl TaskPool {
+    pub fn new(name: &str, size: usize) -> Self {
+        Self {
+            name: name.to_owned(),
+            size,
+        }
+    }
+}
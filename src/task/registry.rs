@@ -0,0 +1,67 @@
+//! Action registry
+//!
+//! An [`ActionRegistry`] maps action type names (such as `"http"` or `"my_custom"`) to
+//! factories that build an [`Action`]. It is used to reconstruct the actions of tasks that
+//! are described by data rather than code, for example when importing a
+//! [`DagStructure`](crate::engine::DagStructure).
+//!
+//! Factories receive the string arguments given for the task, so that one action type can
+//! be configured differently per task.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::{Action, ActionRegistry, CommandAction};
+//! use std::sync::Arc;
+//!
+//! let mut registry = ActionRegistry::new();
+//! registry.register("command", |args| {
+//!     let cmd = args.get("cmd").map(String::as_str).unwrap_or("true");
+//!     Action::Structure(Arc::new(CommandAction::new(cmd)))
+//! });
+//! assert!(registry.contains("command"));
+//! ```
+
+use super::Action;
+use std::{collections::HashMap, sync::Arc};
+
+/// Builds an [`Action`] from the string arguments given for a task.
+pub type ActionFactory = dyn Fn(&HashMap<String, String>) -> Action + Send + Sync;
+
+/// Factories of actions, keyed by action type name.
+#[derive(Clone, Default)]
+pub struct ActionRegistry {
+    factories: HashMap<String, Arc<ActionFactory>>,
+}
+
+impl std::fmt::Debug for ActionRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.factories.keys()).finish()
+    }
+}
+
+impl ActionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a factory under the given action type name, replacing any factory previously
+    /// registered under that name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&HashMap<String, String>) -> Action + Send + Sync + 'static,
+    ) {
+        self.factories.insert(name.to_owned(), Arc::new(factory));
+    }
+
+    /// Whether a factory is registered under the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Build an action of the given type, or `None` if no factory is registered under `name`.
+    pub fn create(&self, name: &str, args: &HashMap<String, String>) -> Option<Action> {
+        self.factories.get(name).map(|factory| factory(args))
+    }
+}
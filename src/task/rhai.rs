@@ -0,0 +1,88 @@
+use crate::{task::Content, Complex, EnvVar, Fingerprinter, Input, Output, ParseError};
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::sync::Arc;
+
+/// [`RhaiAction`] is a specific implementation of [`Complex`] that evaluates a
+/// [Rhai](https://rhai.rs) script, so that tasks can be scripted without a foreign runtime.
+///
+/// The script is compiled once, when the action is created, and evaluated on every run.
+/// It can read:
+/// - `inputs`: an array of the outputs of the predecessor tasks. Strings, integers, floats,
+///   booleans and the stdout of commands are available; other outputs are `()`.
+/// - `env(key)`: the value of a variable of the dag's [`EnvVar`], or `()` if it is not set
+///   or has a type the script cannot read.
+///
+/// The value of the script's last expression becomes the output of the task: a `String`,
+/// `i64`, `f64` or `bool`, no output for `()`, and a [`rhai::Dynamic`] otherwise. A script
+/// error fails the task.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Dag, DefaultTask, RhaiAction};
+///
+/// let action = RhaiAction::new("let total = 0; for x in inputs { total += x } total").unwrap();
+/// let task = DefaultTask::with_action("sum", action);
+/// ```
+pub struct RhaiAction {
+    script: String,
+    ast: AST,
+}
+
+impl RhaiAction {
+    /// Compile a script. Returns an error if the script has a syntax error.
+    pub fn new(script: &str) -> Result<Self, ParseError> {
+        let ast = Engine::new()
+            .compile(script)
+            .map_err(|err| ParseError(format!("Illegal rhai script: {}", err)))?;
+        Ok(Self {
+            script: script.to_owned(),
+            ast,
+        })
+    }
+}
+
+impl Complex for RhaiAction {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let mut engine = Engine::new();
+        engine.register_fn("env", move |key: &str| {
+            env.get_content(key).map_or(Dynamic::UNIT, to_dynamic)
+        });
+        let inputs: rhai::Array = input.get_iter().map(to_dynamic).collect();
+        let mut scope = Scope::new();
+        scope.push("inputs", inputs);
+
+        match engine.eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast) {
+            Ok(value) if value.is_unit() => Output::empty(),
+            Ok(value) if value.is_string() => Output::new(value.into_string().unwrap()),
+            Ok(value) if value.is_int() => Output::new(value.as_int().unwrap()),
+            Ok(value) if value.is_float() => Output::new(value.as_float().unwrap()),
+            Ok(value) if value.is_bool() => Output::new(value.as_bool().unwrap()),
+            Ok(value) => Output::new(value),
+            Err(err) => Output::error(err.to_string()),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        fingerprint.bytes("rhai", self.script.as_bytes());
+    }
+}
+
+/// Convert a content to a value the script can read.
+fn to_dynamic(content: &Content) -> Dynamic {
+    if let Some(value) = content.get::<Dynamic>() {
+        value.clone()
+    } else if let Some(value) = content.get::<i64>() {
+        Dynamic::from_int(*value)
+    } else if let Some(value) = content.get::<i32>() {
+        Dynamic::from_int(*value as i64)
+    } else if let Some(value) = content.get::<usize>() {
+        Dynamic::from_int(*value as i64)
+    } else if let Some(value) = content.get::<f64>() {
+        Dynamic::from_float(*value)
+    } else if let Some(value) = content.get::<bool>() {
+        Dynamic::from_bool(*value)
+    } else {
+        content.as_text().map_or(Dynamic::UNIT, Dynamic::from)
+    }
+}
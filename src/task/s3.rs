@@ -0,0 +1,200 @@
+use crate::{
+    task::{block_on_thread, cmd::render, Content},
+    Complex, EnvVar, Fingerprinter, Input, Output,
+};
+use aws_sdk_s3::{
+    config::{
+        BehaviorVersion, Credentials, Region, RequestChecksumCalculation,
+        ResponseChecksumValidation,
+    },
+    error::DisplayErrorContext,
+    primitives::ByteStream,
+    Client,
+};
+use std::{fs, path::Path, sync::Arc};
+
+/// [`S3DownloadAction`] is a specific implementation of [`Complex`] that downloads an object
+/// from S3, or from an S3-compatible object storage, to a local file. The output of the task
+/// is the path of the file, as a `String`, so that downstream tasks can process it.
+///
+/// The bucket, the key and the path are templates, which may refer to the outputs of the
+/// predecessors and to environment variables like the command of a
+/// [`CommandAction`](crate::CommandAction).
+///
+/// The client is configured from these variables of the dag's [`EnvVar`]:
+/// - `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` (required), `AWS_SESSION_TOKEN`.
+/// - `AWS_REGION`, `us-east-1` if it is not set.
+/// - `AWS_ENDPOINT_URL`, the address of an S3-compatible object storage. Buckets are then
+///   addressed by path rather than by host name.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{DefaultTask, S3DownloadAction};
+///
+/// let download = S3DownloadAction::new("data", "{{ inputs.day }}.csv", "/tmp/{{ inputs.day }}.csv");
+/// let task = DefaultTask::with_action("download", download);
+/// ```
+pub struct S3DownloadAction {
+    bucket: String,
+    key: String,
+    path: String,
+}
+
+impl S3DownloadAction {
+    pub fn new(bucket: &str, key: &str, path: &str) -> Self {
+        Self {
+            bucket: bucket.to_owned(),
+            key: key.to_owned(),
+            path: path.to_owned(),
+        }
+    }
+}
+
+impl Complex for S3DownloadAction {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let rendered =
+            [&self.bucket, &self.key, &self.path].map(|template| render(template, &input, &env));
+        let [bucket, key, path] = match rendered {
+            [Ok(bucket), Ok(key), Ok(path)] => [bucket, key, path],
+            [Err(err), ..] | [_, Err(err), _] | [.., Err(err)] => return Output::error(err),
+        };
+        let client = match client(&env) {
+            Ok(client) => client,
+            Err(err) => return Output::error(err),
+        };
+
+        let download = async {
+            let object = client
+                .get_object()
+                .bucket(&bucket)
+                .key(&key)
+                .send()
+                .await
+                .map_err(|err| DisplayErrorContext(err).to_string())?;
+            let bytes = object.body.collect().await.map_err(|err| err.to_string())?;
+            if let Some(dir) = Path::new(&path).parent() {
+                fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+            }
+            fs::write(&path, bytes.into_bytes()).map_err(|err| err.to_string())
+        };
+        match block_on_thread(download) {
+            Ok(Ok(())) => Output::new(path),
+            Ok(Err(err)) => Output::error(format!(
+                "Failed to download s3://{}/{}: {}",
+                bucket, key, err
+            )),
+            Err(err) => Output::error(err.to_string()),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        fingerprint
+            .bytes("s3_download", &[])
+            .bytes("bucket", self.bucket.as_bytes())
+            .bytes("key", self.key.as_bytes())
+            .bytes("path", self.path.as_bytes());
+    }
+}
+
+/// [`S3UploadAction`] is a specific implementation of [`Complex`] that uploads a local file to
+/// S3, or to an S3-compatible object storage. The output of the task is the `s3://` URI of the
+/// object, as a `String`.
+///
+/// The path, the bucket and the key are templates, and the client is configured from the
+/// dag's [`EnvVar`], as for an [`S3DownloadAction`].
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{DefaultTask, S3UploadAction};
+///
+/// let upload = S3UploadAction::new("{{ inputs.report }}", "reports", "{{ env.DAY }}.html");
+/// let task = DefaultTask::with_action("upload", upload);
+/// ```
+pub struct S3UploadAction {
+    path: String,
+    bucket: String,
+    key: String,
+}
+
+impl S3UploadAction {
+    pub fn new(path: &str, bucket: &str, key: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            bucket: bucket.to_owned(),
+            key: key.to_owned(),
+        }
+    }
+}
+
+impl Complex for S3UploadAction {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let rendered =
+            [&self.path, &self.bucket, &self.key].map(|template| render(template, &input, &env));
+        let [path, bucket, key] = match rendered {
+            [Ok(path), Ok(bucket), Ok(key)] => [path, bucket, key],
+            [Err(err), ..] | [_, Err(err), _] | [.., Err(err)] => return Output::error(err),
+        };
+        let client = match client(&env) {
+            Ok(client) => client,
+            Err(err) => return Output::error(err),
+        };
+
+        let upload = async {
+            let body = ByteStream::from_path(&path)
+                .await
+                .map_err(|err| err.to_string())?;
+            client
+                .put_object()
+                .bucket(&bucket)
+                .key(&key)
+                .body(body)
+                .send()
+                .await
+                .map_err(|err| DisplayErrorContext(err).to_string())
+        };
+        match block_on_thread(upload) {
+            Ok(Ok(_)) => Output::new(format!("s3://{}/{}", bucket, key)),
+            Ok(Err(err)) => Output::error(format!("Failed to upload {}: {}", path, err)),
+            Err(err) => Output::error(err.to_string()),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        fingerprint
+            .bytes("s3_upload", &[])
+            .bytes("path", self.path.as_bytes())
+            .bytes("bucket", self.bucket.as_bytes())
+            .bytes("key", self.key.as_bytes());
+    }
+}
+
+/// Create a client with the credentials, region and endpoint of the [`EnvVar`].
+pub(crate) fn client(env: &EnvVar) -> Result<Client, String> {
+    let var = |key: &str| env.get_content(key).and_then(Content::as_text);
+    let required = |key: &str| var(key).ok_or_else(|| format!("No '{}' in env", key));
+
+    let credentials = Credentials::new(
+        required("AWS_ACCESS_KEY_ID")?,
+        required("AWS_SECRET_ACCESS_KEY")?,
+        var("AWS_SESSION_TOKEN"),
+        None,
+        "dagrs",
+    );
+    let mut config = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new(
+            var("AWS_REGION").unwrap_or_else(|| "us-east-1".to_owned()),
+        ))
+        .credentials_provider(credentials);
+    if let Some(endpoint) = var("AWS_ENDPOINT_URL") {
+        // Many S3-compatible stores do not support the checksums of newer S3 clients.
+        config = config
+            .endpoint_url(endpoint)
+            .force_path_style(true)
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+            .response_checksum_validation(ResponseChecksumValidation::WhenRequired);
+    }
+    Ok(Client::from_conf(config.build()))
+}
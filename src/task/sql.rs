@@ -0,0 +1,206 @@
+use crate::{
+    task::{block_on_thread, Content},
+    Complex, EnvVar, Fingerprinter, Input, Output,
+};
+use sqlx::{any::AnyRow, Column, Connection, Row};
+use std::sync::Arc;
+
+/// A value of a column of a query result, or a parameter of a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+/// The result of a query run by a [`SqlAction`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SqlRows {
+    /// The names of the columns.
+    pub columns: Vec<String>,
+    /// The values of each row, in column order.
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl SqlRows {
+    /// The value of the named column in the given row.
+    pub fn get(&self, row: usize, column: &str) -> Option<&SqlValue> {
+        let index = self.columns.iter().position(|c| c == column)?;
+        self.rows.get(row)?.get(index)
+    }
+}
+
+/// A parameter of a [`SqlAction`]'s query.
+#[derive(Debug, Clone)]
+enum Param {
+    Value(SqlValue),
+    /// The output of the predecessor task with the given name.
+    Input(String),
+}
+
+/// [`SqlAction`] is a specific implementation of [`Complex`] that runs a SQL query and outputs
+/// the resulting rows as [`SqlRows`].
+///
+/// The database is given by a connection string, such as `postgres://user@host/db` or
+/// `sqlite::memory:`, read from the dag's [`EnvVar`] under the key `DATABASE_URL`, or
+/// another key set with [`SqlAction::url_env`]. SQLite, PostgreSQL and MySQL are supported.
+///
+/// The query may have parameters (`?` or `$1`, depending on the database), which are bound in
+/// order to fixed values or to the outputs of predecessor tasks. Outputs that are integers,
+/// floats, booleans, text, the stdout of commands or [`SqlValue`]s can be bound.
+///
+/// The fingerprint of the action holds the query, its parameters and the key of the
+/// connection string. Add the key to [`DagConfig::fingerprint_env`] so that it holds the
+/// connection string itself too.
+///
+/// [`DagConfig::fingerprint_env`]: crate::engine::DagConfig::fingerprint_env
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{DefaultTask, SqlAction};
+///
+/// let query = SqlAction::new("SELECT name FROM users WHERE id = $1").bind_input("user id");
+/// let task = DefaultTask::with_action("user name", query);
+/// ```
+pub struct SqlAction {
+    query: String,
+    url_env: String,
+    params: Vec<Param>,
+}
+
+impl SqlAction {
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_owned(),
+            url_env: "DATABASE_URL".to_owned(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Read the connection string from the given key of the [`EnvVar`].
+    pub fn url_env(mut self, key: &str) -> Self {
+        self.url_env = key.to_owned();
+        self
+    }
+
+    /// Bind the next parameter of the query to a fixed value.
+    pub fn bind(mut self, value: SqlValue) -> Self {
+        self.params.push(Param::Value(value));
+        self
+    }
+
+    /// Bind the next parameter of the query to the output of the predecessor task with the
+    /// given name.
+    pub fn bind_input(mut self, task: &str) -> Self {
+        self.params.push(Param::Input(task.to_owned()));
+        self
+    }
+
+    async fn query(&self, url: &str, params: Vec<SqlValue>) -> Result<SqlRows, sqlx::Error> {
+        sqlx::any::install_default_drivers();
+        let mut conn = sqlx::AnyConnection::connect(url).await?;
+        let mut query = sqlx::query(&self.query);
+        for param in params {
+            query = match param {
+                SqlValue::Null => query.bind(None::<String>),
+                SqlValue::Int(value) => query.bind(value),
+                SqlValue::Float(value) => query.bind(value),
+                SqlValue::Bool(value) => query.bind(value),
+                SqlValue::Text(value) => query.bind(value),
+                SqlValue::Bytes(value) => query.bind(value),
+            };
+        }
+        let rows = query.fetch_all(&mut conn).await?;
+        conn.close().await?;
+        Ok(SqlRows {
+            columns: rows.first().map_or_else(Vec::new, |row| {
+                row.columns().iter().map(|c| c.name().to_owned()).collect()
+            }),
+            rows: rows.iter().map(row_values).collect(),
+        })
+    }
+}
+
+impl Complex for SqlAction {
+    fn run(&self, input: Input, env: Arc<EnvVar>) -> Output {
+        let Some(url) = env.get_content(&self.url_env).and_then(Content::as_text) else {
+            return Output::error(format!("No connection string in env '{}'", self.url_env));
+        };
+        let mut params = Vec::with_capacity(self.params.len());
+        for param in self.params.iter() {
+            match param {
+                Param::Value(value) => params.push(value.clone()),
+                Param::Input(task) => match input.get_by_name(task).and_then(to_sql_value) {
+                    Some(value) => params.push(value),
+                    None => {
+                        return Output::error(format!("No sql value input from task '{}'", task))
+                    }
+                },
+            }
+        }
+
+        match block_on_thread(self.query(&url, params)) {
+            Ok(Ok(rows)) => Output::new(rows),
+            Ok(Err(err)) => Output::error(err.to_string()),
+            Err(err) => Output::error(err.to_string()),
+        }
+    }
+
+    fn fingerprint(&self, fingerprint: &mut Fingerprinter) {
+        fingerprint
+            .bytes("sql", self.query.as_bytes())
+            .bytes("url_env", self.url_env.as_bytes());
+        for param in &self.params {
+            match param {
+                Param::Value(value) => {
+                    fingerprint.bytes("value", format!("{:?}", value).as_bytes())
+                }
+                Param::Input(task) => fingerprint.bytes("input", task.as_bytes()),
+            };
+        }
+    }
+}
+
+fn row_values(row: &AnyRow) -> Vec<SqlValue> {
+    (0..row.len())
+        .map(|i| {
+            let value = if let Ok(value) = row.try_get::<Option<i64>, _>(i) {
+                value.map(SqlValue::Int)
+            } else if let Ok(value) = row.try_get::<Option<f64>, _>(i) {
+                value.map(SqlValue::Float)
+            } else if let Ok(value) = row.try_get::<Option<bool>, _>(i) {
+                value.map(SqlValue::Bool)
+            } else if let Ok(value) = row.try_get::<Option<String>, _>(i) {
+                value.map(SqlValue::Text)
+            } else {
+                row.try_get::<Option<Vec<u8>>, _>(i)
+                    .ok()
+                    .flatten()
+                    .map(SqlValue::Bytes)
+            };
+            value.unwrap_or(SqlValue::Null)
+        })
+        .collect()
+}
+
+fn to_sql_value(content: &Content) -> Option<SqlValue> {
+    if let Some(value) = content.get::<SqlValue>() {
+        Some(value.clone())
+    } else if let Some(value) = content.get::<i64>() {
+        Some(SqlValue::Int(*value))
+    } else if let Some(value) = content.get::<i32>() {
+        Some(SqlValue::Int(*value as i64))
+    } else if let Some(value) = content.get::<usize>() {
+        Some(SqlValue::Int(*value as i64))
+    } else if let Some(value) = content.get::<f64>() {
+        Some(SqlValue::Float(*value))
+    } else if let Some(value) = content.get::<bool>() {
+        Some(SqlValue::Bool(*value))
+    } else {
+        content.as_text().map(SqlValue::Text)
+    }
+}
@@ -37,16 +37,27 @@
 
 use std::{
     any::Any,
+    fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
     slice::Iter,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
+    time::Instant,
 };
 
 use tokio::sync::Semaphore;
 
+use crate::{task::Artifact, utils::LogBuffer};
+
 /// Container type to store task output.
+///
+/// The value is stored behind an [`Arc`]: cloning a [`Content`], as the dag does to give an
+/// output to each successor of a task, never copies the value. A multi-gigabyte output is
+/// shared by all successors. Use [`Content::shared`] for byte buffers, so that the producer can
+/// keep a handle on the buffer without copying it either.
 #[derive(Debug, Clone)]
 pub struct Content {
     content: Arc<dyn Any + Send + Sync>,
@@ -64,6 +75,31 @@ impl Content {
         Self { content: val }
     }
 
+    /// Construct a [`Content`] holding a shared byte buffer, which clones cheaply.
+    ///
+    /// ```rust
+    /// use dagrs::task::Content;
+    /// use std::sync::Arc;
+    ///
+    /// let buffer: Arc<[u8]> = vec![0u8; 1 << 20].into();
+    /// let content = Content::shared(buffer.clone());
+    /// let clone = content.clone();
+    /// assert!(std::ptr::eq(clone.as_bytes().unwrap(), &buffer[..]));
+    /// ```
+    pub fn shared(bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self::new(bytes.into())
+    }
+
+    /// The stored bytes, if the value is a buffer created with [`Content::shared`] or a
+    /// `Vec<u8>`.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Some(bytes) = self.get::<Arc<[u8]>>() {
+            Some(bytes)
+        } else {
+            self.get::<Vec<u8>>().map(Vec::as_slice)
+        }
+    }
+
     pub fn get<H: 'static>(&self) -> Option<&H> {
         self.content.downcast_ref::<H>()
     }
@@ -71,6 +107,100 @@ impl Content {
     pub fn into_inner<H: Send + Sync + 'static>(self) -> Option<Arc<H>> {
         self.content.downcast::<H>().ok()
     }
+
+    /// The stored value as text, if it is a `String`, `&'static str`, or the `(stdout, stderr)`
+    /// lines produced by [`CommandAction`](crate::CommandAction), of which the stdout is
+    /// returned.
+    pub fn as_text(&self) -> Option<String> {
+        if let Some(s) = self.get::<String>() {
+            Some(s.clone())
+        } else if let Some(s) = self.get::<&'static str>() {
+            Some(s.to_string())
+        } else if let Some(inner) = self.get::<Content>() {
+            inner.as_text()
+        } else if let Some(artifact) = self.get::<Artifact>() {
+            Some(artifact.path().display().to_string())
+        } else {
+            self.get::<(Vec<String>, Vec<String>)>()
+                .map(|(stdout, _)| stdout.join("\n"))
+        }
+    }
+
+    /// Size in bytes of the stored value, if it is a `String`, `&'static str`, bytes, or the
+    /// `(stdout, stderr)` lines produced by [`CommandAction`](crate::CommandAction).
+    /// Returns `None` for other types.
+    pub fn byte_size(&self) -> Option<usize> {
+        if let Some(s) = self.get::<String>() {
+            Some(s.len())
+        } else if let Some(s) = self.get::<&'static str>() {
+            Some(s.len())
+        } else if let Some(bytes) = self.as_bytes() {
+            Some(bytes.len())
+        } else if let Some(inner) = self.get::<Content>() {
+            inner.byte_size()
+        } else {
+            self.get::<(Vec<String>, Vec<String>)>()
+                .map(|(stdout, stderr)| stdout.iter().chain(stderr).map(String::len).sum())
+        }
+    }
+
+    /// The stored text or bytes, if the value is a `String`, `&'static str` or bytes.
+    pub(crate) fn payload(&self) -> Option<&[u8]> {
+        if let Some(s) = self.get::<String>() {
+            Some(s.as_bytes())
+        } else if let Some(s) = self.get::<&'static str>() {
+            Some(s.as_bytes())
+        } else if let Some(bytes) = self.as_bytes() {
+            Some(bytes)
+        } else {
+            self.get::<Content>().and_then(Content::payload)
+        }
+    }
+
+    /// The stored value cut to at most `limit` bytes, of the same type, if it is text, bytes,
+    /// or the `(stdout, stderr)` lines produced by [`CommandAction`](crate::CommandAction), of
+    /// which the first lines are kept.
+    pub(crate) fn truncate(&self, limit: usize) -> Option<Content> {
+        fn cut(s: &str, limit: usize) -> &str {
+            let mut end = limit.min(s.len());
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            &s[..end]
+        }
+        if let Some(s) = self.get::<String>() {
+            Some(Content::new(cut(s, limit).to_owned()))
+        } else if let Some(s) = self.get::<&'static str>() {
+            Some(Content::new(cut(s, limit)))
+        } else if let Some(bytes) = self.get::<Arc<[u8]>>() {
+            Some(Content::shared(&bytes[..limit.min(bytes.len())]))
+        } else if let Some(bytes) = self.get::<Vec<u8>>() {
+            Some(Content::new(bytes[..limit.min(bytes.len())].to_vec()))
+        } else if let Some(inner) = self.get::<Content>() {
+            inner.truncate(limit)
+        } else {
+            self.get::<(Vec<String>, Vec<String>)>()
+                .map(|(stdout, stderr)| {
+                    let mut left = limit;
+                    let mut keep = |lines: &Vec<String>| -> Vec<String> {
+                        lines
+                            .iter()
+                            .take_while(|line| {
+                                let fits = line.len() <= left;
+                                if fits {
+                                    left -= line.len();
+                                }
+                                fits
+                            })
+                            .cloned()
+                            .collect()
+                    };
+                    let stdout = keep(stdout);
+                    let stderr = keep(stderr);
+                    Content::new((stdout, stderr))
+                })
+        }
+    }
 }
 
 /// [`ExeState`] internally stores [`Output`], which represents whether the execution of
@@ -81,7 +211,7 @@ pub(crate) struct ExecState {
     /// The execution succeed or not.
     success: AtomicBool,
     /// Output produced by a task.
-    output: Arc<Mutex<Output>>,
+    output: Mutex<Output>,
     /// The semaphore is used to control the synchronous blocking of subsequent tasks to obtain the
     /// execution results of this task.
     /// When a task is successfully executed, the permits inside the semaphore will be increased to
@@ -90,6 +220,109 @@ pub(crate) struct ExecState {
     /// The task will obtain a permits synchronously (the permit will not be returned), which means
     /// that the subsequent task has obtained the execution result of this task.
     semaphore: Semaphore,
+    /// Log lines emitted by the task's action, if log capture is enabled.
+    logs: LogBuffer,
+    /// Points in time of the task's execution.
+    timing: Mutex<Timing>,
+    /// The file the output was written to, if it was spilled to disk, see
+    /// [`DagConfig::memory_budget`](crate::engine::DagConfig::memory_budget).
+    spilled: Mutex<Option<(PathBuf, Payload)>>,
+    /// The task did not run because its output files were up to date, see
+    /// [`TaskFiles`](super::TaskFiles).
+    up_to_date: AtomicBool,
+}
+
+/// The type of an output spilled to disk, to read it back as the same type.
+#[derive(Debug, Clone, Copy)]
+enum Payload {
+    String,
+    Vec,
+    Shared,
+}
+
+/// The execution states of the tasks of a dag, stored contiguously and indexed by the index of
+/// each task in the dependency graph, rather than allocated one by one. A run starts with new
+/// states, so that tasks left behind by a previous run cannot change them.
+#[derive(Debug, Default)]
+pub(crate) struct ExecStates {
+    states: Box<[ExecState]>,
+}
+
+/// A handle on one of the [`ExecStates`], kept by a running task.
+#[derive(Debug, Clone)]
+pub(crate) struct ExecStateRef {
+    states: Arc<ExecStates>,
+    index: usize,
+}
+
+impl ExecStates {
+    /// The initial states of `len` tasks.
+    pub(crate) fn new(len: usize) -> Arc<Self> {
+        Arc::new(Self {
+            states: (0..len).map(|_| ExecState::new()).collect(),
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&ExecState> {
+        self.states.get(index)
+    }
+
+    pub(crate) fn handle(self: &Arc<Self>, index: usize) -> ExecStateRef {
+        assert!(index < self.states.len());
+        ExecStateRef {
+            states: self.clone(),
+            index,
+        }
+    }
+
+    /// New states for a partial run: the tasks for which `restart` is true start over, the
+    /// others keep their result, to hand their outputs to the tasks that start over.
+    pub(crate) fn restart(&self, restart: impl Fn(usize) -> bool) -> Arc<Self> {
+        Arc::new(Self {
+            states: self
+                .states
+                .iter()
+                .enumerate()
+                .map(|(index, state)| {
+                    if restart(index) {
+                        ExecState::new()
+                    } else {
+                        state.carry_over()
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+impl Deref for ExecStateRef {
+    type Target = ExecState;
+
+    fn deref(&self) -> &ExecState {
+        &self.states.states[self.index]
+    }
+}
+
+/// Points in time of a task's execution.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Timing {
+    /// When the task's action started running.
+    pub(crate) started: Option<Instant>,
+    /// When the task's action finished running, successfully or not.
+    pub(crate) finished: Option<Instant>,
+    /// When the task was handed to the runtime.
+    #[cfg(feature = "bench")]
+    pub(crate) spawned: Option<Instant>,
+    /// When the outputs of all predecessors were available.
+    #[cfg(feature = "bench")]
+    pub(crate) inputs_ready: Option<Instant>,
+    /// When the task's output was stored and made available to its successors.
+    #[cfg(feature = "bench")]
+    pub(crate) stored: Option<Instant>,
 }
 
 /// Output produced by a task.
@@ -101,8 +334,12 @@ pub enum Output {
 }
 
 /// Task's input value.
-#[derive(Debug)]
-pub struct Input(Vec<Content>);
+#[derive(Debug, Clone)]
+pub struct Input {
+    contents: Vec<Content>,
+    /// The names of the tasks that produced the contents, if known.
+    names: Vec<String>,
+}
 
 impl ExecState {
     /// Construct a new [`ExeState`].
@@ -110,8 +347,25 @@ impl ExecState {
         // initialize the task to failure without output.
         Self {
             success: AtomicBool::new(false),
-            output: Arc::new(Mutex::new(Output::empty())),
+            output: Mutex::new(Output::empty()),
             semaphore: Semaphore::new(0),
+            logs: LogBuffer::default(),
+            timing: Mutex::new(Timing::default()),
+            spilled: Mutex::new(None),
+            up_to_date: AtomicBool::new(false),
+        }
+    }
+
+    /// A copy of the result of the task, without the permits of its semaphore.
+    fn carry_over(&self) -> Self {
+        Self {
+            success: AtomicBool::new(self.success()),
+            output: Mutex::new(Output::Out(self.output.lock().unwrap().get_out())),
+            semaphore: Semaphore::new(0),
+            logs: LogBuffer::new(Mutex::new(self.logs.lock().unwrap().clone())),
+            timing: Mutex::new(*self.timing.lock().unwrap()),
+            spilled: Mutex::new(self.spilled.lock().unwrap().clone()),
+            up_to_date: AtomicBool::new(self.up_to_date()),
         }
     }
 
@@ -123,8 +377,53 @@ impl ExecState {
 
     /// [`Output`] for fetching internal storage.
     /// This function is generally not called directly, but first uses the semaphore for synchronization control.
+    /// An output spilled to disk is read back from its file, on every call.
     pub(crate) fn get_output(&self) -> Option<Content> {
-        self.output.lock().unwrap().get_out()
+        let output = self.output.lock().unwrap();
+        if let Some(content) = output.get_out() {
+            return Some(content);
+        }
+        let spilled = self.spilled.lock().unwrap();
+        let (path, payload) = spilled.as_ref()?;
+        let read = fs::read(path).and_then(|bytes| match payload {
+            Payload::String => String::from_utf8(bytes)
+                .map(Content::new)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Payload::Vec => Ok(Content::new(bytes)),
+            Payload::Shared => Ok(Content::shared(bytes)),
+        });
+        match read {
+            Ok(content) => Some(content),
+            Err(err) => {
+                log::error!(
+                    "Failed to read the spilled output '{}': {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// The size of the output, if it can be spilled to disk: a `String` or bytes.
+    pub(crate) fn spillable_size(&self) -> Option<usize> {
+        let content = self.output.lock().unwrap().get_out()?;
+        spillable(&content).map(|(_, bytes)| bytes.len())
+    }
+
+    /// Write the output to the given file, and drop it from memory.
+    pub(crate) fn spill(&self, path: &Path) -> io::Result<()> {
+        let mut output = self.output.lock().unwrap();
+        let Some(content) = output.get_out() else {
+            return Ok(());
+        };
+        let Some((payload, bytes)) = spillable(&content) else {
+            return Ok(());
+        };
+        fs::write(path, bytes)?;
+        *self.spilled.lock().unwrap() = Some((path.to_owned(), payload));
+        *output = Output::empty();
+        Ok(())
     }
 
     /// The task execution succeed or not.
@@ -141,6 +440,17 @@ impl ExecState {
         self.success.store(false, Ordering::Relaxed)
     }
 
+    /// Record that the task did not run because its output files were up to date. It counts
+    /// as succeeded, with an empty output.
+    pub(crate) fn mark_up_to_date(&self) {
+        self.up_to_date.store(true, Ordering::Relaxed);
+        self.exe_success();
+    }
+
+    pub(crate) fn up_to_date(&self) -> bool {
+        self.up_to_date.load(Ordering::Relaxed)
+    }
+
     /// The semaphore is used to control the synchronous acquisition of task output results.
     /// Under normal circumstances, first use the semaphore to obtain a permit, and then call
     /// the `get_output` function to obtain the output. If the current task is not completed
@@ -149,6 +459,56 @@ impl ExecState {
     pub(crate) fn semaphore(&self) -> &Semaphore {
         &self.semaphore
     }
+
+    /// Record that the task's action starts running.
+    pub(crate) fn mark_started(&self) {
+        self.timing.lock().unwrap().started = Some(Instant::now());
+    }
+
+    /// Record that the task's action finished running, successfully or not.
+    pub(crate) fn mark_finished(&self) {
+        self.timing.lock().unwrap().finished = Some(Instant::now());
+    }
+
+    /// Record a point in time of the task's execution, for measuring the engine's overhead.
+    #[cfg(feature = "bench")]
+    pub(crate) fn mark(&self, f: impl FnOnce(&mut Timing) -> &mut Option<Instant>) {
+        *f(&mut self.timing.lock().unwrap()) = Some(Instant::now());
+    }
+
+    /// When the task's action started running, if it did.
+    pub(crate) fn started_at(&self) -> Option<Instant> {
+        self.timing.lock().unwrap().started
+    }
+
+    /// When the task's action finished running, if it did.
+    pub(crate) fn finished_at(&self) -> Option<Instant> {
+        self.timing.lock().unwrap().finished
+    }
+
+    /// All recorded points in time of the task's execution.
+    #[cfg(feature = "bench")]
+    pub(crate) fn timing(&self) -> Timing {
+        *self.timing.lock().unwrap()
+    }
+
+    /// The buffer the task's log lines are captured into.
+    pub(crate) fn logs(&self) -> &LogBuffer {
+        &self.logs
+    }
+}
+
+/// The type and the bytes of a content that can be spilled to disk.
+fn spillable(content: &Content) -> Option<(Payload, &[u8])> {
+    if let Some(s) = content.get::<String>() {
+        Some((Payload::String, s.as_bytes()))
+    } else if let Some(bytes) = content.get::<Vec<u8>>() {
+        Some((Payload::Vec, bytes))
+    } else {
+        content
+            .get::<Arc<[u8]>>()
+            .map(|bytes| (Payload::Shared, &bytes[..]))
+    }
 }
 
 impl Output {
@@ -176,7 +536,7 @@ impl Output {
     }
 
     /// Determine whether [`Output`] stores error information.
-    pub(crate) fn is_err(&self) -> bool {
+    pub fn is_err(&self) -> bool {
         match self {
             Self::Err(_) | Self::ErrWithExitCode(_, _) => true,
             Self::Out(_) => false,
@@ -184,7 +544,7 @@ impl Output {
     }
 
     /// Get the contents of [`Output`].
-    pub(crate) fn get_out(&self) -> Option<Content> {
+    pub fn get_out(&self) -> Option<Content> {
         match self {
             Self::Out(ref out) => out.clone(),
             Self::Err(_) | Self::ErrWithExitCode(_, _) => None,
@@ -192,7 +552,7 @@ impl Output {
     }
 
     /// Get error information stored in [`Output`].
-    pub(crate) fn get_err(&self) -> Option<String> {
+    pub fn get_err(&self) -> Option<String> {
         match self {
             Self::Out(_) => None,
             Self::Err(err) => Some(err.to_string()),
@@ -210,12 +570,29 @@ impl Output {
 impl Input {
     /// Constructs input using output produced by a non-empty predecessor task.
     pub fn new(input: Vec<Content>) -> Self {
-        Self(input)
+        Self {
+            contents: input,
+            names: Vec::new(),
+        }
+    }
+
+    /// Constructs input from the outputs of the predecessor tasks with the given names.
+    pub(crate) fn with_names(contents: Vec<Content>, names: Vec<String>) -> Self {
+        Self { contents, names }
     }
 
     /// Since [`Input`] can contain multi-input values, and it's implemented
     /// by [`Vec`] actually, of course it can be turned into a iterator.
-    pub fn get_iter(&self) -> Iter<Content> {
-        self.0.iter()
+    pub fn get_iter(&self) -> Iter<'_, Content> {
+        self.contents.iter()
+    }
+
+    /// Get the output of the predecessor task with the given name. Names are only known when
+    /// the input is provided by a dag.
+    pub fn get_by_name(&self, name: &str) -> Option<&Content> {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .map(|index| &self.contents[index])
     }
 }
@@ -0,0 +1,109 @@
+use super::{DefaultTask, TypedAction};
+use crate::{EnvVar, Output};
+use std::{marker::PhantomData, sync::Arc};
+
+/// A [`DefaultTask`] that takes an input of type `I` and outputs a value of type `O`.
+///
+/// A [`TypedTask`] only runs after the typed tasks whose output is its input, so connecting a
+/// task that outputs a `String` to one that takes a `u32` does not compile. The task is created
+/// from a [`TypedAction`], whose associated types give `I` and `O`, or as a
+/// [`source`](TypedTask::source) without input. Once connected, the tasks are turned into
+/// [`DefaultTask`]s to build the dag.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{Complex, Dag, EnvVar, Input, Output, TypedAction, TypedTask};
+/// use std::sync::Arc;
+///
+/// struct Parse;
+///
+/// impl Complex for Parse {
+///     fn run(&self, input: Input, _env: Arc<EnvVar>) -> Output {
+///         let text = input.get_iter().find_map(|content| content.get::<String>()).unwrap();
+///         Output::new(text.parse::<u32>().unwrap())
+///     }
+/// }
+///
+/// impl TypedAction for Parse {
+///     type In = String;
+///     type Out = u32;
+/// }
+///
+/// let text = TypedTask::source("text", |_env| "42".to_string());
+/// let mut parse = TypedTask::new("parse", Parse);
+/// parse.after(&text);
+/// let mut dag = Dag::with_tasks(vec![text.into_task(), parse.into_task()]);
+/// assert!(dag.start().unwrap());
+/// assert_eq!(dag.get_result::<u32>().as_deref(), Some(&42));
+/// ```
+///
+/// A number does not go into a task that takes a `String`:
+///
+/// ```compile_fail
+/// # use dagrs::{Complex, EnvVar, Input, Output, TypedAction, TypedTask};
+/// # use std::sync::Arc;
+/// # struct Parse;
+/// # impl Complex for Parse {
+/// #     fn run(&self, _input: Input, _env: Arc<EnvVar>) -> Output {
+/// #         Output::empty()
+/// #     }
+/// # }
+/// # impl TypedAction for Parse {
+/// #     type In = String;
+/// #     type Out = u32;
+/// # }
+/// let number = TypedTask::source("number", |_env| 42u32);
+/// let mut parse = TypedTask::new("parse", Parse);
+/// parse.after(&number);
+/// ```
+pub struct TypedTask<I, O> {
+    task: DefaultTask,
+    types: PhantomData<fn(I) -> O>,
+}
+
+impl<O: Send + Sync + 'static> TypedTask<(), O> {
+    /// Create a task without input, whose closure creates the output.
+    pub fn source(name: &str, source: impl Fn(Arc<EnvVar>) -> O + Send + Sync + 'static) -> Self {
+        Self::from_task(DefaultTask::with_closure(name, move |_input, env| {
+            Output::new(source(env))
+        }))
+    }
+}
+
+impl<I, O> TypedTask<I, O> {
+    /// Create a task with the given name that runs the typed action.
+    pub fn new<A>(name: &str, action: A) -> Self
+    where
+        A: TypedAction<In = I, Out = O> + Send + Sync + 'static,
+    {
+        Self::from_task(DefaultTask::with_action(name, action))
+    }
+
+    fn from_task(task: DefaultTask) -> Self {
+        Self {
+            task,
+            types: PhantomData,
+        }
+    }
+
+    /// Run this task after a task whose output is the input of this one.
+    pub fn after<P>(&mut self, predecessor: &TypedTask<P, I>) {
+        self.task.set_predecessors(&[&predecessor.task]);
+    }
+
+    /// The task, to read it.
+    pub fn task(&self) -> &DefaultTask {
+        &self.task
+    }
+
+    /// The task, to change its settings. Its predecessors added there are not type checked.
+    pub fn task_mut(&mut self) -> &mut DefaultTask {
+        &mut self.task
+    }
+
+    /// The task, to add it to a dag.
+    pub fn into_task(self) -> DefaultTask {
+        self.task
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{task::ExecState, Dag, ExecutionSnapshot};
+use std::path::Path;
+
+fn state<'a>(dag: &'a Dag, name: &str) -> &'a ExecState {
+    let id = dag
+        .find_task_by_name(name)
+        .unwrap_or_else(|| panic!("no task named '{}' in the dag", name));
+    dag.exec_state(id).unwrap_or_else(|| {
+        panic!(
+            "task '{}' has not been scheduled, was the dag started?",
+            name
+        )
+    })
+}
+
+/// Assert that the action of the task named `name` ran.
+#[track_caller]
+pub fn assert_ran(dag: &Dag, name: &str) {
+    assert!(
+        state(dag, name).started_at().is_some(),
+        "expected task '{}' to run, but it did not",
+        name
+    );
+}
+
+/// Assert that the action of the task named `name` did not run.
+#[track_caller]
+pub fn assert_not_ran(dag: &Dag, name: &str) {
+    assert!(
+        state(dag, name).started_at().is_none(),
+        "expected task '{}' not to run, but it did",
+        name
+    );
+}
+
+/// Assert that the actions of the tasks named `first` and `second` both ran, and that
+/// `first` finished before `second` started.
+#[track_caller]
+pub fn assert_ran_before(dag: &Dag, first: &str, second: &str) {
+    let finished = state(dag, first)
+        .finished_at()
+        .unwrap_or_else(|| panic!("expected task '{}' to run, but it did not", first));
+    let started = state(dag, second)
+        .started_at()
+        .unwrap_or_else(|| panic!("expected task '{}' to run, but it did not", second));
+    assert!(
+        finished <= started,
+        "expected task '{}' to run before task '{}'",
+        first,
+        second
+    );
+}
+
+/// Assert that the execution snapshot of the last run of `dag` equals the snapshot stored at
+/// `path`. If the file does not exist, or the `DAGRS_UPDATE_SNAPSHOTS` environment variable
+/// is set, the snapshot is written to `path` instead.
+#[track_caller]
+pub fn assert_snapshot(dag: &Dag, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let snapshot = dag.execution_snapshot();
+    if !path.exists() || std::env::var_os("DAGRS_UPDATE_SNAPSHOTS").is_some() {
+        snapshot
+            .save(path)
+            .unwrap_or_else(|err| panic!("failed to write snapshot {}: {}", path.display(), err));
+        return;
+    }
+    let expected = ExecutionSnapshot::load(path)
+        .unwrap_or_else(|err| panic!("failed to read snapshot {}: {}", path.display(), err));
+    assert_eq!(
+        snapshot,
+        expected,
+        "execution differs from snapshot {}",
+        path.display()
+    );
+}
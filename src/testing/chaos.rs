@@ -0,0 +1,162 @@
+use crate::{Dag, FaultInjector};
+use std::fmt;
+
+type Invariant = (String, Box<dyn Fn(&Dag) -> bool>);
+
+/// Runs a dag repeatedly under random faults and checks invariants after each run.
+///
+/// Each run builds a new dag, injects the faults of a [`FaultInjector`] created with the seed
+/// of the run, runs the dag, and checks every invariant on it. The seeds of the runs are
+/// consecutive, starting at the first seed, so that a failing seed can be run again alone.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::testing::{Chaos, MockAction};
+/// use dagrs::{Dag, FaultInjector};
+///
+/// let report = Chaos::new(
+///     || {
+///         let mut fetch = MockAction::new().returns(1usize).task("fetch");
+///         fetch.set_retries(3);
+///         Dag::with_tasks(vec![fetch])
+///     },
+///     |seed| FaultInjector::new(seed).fail("fetch", 0.3),
+/// )
+/// .runs(50)
+/// .invariant("fetch succeeds", |dag| dag.report().success)
+/// .run();
+/// assert_eq!(report.runs, 50);
+/// ```
+pub struct Chaos {
+    dag: Box<dyn Fn() -> Dag>,
+    faults: Box<dyn Fn(u64) -> FaultInjector>,
+    runs: usize,
+    first_seed: u64,
+    invariants: Vec<Invariant>,
+}
+
+impl Chaos {
+    /// Run the dags built by `dag` under the faults of the injectors built by `faults` from
+    /// the seed of each run.
+    pub fn new(
+        dag: impl Fn() -> Dag + 'static,
+        faults: impl Fn(u64) -> FaultInjector + 'static,
+    ) -> Self {
+        Self {
+            dag: Box::new(dag),
+            faults: Box::new(faults),
+            runs: 100,
+            first_seed: 0,
+            invariants: Vec::new(),
+        }
+    }
+
+    /// The number of runs, 100 by default.
+    pub fn runs(mut self, runs: usize) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    /// The seed of the first run, 0 by default.
+    pub fn first_seed(mut self, seed: u64) -> Self {
+        self.first_seed = seed;
+        self
+    }
+
+    /// Check after each run that `check` holds for the dag. `name` describes the invariant in
+    /// the report.
+    pub fn invariant(mut self, name: &str, check: impl Fn(&Dag) -> bool + 'static) -> Self {
+        self.invariants.push((name.to_owned(), Box::new(check)));
+        self
+    }
+
+    /// Run the dag the given number of times and report the runs that violated an invariant.
+    pub fn run(&self) -> ChaosReport {
+        let mut failures = Vec::new();
+        for seed in (self.first_seed..).take(self.runs) {
+            let faults = (self.faults)(seed);
+            let mut dag = (self.dag)().with_fault_injector(faults.clone());
+            let violated = match dag.start() {
+                Ok(_) => self
+                    .invariants
+                    .iter()
+                    .filter(|(_, check)| !check(&dag))
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                Err(err) => vec![format!("the dag runs: {}", err)],
+            };
+            if !violated.is_empty() {
+                failures.push(ChaosFailure {
+                    seed,
+                    faults: faults.injected(),
+                    violated,
+                });
+            }
+        }
+        failures.sort_by_key(|failure| (failure.faults, failure.seed));
+        ChaosReport {
+            runs: self.runs,
+            failures,
+        }
+    }
+}
+
+/// A run of [`Chaos`] that violated invariants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChaosFailure {
+    pub seed: u64,
+    /// The number of faults injected into the run.
+    pub faults: usize,
+    /// The names of the violated invariants.
+    pub violated: Vec<String>,
+}
+
+/// The result of [`Chaos::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChaosReport {
+    pub runs: usize,
+    /// The runs that violated invariants, the ones with the fewest faults first.
+    pub failures: Vec<ChaosFailure>,
+}
+
+impl ChaosReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// The seed of the failing run with the fewest faults, the simplest one to debug.
+    pub fn minimal_seed(&self) -> Option<u64> {
+        self.failures.first().map(|failure| failure.seed)
+    }
+
+    /// Assert that no run violated an invariant, and list the failing seeds otherwise.
+    #[track_caller]
+    pub fn assert_ok(&self) {
+        assert!(self.is_ok(), "{}", self);
+    }
+}
+
+impl fmt::Display for ChaosReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} chaos runs violated invariants",
+            self.failures.len(),
+            self.runs
+        )?;
+        for failure in self.failures.iter().take(10) {
+            write!(
+                f,
+                "\n  seed {} ({} faults): {}",
+                failure.seed,
+                failure.faults,
+                failure.violated.join(", ")
+            )?;
+        }
+        if self.failures.len() > 10 {
+            write!(f, "\n  ...")?;
+        }
+        Ok(())
+    }
+}
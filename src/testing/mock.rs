@@ -0,0 +1,106 @@
+use crate::{task::Content, Complex, DefaultTask, EnvVar, Input, Output};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// One scripted result of a [`MockAction`].
+#[derive(Clone)]
+enum Scripted {
+    Value(Option<Content>),
+    Error(String),
+    Panic(String),
+}
+
+#[derive(Default)]
+struct MockState {
+    /// Results returned by the next calls, in order.
+    script: VecDeque<Scripted>,
+    /// Result returned once the script is exhausted.
+    fallback: Option<Scripted>,
+    /// Inputs of every call.
+    calls: Vec<Vec<Content>>,
+}
+
+/// A [`Complex`] action with scripted results that records its calls.
+///
+/// [`MockAction`] is cheap to clone, and clones share their script and call records, so the
+/// test keeps one clone to inspect the calls after the dag has run.
+///
+/// Each call consumes the next result queued with [`returns`](MockAction::returns),
+/// [`fails`](MockAction::fails) or [`panics`](MockAction::panics). Once the queue is
+/// exhausted, the last queued result is repeated; an action with nothing queued returns
+/// [`Output::empty`].
+#[derive(Clone, Default)]
+pub struct MockAction {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockAction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a successful output.
+    pub fn returns<H: Send + Sync + 'static>(self, val: H) -> Self {
+        self.push(Scripted::Value(Some(Content::new(val))))
+    }
+
+    /// Queue a successful empty output.
+    pub fn returns_empty(self) -> Self {
+        self.push(Scripted::Value(None))
+    }
+
+    /// Queue a failure with the given error message.
+    pub fn fails(self, msg: &str) -> Self {
+        self.push(Scripted::Error(msg.to_owned()))
+    }
+
+    /// Queue a panic with the given message.
+    pub fn panics(self, msg: &str) -> Self {
+        self.push(Scripted::Panic(msg.to_owned()))
+    }
+
+    fn push(self, scripted: Scripted) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.fallback = Some(scripted.clone());
+            state.script.push_back(scripted);
+        }
+        self
+    }
+
+    /// Create a [`DefaultTask`] with the given name running a clone of this action.
+    pub fn task(&self, name: &str) -> DefaultTask {
+        DefaultTask::with_action(name, self.clone())
+    }
+
+    /// The inputs of every call so far, in call order.
+    pub fn calls(&self) -> Vec<Vec<Content>> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// The number of calls so far.
+    pub fn call_count(&self) -> usize {
+        self.state.lock().unwrap().calls.len()
+    }
+}
+
+impl Complex for MockAction {
+    fn run(&self, input: Input, _env: Arc<EnvVar>) -> Output {
+        let scripted = {
+            let mut state = self.state.lock().unwrap();
+            state.calls.push(input.get_iter().cloned().collect());
+            state
+                .script
+                .pop_front()
+                .or_else(|| state.fallback.clone())
+                .unwrap_or(Scripted::Value(None))
+        };
+        match scripted {
+            Scripted::Value(content) => Output::Out(content),
+            Scripted::Error(msg) => Output::Err(msg),
+            Scripted::Panic(msg) => panic!("{}", msg),
+        }
+    }
+}
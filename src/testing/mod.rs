@@ -0,0 +1,47 @@
+//! Tools for testing dags.
+//!
+//! # [`MockAction`]: scripted task behavior
+//!
+//! A [`MockAction`] is a [`Complex`](crate::Complex) action whose behavior is scripted by the
+//! test: it returns predefined outputs, fails or panics on demand, and records the inputs of
+//! every call, so that the wiring of a dag can be unit tested without running real workloads.
+//!
+//! # Assertions
+//!
+//! After a dag has been executed, [`assert_ran`], [`assert_not_ran`] and [`assert_ran_before`]
+//! check which tasks ran and in which order. [`assert_snapshot`] compares the whole run with
+//! an [`ExecutionSnapshot`](crate::ExecutionSnapshot) file, so that unintended scheduling
+//! changes are detected.
+//!
+//! # Chaos
+//!
+//! [`Chaos`] runs a dag many times under the random faults of a
+//! [`FaultInjector`](crate::FaultInjector), and checks invariants after each run, so that the
+//! retries and fallbacks of a dag are shown to keep it correct. It reports the failing seeds,
+//! the ones with the fewest faults first.
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::testing::{assert_ran_before, MockAction};
+//! use dagrs::Dag;
+//!
+//! let a = MockAction::new().returns(1usize);
+//! let b = MockAction::new();
+//! let task_a = a.task("A");
+//! let mut task_b = b.task("B");
+//! task_b.set_predecessors(&[&task_a]);
+//!
+//! let mut dag = Dag::with_tasks(vec![task_a, task_b]);
+//! assert!(dag.start().unwrap());
+//! assert_ran_before(&dag, "A", "B");
+//! assert_eq!(b.calls()[0][0].get::<usize>(), Some(&1));
+//! ```
+
+mod assertions;
+mod chaos;
+mod mock;
+
+pub use self::assertions::{assert_not_ran, assert_ran, assert_ran_before, assert_snapshot};
+pub use self::chaos::{Chaos, ChaosFailure, ChaosReport};
+pub use self::mock::MockAction;
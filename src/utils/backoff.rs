@@ -0,0 +1,185 @@
+//! Backoff between retries.
+
+use super::random_f64;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How the delay grows from one retry to the next.
+#[derive(Debug, Clone, Copy)]
+enum Growth {
+    Constant,
+    /// The delay grows by the given step on each retry.
+    Linear(Duration),
+    /// The delay is multiplied by the given factor on each retry.
+    Exponential(f64),
+}
+
+/// The delays between the attempts of an operation that is retried, such as the action of a
+/// task (see [`DagConfig::backoff`](crate::engine::DagConfig::backoff)) or the delivery of a
+/// notification.
+///
+/// The delay before the first retry is the initial delay, and it grows for the next retries
+/// in constant, linear or exponential steps, up to an optional maximum. A jitter randomizes
+/// each delay, so that clients retrying at the same time spread out. A maximum elapsed time
+/// stops the retries once the first attempt started that long ago.
+///
+/// The default backoff retries immediately, without a time limit.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::utils::Backoff;
+/// use std::time::Duration;
+///
+/// let backoff = Backoff::exponential(Duration::from_millis(100), 2.0)
+///     .max_delay(Duration::from_secs(5))
+///     .jitter(0.1)
+///     .max_elapsed(Duration::from_secs(60));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    initial: Duration,
+    growth: Growth,
+    max_delay: Option<Duration>,
+    /// The fraction of a delay by which it is randomly shortened or lengthened.
+    jitter: f64,
+    max_elapsed: Option<Duration>,
+}
+
+impl Backoff {
+    /// The same delay before every retry.
+    pub fn constant(delay: Duration) -> Self {
+        Self {
+            initial: delay,
+            growth: Growth::Constant,
+            max_delay: None,
+            jitter: 0.0,
+            max_elapsed: None,
+        }
+    }
+
+    /// A delay that grows by `step` on each retry.
+    pub fn linear(initial: Duration, step: Duration) -> Self {
+        Self {
+            growth: Growth::Linear(step),
+            ..Self::constant(initial)
+        }
+    }
+
+    /// A delay that is multiplied by `factor` on each retry.
+    pub fn exponential(initial: Duration, factor: f64) -> Self {
+        Self {
+            growth: Growth::Exponential(factor),
+            ..Self::constant(initial)
+        }
+    }
+
+    /// Limit each delay, before the jitter is applied.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    /// Shorten or lengthen each delay randomly by up to the given fraction of it, between 0
+    /// and 1.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Stop retrying once the first attempt started this long ago.
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    /// The delay before the given retry, counted from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let steps = retry.saturating_sub(1);
+        let delay = match self.growth {
+            Growth::Constant => self.initial,
+            Growth::Linear(step) => self.initial.saturating_add(step.saturating_mul(steps)),
+            Growth::Exponential(factor) => {
+                // The exponent is clamped rather than wrapped to a negative one.
+                let exponent = steps.min(i32::MAX as u32) as i32;
+                let secs = self.initial.as_secs_f64() * factor.powi(exponent);
+                Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
+            }
+        };
+        let delay = self.max_delay.map_or(delay, |max| delay.min(max));
+        if self.jitter == 0.0 {
+            return delay;
+        }
+        // A random factor in [1 - jitter, 1 + jitter], applied to the seconds so that a delay
+        // too long for a `Duration` saturates instead of overflowing.
+        let secs = delay.as_secs_f64() * (1.0 + self.jitter * (2.0 * random_f64() - 1.0));
+        Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
+    }
+
+    /// The delay before the given retry, counted from 1, of an operation first attempted at
+    /// `start`, or `None` if it should not be retried anymore.
+    pub fn next_delay(&self, retry: u32, start: Instant) -> Option<Duration> {
+        let delay = self.delay(retry);
+        match self.max_elapsed {
+            Some(max) if start.elapsed().saturating_add(delay) > max => None,
+            _ => Some(delay),
+        }
+    }
+
+    /// Run an operation, retrying it up to `retries` times while it fails, and sleeping for
+    /// the delays of the backoff in between. Returns the last result. The sleeps block the
+    /// thread, so this is not for the worker threads of an async runtime.
+    pub fn retry<T, E>(
+        &self,
+        retries: u32,
+        mut operation: impl FnMut() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let mut retry = 0;
+        loop {
+            let result = operation();
+            if result.is_ok() || retry == retries {
+                return result;
+            }
+            retry += 1;
+            match self.next_delay(retry, start) {
+                Some(delay) => thread::sleep(delay),
+                None => return result,
+            }
+        }
+    }
+}
+
+// Factors and jitters are compared by their bits, so that a backoff always equals itself.
+impl PartialEq for Growth {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Growth::Constant, Growth::Constant) => true,
+            (Growth::Linear(a), Growth::Linear(b)) => a == b,
+            (Growth::Exponential(a), Growth::Exponential(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Growth {}
+
+impl PartialEq for Backoff {
+    fn eq(&self, other: &Self) -> bool {
+        self.initial == other.initial
+            && self.growth == other.growth
+            && self.max_delay == other.max_delay
+            && self.jitter.to_bits() == other.jitter.to_bits()
+            && self.max_elapsed == other.max_elapsed
+    }
+}
+
+impl Eq for Backoff {}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::constant(Duration::ZERO)
+    }
+}
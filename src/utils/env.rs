@@ -1,6 +1,6 @@
 use crate::task::Content;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub type Variable = Content;
 
@@ -11,17 +11,21 @@ pub type Variable = Content;
 /// Before all tasks run, the user builds a [`EnvVar`] and sets all the environment
 /// variables. One [`EnvVar`] corresponds to one dag. All tasks in a job can
 /// be shared and immutable at runtime. environment variables.
-#[derive(Debug, Default)]
+///
+/// Secrets, such as tokens and passwords, are set with [`EnvVar::set_secret`]. Actions read
+/// them like any other variable, but the dag masks their values in its logs, events, reports
+/// and captured log lines.
+#[derive(Debug, Default, Clone)]
 pub struct EnvVar {
     variables: HashMap<String, Variable>,
+    /// The names of the variables that hold secrets.
+    secrets: HashSet<String>,
 }
 
 impl EnvVar {
     /// Allocate a new [`EnvVar`].
     pub fn new() -> Self {
-        Self {
-            variables: HashMap::new(),
-        }
+        Self::default()
     }
 
     #[allow(unused)]
@@ -35,6 +39,44 @@ impl EnvVar {
     pub fn set<H: Send + Sync + 'static>(&mut self, name: &str, var: H) {
         let mut v = Variable::new(var);
         self.variables.insert(name.to_owned(), v);
+        self.secrets.remove(name);
+    }
+
+    /// Set a secret variable. Actions get it as a `String`, like a variable set with
+    /// [`EnvVar::set`], and it fills the placeholders of a
+    /// [`CommandAction`](crate::CommandAction), but [`EnvVar::redact`] masks its value.
+    ///
+    /// # Example
+    /// ```rust
+    /// # let mut env = dagrs::EnvVar::new();
+    /// env.set_secret("API_TOKEN", "s3cr3t");
+    /// assert_eq!(env.get::<String>("API_TOKEN").unwrap(), "s3cr3t");
+    /// assert_eq!(env.redact("Authorization: Bearer s3cr3t"), "Authorization: Bearer ***");
+    /// ```
+    pub fn set_secret(&mut self, name: &str, value: impl Into<String>) {
+        self.variables
+            .insert(name.to_owned(), Variable::new(value.into()));
+        self.secrets.insert(name.to_owned());
+    }
+
+    /// Whether the variable with the given name is a secret.
+    pub fn is_secret(&self, name: &str) -> bool {
+        self.secrets.contains(name)
+    }
+
+    /// Replace the values of the secrets in a text with `***`. Longer values are replaced
+    /// first, so that a secret that contains another one is masked as a whole.
+    pub fn redact(&self, text: &str) -> String {
+        let mut values: Vec<&String> = self
+            .secrets
+            .iter()
+            .filter_map(|name| self.get_ref::<String>(name))
+            .filter(|value| !value.is_empty())
+            .collect();
+        values.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        values.into_iter().fold(text.to_owned(), |text, value| {
+            text.replace(value.as_str(), "***")
+        })
     }
 
     /// Get environment variables through keys of type &str.
@@ -44,6 +86,16 @@ impl EnvVar {
         self.get_ref(name).cloned()
     }
 
+    /// Get the content of an environment variable.
+    pub(crate) fn get_content(&self, name: &str) -> Option<&Content> {
+        self.variables.get(name)
+    }
+
+    /// Set an environment variable to a content.
+    pub(crate) fn set_content(&mut self, name: &str, content: Content) {
+        self.variables.insert(name.to_owned(), content);
+    }
+
     /// Get environment variables through keys of type &str.
     pub fn get_ref<H: Send + Sync + 'static>(&self, name: &str) -> Option<&H> {
         if let Some(content) = self.variables.get(name) {
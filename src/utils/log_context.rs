@@ -0,0 +1,232 @@
+//! Per-task log context
+//!
+//! While a task's action runs, the engine records which task is running on the current
+//! thread, and in which run of the dag. [`ContextLogger`] wraps any [`log::Log`] implementation
+//! and uses this context to prefix every log line emitted inside an action with the run id and
+//! the task's name and id. When the dag
+//! is built with [`Dag::capture_logs`](crate::Dag::capture_logs), the lines are also stored
+//! with the task's execution state, and can be read with [`Dag::get_logs`](crate::Dag::get_logs).
+//!
+//! The context also holds the [`Heartbeat`] of the running task, see [`heartbeat`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use dagrs::utils::ContextLogger;
+//!
+//! let logger = env_logger::Builder::new().build();
+//! ContextLogger::new(logger).init(log::LevelFilter::Info).unwrap();
+//! ```
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Log lines captured for a task.
+pub(crate) type LogBuffer = Arc<Mutex<Vec<String>>>;
+
+/// The task running on the current thread.
+#[derive(Clone)]
+struct TaskContext {
+    id: usize,
+    name: String,
+    run_id: Option<String>,
+    capture: Option<LogBuffer>,
+    heartbeat: Heartbeat,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<TaskContext>> = const { RefCell::new(None) };
+}
+
+/// Get the id and name of the task whose action is running on the current thread.
+pub fn current_task() -> Option<(usize, String)> {
+    CONTEXT.with(|ctx| ctx.borrow().as_ref().map(|c| (c.id, c.name.clone())))
+}
+
+/// Get the id of the run of the dag whose action is running on the current thread, see
+/// [`Dag::run_id`](crate::Dag::run_id).
+pub fn current_run() -> Option<String> {
+    CONTEXT.with(|ctx| ctx.borrow().as_ref().and_then(|c| c.run_id.clone()))
+}
+
+/// Report that the action running on the current thread is making progress, see [`Heartbeat`].
+/// It does nothing outside of an action.
+pub fn heartbeat() {
+    if let Some(heartbeat) = Heartbeat::current() {
+        heartbeat.beat();
+    }
+}
+
+/// The heartbeat of a running action. A long-running action beats regularly, so that the
+/// watchdog of the dag does not consider it stalled, see
+/// [`DagConfig::stall_timeout`](crate::engine::DagConfig::stall_timeout).
+///
+/// [`heartbeat`] beats on the thread of the action. An action that works on other threads gets
+/// its heartbeat with [`Heartbeat::current`] and moves it there.
+///
+/// # Example
+///
+/// ```rust
+/// use dagrs::{utils::heartbeat, DefaultTask, Output};
+///
+/// let task = DefaultTask::with_closure("import", |_input, _env| {
+///     for _chunk in 0..3 {
+///         // ... import a chunk ...
+///         heartbeat();
+///     }
+///     Output::empty()
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    start: Instant,
+    /// Milliseconds from `start` to the last beat.
+    last: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            last: Arc::default(),
+        }
+    }
+
+    /// The heartbeat of the action running on the current thread.
+    pub fn current() -> Option<Heartbeat> {
+        CONTEXT.with(|ctx| ctx.borrow().as_ref().map(|c| c.heartbeat.clone()))
+    }
+
+    pub fn beat(&self) {
+        let elapsed = self.start.elapsed().as_millis() as u64;
+        self.last.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    /// The time since the last beat, or since the action started if it never beat.
+    pub(crate) fn silence(&self) -> Duration {
+        self.start
+            .elapsed()
+            .saturating_sub(Duration::from_millis(self.last.load(Ordering::Relaxed)))
+    }
+}
+
+/// Restores the previous context when dropped, even if the action panics.
+struct ContextGuard(Option<TaskContext>);
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CONTEXT.with(|ctx| *ctx.borrow_mut() = previous);
+    }
+}
+
+/// Run `f` with the given task set as the current task.
+pub(crate) fn scope<R>(
+    id: usize,
+    name: &str,
+    run_id: Option<String>,
+    capture: Option<LogBuffer>,
+    heartbeat: Heartbeat,
+    f: impl FnOnce() -> R,
+) -> R {
+    let previous = CONTEXT.with(|ctx| {
+        ctx.borrow_mut().replace(TaskContext {
+            id,
+            name: name.to_owned(),
+            run_id,
+            capture,
+            heartbeat,
+        })
+    });
+    let _guard = ContextGuard(previous);
+    f()
+}
+
+/// The context of the task running on a thread, carried to the threads its action works on,
+/// so that their log lines and heartbeats count for the task.
+#[derive(Clone)]
+pub(crate) struct LogContext(Option<TaskContext>);
+
+impl LogContext {
+    /// The context of the current thread.
+    pub(crate) fn current() -> Self {
+        Self(CONTEXT.with(|ctx| ctx.borrow().clone()))
+    }
+
+    /// Run `f` in this context, on the current thread.
+    pub(crate) fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let previous = CONTEXT.with(|ctx| std::mem::replace(&mut *ctx.borrow_mut(), self.0));
+        let _guard = ContextGuard(previous);
+        f()
+    }
+}
+
+/// A [`Log`] implementation that prefixes log lines emitted inside an action with
+/// `[run name #id]` of the running task, captures them if requested, and then forwards them
+/// to the wrapped logger.
+pub struct ContextLogger<L: Log> {
+    inner: L,
+}
+
+impl<L: Log + 'static> ContextLogger<L> {
+    pub fn new(inner: L) -> Self {
+        Self { inner }
+    }
+
+    /// Install this logger as the global logger with the given maximum level.
+    pub fn init(self, level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl<L: Log> Log for ContextLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        CONTEXT.with(|ctx| match &*ctx.borrow() {
+            Some(ctx) => {
+                if let Some(buffer) = &ctx.capture {
+                    buffer
+                        .lock()
+                        .unwrap()
+                        .push(format!("[{}] {}", record.level(), record.args()));
+                }
+                let run = ctx
+                    .run_id
+                    .as_deref()
+                    .map_or_else(String::new, |id| id.to_owned() + " ");
+                self.inner.log(
+                    &Record::builder()
+                        .args(format_args!(
+                            "[{}{} #{}] {}",
+                            run,
+                            ctx.name,
+                            ctx.id,
+                            record.args()
+                        ))
+                        .metadata(record.metadata().clone())
+                        .module_path(record.module_path())
+                        .file(record.file())
+                        .line(record.line())
+                        .build(),
+                )
+            }
+            None => self.inner.log(record),
+        })
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
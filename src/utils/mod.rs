@@ -3,9 +3,22 @@
 //! This module contains common tools for the program, such as: environment
 //! variables, task generation macros.
 
+mod backoff;
 mod env;
 pub mod file;
+mod log_context;
+#[cfg(any(feature = "notify", feature = "notify-chat"))]
+mod outbox;
 mod parser;
+mod random;
+mod rotating_file;
 
+pub use self::backoff::Backoff;
 pub use self::env::EnvVar;
+pub use self::log_context::{current_run, current_task, heartbeat, ContextLogger, Heartbeat};
+pub(crate) use self::log_context::{scope as log_scope, LogBuffer, LogContext};
+#[cfg(any(feature = "notify", feature = "notify-chat"))]
+pub(crate) use self::outbox::Outbox;
 pub use self::parser::{ParseError, Parser};
+pub(crate) use self::random::{random_f64, splitmix64, unit_f64};
+pub use self::rotating_file::{RotatingFileWriter, Rotation};
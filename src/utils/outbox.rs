@@ -0,0 +1,49 @@
+//! Delivery of messages in the background.
+
+use std::{
+    fmt::Debug,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs jobs one after another, in the order they were sent, on a thread of its own, so that
+/// slow deliveries such as webhooks or emails, and the backoff between their retries, do not
+/// block the threads that run the tasks. The thread is started with the first job, and ends
+/// once the outbox is dropped and the jobs left are done.
+#[derive(Default)]
+pub(crate) struct Outbox {
+    sender: Mutex<Option<mpsc::Sender<Job>>>,
+}
+
+impl Outbox {
+    /// Queue a job.
+    pub(crate) fn send(&self, job: impl FnOnce() + Send + 'static) {
+        let mut sender = self.sender.lock().unwrap();
+        let sender = sender.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            thread::spawn(move || receiver.into_iter().for_each(|job| job()));
+            sender
+        });
+        sender.send(Box::new(job)).ok();
+    }
+
+    /// Wait for the jobs queued so far to be done.
+    pub(crate) fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.lock().unwrap().is_none() {
+            return;
+        }
+        self.send(move || {
+            done.send(()).ok();
+        });
+        wait.recv().ok();
+    }
+}
+
+impl Debug for Outbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Outbox").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,37 @@
+//! Small pseudo-random generators.
+
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// The next number of a SplitMix64 generator with the given state.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A number in `[0, 1)` drawn from the given 64 random bits.
+pub(crate) fn unit_f64(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+thread_local! {
+    /// The state of the generator of the thread, seeded once from the random keys of the
+    /// standard library.
+    static STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish());
+}
+
+/// A random number in `[0, 1)`, from a generator of the current thread. Not for security.
+pub(crate) fn random_f64() -> f64 {
+    STATE.with(|state| {
+        let mut next = state.get();
+        let bits = splitmix64(&mut next);
+        state.set(next);
+        unit_f64(bits)
+    })
+}
@@ -0,0 +1,166 @@
+//! Size- and time-limited log files.
+//!
+//! [`RotatingFileWriter`] is a [`Write`] implementation that rotates the file it writes to
+//! according to a [`Rotation`] policy, so that long-running engines don't grow unbounded log
+//! files. When the file is rotated, `dagrs.log` is renamed to `dagrs.log.1`, `dagrs.log.1`
+//! to `dagrs.log.2` and so on, and files beyond `max_files` are removed.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use dagrs::utils::{RotatingFileWriter, Rotation};
+//!
+//! let rotation = Rotation::new().max_size(10 * 1024 * 1024).max_files(5);
+//! let writer = RotatingFileWriter::new("dagrs.log", rotation).unwrap();
+//! env_logger::Builder::new()
+//!     .target(env_logger::Target::Pipe(Box::new(writer)))
+//!     .init();
+//! ```
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// When to rotate a log file, and how many rotated files to keep.
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    max_size: Option<u64>,
+    max_files: usize,
+    daily: bool,
+}
+
+impl Rotation {
+    /// A policy that never rotates and keeps 5 rotated files.
+    pub fn new() -> Self {
+        Self {
+            max_size: None,
+            max_files: 5,
+            daily: false,
+        }
+    }
+
+    /// Rotate before the file would grow beyond `bytes`.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Keep at most `count` rotated files besides the current one.
+    pub fn max_files(mut self, count: usize) -> Self {
+        self.max_files = count;
+        self
+    }
+
+    /// Rotate when the (UTC) day changes.
+    pub fn daily(mut self, daily: bool) -> Self {
+        self.daily = daily;
+        self
+    }
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A log file that rotates itself according to a [`Rotation`] policy.
+pub struct RotatingFileWriter {
+    path: PathBuf,
+    rotation: Rotation,
+    file: File,
+    size: u64,
+    day: u64,
+}
+
+impl RotatingFileWriter {
+    /// Open the file at `path` for appending, creating it if necessary.
+    pub fn new(path: impl AsRef<Path>, rotation: Rotation) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            rotation,
+            file,
+            size,
+            day: today(),
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        name.into()
+    }
+
+    fn should_rotate(&self, incoming: usize) -> bool {
+        let too_large = self
+            .rotation
+            .max_size
+            .is_some_and(|max| self.size > 0 && self.size + incoming as u64 > max);
+        let new_day = self.rotation.daily && today() != self.day;
+        too_large || new_day
+    }
+
+    /// Rotate the file now, unless it is empty, for example so that a new run starts a new
+    /// file instead of appending to the file of the last one.
+    pub fn rotate_now(&mut self) -> io::Result<()> {
+        if self.size == 0 {
+            return Ok(());
+        }
+        self.rotate()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let max_files = self.rotation.max_files;
+        if max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(max_files);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for index in (1..max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        self.day = today();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_rotate(buf.len()) {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Days since the unix epoch.
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
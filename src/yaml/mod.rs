@@ -32,11 +32,11 @@
 //!   f:
 //!     name: "Task 6"
 //!     after: [ g ]
-//!     cmd: python3 ./tests/config/test.py
+//!     cmd: python3 ./test.py
 //!   g:
 //!     name: "Task 7"
 //!     after: [ h ]
-//!     cmd: node ./tests/config/test.js
+//!     cmd: node ./test.js
 //!   h:
 //!     name: "Task 8"
 //!     cmd: echo h
@@ -52,6 +52,7 @@
 //! let dag = Dag::with_yaml("some_path",std::collections::HashMap::new());
 //! ```
 
+mod schema;
 mod yaml_parser;
 mod yaml_task;
 
@@ -77,6 +78,18 @@ pub enum YamlTaskError {
     /// `script` is not defined.
     #[error("The 'script' attribute is not defined. [{0}]")]
     NoScriptAttr(String),
+    /// An attribute has a value of the wrong type or out of range.
+    #[error("The '{1}' attribute has an illegal value. [{0}]")]
+    IllegalAttr(String, String),
+    /// No action factory is registered for the task's action type.
+    #[error("The action type '{1}' is not registered. [{0}]")]
+    UnknownActionType(String, String),
+    /// The template a task uses is not defined.
+    #[error("The template '{1}' is not defined. [{0}]")]
+    UnknownTemplate(String, String),
+    /// An attribute is not recognized, in strict mode. The last field suggests a known one.
+    #[error("The '{1}' attribute is unknown{2}. [{0}]")]
+    UnknownAttr(String, String, String),
 }
 
 /// Error about file information.
@@ -0,0 +1,153 @@
+//! JSON Schema of the yaml configuration file.
+
+use serde_json::{json, Value};
+
+/// The JSON Schema of the configuration files accepted by [`YamlParser`](super::YamlParser).
+pub(super) fn json_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "dagrs configuration",
+        "type": "object",
+        "required": ["dagrs"],
+        "properties": {
+            "dagrs": {
+                "description": "The tasks of the dag, by task id.",
+                "type": "object",
+                "additionalProperties": {
+                    "allOf": [
+                        { "$ref": "#/$defs/task" },
+                        { "required": ["name"] }
+                    ]
+                }
+            },
+            "vars": {
+                "description": "Values referred to as ${vars.<name>} in the tasks and profiles.",
+                "type": "object",
+                "additionalProperties": { "$ref": "#/$defs/scalar" }
+            },
+            "templates": {
+                "description": "Task bodies instantiated by tasks with uses, by name.",
+                "type": "object",
+                "additionalProperties": { "$ref": "#/$defs/task" }
+            },
+            "pools": {
+                "description": "The number of tasks of each pool that may run at the same time, by pool name.",
+                "type": "object",
+                "additionalProperties": { "type": "integer", "minimum": 1 }
+            },
+            "profiles": {
+                "description": "Profiles selected with --profile, by name.",
+                "type": "object",
+                "additionalProperties": { "$ref": "#/$defs/profile" }
+            }
+        },
+        "$defs": {
+            "scalar": { "type": ["string", "number", "boolean"] },
+            "task": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "name": { "type": "string" },
+                    "after": {
+                        "description": "The ids of the tasks that run before this one.",
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                    "cmd": {
+                        "description": "The command of the task, unless it gives a type.",
+                        "type": "string"
+                    },
+                    "shell": {
+                        "description": "The shell that runs the command and the condition.",
+                        "enum": ["sh", "bash", "zsh", "powershell", "cmd"]
+                    },
+                    "type": {
+                        "description": "The action type, created by the action registry.",
+                        "type": "string"
+                    },
+                    "args": {
+                        "description": "The arguments of the action type.",
+                        "type": "object",
+                        "additionalProperties": { "$ref": "#/$defs/scalar" }
+                    },
+                    "timeout": {
+                        "description": "Seconds the action may run before the task fails.",
+                        "type": "number",
+                        "minimum": 0
+                    },
+                    "retries": {
+                        "description": "How many times a failed action is run again.",
+                        "type": "integer",
+                        "minimum": 0
+                    },
+                    "delay": {
+                        "description": "How long the task waits before it runs: seconds, or a string with a unit (500ms, 5s, 2m, 1h).",
+                        "type": ["number", "string"]
+                    },
+                    "not_before": {
+                        "description": "The UTC time of day (HH:MM or HH:MM:SS) the task waits for.",
+                        "type": "string"
+                    },
+                    "not_after": {
+                        "description": "The UTC time of day (HH:MM or HH:MM:SS) after which the task fails instead of running.",
+                        "type": "string"
+                    },
+                    "if": {
+                        "description": "A command that must exit with code 0 for the task to run.",
+                        "type": "string"
+                    },
+                    "inputs": {
+                        "description": "The files the task reads, that are compared to its outputs.",
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                    "outputs": {
+                        "description": "The files the task writes; it does not run if they are newer than its inputs.",
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                    "lock": {
+                        "description": "The named lock, or locks, the task holds while it runs.",
+                        "oneOf": [
+                            { "type": "string" },
+                            { "type": "array", "items": { "type": "string" } }
+                        ]
+                    },
+                    "pool": {
+                        "description": "The pool of the top-level pools section the task runs in.",
+                        "type": "string"
+                    },
+                    "execution": {
+                        "description": "Where the action runs: inline on the runtime, or on a blocking thread for CPU-bound work.",
+                        "enum": ["inline", "blocking"]
+                    },
+                    "uses": {
+                        "description": "The template of the task.",
+                        "type": "string"
+                    },
+                    "with": {
+                        "description": "The arguments of the template, referred to as ${with.<name>}.",
+                        "type": "object",
+                        "additionalProperties": { "$ref": "#/$defs/scalar" }
+                    }
+                }
+            },
+            "profile": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "env": {
+                        "description": "Environment variables for the commands of all tasks.",
+                        "type": "object",
+                        "additionalProperties": { "$ref": "#/$defs/scalar" }
+                    },
+                    "tasks": {
+                        "description": "Attributes overriding those of the tasks, by task id.",
+                        "type": "object",
+                        "additionalProperties": { "$ref": "#/$defs/task" }
+                    }
+                }
+            }
+        }
+    })
+}
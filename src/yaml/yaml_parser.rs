@@ -1,14 +1,426 @@
 //! Default yaml configuration file parser.
 
-use super::{FileContentError, YamlTask, YamlTaskError};
-use crate::{utils::file::load_file, utils::ParseError, Action, CommandAction, Parser, Task};
-use std::{collections::HashMap, sync::Arc};
-use yaml_rust::{Yaml, YamlLoader};
+use super::{FileContentError, FileNotFound, YamlTask, YamlTaskError};
+use crate::{
+    engine::{TaskConfig, TimeOfDay},
+    utils::file::load_file,
+    utils::ParseError,
+    Action, ActionRegistry, CommandAction, CommandCondition, Parser, Shell, Task, TaskFiles,
+    TaskPool,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use yaml_rust::{yaml::Hash, Yaml, YamlLoader};
 
 /// An implementation of [`Parser`]. It is the default yaml configuration file parser.
-pub struct YamlParser;
+///
+/// # Profiles
+///
+/// A configuration file may define profiles, such as `dev` or `prod`, next to its tasks. A
+/// profile gives environment variables for the commands of all tasks, and attributes that
+/// override those of single tasks. The parser applies the profile selected with
+/// [`YamlParser::with_profile`], if any.
+///
+/// ```yaml
+/// dagrs:
+///   a:
+///     name: "Upload"
+///     cmd: ./upload.sh
+/// profiles:
+///   prod:
+///     env:
+///       TARGET: https://example.com
+///     tasks:
+///       a:
+///         retries: 3
+/// ```
+///
+/// # Action types
+///
+/// Instead of a `cmd`, a task may give the `type` of its action, and optionally string `args`
+/// for it. The action is created by the factory registered under that type in the
+/// [`ActionRegistry`] of the parser, see [`YamlParser::action_registry`].
+///
+/// ```yaml
+/// dagrs:
+///   a:
+///     name: "Fetch"
+///     type: http
+///     args:
+///       url: https://example.com
+/// ```
+///
+/// # Variables
+///
+/// Values of the top-level `vars` section are referred to as `${vars.<name>}` in any string of
+/// the tasks and profiles. A string that is a single reference takes the value of the variable
+/// with its type, so that it may also be a `timeout` or a `retries`. Other `${...}` are left
+/// as they are, for the shell.
+///
+/// The parameters given with [`YamlParser::params`] are referred to as `${params.<key>}`, in
+/// the tasks, the profiles and the values of variables. In the `cmd` and `if` commands, and in
+/// the variables they refer to, a parameter is an `{{ env.<key> }}` placeholder of the command,
+/// whose value is quoted for the shell like the other placeholders of a [`CommandAction`]:
+/// `cmd: deploy --to ${params.target}` passes the parameter as one argument, whatever it
+/// holds. The values given to a template with `with` are inserted as they are, so commands
+/// should refer to parameters themselves.
+///
+/// ```yaml
+/// vars:
+///   host: example.com
+///   wait: 30
+/// dagrs:
+///   a:
+///     name: "Ping"
+///     cmd: ping -c 1 ${vars.host}
+///     timeout: ${vars.wait}
+/// ```
+///
+/// # Templates
+///
+/// The top-level `templates` section defines task bodies shared by several tasks. A task
+/// instantiates a template with `uses`, and gives the arguments of the template with `with`,
+/// referred to as `${with.<name>}` in the template. The attributes of the task override those
+/// of the template.
+///
+/// ```yaml
+/// templates:
+///   deploy:
+///     cmd: ./deploy.sh --region ${with.region}
+///     timeout: 600
+/// dagrs:
+///   eu:
+///     name: "Deploy EU"
+///     uses: deploy
+///     with:
+///       region: eu-west-1
+///   us:
+///     name: "Deploy US"
+///     uses: deploy
+///     with:
+///       region: us-east-1
+/// ```
+///
+/// # Relative paths
+///
+/// The commands of a configuration file read with [`Parser::parse_tasks`] run in the directory
+/// of the file, so that the relative paths of scripts and files they refer to do not depend on
+/// where dagrs is started. [`YamlParser::relative_to_cwd`] runs them in the working directory
+/// of the process instead.
+///
+/// # Files
+///
+/// A task may list the files it reads as `inputs` and the files it writes as `outputs`. It does
+/// not run if all its outputs exist and are newer than its inputs, see [`TaskFiles`]. Relative
+/// paths are relative to the directory the commands run in.
+///
+/// ```yaml
+/// dagrs:
+///   a:
+///     name: "Compile"
+///     cmd: cc -c main.c
+///     inputs: [main.c, main.h]
+///     outputs: [main.o]
+/// ```
+///
+/// # Locks
+///
+/// Tasks that give the same `lock`, or one of the same list of locks, never run at the same
+/// time, even if they do not depend on each other, see [`Task::locks`].
+///
+/// ```yaml
+/// dagrs:
+///   a:
+///     name: "Migrate users"
+///     cmd: ./migrate.sh users
+///     lock: db-migrations
+///   b:
+///     name: "Migrate orders"
+///     cmd: ./migrate.sh orders
+///     lock: [db-migrations, orders]
+/// ```
+///
+/// # Pools
+///
+/// The top-level `pools` section gives the size of each pool, and a task joins a pool with
+/// `pool`. At most as many tasks of a pool as its size run at the same time, see [`TaskPool`].
+///
+/// ```yaml
+/// pools:
+///   downloads: 4
+/// dagrs:
+///   a:
+///     name: "Fetch A"
+///     cmd: curl -O https://example.com/a
+///     pool: downloads
+///   b:
+///     name: "Fetch B"
+///     cmd: curl -O https://example.com/b
+///     pool: downloads
+/// ```
+///
+/// # Execution
+///
+/// A CPU-bound task gives `execution: blocking`, so that its action runs on a blocking thread
+/// instead of holding up the runtime, see [`Execution`](crate::engine::Execution).
+///
+/// ```yaml
+/// dagrs:
+///   a:
+///     name: "Compress"
+///     cmd: xz -9 data.tar
+///     execution: blocking
+/// ```
+///
+/// # Strict mode
+///
+/// Attributes the parser does not know are ignored, so that a misspelled `timout` silently has
+/// no effect. A parser created with [`YamlParser::strict`] rejects them instead.
+#[derive(Debug, Clone, Default)]
+pub struct YamlParser {
+    /// The name of the selected profile.
+    profile: Option<String>,
+    /// Factories of the actions of tasks that give an action type.
+    registry: Option<ActionRegistry>,
+    /// Reject unknown attributes.
+    strict: bool,
+    /// Run commands in the working directory of the process rather than the directory of the
+    /// configuration file.
+    cwd_paths: bool,
+    /// The values of the `${params.<key>}` references.
+    params: Option<HashMap<String, String>>,
+}
+
+/// The attributes of a task.
+const TASK_KEYS: &[&str] = &[
+    "name",
+    "after",
+    "cmd",
+    "shell",
+    "type",
+    "args",
+    "timeout",
+    "retries",
+    "delay",
+    "not_before",
+    "not_after",
+    "if",
+    "inputs",
+    "outputs",
+    "lock",
+    "pool",
+    "execution",
+    "uses",
+    "with",
+];
+/// The top-level keys of a configuration file.
+const ROOT_KEYS: &[&str] = &["dagrs", "profiles", "vars", "templates", "pools"];
+/// The attributes of a profile.
+const PROFILE_KEYS: &[&str] = &["env", "tasks"];
+
+/// The settings of the selected profile.
+#[derive(Default)]
+struct Profile<'a> {
+    /// Environment variables for the commands of all tasks.
+    envs: Vec<(String, String)>,
+    /// Attributes overriding those of the tasks, by task id.
+    tasks: Option<&'a Hash>,
+}
 
 impl YamlParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a parser that applies the given profile of the configuration file.
+    pub fn with_profile(profile: &str) -> Self {
+        Self {
+            profile: Some(profile.to_owned()),
+            ..Self::default()
+        }
+    }
+
+    /// Create the actions of tasks that give an action `type` with the factories of the given
+    /// registry.
+    pub fn action_registry(mut self, registry: ActionRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Fail on attributes and keys the parser does not know, such as misspelled ones, instead
+    /// of ignoring them.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Replace the `${params.<key>}` references of the configuration file with the given
+    /// parameters, see [`Dag::with_params`](crate::Dag::with_params).
+    pub fn params(mut self, params: HashMap<String, String>) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Resolve the relative paths of commands against the working directory of the process
+    /// instead of the directory of the configuration file.
+    pub fn relative_to_cwd(mut self) -> Self {
+        self.cwd_paths = true;
+        self
+    }
+
+    /// The directory the commands of the given configuration file run in, if not the working
+    /// directory of the process.
+    pub fn base_dir<'a>(&self, file: &'a str) -> Option<&'a Path> {
+        if self.cwd_paths {
+            return None;
+        }
+        Path::new(file)
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+    }
+
+    /// In strict mode, check that all keys of `hash` are in `known`.
+    fn check_keys(&self, location: &str, hash: &Hash, known: &[&str]) -> Result<(), YamlTaskError> {
+        if !self.strict {
+            return Ok(());
+        }
+        for key in hash.keys() {
+            let key = match key {
+                Yaml::String(key) => key.as_str(),
+                key => {
+                    return Err(YamlTaskError::UnknownAttr(
+                        location.to_owned(),
+                        format!("{:?}", key),
+                        String::new(),
+                    ))
+                }
+            };
+            if !known.contains(&key) {
+                let hint = known
+                    .iter()
+                    .find(|known| edit_distance(key, known) <= 2)
+                    .map(|known| format!(", did you mean '{}'?", known))
+                    .unwrap_or_default();
+                return Err(YamlTaskError::UnknownAttr(
+                    location.to_owned(),
+                    key.to_owned(),
+                    hint,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The item of a task, with the attributes of the template it uses, if any.
+    fn expand_template(&self, id: &str, item: &Yaml, doc: &Yaml) -> Result<Yaml, ParseError> {
+        let name = match &item["uses"] {
+            Yaml::String(name) => name,
+            Yaml::BadValue => return Ok(item.clone()),
+            _ => return Err(YamlTaskError::IllegalAttr(id.to_owned(), "uses".to_owned()).into()),
+        };
+        let template = doc["templates"][name.as_str()]
+            .as_hash()
+            .ok_or(YamlTaskError::UnknownTemplate(id.to_owned(), name.clone()))?;
+        self.check_keys(&format!("templates.{}", name), template, TASK_KEYS)?;
+        if template.contains_key(&Yaml::String("uses".to_owned())) {
+            return Err(ParseError(format!(
+                "Template '{}' cannot use another template.",
+                name
+            )));
+        }
+        let mut args = HashMap::new();
+        if let Some(with) = item["with"].as_hash() {
+            for (key, value) in with {
+                match (key.as_str(), scalar_to_string(value)) {
+                    (Some(key), Some(_)) => args.insert(format!("with.{}", key), value.clone()),
+                    _ => {
+                        return Err(
+                            YamlTaskError::IllegalAttr(id.to_owned(), "with".to_owned()).into()
+                        )
+                    }
+                };
+            }
+        }
+        let mut expanded = Yaml::Hash(template.clone());
+        substitute(&mut expanded, &args, None, &["with"])?;
+        let Yaml::Hash(mut expanded) = expanded else {
+            unreachable!()
+        };
+        if let Some(attrs) = item.as_hash() {
+            expanded.extend(
+                attrs
+                    .iter()
+                    .filter(|(key, _)| !matches!(key.as_str(), Some("uses" | "with")))
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+        }
+        Ok(Yaml::Hash(expanded))
+    }
+
+    /// The JSON Schema of the configuration files accepted by the parser, for editors and
+    /// validation tools. It is also printed by `dagrs --schema`.
+    pub fn json_schema() -> serde_json::Value {
+        let schema = super::schema::json_schema();
+        // The schema documents every attribute of a task, and only those.
+        debug_assert!(schema["$defs"]["task"]["properties"]
+            .as_object()
+            .is_some_and(|properties| properties.len() == TASK_KEYS.len()
+                && TASK_KEYS.iter().all(|key| properties.contains_key(*key))));
+        schema
+    }
+
+    /// The environment variables and task overrides of the selected profile.
+    fn profile<'a>(&self, doc: &'a Yaml) -> Result<Profile<'a>, ParseError> {
+        let Some(name) = &self.profile else {
+            return Ok(Profile::default());
+        };
+        let profile = &doc["profiles"][name.as_str()];
+        if profile.as_hash().is_none() {
+            return Err(ParseError(format!("Profile '{}' not found.", name)));
+        }
+        let location = format!("profiles.{}", name);
+        if let Some(profile) = profile.as_hash() {
+            self.check_keys(&location, profile, PROFILE_KEYS)?;
+        }
+        if let Some(tasks) = profile["tasks"].as_hash() {
+            for (id, attrs) in tasks {
+                let id = id.as_str().unwrap_or_default();
+                if doc["dagrs"][id].is_badvalue() {
+                    let msg = format!("Profile '{}' overrides the unknown task '{}'.", name, id);
+                    if self.strict {
+                        return Err(ParseError(msg));
+                    }
+                    log::warn!("{}", msg);
+                }
+                if let Some(attrs) = attrs.as_hash() {
+                    self.check_keys(&format!("{}.tasks.{}", location, id), attrs, TASK_KEYS)?;
+                }
+            }
+        }
+        let mut envs = Vec::new();
+        if let Some(env) = profile["env"].as_hash() {
+            for (key, value) in env {
+                let value = scalar_to_string(value).ok_or(ParseError(format!(
+                    "Profile '{}' has an illegal env value.",
+                    name
+                )))?;
+                let key = key.as_str().ok_or(ParseError(format!(
+                    "Profile '{}' has an illegal env name.",
+                    name
+                )))?;
+                envs.push((key.to_owned(), value));
+            }
+        }
+        Ok(Profile {
+            envs,
+            tasks: profile["tasks"].as_hash(),
+        })
+    }
+
     /// Parses an item in the configuration file into a task.
     /// An item refers to:
     ///
@@ -17,11 +429,24 @@ impl YamlParser {
     ///    after: [b, c]
     ///    cmd: echo a
     /// ```
+    ///
+    /// An item may also give a `timeout` in seconds and a number of `retries`, overriding the
+    /// defaults of the dag, a `delay` before it runs, in seconds or with a unit such as `500ms`,
+    /// `5s`, `2m` or `1h`, a scheduling window between the UTC times of day `not_before` and
+    /// `not_after`, see [`TaskConfig`], and the `shell` that runs its command: `sh`, `bash`, `zsh`,
+    /// `powershell` or `cmd`. The default is `powershell` on Windows and `sh` elsewhere.
+    /// The command may refer to the outputs of the predecessors and to environment variables,
+    /// see [`CommandAction`]. An `if` command, run in the same shell, makes the task run only
+    /// if it exits with code 0, see [`CommandCondition`]. A task whose action is given by the
+    /// caller or by its `type` runs no command, so it may only have a `shell` for its `if`.
     fn parse_one(
         &self,
         id: &str,
         item: &Yaml,
         specific_action: Option<Action>,
+        envs: &[(String, String)],
+        dir: Option<&Path>,
+        pools: &Yaml,
     ) -> Result<YamlTask, YamlTaskError> {
         // Get name first
         let name = item["name"]
@@ -36,19 +461,142 @@ impl YamlParser {
                 .for_each(|task_id| precursors.push(task_id.as_str().unwrap().to_owned()));
         }
 
-        if let Some(action) = specific_action {
-            Ok(YamlTask::new(id, precursors, name, action))
+        let config = TaskConfig {
+            timeout: match &item["timeout"] {
+                Yaml::Integer(secs) if *secs >= 0 => Some(Duration::from_secs(*secs as u64)),
+                Yaml::Real(_) => item["timeout"]
+                    .as_f64()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+                Yaml::BadValue => None,
+                _ => return Err(YamlTaskError::IllegalAttr(name, "timeout".to_owned())),
+            },
+            retries: match &item["retries"] {
+                Yaml::Integer(retries) => {
+                    Some(u32::try_from(*retries).map_err(|_| {
+                        YamlTaskError::IllegalAttr(name.clone(), "retries".to_owned())
+                    })?)
+                }
+                Yaml::BadValue => None,
+                _ => return Err(YamlTaskError::IllegalAttr(name, "retries".to_owned())),
+            },
+            delay: parse_duration(&item["delay"])
+                .ok_or_else(|| YamlTaskError::IllegalAttr(name.clone(), "delay".to_owned()))?,
+            not_before: parse_time_of_day(&item["not_before"])
+                .ok_or_else(|| YamlTaskError::IllegalAttr(name.clone(), "not_before".to_owned()))?,
+            not_after: parse_time_of_day(&item["not_after"])
+                .ok_or_else(|| YamlTaskError::IllegalAttr(name.clone(), "not_after".to_owned()))?,
+            execution: match &item["execution"] {
+                Yaml::String(execution) => Some(execution.parse().map_err(|_| {
+                    YamlTaskError::IllegalAttr(name.clone(), "execution".to_owned())
+                })?),
+                Yaml::BadValue => None,
+                _ => return Err(YamlTaskError::IllegalAttr(name, "execution".to_owned())),
+            },
+            ..TaskConfig::default()
+        };
+
+        let shell = match &item["shell"] {
+            Yaml::String(shell) => shell
+                .parse()
+                .map_err(|_| YamlTaskError::IllegalAttr(name.clone(), "shell".to_owned()))?,
+            Yaml::BadValue => Shell::default(),
+            _ => return Err(YamlTaskError::IllegalAttr(name, "shell".to_owned())),
+        };
+        let condition = match &item["if"] {
+            Yaml::String(cmd) => {
+                let mut condition = CommandCondition::new(cmd).with_shell(shell);
+                if let Some(dir) = dir {
+                    condition = condition.with_current_dir(dir);
+                }
+                Some(
+                    envs.iter()
+                        .map(|(key, value)| (key, value))
+                        .chain(self.command_params(cmd))
+                        .fold(condition, |condition, (key, value)| {
+                            condition.with_env(key, value)
+                        }),
+                )
+            }
+            Yaml::BadValue => None,
+            _ => return Err(YamlTaskError::IllegalAttr(name, "if".to_owned())),
+        };
+        let runs_command = specific_action.is_none() && item["type"].is_badvalue();
+        if !item["shell"].is_badvalue() && !runs_command && condition.is_none() {
+            return Err(YamlTaskError::IllegalAttr(name, "shell".to_owned()));
+        }
+
+        let inputs = parse_paths(&item["inputs"], dir)
+            .ok_or_else(|| YamlTaskError::IllegalAttr(name.clone(), "inputs".to_owned()))?;
+        let outputs = parse_paths(&item["outputs"], dir)
+            .ok_or_else(|| YamlTaskError::IllegalAttr(name.clone(), "outputs".to_owned()))?;
+        let locks = match &item["lock"] {
+            Yaml::String(lock) => vec![lock.clone()],
+            Yaml::Array(locks) => locks
+                .iter()
+                .map(|lock| lock.as_str().map(str::to_owned))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| YamlTaskError::IllegalAttr(name.clone(), "lock".to_owned()))?,
+            Yaml::BadValue => Vec::new(),
+            _ => return Err(YamlTaskError::IllegalAttr(name, "lock".to_owned())),
+        };
+        // The pool must be one of the top-level section, with a positive size.
+        let pool = match &item["pool"] {
+            Yaml::String(pool) => match pools[pool.as_str()] {
+                Yaml::Integer(size) if size > 0 => Some(TaskPool::new(pool, size as usize)),
+                _ => return Err(YamlTaskError::IllegalAttr(name, "pool".to_owned())),
+            },
+            Yaml::BadValue => None,
+            _ => return Err(YamlTaskError::IllegalAttr(name, "pool".to_owned())),
+        };
+
+        let mut task = if let Some(action) = specific_action {
+            YamlTask::new(id, precursors, name, action)
+        } else if let Some(action_type) = item["type"].as_str() {
+            let mut args = HashMap::new();
+            if let Some(hash) = item["args"].as_hash() {
+                for (key, value) in hash {
+                    match (key.as_str(), scalar_to_string(value)) {
+                        (Some(key), Some(value)) => args.insert(key.to_owned(), value),
+                        _ => return Err(YamlTaskError::IllegalAttr(name, "args".to_owned())),
+                    };
+                }
+            }
+            let registry = self.registry.as_ref();
+            let action = registry.and_then(|registry| registry.create(action_type, &args));
+            let action = action.ok_or_else(|| {
+                YamlTaskError::UnknownActionType(name.clone(), action_type.to_owned())
+            })?;
+            let mut task = YamlTask::new(id, precursors, name, action);
+            task.set_action_type(action_type);
+            task.set_action_args(args);
+            task
         } else {
             let cmd = item["cmd"]
                 .as_str()
                 .ok_or(YamlTaskError::NoScriptAttr(name.clone()))?;
-            Ok(YamlTask::new(
-                id,
-                precursors,
-                name,
-                Action::Structure(Arc::new(CommandAction::new(cmd))),
-            ))
+            let mut action = CommandAction::new(cmd).with_shell(shell);
+            if let Some(dir) = dir {
+                action = action.with_current_dir(dir);
+            }
+            let action = envs
+                .iter()
+                .map(|(key, value)| (key, value))
+                .chain(self.command_params(cmd))
+                .fold(action, |action, (key, value)| action.with_env(key, value));
+            YamlTask::new(id, precursors, name, Action::Structure(Arc::new(action)))
+        };
+        task.set_config(config);
+        if let Some(condition) = condition {
+            task.set_condition(condition);
+        }
+        if !inputs.is_empty() || !outputs.is_empty() {
+            task.set_files(TaskFiles::new(inputs, outputs));
         }
+        task.set_locks(locks);
+        if let Some(pool) = pool {
+            task.set_pool(pool);
+        }
+        Ok(task)
     }
 }
 
@@ -58,8 +606,7 @@ impl Parser for YamlParser {
         file: &str,
         specific_actions: HashMap<String, Action>,
     ) -> Result<Vec<Box<dyn Task>>, ParseError> {
-        let content = load_file(file)?;
-        self.parse_tasks_from_str(&content, specific_actions)
+        self.parse_files(&[file], specific_actions)
     }
 
     fn parse_tasks_from_str(
@@ -67,46 +614,372 @@ impl Parser for YamlParser {
         content: &str,
         mut specific_actions: HashMap<String, Action>,
     ) -> Result<Vec<Box<dyn Task>>, ParseError> {
-        // Parse Yaml
-        let yaml_tasks =
+        let doc = self.load_doc(content)?;
+        let tasks = self.parse_doc(&doc, &mut specific_actions, None, true)?;
+        link(tasks)
+    }
+}
+
+impl YamlParser {
+    /// Parse the tasks of several configuration files, or of all `.yaml` and `.yml` files of
+    /// directories, into one dag. Tasks may run after tasks of other files, but the `vars` and
+    /// `templates` of a file only apply to its own tasks. A task id defined by two files is an
+    /// error.
+    ///
+    /// If a profile is selected, the files that do not define it are parsed without profile;
+    /// it is an error if none of them defines it.
+    pub fn parse_files(
+        &self,
+        paths: &[impl AsRef<Path>],
+        mut specific_actions: HashMap<String, Action>,
+    ) -> Result<Vec<Box<dyn Task>>, ParseError> {
+        let mut files = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            if path.is_dir() {
+                let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+                    .map_err(FileNotFound)?
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        matches!(
+                            path.extension().and_then(|ext| ext.to_str()),
+                            Some("yaml" | "yml")
+                        )
+                    })
+                    .collect();
+                entries.sort();
+                files.extend(entries);
+            } else {
+                files.push(path.to_owned());
+            }
+        }
+        if files.is_empty() {
+            return Err(ParseError("No configuration files found.".to_owned()));
+        }
+
+        let single = files.len() == 1;
+        let mut profile_found = false;
+        let mut origins: HashMap<String, &Path> = HashMap::new();
+        let mut tasks = Vec::new();
+        for file in &files {
+            let path = file.to_string_lossy();
+            let content = load_file(&path)?;
+            let doc = self.load_doc(&content)?;
+            if let Some(name) = &self.profile {
+                profile_found |= !doc["profiles"][name.as_str()].is_badvalue();
+            }
+            for task in self.parse_doc(&doc, &mut specific_actions, self.base_dir(&path), single)? {
+                if let Some(other) = origins.insert(task.str_id().to_owned(), file) {
+                    return Err(ParseError(format!(
+                        "Task '{}' is defined in both '{}' and '{}'.",
+                        task.str_id(),
+                        other.display(),
+                        file.display()
+                    )));
+                }
+                tasks.push(task);
+            }
+        }
+        if let (Some(name), false) = (&self.profile, single || profile_found) {
+            return Err(ParseError(format!("Profile '{}' not found.", name)));
+        }
+        link(tasks)
+    }
+
+    /// The parameters the given command refers to, as the `{{ env.<key> }}` placeholders that
+    /// replace their `${params.<key>}` references, set as environment variables of the command
+    /// so that they have a value without [`Dag::with_params`](crate::Dag::with_params).
+    fn command_params<'a>(
+        &'a self,
+        cmd: &'a str,
+    ) -> impl Iterator<Item = (&'a String, &'a String)> + 'a {
+        self.params
+            .iter()
+            .flatten()
+            .filter(move |(key, _)| cmd.contains(&format!("{{{{ env.{} }}}}", key)))
+    }
+
+    /// Load the content of a configuration file, and replace its variables.
+    fn load_doc(&self, content: &str) -> Result<Yaml, ParseError> {
+        let docs =
             YamlLoader::load_from_str(content).map_err(FileContentError::IllegalYamlContent)?;
-        if yaml_tasks.is_empty() {
+        let Some(mut doc) = docs.into_iter().next() else {
             return Err(ParseError("No Tasks found".to_string()));
+        };
+        if let Some(root) = doc.as_hash() {
+            self.check_keys("<root>", root, ROOT_KEYS)?;
         }
-        let yaml_tasks = yaml_tasks[0]["dagrs"]
+        substitute_vars(self.params.as_ref().unwrap_or(&HashMap::new()), &mut doc)?;
+        Ok(doc)
+    }
+
+    /// Parse the tasks of a configuration file whose commands run in `dir`, if given, without
+    /// their predecessors. If `require_profile` is false, a missing profile is not an error.
+    fn parse_doc(
+        &self,
+        doc: &Yaml,
+        specific_actions: &mut HashMap<String, Action>,
+        dir: Option<&Path>,
+        require_profile: bool,
+    ) -> Result<Vec<YamlTask>, ParseError> {
+        let yaml_tasks = doc["dagrs"]
             .as_hash()
             .ok_or(YamlTaskError::StartWordError)?;
+        let profile = match &self.profile {
+            Some(name) if !require_profile && doc["profiles"][name.as_str()].is_badvalue() => {
+                Profile::default()
+            }
+            _ => self.profile(doc)?,
+        };
 
         let mut tasks = Vec::with_capacity(yaml_tasks.len());
-        let mut map = HashMap::with_capacity(yaml_tasks.len());
         // Read tasks
         for (v, w) in yaml_tasks {
             let id = v
                 .as_str()
                 .ok_or(ParseError("Invalid YAML Node Type".to_string()))?;
-            let task = specific_actions.remove(id).map_or_else(
-                || self.parse_one(id, w, None),
-                |action| self.parse_one(id, w, Some(action)),
+            let expanded = self.expand_template(id, w, doc)?;
+            let item = match profile
+                .tasks
+                .and_then(|tasks| tasks.get(v))
+                .and_then(Yaml::as_hash)
+            {
+                Some(attrs) => {
+                    let mut item = expanded.as_hash().cloned().unwrap_or_default();
+                    item.extend(attrs.iter().map(|(k, v)| (k.clone(), v.clone())));
+                    Yaml::Hash(item)
+                }
+                None => expanded,
+            };
+            if let Some(attrs) = w.as_hash() {
+                self.check_keys(id, attrs, TASK_KEYS)?;
+            }
+            let task = self.parse_one(
+                id,
+                &item,
+                specific_actions.remove(id),
+                &profile.envs,
+                dir,
+                &doc["pools"],
             )?;
-            map.insert(id, task.id());
             tasks.push(task);
         }
+        Ok(tasks)
+    }
+}
 
-        for task in tasks.iter_mut() {
-            let mut pres = Vec::new();
-            for pre in task.str_precursors() {
-                if map.contains_key(&pre[..]) {
-                    pres.push(map[&pre[..]]);
-                } else {
-                    return Err(YamlTaskError::NotFoundPrecursor(task.name().to_string()).into());
+/// Resolve the predecessors of the tasks by their ids in the configuration files.
+fn link(mut tasks: Vec<YamlTask>) -> Result<Vec<Box<dyn Task>>, ParseError> {
+    let map: HashMap<String, usize> = tasks
+        .iter()
+        .map(|task| (task.str_id().to_owned(), task.id()))
+        .collect();
+    for task in tasks.iter_mut() {
+        let mut pres = Vec::new();
+        for pre in task.str_precursors() {
+            match map.get(&pre) {
+                Some(id) => pres.push(*id),
+                None => {
+                    return Err(YamlTaskError::NotFoundPrecursor(task.name().to_string()).into())
                 }
             }
-            task.init_precursors(pres);
         }
+        task.init_precursors(pres);
+    }
 
-        Ok(tasks
-            .into_iter()
-            .map(|task| Box::new(task) as Box<dyn Task>)
-            .collect())
+    Ok(tasks
+        .into_iter()
+        .map(|task| Box::new(task) as Box<dyn Task>)
+        .collect())
+}
+
+/// A duration in seconds, or a string with a unit: `ms`, `s`, `m` or `h`. `Some(None)` if the
+/// attribute is absent, `None` if it is illegal.
+fn parse_duration(value: &Yaml) -> Option<Option<Duration>> {
+    let duration = match value {
+        Yaml::BadValue => return Some(None),
+        Yaml::Integer(secs) => Duration::from_secs(u64::try_from(*secs).ok()?),
+        Yaml::Real(_) => Duration::try_from_secs_f64(value.as_f64()?).ok()?,
+        Yaml::String(text) => {
+            let text = text.trim();
+            let split = text.find(|c: char| c.is_alphabetic())?;
+            let amount: f64 = text[..split].trim().parse().ok()?;
+            let unit = match &text[split..] {
+                "ms" => 0.001,
+                "s" => 1.0,
+                "m" => 60.0,
+                "h" => 3600.0,
+                _ => return None,
+            };
+            Duration::try_from_secs_f64(amount * unit).ok()?
+        }
+        _ => return None,
+    };
+    Some(Some(duration))
+}
+
+/// A list of paths, relative to `dir` if given. Empty if the attribute is absent, `None` if it
+/// is illegal.
+fn parse_paths(value: &Yaml, dir: Option<&Path>) -> Option<Vec<PathBuf>> {
+    match value {
+        Yaml::Array(paths) => paths
+            .iter()
+            .map(|path| {
+                let path = Path::new(path.as_str()?);
+                Some(dir.map_or_else(|| path.to_owned(), |dir| dir.join(path)))
+            })
+            .collect(),
+        Yaml::BadValue => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+/// A time of day such as `22:30`. `Some(None)` if the attribute is absent, `None` if it is
+/// illegal.
+fn parse_time_of_day(value: &Yaml) -> Option<Option<TimeOfDay>> {
+    match value {
+        Yaml::BadValue => Some(None),
+        Yaml::String(text) => text.parse().ok().map(Some),
+        _ => None,
+    }
+}
+
+/// The text of a string, number or boolean.
+fn scalar_to_string(value: &Yaml) -> Option<String> {
+    match value {
+        Yaml::String(s) | Yaml::Real(s) => Some(s.clone()),
+        Yaml::Integer(i) => Some(i.to_string()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// The Levenshtein distance between two strings, to suggest the attribute a misspelled one
+/// stands for.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != *cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Replace the references to the parameters and to the `vars` section in the tasks and
+/// profiles of the document.
+/// In the `cmd` and `if` commands, a parameter becomes an `{{ env.<key> }}` placeholder, so
+/// that its value is quoted for the shell, see [`YamlParser::command_params`].
+fn substitute_vars(params: &HashMap<String, String>, doc: &mut Yaml) -> Result<(), ParseError> {
+    let mut vars: HashMap<String, Yaml> = params
+        .iter()
+        .map(|(key, value)| (format!("params.{}", key), Yaml::from_str(value)))
+        .collect();
+    let mut command_vars: HashMap<String, Yaml> = params
+        .keys()
+        .map(|key| {
+            let placeholder = format!("{{{{ env.{} }}}}", key);
+            (format!("params.{}", key), Yaml::String(placeholder))
+        })
+        .collect();
+    if let Some(hash) = doc["vars"].as_hash() {
+        for (key, value) in hash {
+            let mut value = value.clone();
+            substitute(&mut value, &vars, None, &["params"])?;
+            let key = key
+                .as_str()
+                .ok_or(ParseError("Illegal variable name.".to_owned()))?;
+            if scalar_to_string(&value).is_none() {
+                return Err(ParseError(format!(
+                    "The variable '{}' has an illegal value.",
+                    key
+                )));
+            }
+            vars.insert(format!("vars.{}", key), value);
+            let mut value = hash[&Yaml::String(key.to_owned())].clone();
+            substitute(&mut value, &command_vars, None, &["params"])?;
+            command_vars.insert(format!("vars.{}", key), value);
+        }
+    }
+    let Yaml::Hash(root) = doc else {
+        return Ok(());
+    };
+    for section in ["dagrs", "profiles", "templates", "pools"] {
+        if let Some(section) = root.get_mut(&Yaml::String(section.to_owned())) {
+            substitute(section, &vars, Some(&command_vars), &["vars", "params"])?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the `${<scope>.<name>}` references to the given values in all strings of `yaml`,
+/// and to the `commands` values in the `cmd` and `if` commands of tasks, if given.
+/// A reference in one of the given scopes must have a value; references in other scopes are
+/// kept.
+fn substitute(
+    yaml: &mut Yaml,
+    values: &HashMap<String, Yaml>,
+    commands: Option<&HashMap<String, Yaml>>,
+    scopes: &[&str],
+) -> Result<(), ParseError> {
+    match yaml {
+        Yaml::String(text) => {
+            if let Some(value) = text
+                .strip_prefix("${")
+                .and_then(|rest| rest.strip_suffix('}'))
+                .and_then(|key| values.get(key.trim()))
+            {
+                *yaml = value.clone();
+                return Ok(());
+            }
+            let mut result = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${") {
+                let Some(len) = rest[start..].find('}') else {
+                    break;
+                };
+                let key = rest[start + 2..start + len].trim();
+                result.push_str(&rest[..start]);
+                match values.get(key) {
+                    Some(value) => result.push_str(&scalar_to_string(value).unwrap_or_default()),
+                    None if key
+                        .split_once('.')
+                        .is_some_and(|(scope, _)| scopes.contains(&scope)) =>
+                    {
+                        return Err(ParseError(format!("Variable '{}' is not defined.", key)))
+                    }
+                    None => result.push_str(&rest[start..=start + len]),
+                }
+                rest = &rest[start + len + 1..];
+            }
+            result.push_str(rest);
+            *text = result;
+        }
+        Yaml::Array(items) => {
+            for item in items {
+                substitute(item, values, commands, scopes)?;
+            }
+        }
+        Yaml::Hash(hash) => {
+            for (key, value) in hash.iter_mut() {
+                match (key.as_str(), commands) {
+                    (Some("cmd" | "if"), Some(commands)) => {
+                        substitute(value, commands, None, scopes)?
+                    }
+                    // The arguments of registered actions are not commands.
+                    (Some("args"), _) => substitute(value, values, None, scopes)?,
+                    _ => substitute(value, values, commands, scopes)?,
+                }
+            }
+        }
+        _ => {}
     }
+    Ok(())
 }
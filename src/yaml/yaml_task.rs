@@ -7,7 +7,8 @@
 //! It is different from `DefaultTask`, in addition to the four mandatory attributes of the
 //! task type, he has several additional attributes.
 
-use crate::{alloc_id, Action, Task};
+use crate::{alloc_id, engine::TaskConfig, Action, Condition, Task, TaskFiles, TaskPool};
+use std::{collections::HashMap, sync::Arc};
 
 /// Task struct for yaml file.
 pub struct YamlTask {
@@ -19,6 +20,20 @@ pub struct YamlTask {
     precursors: Vec<String>,
     precursors_id: Vec<usize>,
     action: Action,
+    /// Execution settings given in yaml.
+    config: TaskConfig,
+    /// The action type given in yaml, see [`Task::action_type`].
+    action_type: Option<String>,
+    /// The `args` of the action type given in yaml, see [`Task::action_args`].
+    action_args: HashMap<String, String>,
+    /// The condition given in yaml, see [`Task::condition`].
+    condition: Option<Arc<dyn Condition>>,
+    /// The `inputs` and `outputs` given in yaml, see [`Task::files`].
+    files: Option<TaskFiles>,
+    /// The `lock` given in yaml, see [`Task::locks`].
+    locks: Vec<String>,
+    /// The `pool` given in yaml, see [`Task::pool`].
+    pool: Option<TaskPool>,
 }
 
 impl YamlTask {
@@ -31,6 +46,13 @@ impl YamlTask {
             precursors,
             precursors_id: Vec::new(),
             action,
+            config: TaskConfig::default(),
+            action_type: None,
+            action_args: HashMap::new(),
+            condition: None,
+            files: None,
+            locks: Vec::new(),
+            pool: None,
         }
     }
     /// After the configuration file is parsed, the id of each task has been assigned.
@@ -46,6 +68,41 @@ impl YamlTask {
     pub fn str_precursors(&self) -> Vec<String> {
         self.precursors.clone()
     }
+    /// Set the execution settings given in yaml.
+    pub fn set_config(&mut self, config: TaskConfig) {
+        self.config = config;
+    }
+
+    /// Set the action type given in yaml.
+    pub fn set_action_type(&mut self, action_type: &str) {
+        self.action_type = Some(action_type.to_owned());
+    }
+
+    /// Set the `args` of the action type given in yaml.
+    pub fn set_action_args(&mut self, args: HashMap<String, String>) {
+        self.action_args = args;
+    }
+
+    /// Set the condition given in yaml.
+    pub fn set_condition(&mut self, condition: impl Condition + 'static) {
+        self.condition = Some(Arc::new(condition));
+    }
+
+    /// Set the `inputs` and `outputs` given in yaml.
+    pub fn set_files(&mut self, files: TaskFiles) {
+        self.files = Some(files);
+    }
+
+    /// Set the locks given in yaml.
+    pub fn set_locks(&mut self, locks: Vec<String>) {
+        self.locks = locks;
+    }
+
+    /// Set the pool given in yaml.
+    pub fn set_pool(&mut self, pool: TaskPool) {
+        self.pool = Some(pool);
+    }
+
     /// Get the unique ID of the task defined in yaml.
     #[allow(unused)]
     pub fn str_id(&self) -> &str {
@@ -66,4 +123,25 @@ impl Task for YamlTask {
     fn name(&self) -> &str {
         &self.name
     }
+    fn config(&self) -> TaskConfig {
+        self.config
+    }
+    fn action_type(&self) -> Option<&str> {
+        self.action_type.as_deref()
+    }
+    fn action_args(&self) -> Option<&HashMap<String, String>> {
+        (!self.action_args.is_empty()).then_some(&self.action_args)
+    }
+    fn condition(&self) -> Option<Arc<dyn Condition>> {
+        self.condition.clone()
+    }
+    fn files(&self) -> Option<&TaskFiles> {
+        self.files.as_ref()
+    }
+    fn locks(&self) -> &[String] {
+        &self.locks
+    }
+    fn pool(&self) -> Option<&TaskPool> {
+        self.pool.as_ref()
+    }
 }
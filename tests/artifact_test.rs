@@ -0,0 +1,248 @@
+//! Tests of the file artifacts passed between tasks.
+
+use dagrs::{
+    engine::DagConfig, Artifact, ArtifactDir, CleanupPolicy, CommandAction, Dag, DefaultTask,
+    ImportMode, Output, Task,
+};
+use std::{fs, path::PathBuf, sync::Arc};
+
+/// A task that writes an artifact with the given content.
+fn writer(content: &'static str) -> DefaultTask {
+    DefaultTask::with_closure("write", move |_, env| {
+        let artifact = ArtifactDir::from_env(&env)
+            .unwrap()
+            .create("out/data.txt")
+            .unwrap();
+        fs::write(artifact.path(), content).unwrap();
+        Output::new(artifact)
+    })
+}
+
+fn parent_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dagrs_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn artifacts_passed_to_successors() {
+    let write = writer("hello");
+    let mut cat = DefaultTask::with_action("cat", CommandAction::new("cat {{ inputs.write }}"));
+    cat.set_predecessors(&[&write]);
+    let mut dag = Dag::with_tasks(vec![write, cat]);
+    assert!(dag.start().unwrap());
+    assert_eq!(
+        dag.get_result::<dagrs::task::Content>()
+            .and_then(|content| content.as_text()),
+        Some("hello".to_string())
+    );
+
+    // The run directory is removed with the dag by default.
+    let write = writer("hello");
+    let id = write.id();
+    let mut dag = Dag::with_tasks(vec![write]);
+    assert!(dag.start().unwrap());
+    let artifact = dag.get_results::<Artifact>()[&id].clone().unwrap();
+    assert!(artifact.path().exists());
+    drop(dag);
+    assert!(!artifact.path().exists());
+}
+
+#[test]
+fn artifact_cleanup_policies() {
+    let parent = parent_dir("artifact_cleanup");
+    let run = |cleanup, fail: bool| {
+        let write = writer("data");
+        let mut check = DefaultTask::with_closure("check", move |input, _| {
+            let artifact = input.get_iter().next().unwrap().get::<Artifact>().unwrap();
+            if fail {
+                Output::error("rejected".to_string())
+            } else {
+                Output::new(artifact.clone())
+            }
+        });
+        check.set_predecessors(&[&write]);
+        let mut dag =
+            Dag::with_tasks(vec![write, check]).with_artifacts(ArtifactDir::new(&parent, cleanup));
+        assert_eq!(dag.start().unwrap(), !fail);
+        drop(dag);
+        fs::read_dir(&parent).unwrap().count()
+    };
+    assert_eq!(run(CleanupPolicy::Always, true), 0);
+    assert_eq!(run(CleanupPolicy::OnSuccess, false), 0);
+    assert_eq!(run(CleanupPolicy::OnSuccess, true), 1);
+    assert_eq!(run(CleanupPolicy::Never, false), 2);
+    fs::remove_dir_all(parent).unwrap();
+}
+
+#[test]
+fn artifact_import_and_export() {
+    let parent = parent_dir("artifact_import");
+    let source = parent.join("source.txt");
+    fs::write(&source, "imported").unwrap();
+    let dir = ArtifactDir::new(&parent, CleanupPolicy::Always);
+
+    let copy = dir.import(&source, "copy.txt", ImportMode::Copy).unwrap();
+    let link = dir.import(&source, "link.txt", ImportMode::Link).unwrap();
+    assert_eq!(copy.read_to_string().unwrap(), "imported");
+    assert_eq!(link.read().unwrap(), b"imported");
+
+    copy.copy_to(parent.join("exported.txt")).unwrap();
+    link.link_to(parent.join("linked.txt")).unwrap();
+    drop(dir);
+    assert_eq!(
+        fs::read_to_string(parent.join("exported.txt")).unwrap(),
+        "imported"
+    );
+    assert_eq!(
+        fs::read_to_string(parent.join("linked.txt")).unwrap(),
+        "imported"
+    );
+    fs::remove_dir_all(parent).unwrap();
+}
+
+#[test]
+fn captured_command_output() {
+    let greet = DefaultTask::with_action("greet", CommandAction::new("echo hello; echo oops >&2"));
+    let mut fail = DefaultTask::with_action("fail task", CommandAction::new("echo bye; exit 1"));
+    fail.set_predecessors(&[&greet]);
+    let mut dag = Dag::with_tasks(vec![greet, fail]).capture_command_output(false);
+    assert!(!dag.start().unwrap());
+
+    let report = dag.report();
+    let stdout = report.tasks[0].stdout_file.as_ref().unwrap();
+    assert!(stdout.ends_with(format!("output/{}-greet.stdout", report.tasks[0].id)));
+    assert_eq!(fs::read_to_string(stdout).unwrap(), "hello\n");
+    let stderr = report.tasks[0].stderr_file.as_ref().unwrap();
+    assert_eq!(fs::read_to_string(stderr).unwrap(), "oops\n");
+    // The output of failed commands is captured too.
+    let stdout = report.tasks[1].stdout_file.as_ref().unwrap();
+    assert!(stdout.ends_with(format!("output/{}-fail_task.stdout", report.tasks[1].id)));
+    assert_eq!(fs::read_to_string(stdout).unwrap(), "bye\n");
+
+    let greet = DefaultTask::with_action("greet", CommandAction::new("echo hello"));
+    let mut dag = Dag::with_tasks(vec![greet]);
+    assert!(dag.start().unwrap());
+    assert!(dag.report().tasks[0].stdout_file.is_none());
+}
+
+#[test]
+fn command_scratch_dirs() {
+    let parent = parent_dir("scratch");
+    // Commands that run concurrently write a file of the same name, each in its own directory.
+    let mut tasks: Vec<DefaultTask> = (0..4)
+        .map(|i| {
+            let cmd = format!("echo {} > out.txt && sleep 0.1 && cat out.txt", i);
+            DefaultTask::with_action(
+                &format!("step {}", i),
+                CommandAction::new(&cmd).in_scratch_dir(),
+            )
+        })
+        .collect();
+    let kept = DefaultTask::with_action(
+        "kept",
+        CommandAction::new("echo kept > \"$DAGRS_SCRATCH_DIR/file\""),
+    );
+    let removed = DefaultTask::with_action(
+        "removed",
+        CommandAction::new("touch {{ scratch }}/file").with_scratch_cleanup(CleanupPolicy::Always),
+    );
+    let (kept_id, removed_id) = (kept.id(), removed.id());
+    tasks.extend([kept, removed]);
+    let mut dag =
+        Dag::with_tasks(tasks).with_artifacts(ArtifactDir::new(&parent, CleanupPolicy::Never));
+    assert!(dag.start().unwrap());
+
+    let report = dag.report();
+    for i in 0..4 {
+        let name = format!("step {}", i);
+        let task = report.tasks.iter().find(|task| task.name == name).unwrap();
+        assert_eq!(task.output.as_deref(), Some(i.to_string().as_str()));
+    }
+    let run_dir = fs::read_dir(&parent)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let scratch = run_dir.join("scratch");
+    assert_eq!(
+        fs::read_to_string(scratch.join(format!("{}-kept/file", kept_id))).unwrap(),
+        "kept\n"
+    );
+    assert!(!scratch.join(format!("{}-removed", removed_id)).exists());
+    fs::remove_dir_all(parent).unwrap();
+}
+
+#[test]
+fn outputs_spilled_beyond_memory_budget() {
+    let text = DefaultTask::with_closure("text", |_, _| Output::new("a".repeat(1000)));
+    let mut bytes = DefaultTask::with_closure("bytes", |_, _| Output::new(vec![7u8; 1000]));
+    bytes.set_predecessors(&[&text]);
+    let mut shared = DefaultTask::with_closure("shared", |_, _| {
+        Output::new(Arc::<[u8]>::from(vec![9u8; 100]))
+    });
+    shared.set_predecessors(&[&bytes]);
+    let mut check = DefaultTask::with_closure("check", |input, _| {
+        let text = input.get_by_name("text").unwrap().get::<String>().unwrap();
+        let bytes = input
+            .get_by_name("bytes")
+            .unwrap()
+            .get::<Vec<u8>>()
+            .unwrap();
+        let shared = input.get_by_name("shared").unwrap().as_bytes().unwrap();
+        Output::new(text.len() + bytes.len() + shared.len())
+    });
+    check.set_predecessors(&[&text, &bytes, &shared]);
+    let text_id = text.id();
+
+    let parent = parent_dir("spill");
+    let mut dag = Dag::with_tasks(vec![text, bytes, shared, check])
+        .with_config(DagConfig::new().memory_budget(1500))
+        .with_artifacts(ArtifactDir::new(&parent, CleanupPolicy::Never));
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<usize>().unwrap(), 2100);
+
+    // Only the oldest output was spilled, and it is read back from its file.
+    let run_dir = fs::read_dir(&parent)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let spilled: Vec<PathBuf> = fs::read_dir(run_dir.join("spill"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(spilled.len(), 1);
+    assert!(spilled[0].ends_with(text_id.to_string()));
+    let results = dag.get_results::<String>();
+    assert_eq!(results[&text_id].as_deref().map(String::len), Some(1000));
+    fs::remove_dir_all(parent).unwrap();
+}
+
+#[test]
+fn each_run_has_its_own_run_directory() {
+    let parent = parent_dir("artifact_runs");
+    let write = writer("data");
+    let id = write.id();
+    let mut dag = Dag::with_tasks(vec![write])
+        .with_artifacts(ArtifactDir::new(&parent, CleanupPolicy::Never));
+    assert!(dag.start().unwrap());
+    let first = dag.get_results::<Artifact>()[&id].clone().unwrap();
+    assert!(dag.start().unwrap());
+    let second = dag.get_results::<Artifact>()[&id].clone().unwrap();
+    assert_ne!(first.path(), second.path());
+    assert_eq!(fs::read_dir(&parent).unwrap().count(), 2);
+    drop(dag);
+    fs::remove_dir_all(&parent).unwrap();
+
+    // The directory of a run is cleaned up once the next run starts.
+    let write = writer("data");
+    let id = write.id();
+    let mut dag = Dag::with_tasks(vec![write]);
+    assert!(dag.start().unwrap());
+    let first = dag.get_results::<Artifact>()[&id].clone().unwrap();
+    assert!(dag.start().unwrap());
+    assert!(!first.path().exists());
+}
@@ -0,0 +1,112 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use dagrs::{engine::DagConfig, utils::Backoff, Dag, DefaultTask, Output};
+
+const MS: Duration = Duration::from_millis(1);
+
+#[test]
+fn backoff_delays() {
+    assert_eq!(Backoff::default().delay(3), Duration::ZERO);
+    assert_eq!(Backoff::constant(10 * MS).delay(3), 10 * MS);
+    let linear = Backoff::linear(10 * MS, 5 * MS);
+    assert_eq!(
+        [1, 2, 3].map(|retry| linear.delay(retry)),
+        [10 * MS, 15 * MS, 20 * MS]
+    );
+    let exponential = Backoff::exponential(10 * MS, 2.0).max_delay(30 * MS);
+    assert_eq!(
+        [1, 2, 3].map(|retry| exponential.delay(retry)),
+        [10 * MS, 20 * MS, 30 * MS]
+    );
+
+    let jittered = Backoff::constant(100 * MS).jitter(0.5);
+    let delays: Vec<Duration> = (1..20).map(|retry| jittered.delay(retry)).collect();
+    assert!(delays
+        .iter()
+        .all(|delay| *delay >= 50 * MS && *delay <= 150 * MS));
+    assert!(delays.iter().any(|delay| *delay != delays[0]));
+    let odd = Backoff::exponential(MS, f64::NAN);
+    assert_eq!(odd, odd);
+
+    let limited = Backoff::constant(100 * MS).max_elapsed(150 * MS);
+    let start = Instant::now();
+    assert_eq!(limited.next_delay(1, start), Some(100 * MS));
+    assert_eq!(limited.next_delay(1, start - 100 * MS), None);
+}
+
+#[test]
+fn backoff_overflow() {
+    // Delays too long for a `Duration` saturate, with or without a jitter.
+    let exponential = Backoff::exponential(MS, 2.0);
+    assert_eq!(exponential.delay(u32::MAX), Duration::MAX);
+    let jittered = exponential.jitter(0.5);
+    for retry in [1_000, u32::MAX / 2 + 2, u32::MAX] {
+        assert!(jittered.delay(retry) >= Duration::MAX / 3);
+    }
+    let linear = Backoff::linear(Duration::MAX, Duration::MAX).jitter(1.0);
+    assert!(linear.delay(u32::MAX) <= Duration::MAX);
+}
+
+#[test]
+fn backoff_retry() {
+    let mut attempts = 0;
+    let start = Instant::now();
+    let result: Result<(), u32> = Backoff::constant(5 * MS).retry(3, || {
+        attempts += 1;
+        Err(attempts)
+    });
+    assert_eq!(result, Err(4));
+    assert!(start.elapsed() >= 15 * MS);
+
+    let mut attempts = 0;
+    let result = Backoff::default().retry(3, || {
+        attempts += 1;
+        if attempts == 2 {
+            Ok(attempts)
+        } else {
+            Err(())
+        }
+    });
+    assert_eq!(result, Ok(2));
+}
+
+#[test]
+fn task_retry_backoff() {
+    let attempts = Arc::new(AtomicU32::new(0));
+    let counter = attempts.clone();
+    let task = DefaultTask::with_closure("flaky", move |_, _| {
+        if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+            Output::error("not yet".to_string())
+        } else {
+            Output::new(1)
+        }
+    });
+    let start = Instant::now();
+    let mut dag = Dag::with_tasks(vec![task]).with_config(
+        DagConfig::new()
+            .retries(3)
+            .backoff(Backoff::linear(20 * MS, 20 * MS)),
+    );
+    assert!(dag.start().unwrap());
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    assert!(start.elapsed() >= 60 * MS);
+
+    // The retries stop once the maximum elapsed time would be exceeded.
+    let attempts = Arc::new(AtomicU32::new(0));
+    let counter = attempts.clone();
+    let mut task = DefaultTask::with_closure("broken", move |_, _| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Output::error("broken".to_string())
+    });
+    task.set_retries(10);
+    task.set_backoff(Backoff::constant(50 * MS).max_elapsed(120 * MS));
+    let mut dag = Dag::with_tasks(vec![task]);
+    assert!(!dag.start().unwrap());
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
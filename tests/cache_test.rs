@@ -0,0 +1,148 @@
+//! Tests of the caching of the outputs of tasks.
+
+use dagrs::{
+    engine::{CacheBackend, DiskCache},
+    Dag, DefaultTask, Fingerprinter, Input, Output, Task,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[test]
+fn disk_cache_entries() {
+    let dir = std::env::temp_dir().join(format!("dagrs_disk_cache_{}", std::process::id()));
+    let cache = DiskCache::new(&dir);
+    let fingerprint = Fingerprinter::new().command("make").finish();
+    assert_eq!(cache.get(&fingerprint).unwrap(), None);
+    cache.put(&fingerprint, b"built").unwrap();
+    assert_eq!(
+        cache.get(&fingerprint).unwrap().as_deref(),
+        Some(&b"built"[..])
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// A dag whose `build` task counts its runs, and whose `upload` task outputs what it got.
+fn build_dag(runs: &Arc<AtomicUsize>, cache: impl CacheBackend + 'static) -> (Dag, usize) {
+    let runs = runs.clone();
+    let build = DefaultTask::with_closure("build", move |_, _| {
+        runs.fetch_add(1, Ordering::SeqCst);
+        Output::new("artifact".to_string())
+    });
+    let mut upload = DefaultTask::with_closure("upload", |input: Input, _| {
+        let artifact = input.get_iter().next().unwrap().get::<String>().unwrap();
+        Output::new(format!("uploaded {}", artifact))
+    });
+    upload.set_predecessors(&[&build]);
+    let mut uncached = DefaultTask::with_closure("notify", |_, _| Output::empty());
+    uncached.set_cache(false);
+    uncached.set_predecessors(&[&upload]);
+    let id = upload.id();
+    (
+        Dag::with_tasks(vec![build, upload, uncached]).with_cache(cache),
+        id,
+    )
+}
+
+#[test]
+fn cached_outputs() {
+    let dir = std::env::temp_dir().join(format!("dagrs_cached_outputs_{}", std::process::id()));
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let (mut dag, _) = build_dag(&runs, DiskCache::new(&dir));
+    assert!(dag.start().unwrap());
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    // Another dag with the same tasks restores the outputs from the cache.
+    let (mut dag, upload) = build_dag(&runs, DiskCache::new(&dir));
+    assert!(dag.start().unwrap());
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    let report = dag.report();
+    let output = report.tasks.iter().find(|t| t.id == upload).unwrap();
+    assert_eq!(output.output.as_deref(), Some("uploaded artifact"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn async_closures_are_not_cached() {
+    let dir = std::env::temp_dir().join(format!("dagrs_async_cache_{}", std::process::id()));
+    let runs = Arc::new(AtomicUsize::new(0));
+    let run = |cache: Option<bool>| {
+        let runs = runs.clone();
+        let mut fetch = DefaultTask::with_async_closure("fetch", move |_, _| {
+            let runs = runs.clone();
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Output::new("page".to_string())
+            }
+        });
+        if let Some(cache) = cache {
+            fetch.set_cache(cache);
+        }
+        let mut dag = Dag::with_tasks(vec![fetch]).with_cache(DiskCache::new(&dir));
+        assert!(dag.start().unwrap());
+    };
+    run(None);
+    run(None);
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+    // A task may still opt in.
+    run(Some(true));
+    run(Some(true));
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "cache-http")]
+#[test]
+fn http_cache() {
+    use dagrs::engine::HttpCache;
+    use std::{collections::HashMap, thread};
+    use tiny_http::{Method, Response, Server};
+
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/cache", server.server_addr().to_ip().unwrap());
+    thread::spawn(move || {
+        let mut entries = HashMap::new();
+        for mut request in server.incoming_requests() {
+            let authorized = request
+                .headers()
+                .iter()
+                .any(|h| h.field.equiv("Authorization") && h.value == "Bearer token");
+            let path = request.url().to_owned();
+            let response = match request.method() {
+                _ if !authorized => Response::from_data(Vec::new()).with_status_code(401),
+                Method::Put => {
+                    let mut body = Vec::new();
+                    request.as_reader().read_to_end(&mut body).unwrap();
+                    entries.insert(path, body);
+                    Response::from_data(Vec::new())
+                }
+                Method::Get => match entries.get(&path) {
+                    Some(body) => Response::from_data(body.clone()),
+                    None => Response::from_data(Vec::new()).with_status_code(404),
+                },
+                _ => Response::from_data(Vec::new()).with_status_code(405),
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    let cache = HttpCache::new(&url).with_header("Authorization", "Bearer token");
+    let fingerprint = Fingerprinter::new().command("make").finish();
+    assert_eq!(cache.get(&fingerprint).unwrap(), None);
+    cache.put(&fingerprint, b"built").unwrap();
+    assert_eq!(
+        cache.get(&fingerprint).unwrap().as_deref(),
+        Some(&b"built"[..])
+    );
+    assert!(HttpCache::new(&url).get(&fingerprint).is_err());
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    for _ in 0..2 {
+        let cache = HttpCache::new(&url).with_header("Authorization", "Bearer token");
+        let (mut dag, _) = build_dag(&runs, cache);
+        assert!(dag.start().unwrap());
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
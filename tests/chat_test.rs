@@ -0,0 +1,65 @@
+//! Tests of the chat notifications.
+
+use dagrs::{ChatObserver, ChatService, Dag, DefaultTask, EnvVar, Output};
+use std::{
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::Duration,
+};
+use tiny_http::{Response, Server};
+
+/// A webhook that forwards the bodies of the requests it receives.
+fn webhook() -> (String, Receiver<serde_json::Value>) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let body = serde_json::from_reader(request.as_reader()).unwrap();
+            sender.send(body).unwrap();
+            let _ = request.respond(Response::empty(200));
+        }
+    });
+    (url, receiver)
+}
+
+#[test]
+fn chat_observer() {
+    let (url, bodies) = webhook();
+    let mut env = EnvVar::new();
+    env.set("SLACK_WEBHOOK_URL", url.clone());
+    assert!(ChatObserver::from_env(ChatService::Discord, &env).is_none());
+
+    let a = DefaultTask::with_closure("extract", |_, _| Output::new(1));
+    let mut b = DefaultTask::with_closure("load", |_, _| Output::error("disk full".to_string()));
+    b.set_predecessors(&[&a]);
+    let mut c = DefaultTask::with_closure("report", |_, _| Output::new(1));
+    c.set_predecessors(&[&b]);
+    let mut dag = Dag::with_tasks(vec![a, b, c]);
+    dag.add_observer(
+        ChatObserver::from_env(ChatService::Slack, &env)
+            .unwrap()
+            .with_title("ETL"),
+    );
+    assert!(!dag.start().unwrap());
+
+    let started = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(started["text"], "ETL: run started with 3 tasks");
+    let finished = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    let text = finished["text"].as_str().unwrap();
+    assert!(text.starts_with("ETL: run failed"));
+    assert!(text.contains("• extract: succeeded in"));
+    assert!(text.contains("• load: failed after"));
+    assert!(text.ends_with("disk full\n• report: skipped"));
+
+    let task = DefaultTask::with_closure("a", |_, _| Output::new(1));
+    let mut dag = Dag::with_tasks(vec![task]);
+    dag.add_observer(ChatObserver::new(ChatService::Discord, &url));
+    assert!(dag.start().unwrap());
+    bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    let finished = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(finished["content"]
+        .as_str()
+        .unwrap()
+        .starts_with("dagrs: run succeeded"));
+}
@@ -0,0 +1,33 @@
+//! Tests of the `dagrs` command.
+
+use std::process::Command;
+
+#[test]
+fn json_output_captures_commands() {
+    let dir = std::env::temp_dir().join(format!("dagrs_cli_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let yaml = dir.join("echo.yaml");
+    std::fs::write(
+        &yaml,
+        "dagrs:\n  a:\n    name: \"Echo\"\n    cmd: echo hello\n",
+    )
+    .unwrap();
+    let run = Command::new(env!("CARGO_BIN_EXE_dagrs"))
+        .arg("--yaml")
+        .arg(&yaml)
+        .args(["--output", "json", "--artifacts"])
+        .arg(dir.join("runs"))
+        .output()
+        .unwrap();
+    assert!(run.status.success());
+
+    // The summary alone is on stdout, and names the files the output of the command is in.
+    let report: serde_json::Value = serde_json::from_slice(&run.stdout).unwrap();
+    let task = &report["tasks"][0];
+    let stdout = task["stdout_file"].as_str().unwrap();
+    assert!(stdout.starts_with(dir.join("runs").to_str().unwrap()));
+    assert_eq!(std::fs::read_to_string(stdout).unwrap().trim(), "hello");
+    assert!(std::path::Path::new(task["stderr_file"].as_str().unwrap()).is_file());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,288 @@
+//! Tests of the wrappers and combinators of actions.
+
+use dagrs::utils::Backoff;
+use dagrs::{
+    task::Content, Action, ActionExt, ActionLayer, CircuitBreaker, CircuitState, Complex, Dag,
+    DefaultTask, EnvVar, Fallback, Input, LogLayer, Output, Par, Seq, Task,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// An action that fails while `failing` is set, and outputs the number of its runs.
+fn flaky(runs: Arc<AtomicU32>, failing: Arc<AtomicU32>) -> Action {
+    Action::Closure(Arc::new(move |_, _| {
+        let run = runs.fetch_add(1, Ordering::SeqCst) + 1;
+        if failing.load(Ordering::SeqCst) > 0 {
+            Output::error("unavailable".to_string())
+        } else {
+            Output::new(run)
+        }
+    }))
+}
+
+fn run(action: &impl Complex) -> Output {
+    action.run(Input::new(Vec::new()), Arc::new(EnvVar::new()))
+}
+
+#[test]
+fn circuit_breaker() {
+    let runs = Arc::new(AtomicU32::new(0));
+    let failing = Arc::new(AtomicU32::new(1));
+    let breaker = CircuitBreaker::new(
+        flaky(runs.clone(), failing.clone()),
+        2,
+        Duration::from_millis(50),
+    );
+
+    assert!(run(&breaker).is_err());
+    assert_eq!(breaker.state(), CircuitState::Closed(1));
+    assert!(run(&breaker).is_err());
+    assert!(matches!(breaker.state(), CircuitState::Open(_)));
+    // The open breaker fails fast, without running the action.
+    assert_eq!(run(&breaker).get_err().unwrap(), "Circuit breaker is open");
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+    // A failed probe opens the breaker again.
+    thread::sleep(Duration::from_millis(60));
+    assert!(run(&breaker).is_err());
+    assert!(matches!(breaker.state(), CircuitState::Open(_)));
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+    // A successful probe closes it.
+    failing.store(0, Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(60));
+    assert!(!run(&breaker).is_err());
+    assert_eq!(breaker.state(), CircuitState::Closed(0));
+}
+
+#[test]
+fn circuit_breaker_fallback() {
+    let runs = Arc::new(AtomicU32::new(0));
+    let failing = Arc::new(AtomicU32::new(0));
+    let breaker = CircuitBreaker::new(
+        flaky(runs.clone(), failing.clone()),
+        1,
+        Duration::from_secs(60),
+    )
+    .fallback_to_last_output();
+
+    let output = run(&breaker);
+    assert_eq!(output.get_out().unwrap().get::<u32>(), Some(&1));
+    failing.store(1, Ordering::SeqCst);
+    assert!(run(&breaker).is_err());
+    let output = run(&breaker);
+    assert_eq!(output.get_out().unwrap().get::<u32>(), Some(&1));
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+/// An action that outputs the given value, after the given delay.
+fn constant(value: u32, delay: Duration) -> Action {
+    Action::Closure(Arc::new(move |_, _| {
+        thread::sleep(delay);
+        Output::new(value)
+    }))
+}
+
+fn failing() -> Action {
+    Action::Closure(Arc::new(|_, _| Output::error("failed".to_string())))
+}
+
+fn value(output: Output) -> Option<u32> {
+    output.get_out()?.get::<u32>().copied()
+}
+
+#[test]
+fn fallback() {
+    let fallback = Fallback::new(constant(1, Duration::ZERO), constant(2, Duration::ZERO));
+    assert_eq!(value(run(&fallback)), Some(1));
+    let fallback = Fallback::new(failing(), constant(2, Duration::ZERO));
+    assert_eq!(value(run(&fallback)), Some(2));
+    let fallback = Fallback::new(failing(), failing());
+    assert!(run(&fallback).is_err());
+
+    let fallback = Fallback::new(
+        constant(1, Duration::from_secs(5)),
+        constant(2, Duration::ZERO),
+    )
+    .primary_timeout(Duration::from_millis(20));
+    assert_eq!(value(run(&fallback)), Some(2));
+
+    // The secondary action receives the inputs of the primary.
+    let sum = Action::Closure(Arc::new(|input: Input, _| {
+        Output::new(input.get_iter().filter_map(|c| c.get::<u32>()).sum::<u32>())
+    }));
+    let fallback = Fallback::new(failing(), sum);
+    let input = Input::new(vec![Content::new(3u32), Content::new(4u32)]);
+    assert_eq!(value(fallback.run(input, Arc::new(EnvVar::new()))), Some(7));
+}
+
+#[test]
+fn layers() {
+    // A layer that appends its name to the output of the inner action.
+    fn append(name: &'static str) -> Box<dyn ActionLayer> {
+        Box::new(move |inner: Action| {
+            Action::Closure(Arc::new(move |input, env| {
+                let output = inner.run(input, env);
+                let inner = output.get_out().unwrap();
+                Output::new(format!("{}{}", inner.get::<String>().unwrap(), name))
+            }))
+        })
+    }
+    let action = Action::Closure(Arc::new(|_, _| Output::new("action".to_string())));
+    let task = DefaultTask::with_layers(
+        "layered",
+        action,
+        vec![append(" outer"), Box::new(LogLayer), append(" inner")],
+    );
+    let output = task
+        .action()
+        .run(Input::new(vec![]), Arc::new(EnvVar::new()));
+    assert_eq!(
+        output.get_out().unwrap().get::<String>().unwrap(),
+        "action inner outer"
+    );
+}
+
+#[test]
+fn timeout_and_retries() {
+    let action = constant(1, Duration::from_secs(5)).with_timeout(Duration::from_millis(20));
+    assert!(run(&action).get_err().unwrap().contains("timed out"));
+    let action = constant(1, Duration::ZERO).with_timeout(Duration::from_secs(5));
+    assert_eq!(value(run(&action)), Some(1));
+
+    // An action that fails on its first `failures` runs.
+    let failing_first = |runs: Arc<AtomicU32>, failures: u32| {
+        Action::Closure(Arc::new(move |_, _| {
+            if runs.fetch_add(1, Ordering::SeqCst) < failures {
+                Output::error("unavailable".to_string())
+            } else {
+                Output::empty()
+            }
+        }))
+    };
+    let runs = Arc::new(AtomicU32::new(0));
+    let action =
+        failing_first(runs.clone(), 2).with_retries(2, Backoff::constant(Duration::from_millis(1)));
+    // The waits between the attempts run on a blocking thread.
+    assert!(action.is_blocking());
+    assert!(!run(&action).is_err());
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+    let runs = Arc::new(AtomicU32::new(0));
+    let action = failing_first(runs.clone(), 5).with_retries(2, Backoff::default());
+    assert!(!action.is_blocking());
+    assert!(run(&action).is_err());
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn seq_and_par() {
+    let add = |n: u32| {
+        Action::Closure(Arc::new(move |input: Input, _| {
+            let value = input
+                .get_iter()
+                .next()
+                .and_then(|c| c.get::<u32>().copied());
+            Output::new(value.unwrap_or(0) + n)
+        }))
+    };
+    let seq = Seq(vec![add(1), add(2), add(3)]);
+    assert_eq!(value(run(&seq)), Some(6));
+    let seq = Seq(vec![add(1), failing(), add(3)]);
+    assert!(run(&seq).is_err());
+
+    // The actions run concurrently: three sleeps of 200ms take much less than 600ms.
+    let par = Par(vec![
+        constant(1, Duration::from_millis(200)),
+        constant(2, Duration::from_millis(200)),
+        constant(3, Duration::from_millis(200)),
+    ]);
+    let start = Instant::now();
+    let output = run(&par);
+    assert!(start.elapsed() < Duration::from_millis(500));
+    let outputs = output.get_out().unwrap();
+    let values: Vec<u32> = outputs
+        .get::<Vec<Option<Content>>>()
+        .unwrap()
+        .iter()
+        .map(|c| *c.as_ref().unwrap().get::<u32>().unwrap())
+        .collect();
+    assert_eq!(values, vec![1, 2, 3]);
+    let par = Par(vec![add(1), failing()]);
+    assert_eq!(run(&par).get_err().unwrap(), "failed");
+}
+
+#[test]
+fn async_closure() {
+    let a = DefaultTask::with_async_closure("a", |_input, _env| async {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Output::new(1u32)
+    });
+    let mut b = DefaultTask::with_async_closure("b", |input: Input, _env| async move {
+        let value = input.get_iter().next().unwrap().get::<u32>().copied();
+        tokio::task::yield_now().await;
+        Output::new(value.unwrap() + 1)
+    });
+    b.set_predecessors(&[&a]);
+    let mut dag = Dag::with_tasks(vec![a, b]);
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<u32>().unwrap(), 2);
+}
+
+#[test]
+fn lazy_tasks() {
+    let built = Arc::new(AtomicU32::new(0));
+    let lazy = |name: &str, value: u32| {
+        let built = built.clone();
+        DefaultTask::lazy(name, move || {
+            built.fetch_add(1, Ordering::SeqCst);
+            Action::Closure(Arc::new(move |_, _| Output::new(value)))
+        })
+    };
+    let a = DefaultTask::with_closure("a", |_, _| Output::error("failed".to_owned()));
+    let mut b = lazy("b", 2);
+    b.set_predecessors(&[&a]);
+    let c = lazy("c", 3);
+    let mut dag = Dag::with_tasks(vec![a, b, c]).keep_going();
+    assert_eq!(built.load(Ordering::SeqCst), 0);
+    assert!(!dag.start().unwrap());
+    // Only the task that ran built its action, once for all runs.
+    assert_eq!(built.load(Ordering::SeqCst), 1);
+    assert!(!dag.start().unwrap());
+    assert_eq!(built.load(Ordering::SeqCst), 1);
+}
+
+fn double_first(input: Input, _env: Arc<EnvVar>) -> Output {
+    let value = input.get_iter().next().unwrap().get::<u32>().copied();
+    Output::new(value.unwrap() * 2)
+}
+
+async fn fetch(_input: Input, _env: Arc<EnvVar>) -> Output {
+    tokio::task::yield_now().await;
+    Output::new(21u32)
+}
+
+#[test]
+fn action_adapters() {
+    let mut a = DefaultTask::new("a");
+    a.set_action_dyn(Action::from_async_fn(fetch));
+    let mut b = DefaultTask::new("b");
+    b.set_action_dyn(Action::from_fn(double_first));
+    b.set_predecessors(&[&a]);
+    let mut c = DefaultTask::new("c");
+    let closure = |input: Input, _env: Arc<EnvVar>| {
+        let value = input.get_iter().next().unwrap().get::<u32>().copied();
+        Output::new(value.unwrap() + 1)
+    };
+    c.set_action_dyn(closure.into());
+    c.set_predecessors(&[&b]);
+    let mut dag = Dag::with_tasks(vec![a, b, c]);
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<u32>().unwrap(), 43);
+}
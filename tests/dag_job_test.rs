@@ -1,8 +1,22 @@
 //! Some tests of the dag engine.
 
-use std::{collections::HashMap, env::set_var, sync::Arc};
+use std::{
+    collections::HashMap,
+    env::set_var,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
-use dagrs::{Complex, Dag, DagError, DefaultTask, EnvVar, Input, Output};
+use dagrs::{
+    engine::{DagConfig, Execution, OverflowPolicy, TaskStatus, TimeOfDay},
+    testing::MockAction,
+    Action, ActionExt, ActionRegistry, Artifact, CommandCondition, Complex, Condition, Dag,
+    DagError, DagStructure, DefaultTask, EnvVar, ExecutionError, GraphError, Input, Lazy, Output,
+    Parser, SyncAction, Task, YamlParser,
+};
 
 #[test]
 fn yaml_task_correct_execute() {
@@ -72,7 +86,7 @@ struct FailedActionD(usize);
 
 impl Complex for FailedActionD {
     fn run(&self, _input: Input, _env: Arc<EnvVar>) -> Output {
-        Output::Err("error".to_string())
+        Output::Err(format!("error {}", self.0))
     }
 }
 
@@ -152,3 +166,1240 @@ fn task_failed_execute() {
 fn task_keep_going() {
     test_dag(true, Some(8));
 }
+
+#[test]
+fn task_failed_error() {
+    let a = DefaultTask::with_closure("a", |_, _| Output::empty());
+    let mut b = DefaultTask::with_closure("b", |_, _| Output::error("boom".to_string()));
+    b.set_predecessors(&[&a]);
+    let b_id = b.id();
+
+    let mut job = Dag::with_tasks(vec![a, b]);
+    assert!(!job.start().unwrap());
+
+    let errors = job.take_errors();
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        DagError::ExecutionFailed { id, name, source } => {
+            assert_eq!(*id, b_id);
+            assert_eq!(name, "b");
+            assert!(matches!(
+                source.downcast_ref::<ExecutionError>(),
+                Some(ExecutionError::Output(msg)) if msg == "boom"
+            ));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(job.take_errors().is_empty());
+}
+
+#[test]
+fn simulate_wiring() {
+    let a = DefaultTask::with_closure("a", |_, _| panic!("real actions must not run"));
+    let mut b = DefaultTask::with_closure("b", |_, _| Output::empty());
+    let mut c = DefaultTask::with_closure("c", |_, _| Output::empty());
+    let mut d = DefaultTask::with_closure("d", |_, _| Output::empty());
+    b.set_predecessors(&[&a]);
+    c.set_predecessors(&[&a]);
+    d.set_predecessors(&[&b]);
+
+    let dag = Dag::with_tasks(vec![a, b, c, d]);
+    let simulation = dag.simulate().unwrap();
+    assert!(simulation.is_ok());
+    assert_eq!(simulation.unconsumed_outputs.len(), 1);
+
+    let res = Dag::with_yaml("tests/config/loop_error.yaml", HashMap::new())
+        .unwrap()
+        .simulate();
+    assert!(matches!(res, Err(DagError::LoopGraph)));
+}
+
+#[cfg(feature = "bench")]
+#[test]
+fn overhead_report() {
+    let a = DefaultTask::with_closure("a", |_, _| Output::new(1usize));
+    let mut b = DefaultTask::with_closure("b", |_, _| {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        Output::empty()
+    });
+    b.set_predecessors(&[&a]);
+
+    let mut job = Dag::with_tasks(vec![a, b]);
+    assert!(job.start().unwrap());
+    let report = job.overhead_report();
+    assert_eq!(report.tasks.len(), 2);
+    assert!(report.tasks[1].action >= std::time::Duration::from_millis(10));
+    assert!(report.total_action() >= report.tasks[1].action);
+}
+
+#[test]
+fn dag_blocks() {
+    let a = DefaultTask::new("a");
+    let mut b = DefaultTask::new("b");
+    let mut c = DefaultTask::new("c");
+    let mut d = DefaultTask::new("d");
+    b.set_predecessors(&[&a]);
+    c.set_predecessors(&[&a]);
+    d.set_predecessors(&[&b, &a]);
+    let (a_id, b_id, c_id, d_id) = (a.id(), b.id(), c.id(), d.id());
+
+    let mut job = Dag::with_tasks(vec![a, b, c, d]);
+    let blocks = job.blocks().unwrap();
+    let ids: Vec<Vec<usize>> = blocks
+        .iter()
+        .map(|block| block.iter().map(|(id, _)| *id).collect())
+        .collect();
+    assert_eq!(ids, [vec![a_id], vec![b_id, c_id], vec![d_id]]);
+    assert_eq!(blocks[0][0].1, "a");
+    assert!(job.start().unwrap());
+
+    let res = Dag::with_yaml("tests/config/self_loop_error.yaml", HashMap::new())
+        .unwrap()
+        .blocks();
+    assert!(matches!(res, Err(DagError::LoopGraph)));
+}
+
+#[test]
+fn dag_topo_iter() {
+    let mut job = Dag::with_yaml("tests/config/correct.yaml", HashMap::new()).unwrap();
+    let order: Vec<(usize, &dyn Task)> = job.topo_iter().unwrap().collect();
+    assert_eq!(order.len(), 8);
+    for (i, (level, task)) in order.iter().enumerate() {
+        for pre in task.precursors() {
+            let (pre_level, _) = order[..i].iter().find(|(_, t)| t.id() == *pre).unwrap();
+            assert!(pre_level < level);
+        }
+    }
+    assert_eq!(order[0].1.name(), "Task 8");
+    assert!(job.start().unwrap());
+}
+
+#[test]
+fn export_import_structure() {
+    let mut registry = ActionRegistry::new();
+    registry.register("const", |args| {
+        let val: usize = args.get("val").map_or(1, |v| v.parse().unwrap());
+        Action::Closure(Arc::new(move |_, _| Output::new(val)))
+    });
+    registry.register("sum", |_| {
+        Action::Closure(Arc::new(|input: Input, _| {
+            Output::new(
+                input
+                    .get_iter()
+                    .map(|i| i.get::<usize>().unwrap())
+                    .sum::<usize>(),
+            )
+        }))
+    });
+
+    let mut a = DefaultTask::new("a");
+    a.set_action_type("const");
+    let mut b = DefaultTask::new("b");
+    b.set_action_type("sum");
+    b.set_predecessors(&[&a]);
+    let structure = Dag::with_tasks(vec![a, b]).export_structure();
+    assert_eq!(
+        structure.edges,
+        [(structure.tasks[0].id, structure.tasks[1].id)]
+    );
+
+    let mut structure = DagStructure::from_json(&structure.to_json()).unwrap();
+    structure.tasks[0]
+        .args
+        .insert("val".to_string(), "5".to_string());
+    let mut job = Dag::import_structure(&structure, &registry).unwrap();
+    // The arguments survive another export.
+    assert_eq!(job.export_structure().tasks[0].args["val"], "5");
+    assert!(job.start().unwrap());
+    assert_eq!(*job.get_result::<usize>().unwrap(), 5);
+
+    structure.tasks[1].action = Some("unknown".to_string());
+    assert!(Dag::import_structure(&structure, &registry).is_err());
+}
+
+#[test]
+fn structure_diff() {
+    let old = r#"
+dagrs:
+  a:
+    name: "fetch"
+    cmd: echo a
+  b:
+    name: "parse"
+    after: [ a ]
+    cmd: echo b
+  c:
+    name: "report"
+    after: [ b ]
+    cmd: echo c
+"#;
+    let new = r#"
+dagrs:
+  a:
+    name: "fetch"
+    cmd: echo a
+  b:
+    name: "decode"
+    after: [ a ]
+    cmd: echo b
+  c:
+    name: "report"
+    after: [ a, b ]
+    cmd: echo c
+  d:
+    name: "notify"
+    after: [ c ]
+    cmd: echo d
+"#;
+    let old = Dag::with_yaml_str(old, HashMap::new()).unwrap();
+    let new = Dag::with_yaml_str(new, HashMap::new()).unwrap();
+    let diff = old.export_structure().diff(&new.export_structure());
+    assert_eq!(diff.added_tasks, ["notify"]);
+    assert!(diff.removed_tasks.is_empty());
+    assert_eq!(
+        diff.renamed_tasks,
+        [("parse".to_string(), "decode".to_string())]
+    );
+    assert_eq!(
+        diff.added_edges,
+        [
+            ("fetch".to_string(), "report".to_string()),
+            ("report".to_string(), "notify".to_string())
+        ]
+    );
+    assert!(diff.removed_edges.is_empty());
+    assert!(old
+        .export_structure()
+        .diff(&old.export_structure())
+        .is_empty());
+}
+
+#[test]
+fn run_affected() {
+    let a = MockAction::new().returns(1usize);
+    let b = MockAction::new().returns(2usize).returns(3usize);
+    let c = MockAction::new().returns_empty();
+    let d = MockAction::new().returns_empty();
+
+    let task_a = a.task("a");
+    let mut task_b = b.task("b");
+    let mut task_c = c.task("c");
+    let mut task_d = d.task("d");
+    task_b.set_predecessors(&[&task_a]);
+    task_c.set_predecessors(&[&task_b]);
+    task_d.set_predecessors(&[&task_a]);
+    let b_id = task_b.id();
+
+    let mut job = Dag::with_tasks(vec![task_a, task_b, task_c, task_d]);
+    assert!(job.start().unwrap());
+    assert!(job.run_affected(&[b_id]).unwrap());
+
+    assert_eq!(a.call_count(), 1);
+    assert_eq!(b.call_count(), 2);
+    assert_eq!(b.calls()[1][0].get::<usize>(), Some(&1));
+    assert_eq!(c.call_count(), 2);
+    assert_eq!(c.calls()[1][0].get::<usize>(), Some(&3));
+    assert_eq!(d.call_count(), 1);
+    assert!(job.run_affected(&[usize::MAX]).is_err());
+}
+
+#[test]
+fn run_again() {
+    let a = MockAction::new().fails("boom").returns(1usize);
+    let b = MockAction::new().returns(2usize);
+    let task_a = a.task("a");
+    let mut task_b = b.task("b");
+    task_b.set_predecessors(&[&task_a]);
+
+    let mut job = Dag::with_tasks(vec![task_a, task_b]);
+    assert!(!job.start().unwrap());
+    assert_eq!(job.report().tasks[1].status, TaskStatus::Skipped);
+    assert!(job.start().unwrap());
+    assert!(job.take_errors().is_empty());
+    assert_eq!(job.report().tasks[1].status, TaskStatus::Succeeded);
+    assert_eq!(job.get_result::<usize>(), Some(Arc::new(2)));
+    assert!(job.start().unwrap());
+    assert_eq!(a.call_count(), 3);
+    assert_eq!(b.call_count(), 2);
+    assert_eq!(b.calls()[1][0].get::<usize>(), Some(&1));
+
+    // A cancel between runs cancels the next run only.
+    job.cancel_handle().cancel();
+    assert!(!job.start().unwrap());
+    assert!(job.report().cancelled);
+    assert_eq!(job.report().tasks[0].status, TaskStatus::Skipped);
+    assert_eq!(a.call_count(), 3);
+    assert!(job.start().unwrap());
+    assert!(!job.report().cancelled);
+    assert_eq!(a.call_count(), 4);
+}
+
+#[test]
+fn run_sweep() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let (task_running, task_peak) = (running.clone(), peak.clone());
+    let square = DefaultTask::with_closure("square", move |_input, env| {
+        let now = task_running.fetch_add(1, Ordering::SeqCst) + 1;
+        task_peak.fetch_max(now, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(50));
+        task_running.fetch_sub(1, Ordering::SeqCst);
+        let x = env.get::<u64>("X").unwrap();
+        Output::new(x * x)
+    });
+    let mut check = DefaultTask::with_closure("check", |input, _env| {
+        let square = *input.get_by_name("square").unwrap().get::<u64>().unwrap();
+        if square > 10 {
+            Output::error("too large".to_string())
+        } else {
+            Output::new(square.to_string())
+        }
+    });
+    check.set_predecessors(&[&square]);
+
+    let job = Dag::with_tasks(vec![square, check]);
+    let params = (1..=5)
+        .map(|x| {
+            let mut env = EnvVar::new();
+            env.set("X", x as u64);
+            env
+        })
+        .collect();
+    let reports = job.run_sweep(params, 2).unwrap();
+    let outcomes: Vec<bool> = reports.iter().map(|report| report.success).collect();
+    assert_eq!(outcomes, [true, true, true, false, false]);
+    assert_eq!(reports[2].tasks[1].output.as_deref(), Some("9"));
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    assert!(job.report().tasks.is_empty());
+}
+
+#[test]
+fn deterministic_order() {
+    fn run(seed: u64) -> Vec<String> {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let task = |name: &str| {
+            let order = order.clone();
+            let name = name.to_string();
+            DefaultTask::with_closure(&name.clone(), move |_input, _env| {
+                order.lock().unwrap().push(name.clone());
+                Output::empty()
+            })
+        };
+        let root = task("root");
+        let mut leaves: Vec<DefaultTask> = (0..6).map(|i| task(&format!("leaf{}", i))).collect();
+        leaves
+            .iter_mut()
+            .for_each(|leaf| leaf.set_predecessors(&[&root]));
+        let mut last = task("last");
+        last.set_predecessors(&leaves.iter().collect::<Vec<_>>());
+        let mut tasks = vec![root, last];
+        tasks.extend(leaves);
+
+        let mut job = Dag::with_tasks(tasks).deterministic(seed);
+        assert!(job.start().unwrap());
+        let first = order.lock().unwrap().drain(..).collect::<Vec<_>>();
+        assert!(job.start().unwrap());
+        assert_eq!(*order.lock().unwrap(), first);
+        first
+    }
+
+    let order = run(7);
+    assert_eq!(order.len(), 8);
+    assert_eq!((order[0].as_str(), order[7].as_str()), ("root", "last"));
+    assert!((0..20).map(run).any(|other| other != order));
+}
+
+#[test]
+fn add_edge() {
+    let a = MockAction::new().returns(1usize);
+    let b = MockAction::new().returns_empty();
+    let task_a = a.task("a");
+    let task_b = b.task("b");
+    let (a_id, b_id) = (task_a.id(), task_b.id());
+
+    let mut job = Dag::with_tasks(vec![task_a, task_b]);
+    assert_eq!(
+        job.add_edge(usize::MAX, b_id),
+        Err(GraphError::UnknownFrom(usize::MAX))
+    );
+    assert_eq!(
+        job.add_edge(a_id, usize::MAX),
+        Err(GraphError::UnknownTo(usize::MAX))
+    );
+    assert_eq!(job.add_edge(a_id, b_id), Ok(()));
+    assert_eq!(
+        job.add_edge(a_id, b_id),
+        Err(GraphError::DuplicateEdge(a_id, b_id))
+    );
+    assert_eq!(job.try_add_edge(a_id, b_id), Ok(false));
+    assert_eq!(job.export_structure().edges, vec![(a_id, b_id)]);
+
+    assert!(job.start().unwrap());
+    assert_eq!(b.calls()[0][0].get::<usize>(), Some(&1));
+
+    let task_c = DefaultTask::new("c");
+    let c_id = task_c.id();
+    let mut job = Dag::with_tasks(vec![task_c]);
+    job.add_edge(c_id, c_id).unwrap();
+    assert!(matches!(job.start(), Err(DagError::LoopGraph)));
+}
+
+#[test]
+fn shutdown() {
+    let slow = DefaultTask::with_closure("slow", |_input, _env| {
+        std::thread::sleep(Duration::from_secs(5));
+        Output::new(1)
+    });
+    let mut next = DefaultTask::with_closure("next", |_input, _env| Output::new(2));
+    next.set_predecessors(&[&slow]);
+    let mut job = Dag::with_tasks(vec![slow, next]);
+    let cancel = job.cancel_handle();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        cancel.shutdown(Duration::from_millis(100));
+    });
+
+    let start = Instant::now();
+    assert!(!job.start().unwrap());
+    assert!(start.elapsed() < Duration::from_secs(2));
+    let errors = job.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].to_string().contains("aborted by a shutdown"));
+    let report = job.report();
+    assert!(report.cancelled);
+    assert_eq!(report.tasks[0].status, TaskStatus::Failed);
+    assert_eq!(report.tasks[1].status, TaskStatus::Skipped);
+}
+
+#[test]
+fn shutdown_does_not_expire_next_run() {
+    let slow = DefaultTask::with_closure("slow", |_input, _env| {
+        std::thread::sleep(Duration::from_millis(300));
+        Output::new(1)
+    });
+    let mut job = Dag::with_tasks(vec![slow]);
+    let cancel = job.cancel_handle();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        cancel.shutdown(Duration::from_millis(400));
+    });
+    assert!(!job.start().unwrap());
+    // The grace period of the first run ends while the second one runs.
+    assert!(job.start().unwrap());
+    assert_eq!(job.report().tasks[0].status, TaskStatus::Succeeded);
+}
+
+#[test]
+fn run_report() {
+    let yaml = r#"
+dagrs:
+  a:
+    name: "greet"
+    cmd: echo hi
+  b:
+    name: "fail"
+    after: [ a ]
+    cmd: exit 3
+  c:
+    name: "never"
+    after: [ b ]
+    cmd: echo c
+"#;
+    let mut job = Dag::with_yaml_str(yaml, HashMap::new()).unwrap();
+    assert!(!job.start().unwrap());
+
+    let report = job.report();
+    assert!(!report.success);
+    assert!(!report.cancelled);
+    let statuses: Vec<_> = report
+        .tasks
+        .iter()
+        .map(|t| (t.name.as_str(), t.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("greet", TaskStatus::Succeeded),
+            ("fail", TaskStatus::Failed),
+            ("never", TaskStatus::Skipped)
+        ]
+    );
+    assert_eq!(report.tasks[0].output.as_deref(), Some("hi"));
+    assert!(report.tasks[0].duration.is_some());
+    assert!(report.tasks[1].error.is_some());
+    assert!(report.tasks[2].duration.is_none());
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["tasks"][1]["status"], "failed");
+
+    let junit = report.to_junit("pipeline");
+    assert!(junit.contains(
+        "<testsuite name=\"pipeline\" tests=\"3\" failures=\"1\" errors=\"0\" skipped=\"1\""
+    ));
+    assert_eq!(junit.matches("<testcase ").count(), 3);
+    assert!(junit.contains("<testcase name=\"greet\" classname=\"pipeline\""));
+    assert!(junit.contains("<system-out>hi</system-out>"));
+    assert_eq!(junit.matches("<failure message=").count(), 1);
+    assert_eq!(junit.matches("<skipped/>").count(), 1);
+}
+
+#[test]
+fn dag_config_retries() {
+    let flaky = MockAction::new().fails("1").panics("2").returns(3usize);
+    let fragile = MockAction::new().fails("boom");
+
+    let task_flaky = flaky.task("flaky");
+    let mut task_fragile = fragile.task("fragile");
+    task_fragile.set_retries(0);
+
+    let mut job = Dag::with_tasks(vec![task_flaky, task_fragile])
+        .with_config(DagConfig::new().retries(2).keep_going(true));
+    assert!(!job.start().unwrap());
+    assert_eq!(flaky.call_count(), 3);
+    assert_eq!(fragile.call_count(), 1);
+}
+
+#[test]
+fn dag_config_timeout() {
+    let mut slow = DefaultTask::with_closure("slow", |_, _| {
+        std::thread::sleep(Duration::from_secs(2));
+        Output::empty()
+    });
+    slow.set_timeout(Duration::from_millis(100));
+    let fast = DefaultTask::with_closure("fast", |_, _| Output::empty());
+
+    let start = Instant::now();
+    let mut job = Dag::with_tasks(vec![slow, fast]).with_config(
+        DagConfig::new()
+            .timeout(Duration::from_secs(10))
+            .keep_going(true),
+    );
+    assert!(!job.start().unwrap());
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    let errors = job.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        DagError::ExecutionFailed { name, source, .. } if name == "slow"
+            && matches!(source.downcast_ref::<ExecutionError>(), Some(ExecutionError::Timeout(_)))
+    ));
+}
+
+#[test]
+fn timed_out_attempts_end_before_retries() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let overlapped = Arc::new(AtomicUsize::new(0));
+    let (r, o) = (running.clone(), overlapped.clone());
+    let mut slow = DefaultTask::with_closure("slow", move |_, _| {
+        if r.fetch_add(1, Ordering::SeqCst) > 0 {
+            o.fetch_add(1, Ordering::SeqCst);
+        }
+        std::thread::sleep(Duration::from_millis(200));
+        r.fetch_sub(1, Ordering::SeqCst);
+        Output::empty()
+    });
+    slow.set_timeout(Duration::from_millis(50));
+    slow.set_retries(2);
+
+    let mut job = Dag::with_tasks(vec![slow]);
+    assert!(!job.start().unwrap());
+    assert_eq!(overlapped.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn yaml_task_config() {
+    let yaml = r#"
+dagrs:
+  a:
+    name: "slow"
+    cmd: sleep 1
+    timeout: 0.2
+    retries: 1
+"#;
+    // The retry starts once the command of the first attempt ended, and times out too.
+    let start = Instant::now();
+    let mut job = Dag::with_yaml_str(yaml, HashMap::new()).unwrap();
+    assert!(!job.start().unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(1200));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    let illegal = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    retries: -1\n";
+    assert!(Dag::with_yaml_str(illegal, HashMap::new()).is_err());
+}
+
+#[test]
+fn yaml_profiles() {
+    let file = "tests/config/profiles.yaml";
+    let run = |parser: YamlParser| {
+        let mut job =
+            Dag::with_config_file_and_parser(file, Box::new(parser), HashMap::new()).unwrap();
+        assert!(job.start().unwrap());
+        job.report()
+            .tasks
+            .into_iter()
+            .map(|task| task.output.unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(YamlParser::new()), ["target=", "deploy"]);
+    assert_eq!(
+        run(YamlParser::with_profile("dev")),
+        ["target=dev", "deploy"]
+    );
+    assert_eq!(
+        run(YamlParser::with_profile("prod")),
+        ["target=prod", "deploy to production"]
+    );
+    assert!(Dag::with_config_file_and_parser(
+        file,
+        Box::new(YamlParser::with_profile("staging")),
+        HashMap::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn yaml_shell_selection() {
+    let yaml = r#"
+dagrs:
+  a:
+    name: "bash"
+    cmd: "[[ -n $BASH_VERSION ]] && echo bash"
+    shell: bash
+"#;
+    let mut job = Dag::with_yaml_str(yaml, HashMap::new()).unwrap();
+    assert!(job.start().unwrap());
+    assert_eq!(job.report().tasks[0].output.as_deref(), Some("bash"));
+
+    let unknown = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    shell: fish\n";
+    assert!(Dag::with_yaml_str(unknown, HashMap::new()).is_err());
+}
+
+#[test]
+fn conditions() {
+    let a = DefaultTask::with_closure("a", |_input, _env| Output::new(1usize));
+    let mut gated = DefaultTask::with_closure("gated", |_input, _env| Output::new(2usize));
+    gated.set_predecessors(&[&a]);
+    gated.set_condition(|input: &Input, _env: &EnvVar| {
+        input.get_iter().next().unwrap().get::<usize>() == Some(&2)
+    });
+    let mut after = DefaultTask::with_closure("after", |_input, _env| Output::new(3usize));
+    after.set_predecessors(&[&gated]);
+    let mut job = Dag::with_tasks(vec![a, gated, after]);
+    assert!(job.start().unwrap());
+    let report = job.report();
+    assert!(report.success);
+    let statuses: Vec<TaskStatus> = report.tasks.iter().map(|task| task.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            TaskStatus::Succeeded,
+            TaskStatus::Skipped,
+            TaskStatus::Skipped
+        ]
+    );
+
+    let mut env = EnvVar::new();
+    env.set("FLAG", "yes".to_string());
+    let mut yes = DefaultTask::with_closure("yes", |_input, _env| Output::new(1usize));
+    yes.set_condition(CommandCondition::new("test {{ env.FLAG }} = yes"));
+    let mut no = DefaultTask::with_closure("no", |_input, _env| Output::new(2usize));
+    let exit = CommandCondition::new("exit 1");
+    assert!(exit.is_blocking());
+    no.set_condition(exit);
+    let mut job = Dag::with_tasks(vec![yes, no]);
+    job.set_env(env);
+    assert!(job.start().unwrap());
+    for task in job.report().tasks {
+        let expected = if task.name == "yes" {
+            TaskStatus::Succeeded
+        } else {
+            TaskStatus::Skipped
+        };
+        assert_eq!(task.status, expected);
+    }
+
+    let yaml = r#"
+dagrs:
+  a:
+    name: "a"
+    cmd: echo a
+    if: "true"
+  b:
+    name: "b"
+    cmd: echo b
+    if: "false"
+"#;
+    let mut job = Dag::with_yaml_str(yaml, HashMap::new()).unwrap();
+    assert!(job.start().unwrap());
+    let mut outputs: Vec<Option<String>> =
+        job.report().tasks.into_iter().map(|t| t.output).collect();
+    outputs.sort();
+    assert_eq!(outputs, vec![None, Some("a".to_string())]);
+}
+
+#[test]
+fn task_builder() {
+    let a = DefaultTask::with_closure("a", |_input, _env| Output::new(1usize));
+    let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = attempts.clone();
+    let b = DefaultTask::builder("b")
+        .closure(move |input, _env| {
+            let value = *input.get_iter().next().unwrap().get::<usize>().unwrap();
+            match counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                0 => Output::error("flaky".to_string()),
+                _ => Output::new(value + 1),
+            }
+        })
+        .layer(|inner: Action| {
+            Action::Closure(Arc::new(move |input, env| match inner.run(input, env) {
+                Output::Out(Some(content)) => Output::new(*content.get::<usize>().unwrap() * 10),
+                output => output,
+            }))
+        })
+        .after(&[&a])
+        .timeout(Duration::from_secs(5))
+        .retries(1)
+        .condition(|input: &Input, _env: &EnvVar| input.get_iter().count() == 1)
+        .action_type("double")
+        .build();
+    assert_eq!(b.precursors(), &[a.id()]);
+    assert_eq!(b.config().timeout, Some(Duration::from_secs(5)));
+    assert_eq!(b.config().retries, Some(1));
+    assert_eq!(b.action_type(), Some("double"));
+    assert!(b.condition().is_some());
+
+    let mut job = Dag::with_tasks(vec![a, b]);
+    assert!(job.start().unwrap());
+    assert_eq!(*job.get_result::<usize>().unwrap(), 20);
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[test]
+fn templated_command() {
+    let yaml = r#"
+dagrs:
+  a:
+    name: "version"
+    cmd: echo 1.2
+  b:
+    name: "build"
+    after: [ a ]
+    cmd: echo "v{{ inputs.version }} in {{env.BASE_DIR}}"
+"#;
+    let mut job = Dag::with_yaml_str(yaml, HashMap::new()).unwrap();
+    let mut env = EnvVar::new();
+    env.set("BASE_DIR", "/srv".to_string());
+    job.set_env(env);
+    assert!(job.start().unwrap());
+    assert_eq!(
+        job.report().tasks[1].output.as_deref(),
+        Some("v1.2 in /srv")
+    );
+
+    let missing = "dagrs:\n  a:\n    name: a\n    cmd: echo {{ inputs.nothing }}\n";
+    let mut job = Dag::with_yaml_str(missing, HashMap::new()).unwrap();
+    assert!(!job.start().unwrap());
+}
+
+#[test]
+fn templated_command_quotes_values() {
+    let yaml = r#"
+dagrs:
+  a:
+    name: "print"
+    cmd: printf '%s|' {{ env.VALUE }} "{{ env.VALUE }}" 'x{{ env.VALUE }}'; echo '{{.Names}}'
+"#;
+    let value = r#"a'b "c" \ $(echo injected) ; echo `id`"#;
+    let mut job = Dag::with_yaml_str(yaml, HashMap::new()).unwrap();
+    let mut env = EnvVar::new();
+    env.set("VALUE", value.to_string());
+    job.set_env(env);
+    assert!(job.start().unwrap());
+    assert_eq!(
+        job.report().tasks[0].output.as_deref(),
+        Some(format!("{0}|{0}|x{0}|{{{{.Names}}}}", value).as_str())
+    );
+}
+
+#[test]
+fn yaml_action_registry() {
+    let mut registry = ActionRegistry::new();
+    registry.register("greet", |args| {
+        let greeting = format!("hello {}", args["who"]);
+        Action::Closure(Arc::new(move |_, _| Output::new(greeting.clone())))
+    });
+    let yaml = r#"
+dagrs:
+  a:
+    name: "greet"
+    type: greet
+    args:
+      who: world
+"#;
+    let parser = YamlParser::new().action_registry(registry);
+    let mut job = Dag::with_config_str_and_parser(yaml, Box::new(parser), HashMap::new()).unwrap();
+    let structure = job.export_structure();
+    assert_eq!(structure.tasks[0].action.as_deref(), Some("greet"));
+    assert_eq!(structure.tasks[0].args["who"], "world");
+    assert!(job.start().unwrap());
+    assert_eq!(*job.get_result::<String>().unwrap(), "hello world");
+
+    let unknown = Dag::with_yaml_str(yaml, HashMap::new());
+    assert!(unknown.is_err());
+}
+
+#[test]
+fn relative_paths() {
+    let dir = std::env::temp_dir().join(format!("dagrs_relative_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("data.txt"), "data").unwrap();
+    let yaml =
+        "dagrs:\n  a:\n    name: a\n    cmd: test -f ./data.txt\n    if: test -f ./data.txt\n";
+    std::fs::write(dir.join("dag.yaml"), yaml).unwrap();
+    let file = dir.join("dag.yaml");
+    let file = file.to_str().unwrap();
+
+    let parser = YamlParser::new();
+    assert_eq!(parser.base_dir(file), Some(dir.as_path()));
+    let mut job = Dag::with_config_file_and_parser(file, Box::new(parser), HashMap::new()).unwrap();
+    assert!(job.start().unwrap());
+    assert_eq!(job.report().tasks[0].status, TaskStatus::Succeeded);
+
+    let parser = YamlParser::new().relative_to_cwd();
+    assert_eq!(parser.base_dir(file), None);
+    let mut job = Dag::with_config_file_and_parser(file, Box::new(parser), HashMap::new()).unwrap();
+    job.start().unwrap();
+    assert_eq!(job.report().tasks[0].status, TaskStatus::Skipped);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn params() {
+    let params = HashMap::from([
+        ("target".to_owned(), "prod".to_owned()),
+        ("wait".to_owned(), "5".to_owned()),
+    ]);
+    let yaml = "vars:\n  url: https://${params.target}.example.com\ndagrs:\n  a:\n    name: a\n    cmd: test '${vars.url}' = https://prod.example.com && test {{ env.target }} = prod\n    timeout: ${params.wait}\n";
+    let parser = YamlParser::new().params(params.clone());
+    let mut job = Dag::with_config_str_and_parser(yaml, Box::new(parser), HashMap::new())
+        .unwrap()
+        .with_params(params);
+    job.set_env(EnvVar::new());
+    assert!(job.start().unwrap());
+
+    let missing = Dag::with_config_str_and_parser(
+        "dagrs:\n  a:\n    name: a\n    cmd: echo ${params.missing}\n",
+        Box::new(YamlParser::new()),
+        HashMap::new(),
+    );
+    assert!(missing.is_err());
+
+    // Parameters are quoted in commands, also without `Dag::with_params`.
+    let value = "x'; exit 1; echo \"$(false)";
+    let params = HashMap::from([("value".to_owned(), value.to_owned())]);
+    let yaml = "vars:\n  quoted: v${params.value}\ndagrs:\n  a:\n    name: a\n    cmd: printf '%s|' ${params.value} \"${vars.quoted}\"\n";
+    let parser = YamlParser::new().params(params);
+    let mut job = Dag::with_config_str_and_parser(yaml, Box::new(parser), HashMap::new()).unwrap();
+    assert!(job.start().unwrap());
+    assert_eq!(
+        job.report().tasks[0].output.as_deref(),
+        Some(format!("{0}|v{0}|", value).as_str())
+    );
+}
+
+#[test]
+fn delay_and_window() {
+    let mut delayed = DefaultTask::with_closure("delayed", |_input, _env| Output::new(1));
+    delayed.set_delay(Duration::from_millis(200));
+    let start = Instant::now();
+    assert!(Dag::with_tasks(vec![delayed]).start().unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    // Every time of day is after midnight, and none is before it.
+    let opened = DefaultTask::builder("opened")
+        .closure(|_input, _env| Output::new(1))
+        .window(TimeOfDay::new(0, 0, 0), None)
+        .build();
+    assert!(Dag::with_tasks(vec![opened]).start().unwrap());
+    let mut missed = DefaultTask::with_closure("missed", |_input, _env| Output::new(1));
+    missed.set_window(None, TimeOfDay::new(0, 0, 0));
+    let mut dag = Dag::with_tasks(vec![missed]);
+    assert!(!dag.start().unwrap());
+    assert!(matches!(
+        &dag.take_errors()[0],
+        DagError::ExecutionFailed { source, .. }
+            if matches!(source.downcast_ref::<ExecutionError>(), Some(ExecutionError::MissedWindow))
+    ));
+
+    let yaml = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    delay: 250ms\n    not_before: \"00:00\"\n    not_after: 23:59:59\n";
+    let tasks = YamlParser::new()
+        .strict()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .unwrap();
+    let config = tasks[0].config();
+    assert_eq!(config.delay, Some(Duration::from_millis(250)));
+    assert_eq!(config.not_before, TimeOfDay::new(0, 0, 0));
+    assert_eq!(config.not_after, TimeOfDay::new(23, 59, 59));
+    assert_eq!("7:5".parse(), Ok(TimeOfDay::new(7, 5, 0).unwrap()));
+    assert!("24:00".parse::<TimeOfDay>().is_err());
+}
+
+#[test]
+fn output_size_limits() {
+    let run = |task: DefaultTask, config: DagConfig| {
+        let mut dag = Dag::with_tasks(vec![task]).with_config(config);
+        let success = dag.start().unwrap();
+        (success, dag)
+    };
+    let noisy =
+        || DefaultTask::with_closure("noisy", |_, _| Output::new("héllo world".to_string()));
+
+    let (success, dag) = run(
+        noisy(),
+        DagConfig::new().max_output_size(4, OverflowPolicy::Fail),
+    );
+    assert!(!success);
+    assert!(matches!(
+        &dag.take_errors()[0],
+        DagError::ExecutionFailed { source, .. }
+            if matches!(
+                source.downcast_ref::<ExecutionError>(),
+                Some(ExecutionError::OutputTooLarge { size: 12, limit: 4 })
+            )
+    ));
+
+    // The cut falls inside 'é', which is dropped.
+    let config = DagConfig::new().max_output_size(2, OverflowPolicy::Truncate);
+    let (success, dag) = run(noisy(), config);
+    assert!(success);
+    assert_eq!(*dag.get_result::<String>().unwrap(), "h");
+
+    let (success, dag) = run(
+        noisy(),
+        DagConfig::new().max_output_size(4, OverflowPolicy::Spill),
+    );
+    assert!(success);
+    let artifact = dag.get_result::<Artifact>().unwrap();
+    assert_eq!(artifact.read_to_string().unwrap(), "héllo world");
+
+    // The limit of a task overrides the one of the dag, and small outputs are left as they are.
+    let mut task = noisy();
+    task.set_max_output_size(64, OverflowPolicy::Fail);
+    let (success, dag) = run(
+        task,
+        DagConfig::new().max_output_size(4, OverflowPolicy::Fail),
+    );
+    assert!(success);
+    assert_eq!(*dag.get_result::<String>().unwrap(), "héllo world");
+}
+
+#[test]
+fn subgraph_for_targets() {
+    let compile = DefaultTask::with_closure("compile", |_, _| Output::new(1usize));
+    let mut test = DefaultTask::with_closure("test", |input, _| {
+        Output::new(
+            *input
+                .get_by_name("compile")
+                .unwrap()
+                .get::<usize>()
+                .unwrap()
+                + 1,
+        )
+    });
+    test.set_predecessors(&[&compile]);
+    let mut docs = DefaultTask::with_closure("docs", |_, _| Output::new(3usize));
+    docs.set_predecessors(&[&compile]);
+    let mut package = DefaultTask::with_closure("package", |_, _| Output::new(4usize));
+    package.set_predecessors(&[&test, &docs]);
+    let (compile_id, test_id, docs_id) = (compile.id(), test.id(), docs.id());
+
+    let mut dag = Dag::with_tasks(vec![compile, test, docs, package]);
+    let mut sub = dag.subgraph_for(&[test_id]).unwrap();
+    let mut ids: Vec<usize> = sub.export_structure().tasks.iter().map(|t| t.id).collect();
+    ids.sort();
+    assert_eq!(ids, vec![compile_id, test_id]);
+    assert!(sub.start().unwrap());
+    assert_eq!(*sub.get_result::<usize>().unwrap(), 2);
+
+    let sub = dag.subgraph_for(&[test_id, docs_id]).unwrap();
+    assert_eq!(sub.export_structure().tasks.len(), 3);
+    assert!(matches!(
+        dag.subgraph_for(&[usize::MAX]),
+        Err(DagError::UnknownTask(_))
+    ));
+    // The original dag is left as it is.
+    assert!(dag.start().unwrap());
+    assert_eq!(dag.report().tasks.len(), 4);
+}
+
+#[test]
+fn run_targets() {
+    let ran = Arc::new(Mutex::new(Vec::new()));
+    let step = |name: &str, fail: bool| {
+        let ran = ran.clone();
+        let task_name = name.to_string();
+        DefaultTask::with_closure(name, move |_, _| {
+            ran.lock().unwrap().push(task_name.clone());
+            if fail {
+                Output::error("tests failed".to_string())
+            } else {
+                Output::new(task_name.len())
+            }
+        })
+    };
+    let compile = step("compile", false);
+    let mut test = step("test", true);
+    test.set_predecessors(&[&compile]);
+    let mut docs = step("docs", false);
+    docs.set_predecessors(&[&compile]);
+    let mut package = step("package", false);
+    package.set_predecessors(&[&compile]);
+    let dag = Dag::with_tasks(vec![compile, test, docs, package]);
+
+    let outputs = dag.run_targets(&["package", "docs"]).unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(
+        outputs["package"].as_ref().unwrap().get::<usize>(),
+        Some(&7)
+    );
+    assert_eq!(outputs["docs"].as_ref().unwrap().get::<usize>(), Some(&4));
+    let mut ran_tasks = ran.lock().unwrap().clone();
+    ran_tasks.sort();
+    assert_eq!(ran_tasks, vec!["compile", "docs", "package"]);
+
+    assert!(matches!(
+        dag.run_targets(&["test"]),
+        Err(DagError::ExecutionFailed { name, .. }) if name == "test"
+    ));
+    assert!(matches!(
+        dag.run_targets(&["deploy"]),
+        Err(DagError::UnknownTask(name)) if name == "deploy"
+    ));
+}
+
+#[test]
+fn up_to_date_tasks() {
+    let dir = std::env::temp_dir().join(format!("dagrs_up_to_date_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (source, object) = (dir.join("main.c"), dir.join("main.o"));
+    std::fs::write(&source, "int main() {}").unwrap();
+    let _ = std::fs::remove_file(&object);
+
+    let compiled = Arc::new(AtomicUsize::new(0));
+    let counter = compiled.clone();
+    let target = object.clone();
+    let compile = DefaultTask::builder("compile")
+        .closure(move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            std::fs::write(&target, "object").unwrap();
+            Output::empty()
+        })
+        .files([&source], [&object])
+        .build();
+    let link = DefaultTask::builder("link")
+        .closure(|_, _| Output::new(1))
+        .after(&[&compile])
+        .build();
+    let mut dag = Dag::with_tasks(vec![compile, link]);
+    let statuses = |dag: &Dag| {
+        let report = dag.report();
+        report.tasks.iter().map(|t| t.status).collect::<Vec<_>>()
+    };
+
+    // The output is missing, so the task runs.
+    assert!(dag.start().unwrap());
+    assert_eq!(compiled.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        statuses(&dag),
+        vec![TaskStatus::Succeeded, TaskStatus::Succeeded]
+    );
+
+    // The output is newer than the input: the task is up to date, and its successor still runs.
+    assert!(dag.start().unwrap());
+    assert_eq!(compiled.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        statuses(&dag),
+        vec![TaskStatus::UpToDate, TaskStatus::Succeeded]
+    );
+
+    // The input changed after the output was written.
+    let later = std::time::SystemTime::now() + Duration::from_secs(60);
+    std::fs::File::options()
+        .write(true)
+        .open(&source)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    assert!(dag.start().unwrap());
+    assert_eq!(compiled.load(Ordering::SeqCst), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn named_locks() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let migrate = |name: &str, lock: &str| {
+        let (running, most) = (running.clone(), most.clone());
+        DefaultTask::builder(name)
+            .closure(move |_, _| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(50));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Output::empty()
+            })
+            .lock(lock)
+            .build()
+    };
+    let tasks = vec![
+        migrate("users", "db-migrations"),
+        migrate("orders", "db-migrations"),
+        migrate("items", "db-migrations"),
+    ];
+    let mut dag = Dag::with_tasks(tasks);
+    assert!(dag.start().unwrap());
+    // The tasks do not depend on each other, but share a lock.
+    assert_eq!(most.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn concurrency_pools() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let tasks = (0..6)
+        .map(|i| {
+            let (running, most) = (running.clone(), most.clone());
+            DefaultTask::builder(&format!("download {}", i))
+                .closure(move |_, _| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(30));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Output::empty()
+                })
+                .pool("downloads", 2)
+                .build()
+        })
+        .collect();
+    let mut dag = Dag::with_tasks(tasks);
+    assert!(dag.start().unwrap());
+    assert!(most.load(Ordering::SeqCst) <= 2);
+}
+
+#[test]
+fn blocking_execution() {
+    let most_concurrent = |config: DagConfig| {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let tasks = (0..3)
+            .map(|i| {
+                let (running, most) = (running.clone(), most.clone());
+                DefaultTask::builder(&format!("hash {}", i))
+                    .closure(move |_, _| {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        most.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(50));
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Output::empty()
+                    })
+                    .execution(Execution::Blocking)
+                    .build()
+            })
+            .collect();
+        let mut dag = Dag::with_tasks(tasks).with_config(config);
+        assert!(dag.start().unwrap());
+        most.load(Ordering::SeqCst)
+    };
+    // Blocking actions leave the threads of the runtime free to start the others.
+    assert_eq!(most_concurrent(DagConfig::new()), 3);
+    assert_eq!(most_concurrent(DagConfig::new().blocking_threads(1)), 1);
+}
+
+#[test]
+fn blocking_actions() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let tasks: Vec<DefaultTask> = (0..3)
+        .map(|i| {
+            let (running, most) = (running.clone(), most.clone());
+            let action = Action::Closure(Arc::new(move |_, _| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(50));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Output::empty()
+            }));
+            assert!(!action.is_blocking());
+            let action = action.blocking().with_retries(1, Default::default());
+            assert!(action.is_blocking());
+            DefaultTask::with_action(&format!("hash {}", i), action)
+        })
+        .collect();
+    let mut dag = Dag::with_tasks(tasks);
+    assert!(dag.start().unwrap());
+    assert_eq!(most.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn sync_actions() {
+    struct Hash {
+        running: Arc<AtomicUsize>,
+        most: Arc<AtomicUsize>,
+    }
+
+    impl SyncAction for Hash {
+        fn run(&self, _input: Input, _env: Arc<EnvVar>) -> Output {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(50));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Output::empty()
+        }
+    }
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let tasks: Vec<DefaultTask> = (0..3)
+        .map(|i| {
+            let (running, most) = (running.clone(), most.clone());
+            // Lazy actions tell whether they block once built.
+            let action = Lazy::new(move || {
+                Action::from_sync(Hash {
+                    running: running.clone(),
+                    most: most.clone(),
+                })
+            });
+            DefaultTask::with_action(&format!("hash {}", i), action)
+        })
+        .collect();
+    let mut dag = Dag::with_tasks(tasks);
+    assert!(dag.start().unwrap());
+    assert_eq!(most.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn large_chain() {
+    let mut tasks: Vec<DefaultTask> = Vec::with_capacity(20_000);
+    for i in 0..20_000 {
+        let mut task = DefaultTask::with_closure(&format!("step {}", i), |input, _| {
+            let count = input
+                .get_iter()
+                .next()
+                .and_then(|content| content.get::<usize>())
+                .map_or(0, |count| *count);
+            Output::new(count + 1)
+        });
+        if let Some(previous) = tasks.last() {
+            task.set_predecessors(&[previous]);
+        }
+        tasks.push(task);
+    }
+    let start = Instant::now();
+    let mut dag = Dag::with_tasks(tasks);
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<usize>().unwrap(), 20_000);
+    // Tasks that are ready run at once, without waiting for a tick of the timer.
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
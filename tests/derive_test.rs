@@ -0,0 +1,185 @@
+//! Tests of the macros of the derive feature.
+
+use std::sync::Arc;
+
+use dagrs::{
+    action, condition, dependencies, Action, CustomTask, Dag, EnvVar, Input, Output, Task,
+    TypedAction,
+};
+
+#[action]
+async fn fetch(_input: Input, env: Arc<EnvVar>) -> Output {
+    tokio::task::yield_now().await;
+    Output::new(env.get::<usize>("BASE").unwrap() + 1)
+}
+
+#[action]
+fn double_all(input: Input, _env: Arc<EnvVar>) -> Output {
+    Output::new(
+        input
+            .get_iter()
+            .map(|content| content.get::<usize>().unwrap() * 2)
+            .sum::<usize>(),
+    )
+}
+
+#[action]
+async fn halve(x: &usize) -> usize {
+    tokio::task::yield_now().await;
+    x / 2
+}
+
+#[action]
+fn parse(text: String, env: Arc<EnvVar>) -> Result<usize, std::num::ParseIntError> {
+    Ok(text.parse::<usize>()? * env.get::<usize>("FACTOR").unwrap())
+}
+
+#[condition]
+fn has_input(input: &Input, _env: &EnvVar) -> bool {
+    input.get_iter().next().is_some()
+}
+
+#[condition]
+async fn enabled(_input: &Input, env: &EnvVar) -> bool {
+    tokio::task::yield_now().await;
+    env.get::<bool>("ENABLED") == Some(true)
+}
+
+#[test]
+fn action_functions() {
+    let fetch = Fetch::task("fetch");
+    let mut double = DoubleAll::task("double");
+    double.set_predecessors(&[&fetch]);
+
+    let mut dag = Dag::with_tasks(vec![fetch, double]);
+    let mut env = EnvVar::new();
+    env.set("BASE", 20usize);
+    dag.set_env(env);
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<usize>().unwrap(), 42);
+}
+
+#[test]
+fn typed_action_functions() {
+    fn types<A: TypedAction<In = String, Out = usize>>(_: A) {}
+    types(Parse);
+
+    let text = dagrs::DefaultTask::with_closure("text", |_, _| Output::new("42".to_string()));
+    let mut parse = Parse::task("parse");
+    parse.set_predecessors(&[&text]);
+    let mut halve = Halve::task("halve");
+    halve.set_predecessors(&[&parse]);
+    let mut dag = Dag::with_tasks(vec![text, parse, halve]);
+    let mut env = EnvVar::new();
+    env.set("FACTOR", 2usize);
+    dag.set_env(env);
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<usize>().unwrap(), 42);
+
+    let bad = dagrs::DefaultTask::with_closure("text", |_, _| Output::new("x".to_string()));
+    let mut parse = Parse::task("parse");
+    parse.set_predecessors(&[&bad]);
+    let mut dag = Dag::with_tasks(vec![bad, parse]);
+    dag.set_env(EnvVar::new());
+    assert!(!dag.start().unwrap());
+    assert!(dag.report().tasks[1]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("invalid digit"));
+
+    let mut halve = Halve::task("halve");
+    let nothing = dagrs::DefaultTask::with_closure("nothing", |_, _| Output::empty());
+    halve.set_predecessors(&[&nothing]);
+    let mut dag = Dag::with_tasks(vec![nothing, halve]);
+    assert!(!dag.start().unwrap());
+    assert!(dag.report().tasks[1]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("expected an input of type `usize`"));
+}
+
+#[test]
+fn condition_functions() {
+    let run = |enabled: bool| {
+        let fetch = Fetch::task("fetch");
+        let mut double = HasInput::task("double", DoubleAll);
+        double.set_predecessors(&[&fetch]);
+        let mut halve = Enabled::task("halve", Halve);
+        halve.set_predecessors(&[&double]);
+        let mut dag = Dag::with_tasks(vec![fetch, double, halve]);
+        let mut env = EnvVar::new();
+        env.set("BASE", 20usize);
+        env.set("ENABLED", enabled);
+        dag.set_env(env);
+        assert!(dag.start().unwrap());
+        dag.get_result::<usize>().map(|result| *result)
+    };
+    assert_eq!(run(true), Some(21));
+    assert_eq!(run(false), None);
+}
+
+#[derive(CustomTask)]
+#[task(constructors)]
+struct Step {
+    #[task(attr = "id")]
+    id: usize,
+    #[task(attr = "name")]
+    name: String,
+    #[task(attr = "precursors")]
+    precursors: Vec<usize>,
+    #[task(attr = "action")]
+    action: Action,
+    attempts: u32,
+}
+
+#[test]
+fn generated_constructors() {
+    let empty = Step::new("empty");
+    let double = Step::with_action("double", DoubleAll);
+    assert_eq!(empty.name(), "empty");
+    assert_ne!(empty.id(), double.id());
+    assert!(double.precursors().is_empty());
+    assert_eq!(double.attempts, 0);
+
+    let output = double.action().run(
+        Input::new(vec![dagrs::task::Content::new(21usize)]),
+        Arc::new(EnvVar::new()),
+    );
+    assert_eq!(output.get_out().unwrap().get::<usize>(), Some(&42));
+    assert!(empty
+        .action()
+        .run(Input::new(vec![]), Arc::new(EnvVar::new()))
+        .get_out()
+        .is_none());
+}
+
+#[test]
+fn typed_tasks() {
+    let text = dagrs::TypedTask::source("text", |_env| "10".to_string());
+    let mut parse = Parse::typed_task("parse");
+    parse.after(&text);
+    let mut halve = Halve::typed_task("halve");
+    halve.after(&parse);
+    let mut dag = Dag::with_tasks(vec![text.into_task(), parse.into_task(), halve.into_task()]);
+    let mut env = EnvVar::new();
+    env.set("FACTOR", 3usize);
+    dag.set_env(env);
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<usize>().unwrap(), 15);
+}
+
+#[test]
+fn acyclic_dependencies() {
+    // Tasks reached along several paths are not a cycle.
+    let tasks = dependencies!(
+        a -> b c,
+        b -> d,
+        c -> d,
+        d ->
+    );
+    assert_eq!(tasks[3].precursors().len(), 2);
+    let mut dag = Dag::with_tasks(tasks);
+    assert!(dag.start().unwrap());
+}
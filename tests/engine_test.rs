@@ -0,0 +1,143 @@
+//! Tests of the engine running several dags at once.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use dagrs::{engine::Execution, Dag, DefaultTask, Engine, Output};
+
+#[test]
+fn max_parallel_across_dags() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let dag = |name: &str| {
+        let tasks = (0..4)
+            .map(|i| {
+                let (running, most) = (running.clone(), most.clone());
+                DefaultTask::with_closure(&format!("{} {}", name, i), move |_, _| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Output::empty()
+                })
+            })
+            .collect();
+        Dag::with_tasks(tasks)
+    };
+    let mut engine = Engine::default();
+    engine.set_max_parallel(2);
+    engine.append_dag("a", dag("a"));
+    engine.append_dag("b", dag("b"));
+    assert_eq!(engine.run_concurrent(), [true, true]);
+    assert!(most.load(Ordering::SeqCst) <= 2);
+}
+
+#[test]
+fn large_dags_do_not_starve_others() {
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let dag = |name: &'static str, tasks: usize| {
+        let tasks = (0..tasks)
+            .map(|i| {
+                let finished = finished.clone();
+                DefaultTask::with_closure(&format!("{} {}", name, i), move |_, _| {
+                    std::thread::sleep(Duration::from_millis(20));
+                    finished.lock().unwrap().push(name);
+                    Output::empty()
+                })
+            })
+            .collect();
+        Dag::with_tasks(tasks)
+    };
+    let mut engine = Engine::default();
+    engine.set_max_parallel(1);
+    engine.append_dag("large", dag("large", 20));
+    engine.append_dag("small", dag("small", 2));
+    assert_eq!(engine.run_concurrent(), [true, true]);
+
+    // The dags take turns, so the small one finishes long before the large one.
+    let finished = finished.lock().unwrap();
+    let last_small = finished.iter().rposition(|name| *name == "small").unwrap();
+    assert!(last_small < 10, "{:?}", finished);
+}
+
+/// A dag of tasks that run one after the other, and record their dag when they finish.
+fn chain(
+    name: &'static str,
+    tasks: usize,
+    finished: &Arc<Mutex<Vec<&'static str>>>,
+    first: impl Fn() + Send + Sync + 'static,
+) -> Dag {
+    let first = Arc::new(first);
+    let mut chain: Vec<DefaultTask> = Vec::new();
+    for i in 0..tasks {
+        let (finished, first) = (finished.clone(), first.clone());
+        let mut task = DefaultTask::with_closure(&format!("{} {}", name, i), move |_, _| {
+            if i == 0 {
+                first();
+            }
+            std::thread::sleep(Duration::from_millis(30));
+            finished.lock().unwrap().push(name);
+            Output::empty()
+        });
+        // The dags share the workers of the engine, that the batch must not hold up while it
+        // waits for the hotfix.
+        task.set_execution(Execution::Blocking);
+        if let Some(last) = chain.last() {
+            task.set_predecessors(&[last]);
+        }
+        chain.push(task);
+    }
+    Dag::with_tasks(chain)
+}
+
+/// A batch dag whose first task only finishes once the hotfix dag started, and the hotfix.
+fn batch_and_hotfix(finished: &Arc<Mutex<Vec<&'static str>>>) -> (Dag, Dag) {
+    let (started, wait) = mpsc::channel();
+    let wait = Mutex::new(wait);
+    let batch = chain("batch", 5, finished, move || {
+        wait.lock().unwrap().recv().ok();
+    });
+    let started = Mutex::new(started);
+    let hotfix = chain("hotfix", 2, finished, move || {
+        started.lock().unwrap().send(()).ok();
+    });
+    (batch, hotfix)
+}
+
+#[test]
+fn urgent_dags_hold_back_others() {
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = Engine::default();
+    let (batch, hotfix) = batch_and_hotfix(&finished);
+    engine.append_dag("batch", batch);
+    engine.append_dag("hotfix", hotfix);
+    engine.set_urgent("hotfix", true);
+    assert_eq!(engine.run_concurrent(), [true, true]);
+
+    // Only the first task of the batch may finish before the hotfix.
+    let finished = finished.lock().unwrap();
+    let last_hotfix = finished.iter().rposition(|name| *name == "hotfix").unwrap();
+    assert!(last_hotfix <= 2, "{:?}", finished);
+    assert_eq!(finished.len(), 7);
+}
+
+#[test]
+fn urgent_dags_preempt_others() {
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = Engine::default();
+    let (batch, hotfix) = batch_and_hotfix(&finished);
+    engine.append_dag("batch", batch);
+    engine.append_dag("hotfix", hotfix);
+    engine.set_urgent("hotfix", true);
+    engine.set_preemptible("batch", true);
+    assert_eq!(engine.run_concurrent(), [false, true]);
+
+    let finished = finished.lock().unwrap();
+    assert_eq!(finished.iter().filter(|name| **name == "hotfix").count(), 2);
+    assert!(finished.iter().filter(|name| **name == "batch").count() < 5);
+}
@@ -0,0 +1,196 @@
+//! Tests of the execution events reported by the dag.
+
+use std::{
+    sync::{Arc, Mutex},
+    thread::sleep,
+    time::Duration,
+};
+
+use dagrs::{
+    engine::DagConfig,
+    utils::{current_run, heartbeat},
+    Dag, DagError, DefaultTask, Event, ExecutionError, ExecutionObserver, GraphFormat,
+    JsonEventLog, LiveGraph, Output, Task,
+};
+
+#[test]
+fn json_event_log() {
+    let path = std::env::temp_dir().join("dagrs_json_event_log.jsonl");
+    let a = DefaultTask::with_closure("a", |_, _| Output::new("hello".to_string()));
+    let mut b = DefaultTask::with_closure("b", |_, _| Output::error("boom".to_string()));
+    b.set_predecessors(&[&a]);
+
+    let mut dag = Dag::with_tasks(vec![a, b]);
+    dag.add_observer(JsonEventLog::create(&path).unwrap());
+    assert!(!dag.start().unwrap());
+
+    let events: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "dag_started",
+            "task_started",
+            "task_finished",
+            "task_started",
+            "task_failed",
+            "dag_finished"
+        ]
+    );
+    assert_eq!(events[2]["output_size"], 5);
+    assert_eq!(events[4]["name"], "b");
+    assert_eq!(events[4]["error"], "boom");
+    assert_eq!(events[5]["success"], false);
+    assert!(events.iter().all(|e| e["timestamp"].is_u64()));
+    let run_id = dag.run_id().unwrap();
+    assert!(events.iter().all(|e| e["run_id"] == run_id));
+}
+
+/// Records the run id of each event.
+#[derive(Clone, Default)]
+struct RunIds(Arc<Mutex<Vec<String>>>);
+
+impl ExecutionObserver for RunIds {
+    fn on_event(&self, _event: &Event) {
+        panic!("events of a run come with its id");
+    }
+
+    fn on_run_event(&self, run_id: &str, _event: &Event) {
+        self.0.lock().unwrap().push(run_id.to_owned());
+    }
+}
+
+#[test]
+fn run_ids() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let task_seen = seen.clone();
+    let a = DefaultTask::with_closure("a", move |_, env| {
+        let run_id = env.get::<String>("DAGRS_RUN_ID").unwrap();
+        assert_eq!(current_run().as_ref(), Some(&run_id));
+        task_seen.lock().unwrap().push(run_id);
+        Output::empty()
+    });
+    let a_id = a.id();
+    let ids = RunIds::default();
+    let mut dag = Dag::with_tasks(vec![a]);
+    dag.add_observer(ids.clone());
+    assert!(dag.run_id().is_none());
+
+    assert!(dag.start().unwrap());
+    let first = dag.run_id().unwrap().to_owned();
+    assert_eq!(dag.report().run_id.as_ref(), Some(&first));
+    assert!(dag.run_affected(&[a_id]).unwrap());
+    let second = dag.run_id().unwrap().to_owned();
+    assert_ne!(first, second);
+
+    assert_eq!(*seen.lock().unwrap(), [first.clone(), second.clone()]);
+    let ids = ids.0.lock().unwrap();
+    assert_eq!(ids.len(), 8);
+    assert!(ids[..4].iter().all(|id| *id == first));
+    assert!(ids[4..].iter().all(|id| *id == second));
+}
+
+/// Records the names of the stalled tasks.
+#[derive(Clone, Default)]
+struct Stalls(Arc<Mutex<Vec<String>>>);
+
+impl ExecutionObserver for Stalls {
+    fn on_event(&self, event: &Event) {
+        if let Event::TaskStalled { name, .. } = event {
+            self.0.lock().unwrap().push(name.clone());
+        }
+    }
+}
+
+#[test]
+fn stalled_tasks() {
+    let beating = DefaultTask::with_closure("beating", |_, _| {
+        for _ in 0..10 {
+            sleep(Duration::from_millis(20));
+            heartbeat();
+        }
+        Output::empty()
+    });
+    let silent = DefaultTask::with_closure("silent", |_, _| {
+        sleep(Duration::from_millis(300));
+        Output::empty()
+    });
+
+    // Stalled tasks are only reported by default.
+    let stalls = Stalls::default();
+    let mut dag = Dag::with_tasks(vec![beating, silent])
+        .with_config(DagConfig::new().stall_timeout(Duration::from_millis(100)));
+    dag.add_observer(stalls.clone());
+    assert!(dag.start().unwrap());
+    assert_eq!(*stalls.0.lock().unwrap(), ["silent"]);
+
+    let silent = DefaultTask::builder("silent")
+        .closure(|_, _| {
+            sleep(Duration::from_millis(300));
+            Output::empty()
+        })
+        .stall_timeout(Duration::from_millis(50))
+        .build();
+    let mut dag = Dag::with_tasks(vec![silent]).with_config(DagConfig::new().kill_stalled(true));
+    assert!(!dag.start().unwrap());
+    assert!(matches!(
+        &dag.take_errors()[0],
+        DagError::ExecutionFailed { source, .. }
+            if matches!(source.downcast_ref::<ExecutionError>(), Some(ExecutionError::Stalled(_)))
+    ));
+}
+
+#[test]
+fn live_graph() {
+    let path = std::env::temp_dir().join("dagrs_live_graph.json");
+    let read = {
+        let path = path.clone();
+        move || -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+        }
+    };
+    let states = |graph: &serde_json::Value| -> Vec<String> {
+        graph["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["state"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let a = DefaultTask::with_closure("a", |_, _| Output::new(1));
+    let mut b = DefaultTask::with_closure("b", {
+        let seen = seen.clone();
+        let read = read.clone();
+        move |_, _| {
+            *seen.lock().unwrap() = states(&read());
+            Output::error("boom".to_string())
+        }
+    });
+    b.set_predecessors(&[&a]);
+    let mut c = DefaultTask::with_closure("c", |_, _| Output::empty());
+    c.set_predecessors(&[&b]);
+
+    let mut dag = Dag::with_tasks(vec![a, b, c]);
+    dag.add_observer(LiveGraph::new(
+        &dag.export_structure(),
+        &path,
+        GraphFormat::Json,
+    ));
+    assert!(!dag.start().unwrap());
+    assert_eq!(*seen.lock().unwrap(), ["succeeded", "running", "pending"]);
+    let graph = read();
+    assert_eq!(states(&graph), ["succeeded", "failed", "skipped"]);
+    assert_eq!(graph["edges"].as_array().unwrap().len(), 2);
+
+    assert_eq!(GraphFormat::from_path("run.dot"), GraphFormat::Dot);
+    assert_eq!(GraphFormat::from_path("run.mmd"), GraphFormat::Mermaid);
+    assert_eq!(GraphFormat::from_path("run.json"), GraphFormat::Json);
+}
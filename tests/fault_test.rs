@@ -0,0 +1,147 @@
+//! Tests of fault injection.
+
+use std::time::Duration;
+
+use dagrs::{
+    engine::TaskStatus,
+    testing::{Chaos, MockAction},
+    Dag, DagError, ExecutionError, FaultInjector, Task,
+};
+
+#[test]
+fn injected_failures_are_retried() {
+    let fetch = MockAction::new().returns(1usize);
+    let mut task = fetch.task("fetch");
+    task.set_retries(20);
+
+    let mut job =
+        Dag::with_tasks(vec![task]).with_fault_injector(FaultInjector::new(3).fail("fetch", 0.5));
+    assert!(job.start().unwrap());
+    assert_eq!(fetch.call_count(), 1);
+
+    let broken = MockAction::new().returns(1usize);
+    let mut job = Dag::with_tasks(vec![broken.task("fetch")])
+        .with_fault_injector(FaultInjector::new(3).fail("*", 1.0));
+    assert!(!job.start().unwrap());
+    assert_eq!(broken.call_count(), 0);
+    assert!(job.report().tasks[0]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("injected fault"));
+}
+
+#[test]
+fn same_seed_same_faults() {
+    let failed = |seed: u64| {
+        let tasks: Vec<_> = (0..16)
+            .map(|i| MockAction::new().returns_empty().task(&format!("t{}", i)))
+            .collect();
+        let mut job = Dag::with_tasks(tasks)
+            .keep_going()
+            .with_fault_injector(FaultInjector::new(seed).fail("*", 0.5));
+        let mut runs = (0..2).map(|_| {
+            job.start().unwrap();
+            let mut failed: Vec<String> = job
+                .report()
+                .tasks
+                .into_iter()
+                .filter(|task| task.status == TaskStatus::Failed)
+                .map(|task| task.name)
+                .collect();
+            failed.sort();
+            failed
+        });
+        let first = runs.next().unwrap();
+        assert_eq!(runs.next().unwrap(), first);
+        first
+    };
+    let faults = failed(7);
+    assert!(!faults.is_empty() && faults.len() < 16);
+    assert_eq!(failed(7), faults);
+    assert_ne!(failed(8), faults);
+}
+
+#[test]
+fn dropped_outputs() {
+    let a = MockAction::new().returns(1usize);
+    let b = MockAction::new().returns(2usize);
+    let c = MockAction::new().returns_empty();
+    let task_a = a.task("a");
+    let task_b = b.task("b");
+    let mut task_c = c.task("c");
+    task_c.set_predecessors(&[&task_a, &task_b]);
+
+    let mut job = Dag::with_tasks(vec![task_a, task_b, task_c])
+        .with_fault_injector(FaultInjector::new(1).drop("a", "c", 1.0));
+    assert!(job.start().unwrap());
+    let inputs = &c.calls()[0];
+    assert_eq!(inputs.len(), 1);
+    assert_eq!(inputs[0].get::<usize>(), Some(&2));
+}
+
+#[test]
+fn injected_delays_count_towards_timeouts() {
+    let slow = MockAction::new().returns(1usize);
+    let mut task = slow.task("slow");
+    task.set_timeout(Duration::from_millis(100));
+    let id = task.id();
+
+    let mut job = Dag::with_tasks(vec![task]).with_fault_injector(FaultInjector::new(0).delay(
+        "slow",
+        Duration::from_millis(500),
+        1.0,
+    ));
+    assert!(!job.start().unwrap());
+    assert_eq!(job.report().tasks[0].status, TaskStatus::Failed);
+    match &job.take_errors()[0] {
+        DagError::ExecutionFailed {
+            id: failed, source, ..
+        } => {
+            assert_eq!(*failed, id);
+            assert!(matches!(
+                source.downcast_ref::<ExecutionError>(),
+                Some(ExecutionError::Timeout(_))
+            ));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn chaos() {
+    let dag = |retries: u32| {
+        move || {
+            let mut fetch = MockAction::new().returns(1usize).task("fetch");
+            fetch.set_retries(retries);
+            let mut sink = MockAction::new().returns_empty().task("sink");
+            sink.set_predecessors(&[&fetch]);
+            Dag::with_tasks(vec![fetch, sink])
+        }
+    };
+    let faults = |seed| FaultInjector::new(seed).fail("fetch", 0.3);
+
+    let fragile = Chaos::new(dag(0), faults)
+        .runs(40)
+        .invariant("the run succeeds", |dag| dag.report().success)
+        .run();
+    assert!(!fragile.is_ok());
+    assert!(fragile
+        .failures
+        .iter()
+        .all(|failure| failure.faults == 1 && failure.violated == ["the run succeeds"]));
+    let seed = fragile.minimal_seed().unwrap();
+    assert_eq!(fragile.failures.iter().map(|f| f.seed).min(), Some(seed));
+    let again = Chaos::new(dag(0), faults)
+        .first_seed(seed)
+        .runs(1)
+        .invariant("the run succeeds", |dag| dag.report().success)
+        .run();
+    assert_eq!(again.minimal_seed(), Some(seed));
+
+    Chaos::new(dag(10), faults)
+        .runs(40)
+        .invariant("the run succeeds", |dag| dag.report().success)
+        .run()
+        .assert_ok();
+}
@@ -0,0 +1,143 @@
+//! Tests of the fingerprints of tasks.
+
+use dagrs::{
+    engine::DagConfig, Action, ActionLayer, CircuitBreaker, CommandAction, Complex, Dag,
+    DefaultTask, EnvVar, Fingerprint, Fingerprinter, Lazy, LogLayer, Task,
+};
+use std::{sync::Arc, time::Duration};
+
+#[test]
+fn fingerprint_encoding_is_stable() {
+    let upstream = Fingerprinter::new().command("make").finish();
+    let fingerprint = Fingerprinter::new()
+        .command("make install")
+        .env("PREFIX", Some("/usr"))
+        .env("DESTDIR", None)
+        .upstream(&upstream)
+        .bytes("target", b"x86_64")
+        .finish();
+    // Fingerprints persist in caches, so a change of the encoding has to change the version.
+    assert_eq!(
+        fingerprint.to_string(),
+        "10fd64f26109ee924162a0530416ca94bf3da354f3ab9ef9ff705a1129b33f88"
+    );
+    let json = serde_json::to_string(&fingerprint).unwrap();
+    assert_eq!(json, format!("\"{}\"", fingerprint));
+    assert_eq!(
+        serde_json::from_str::<Fingerprint>(&json).unwrap(),
+        fingerprint
+    );
+    assert!("not a fingerprint".parse::<Fingerprint>().is_err());
+}
+
+#[test]
+fn fingerprint_items_do_not_run_together() {
+    let split = |a: &str, b: &str| Fingerprinter::new().command(a).command(b).finish();
+    assert_ne!(split("ab", "c"), split("a", "bc"));
+    assert_ne!(
+        Fingerprinter::new().env("A", None).finish(),
+        Fingerprinter::new().env("A", Some("")).finish()
+    );
+}
+
+#[test]
+fn dag_fingerprints() {
+    let dir = std::env::temp_dir().join(format!("dagrs_fingerprint_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("main.c");
+    std::fs::write(&source, "int main() { return 0; }").unwrap();
+
+    let fingerprints = |command: &str, region: &str| {
+        let mut compile = DefaultTask::with_action("compile", CommandAction::new(command));
+        compile.set_files([&source], [dir.join("main.o")]);
+        let mut package = DefaultTask::with_action("package", CommandAction::new("tar cf a.tar"));
+        package.set_predecessors(&[&compile]);
+        let docs = DefaultTask::with_action("docs", CommandAction::new("doxygen"));
+        let ids = [compile.id(), package.id(), docs.id()];
+        let mut env = EnvVar::new();
+        env.set("REGION", region.to_string());
+        let mut dag = Dag::with_tasks(vec![compile, package, docs])
+            .with_config(DagConfig::new().fingerprint_env(["REGION"]));
+        dag.set_env(env);
+        let fingerprints = dag.fingerprints().unwrap();
+        ids.map(|id| fingerprints[&id])
+    };
+
+    let base = fingerprints("cc -c main.c", "eu");
+    assert_eq!(fingerprints("cc -c main.c", "eu"), base);
+
+    // A change of the command of a task changes its fingerprint and those of its successors.
+    let changed = fingerprints("cc -O2 -c main.c", "eu");
+    assert_ne!(changed[0], base[0]);
+    assert_ne!(changed[1], base[1]);
+    assert_eq!(changed[2], base[2]);
+
+    // So does a change of the contents of one of its input files.
+    std::fs::write(&source, "int main() { return 1; }").unwrap();
+    let changed = fingerprints("cc -c main.c", "eu");
+    assert_ne!(changed[0], base[0]);
+    assert_ne!(changed[1], base[1]);
+    assert_eq!(changed[2], base[2]);
+
+    // The variables of the environment listed in the configuration are part of all of them.
+    let changed = fingerprints("cc -c main.c", "us");
+    assert!(changed.iter().zip(&base).all(|(a, b)| a != b));
+
+    // An input file that cannot be read is an error.
+    std::fs::remove_file(&source).unwrap();
+    let mut compile = DefaultTask::with_action("compile", CommandAction::new("cc -c main.c"));
+    compile.set_files([&source], [dir.join("main.o")]);
+    assert!(Dag::with_tasks(vec![compile]).fingerprints().is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn wrapped_action_fingerprints() {
+    let fingerprint = |action: &dyn Complex| {
+        let mut fingerprinter = Fingerprinter::new();
+        action.fingerprint(&mut fingerprinter);
+        fingerprinter.finish()
+    };
+    let command = |cmd: &str| Action::Structure(Arc::new(CommandAction::new(cmd)));
+    // The wrappers of actions hold the fingerprint of the action they wrap.
+    let wrapped: [fn(Action) -> Box<dyn Complex>; 3] = [
+        |inner| Box::new(LogLayer.layer(inner)),
+        |inner| Box::new(CircuitBreaker::new(inner, 3, Duration::from_secs(1))),
+        |inner| Box::new(Lazy::new(move || inner.clone())),
+    ];
+    for wrap in wrapped {
+        assert_eq!(
+            fingerprint(wrap(command("make")).as_ref()),
+            fingerprint(wrap(command("make")).as_ref())
+        );
+        assert_ne!(
+            fingerprint(wrap(command("make")).as_ref()),
+            fingerprint(wrap(command("make install")).as_ref())
+        );
+    }
+
+    // So does a task of a wrapped action.
+    let task = |cmd: &str| {
+        let task = DefaultTask::with_action("build", LogLayer.layer(command(cmd)));
+        let mut fingerprinter = Fingerprinter::new();
+        task.fingerprint(&mut fingerprinter).unwrap();
+        fingerprinter.finish()
+    };
+    assert_ne!(task("make"), task("make install"));
+}
+
+#[cfg(feature = "rhai")]
+#[test]
+fn rhai_fingerprints() {
+    use dagrs::RhaiAction;
+
+    let fingerprint = |script: &str| {
+        let mut fingerprinter = Fingerprinter::new();
+        RhaiAction::new(script)
+            .unwrap()
+            .fingerprint(&mut fingerprinter);
+        fingerprinter.finish()
+    };
+    assert_eq!(fingerprint("1 + 1"), fingerprint("1 + 1"));
+    assert_ne!(fingerprint("1 + 1"), fingerprint("1 + 2"));
+}
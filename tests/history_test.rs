@@ -0,0 +1,69 @@
+//! Tests of the run history.
+
+use dagrs::{engine::TaskStatus, Dag, DefaultTask, Engine, Output, RunHistory};
+
+#[test]
+fn engine_records_runs() {
+    let path = std::env::temp_dir().join(format!("dagrs_history_{}.jsonl", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let history = RunHistory::new(&path);
+    assert!(history.runs().unwrap().is_empty());
+
+    let mut engine = Engine::default();
+    engine.set_history(history.clone());
+    let a = DefaultTask::with_closure("a", |_, _| Output::new(1));
+    engine.append_dag("ok", Dag::with_tasks(vec![a]));
+    let b = DefaultTask::with_closure("b", |_, _| Output::error("boom".to_string()));
+    engine.append_dag("broken", Dag::with_tasks(vec![b]));
+    assert_eq!(engine.run_sequential(), [true, false]);
+
+    let runs = history.runs().unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!((runs[0].number, runs[0].dag.as_str()), (1, "ok"));
+    assert!(runs[0].started_at <= runs[0].finished_at);
+    let broken = history.get(2).unwrap().unwrap();
+    assert!(!broken.success);
+    assert_eq!(broken.tasks[0].status, TaskStatus::Failed);
+    assert_eq!(broken.tasks[0].error.as_deref(), Some("boom"));
+    assert!(engine.run_dag("ok"));
+    let runs = history.runs_of("ok").unwrap();
+    assert_eq!(runs.len(), 2);
+    assert!(runs[1].success);
+    assert_ne!(runs[0].run_id, runs[1].run_id);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn concurrent_records_and_corrupt_lines() {
+    let path = std::env::temp_dir().join(format!("dagrs_history_c_{}.jsonl", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let history = RunHistory::new(&path);
+
+    let mut engine = Engine::default();
+    engine.set_history(history.clone());
+    for i in 0..8 {
+        let task = DefaultTask::with_closure("a", |_, _| Output::new(1));
+        engine.append_dag(&format!("dag {}", i), Dag::with_tasks(vec![task]));
+    }
+    assert!(engine.run_concurrent().into_iter().all(|ok| ok));
+    let mut numbers: Vec<u64> = history
+        .runs()
+        .unwrap()
+        .iter()
+        .map(|run| run.number)
+        .collect();
+    numbers.sort();
+    assert_eq!(numbers, (1..=8).collect::<Vec<_>>());
+
+    // A line cut short does not hide the other records.
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    std::io::Write::write_all(&mut file, b"{\"number\": 9, \"da\n").unwrap();
+    assert!(engine.run_dag("dag 0"));
+    let runs = history.runs().unwrap();
+    assert_eq!(runs.len(), 9);
+    assert_eq!(runs[8].number, 9);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,30 @@
+//! Tests of the HTML report of a run.
+
+use dagrs::{Dag, DefaultTask, Output};
+
+#[test]
+fn html_report() {
+    let a = DefaultTask::with_closure("a", |_, _| Output::new("<b>hi</b>".to_string()));
+    let mut b = DefaultTask::with_closure("b", |_, _| Output::error("boom".to_string()));
+    b.set_predecessors(&[&a]);
+    let mut c = DefaultTask::with_closure("c", |_, _| Output::empty());
+    c.set_predecessors(&[&b]);
+    let mut dag = Dag::with_tasks(vec![a, b, c]);
+    assert!(!dag.start().unwrap());
+
+    let path = std::env::temp_dir().join("dagrs_html_report.html");
+    dag.save_html_report(&path).unwrap();
+    let html = std::fs::read_to_string(&path).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h1>Run failed</h1>"));
+    assert!(html.contains(dag.run_id().unwrap()));
+    // One box per task and one line per edge.
+    assert_eq!(html.matches("<rect").count(), 3);
+    assert_eq!(html.matches("<line").count(), 2);
+    assert!(html.contains("&lt;b&gt;hi&lt;/b&gt;"));
+    assert!(!html.contains("<b>hi</b>"));
+    for status in ["succeeded", "failed", "skipped"] {
+        assert!(html.contains(&format!("<tr class=\"{}\">", status)));
+    }
+    assert!(html.contains("boom"));
+}
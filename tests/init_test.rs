@@ -0,0 +1,49 @@
+//! Tests of the project created by `dagrs init`.
+
+use std::{collections::HashMap, process::Command, sync::Arc};
+
+use dagrs::{Action, ActionRegistry, Dag, Output, Parser, YamlParser};
+
+#[test]
+fn init_project() {
+    let dir = std::env::temp_dir().join(format!("dagrs_init_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let init = Command::new(env!("CARGO_BIN_EXE_dagrs"))
+        .arg("init")
+        .arg(&dir)
+        .output()
+        .unwrap();
+    assert!(init.status.success());
+
+    // The actions are the `init_actions` example, built against this release of the crate.
+    let main = std::fs::read_to_string(dir.join("actions/src/main.rs")).unwrap();
+    assert_eq!(main, include_str!("../examples/init_actions.rs"));
+    let manifest = std::fs::read_to_string(dir.join("actions/Cargo.toml")).unwrap();
+    assert!(manifest.contains(&format!(
+        "dagrs = {{ version = \"{}\", features = [\"yaml\"] }}",
+        env!("CARGO_PKG_VERSION")
+    )));
+
+    // The workflow runs with the action type of the crate.
+    let mut registry = ActionRegistry::new();
+    registry.register("greet", |args| {
+        let greeting = format!("Hello, {}!", args["who"]);
+        Action::Closure(Arc::new(move |_, _| Output::new(greeting.clone())))
+    });
+    let tasks = YamlParser::new()
+        .action_registry(registry)
+        .parse_tasks(dir.join("workflow.yaml").to_str().unwrap(), HashMap::new())
+        .unwrap();
+    let mut dag = Dag::with_tasks_dyn(tasks);
+    assert!(dag.start().unwrap());
+    assert_eq!(*dag.get_result::<String>().unwrap(), "Hello, dagrs!");
+
+    // A second init does not overwrite the project.
+    let again = Command::new(env!("CARGO_BIN_EXE_dagrs"))
+        .arg("init")
+        .arg(&dir)
+        .output()
+        .unwrap();
+    assert!(!again.status.success());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,141 @@
+//! Tests of the per-task log context.
+
+use std::{
+    io::Write,
+    sync::{Arc, Mutex, Once},
+    time::Duration,
+};
+
+use dagrs::{
+    engine::DagConfig,
+    utils::{current_task, heartbeat, ContextLogger},
+    Action, Dag, DefaultTask, GroupStyle, GroupedOutput, Output, Task,
+};
+use log::{LevelFilter, Log, Metadata, Record};
+
+struct NopLogger;
+
+impl Log for NopLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, _record: &Record) {}
+
+    fn flush(&self) {}
+}
+
+fn init_logger() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        ContextLogger::new(NopLogger)
+            .init(LevelFilter::Info)
+            .unwrap()
+    });
+}
+
+#[test]
+fn capture_task_logs() {
+    init_logger();
+
+    let a = DefaultTask::with_closure("a", |_, _| {
+        let (_, name) = current_task().unwrap();
+        log::info!("running {}", name);
+        Output::empty()
+    });
+    let mut b = DefaultTask::with_closure("b", |_, _| {
+        log::warn!("something is wrong");
+        Output::error("boom".to_string())
+    });
+    b.set_predecessors(&[&a]);
+    let (a_id, b_id) = (a.id(), b.id());
+
+    let mut dag = Dag::with_tasks(vec![a, b]).capture_logs();
+    assert!(!dag.start().unwrap());
+
+    assert_eq!(dag.get_logs(a_id).unwrap(), ["[INFO] running a"]);
+    assert_eq!(dag.get_logs(b_id).unwrap(), ["[WARN] something is wrong"]);
+    assert!(current_task().is_none());
+}
+
+#[test]
+fn async_actions_keep_the_context() {
+    init_logger();
+
+    let action = Action::from_async_fn(|_, _| async {
+        for beat in 0..5 {
+            log::info!("beat {}", beat);
+            heartbeat();
+            std::thread::sleep(Duration::from_millis(40));
+        }
+        Output::empty()
+    });
+    let mut task = DefaultTask::new("async");
+    task.set_action_dyn(action);
+    let id = task.id();
+    let mut dag = Dag::with_tasks(vec![task])
+        .with_config(
+            DagConfig::new()
+                .stall_timeout(Duration::from_millis(100))
+                .kill_stalled(true),
+        )
+        .capture_logs();
+    assert!(dag.start().unwrap());
+    assert_eq!(dag.get_logs(id).unwrap().len(), 5);
+}
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn grouped_output() {
+    init_logger();
+
+    let run = |style| {
+        let tasks: Vec<_> = (0..4)
+            .map(|i| {
+                DefaultTask::with_closure(&format!("t{}", i), move |_, _| {
+                    for line in 0..3 {
+                        log::info!("t{} line {}", i, line);
+                        std::thread::yield_now();
+                    }
+                    if i == 3 {
+                        Output::error("boom".to_string())
+                    } else {
+                        Output::new(format!("t{} done", i))
+                    }
+                })
+            })
+            .collect();
+        let buffer = Buffer::default();
+        let mut dag = Dag::with_tasks(tasks)
+            .keep_going()
+            .with_grouped_output(GroupedOutput::new(style, buffer.clone()));
+        assert!(!dag.start().unwrap());
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        output
+    };
+
+    let output = run(GroupStyle::GitHubActions);
+    for i in 0..3 {
+        let group = format!(
+            "::group::t{i} (ok)\n[INFO] t{i} line 0\n[INFO] t{i} line 1\n[INFO] t{i} line 2\nt{i} done\n::endgroup::\n"
+        );
+        assert!(output.contains(&group), "{}", output);
+    }
+    assert!(output.contains("::group::t3 (failed)\n"));
+    assert!(output.contains("error: boom\n::endgroup::\n"));
+
+    let output = run(GroupStyle::Plain);
+    assert!(output.contains("==> t0 (ok)\n    [INFO] t0 line 0\n"));
+}
@@ -0,0 +1,138 @@
+//! Tests of the notifications of runs and task failures.
+
+use dagrs::{utils::Backoff, Dag, DefaultTask, EmailConfig, Notifier, NotifyOn, Output};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::Duration,
+};
+use tiny_http::{Response, Server};
+
+/// A webhook that forwards the bodies of the requests it receives, after failing the given
+/// number of requests.
+fn flaky_webhook(failures: usize) -> (String, Receiver<serde_json::Value>) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for (i, mut request) in server.incoming_requests().enumerate() {
+            if i < failures {
+                let _ = request.respond(Response::empty(503));
+                continue;
+            }
+            let body = serde_json::from_reader(request.as_reader()).unwrap();
+            sender.send(body).unwrap();
+            let _ = request.respond(Response::empty(200));
+        }
+    });
+    (url, receiver)
+}
+
+fn webhook() -> (String, Receiver<serde_json::Value>) {
+    flaky_webhook(0)
+}
+
+/// An SMTP server that accepts one email and forwards its data.
+fn smtp_server() -> (String, Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("smtp://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+        while let Some(Ok(line)) = lines.next() {
+            let reply = match line.split(' ').next().unwrap().to_uppercase().as_str() {
+                "DATA" => {
+                    writer.write_all(b"354 Go ahead\r\n").unwrap();
+                    let data: Vec<String> = lines
+                        .by_ref()
+                        .map(Result::unwrap)
+                        .take_while(|line| line != ".")
+                        .collect();
+                    sender.send(data.join("\n")).unwrap();
+                    "250 OK\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 Bye\r\n").unwrap();
+                    break;
+                }
+                _ => "250 OK\r\n",
+            };
+            writer.write_all(reply.as_bytes()).unwrap();
+        }
+    });
+    (url, receiver)
+}
+
+fn failing_dag() -> Dag {
+    let a = DefaultTask::with_closure("a", |_, _| Output::new(1));
+    let mut b = DefaultTask::with_closure("b", |_, _| Output::error("broken".to_string()));
+    b.set_predecessors(&[&a]);
+    Dag::with_tasks(vec![a, b])
+}
+
+#[test]
+fn webhook_notifications() {
+    let (url, bodies) = webhook();
+    let mut dag = failing_dag();
+    dag.add_notifier(Notifier::webhook(&url, NotifyOn::TaskFailure));
+    dag.add_notifier(Notifier::webhook(&url, NotifyOn::Failure).template("run {{ status }}"));
+    assert!(!dag.start().unwrap());
+
+    let task_failed = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(task_failed["event"], "task_failed");
+    assert_eq!(task_failed["task"]["name"], "b");
+    assert_eq!(task_failed["message"], "Task 'b' failed: broken");
+    let run_failed = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(run_failed["event"], "run_failed");
+    assert_eq!(run_failed["message"], "run failed");
+    assert_eq!(run_failed["report"]["tasks"][1]["status"], "failed");
+
+    // A successful run does not notify of failures.
+    let task = DefaultTask::with_closure("a", |_, _| Output::new(1));
+    let mut dag = Dag::with_tasks(vec![task]);
+    dag.add_notifier(Notifier::webhook(&url, NotifyOn::Failure));
+    dag.add_notifier(Notifier::webhook(&url, NotifyOn::Completion));
+    assert!(dag.start().unwrap());
+    let run_finished = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(run_finished["event"], "run_finished");
+    assert!(bodies.recv_timeout(Duration::from_millis(200)).is_err());
+}
+
+#[test]
+fn notification_retries() {
+    let (url, bodies) = flaky_webhook(2);
+    let mut dag = failing_dag();
+    dag.add_notifier(
+        Notifier::webhook(&url, NotifyOn::Failure)
+            .retries(2, Backoff::constant(Duration::from_millis(10))),
+    );
+    assert!(!dag.start().unwrap());
+    let run_failed = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(run_failed["event"], "run_failed");
+}
+
+#[test]
+fn email_notification() {
+    let (url, emails) = smtp_server();
+    let mut dag = failing_dag();
+    dag.add_notifier(Notifier::email(
+        EmailConfig {
+            smtp_url: url,
+            from: "dagrs@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+            subject: "Run {{ status }}".to_string(),
+        },
+        NotifyOn::Failure,
+    ));
+    assert!(!dag.start().unwrap());
+
+    let email = emails.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(email.contains("Subject: Run failed"));
+    assert!(email.contains("- a: succeeded"));
+    assert!(email.contains("- b: failed: broken"));
+}
@@ -0,0 +1,32 @@
+//! Tests of the rhai scripting action.
+
+use dagrs::{Dag, DefaultTask, EnvVar, Output, RhaiAction};
+
+#[test]
+fn rhai_action() {
+    let a = DefaultTask::with_closure("a", |_, _| Output::new(2i64));
+    let b = DefaultTask::with_closure("b", |_, _| Output::new(3usize));
+    let mut sum = DefaultTask::with_action(
+        "sum",
+        RhaiAction::new("let total = 0; for x in inputs { total += x } total * env(\"factor\")")
+            .unwrap(),
+    );
+    sum.set_predecessors(&[&a, &b]);
+
+    let mut env = EnvVar::new();
+    env.set("factor", 10i64);
+    let mut job = Dag::with_tasks(vec![a, b, sum]);
+    job.set_env(env);
+    assert!(job.start().unwrap());
+    assert_eq!(*job.get_result::<i64>().unwrap(), 50);
+}
+
+#[test]
+fn rhai_errors() {
+    assert!(RhaiAction::new("let x = ;").is_err());
+
+    let task = DefaultTask::with_action("fail", RhaiAction::new("throw \"boom\"").unwrap());
+    let mut job = Dag::with_tasks(vec![task]);
+    assert!(!job.start().unwrap());
+    assert!(job.take_errors()[0].to_string().contains("boom"));
+}
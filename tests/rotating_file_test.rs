@@ -0,0 +1,58 @@
+use std::{fs, io::Write};
+
+use dagrs::utils::{RotatingFileWriter, Rotation};
+
+#[test]
+fn rotate_by_size() {
+    let dir = std::env::temp_dir().join("dagrs_rotate_by_size");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dagrs.log");
+
+    let rotation = Rotation::new().max_size(10).max_files(2);
+    let mut writer = RotatingFileWriter::new(&path, rotation).unwrap();
+    for line in ["line 1\n", "line 2\n", "line 3\n", "line 4\n"] {
+        writer.write_all(line.as_bytes()).unwrap();
+    }
+    writer.flush().unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "line 4\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("dagrs.log.1")).unwrap(),
+        "line 3\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("dagrs.log.2")).unwrap(),
+        "line 2\n"
+    );
+    assert!(!dir.join("dagrs.log.3").exists());
+}
+
+#[test]
+fn rotate_now() {
+    let dir = std::env::temp_dir().join("dagrs_rotate_now");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dagrs.log");
+    fs::write(&path, "last run\n").unwrap();
+
+    // A new run starts a new file, and keeps the file of the last run.
+    let mut writer = RotatingFileWriter::new(&path, Rotation::new().max_files(1)).unwrap();
+    writer.rotate_now().unwrap();
+    writer.write_all(b"this run\n").unwrap();
+    writer.flush().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "this run\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("dagrs.log.1")).unwrap(),
+        "last run\n"
+    );
+
+    // An empty file is not rotated.
+    fs::write(&path, "").unwrap();
+    let mut writer = RotatingFileWriter::new(&path, Rotation::new().max_files(1)).unwrap();
+    writer.rotate_now().unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("dagrs.log.1")).unwrap(),
+        "last run\n"
+    );
+}
@@ -0,0 +1,111 @@
+//! Tests of the s3 transfer actions, against an in-process object storage.
+
+use dagrs::{Dag, DefaultTask, EnvVar, Output, S3DownloadAction, S3UploadAction};
+use std::{collections::HashMap, fs, thread};
+use tiny_http::{Method, Response, Server};
+
+/// Serve the objects put to a local server, by their paths. Returns the address of the server.
+fn object_storage() -> String {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}", server.server_addr().to_ip().unwrap());
+    thread::spawn(move || {
+        let mut objects = HashMap::new();
+        for mut request in server.incoming_requests() {
+            let path = request.url().split('?').next().unwrap().to_owned();
+            let response = match request.method() {
+                Method::Put => {
+                    let mut body = Vec::new();
+                    request.as_reader().read_to_end(&mut body).unwrap();
+                    objects.insert(path, body);
+                    Response::from_data(Vec::new())
+                }
+                Method::Get => match objects.get(&path) {
+                    Some(body) => Response::from_data(body.clone()),
+                    None => Response::from_string(
+                        "<Error><Code>NoSuchKey</Code><Message>not found</Message></Error>",
+                    )
+                    .with_status_code(404),
+                },
+                _ => Response::from_data(Vec::new()).with_status_code(405),
+            };
+            let _ = request.respond(response);
+        }
+    });
+    addr
+}
+
+fn env(endpoint: &str) -> EnvVar {
+    let mut env = EnvVar::new();
+    env.set("AWS_ACCESS_KEY_ID", "key".to_string());
+    env.set("AWS_SECRET_ACCESS_KEY", "secret".to_string());
+    env.set("AWS_ENDPOINT_URL", endpoint.to_string());
+    env
+}
+
+#[test]
+fn s3_upload_and_download() {
+    let dir = std::env::temp_dir().join(format!("dagrs_s3_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("source.txt");
+    fs::write(&source, "hello s3").unwrap();
+    let source = source.to_str().unwrap().to_owned();
+    let target = dir.join("downloaded/{{ inputs.name }}.txt");
+
+    let file = DefaultTask::with_closure("file", move |_, _| Output::new(source.clone()));
+    let name = DefaultTask::with_closure("name", |_, _| Output::new("report".to_string()));
+    let mut upload = DefaultTask::with_action(
+        "upload",
+        S3UploadAction::new("{{ inputs.file }}", "bucket", "{{ inputs.name }}.txt"),
+    );
+    upload.set_predecessors(&[&file, &name]);
+    let mut download = DefaultTask::with_action(
+        "download",
+        S3DownloadAction::new("bucket", "report.txt", target.to_str().unwrap()),
+    );
+    download.set_predecessors(&[&upload, &name]);
+
+    let mut job = Dag::with_tasks(vec![file, name, upload, download]);
+    job.set_env(env(&object_storage()));
+    assert!(job.start().unwrap());
+
+    let path = job.get_result::<String>().unwrap();
+    assert_eq!(*path, dir.join("downloaded/report.txt").to_str().unwrap());
+    assert_eq!(fs::read_to_string(path.as_str()).unwrap(), "hello s3");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn s3_missing_object() {
+    let download = DefaultTask::with_action(
+        "download",
+        S3DownloadAction::new("bucket", "missing.txt", "/tmp/dagrs_s3_missing.txt"),
+    );
+    let mut job = Dag::with_tasks(vec![download]);
+    job.set_env(env(&object_storage()));
+    assert!(!job.start().unwrap());
+
+    let download = DefaultTask::with_action(
+        "download",
+        S3DownloadAction::new("bucket", "missing.txt", "/tmp/dagrs_s3_missing.txt"),
+    );
+    let mut job = Dag::with_tasks(vec![download]);
+    assert!(!job.start().unwrap());
+}
+
+#[test]
+fn s3_cache() {
+    use dagrs::{
+        engine::{CacheBackend, S3Cache},
+        Fingerprinter,
+    };
+
+    let cache = S3Cache::from_env("bucket", "cache/", &env(&object_storage())).unwrap();
+    let fingerprint = Fingerprinter::new().command("make").finish();
+    assert_eq!(cache.get(&fingerprint).unwrap(), None);
+    cache.put(&fingerprint, b"built").unwrap();
+    assert_eq!(
+        cache.get(&fingerprint).unwrap().as_deref(),
+        Some(&b"built"[..])
+    );
+    assert!(S3Cache::from_env("bucket", "cache/", &EnvVar::new()).is_err());
+}
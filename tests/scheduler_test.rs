@@ -0,0 +1,80 @@
+//! Tests of the cron-style scheduler.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use dagrs::{Dag, DefaultTask, Engine, Output, OverlapPolicy, RunHistory};
+
+/// Schedule a dag taking 1.5s every second and count its runs.
+fn schedule_slow_dag(engine: &mut Engine, name: &str, policy: OverlapPolicy) -> Arc<AtomicUsize> {
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counter = runs.clone();
+    engine
+        .schedule_dag(name, "* * * * * *", policy, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let task = DefaultTask::with_closure("slow", |_, _| {
+                thread::sleep(Duration::from_millis(1500));
+                Output::empty()
+            });
+            Ok(Dag::with_tasks(vec![task]))
+        })
+        .unwrap();
+    runs
+}
+
+#[test]
+fn overlap_policies() {
+    let mut engine = Engine::default();
+    let skip = schedule_slow_dag(&mut engine, "skip", OverlapPolicy::Skip);
+    let parallel = schedule_slow_dag(&mut engine, "parallel", OverlapPolicy::Parallel);
+    let queue = schedule_slow_dag(&mut engine, "queue", OverlapPolicy::Queue);
+
+    let start = Instant::now();
+    engine.run_daemon_until(|| start.elapsed() > Duration::from_millis(3200));
+
+    let (skip, parallel, queue) = (
+        skip.load(Ordering::SeqCst),
+        parallel.load(Ordering::SeqCst),
+        queue.load(Ordering::SeqCst),
+    );
+    assert_eq!(skip, 2);
+    assert!(parallel >= 3);
+    // Queued runs are not dropped, they all run before the daemon returns.
+    assert_eq!(queue, parallel);
+}
+
+#[test]
+fn scheduled_runs_are_recorded() {
+    let path = std::env::temp_dir().join(format!("dagrs_history_s_{}.jsonl", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let mut engine = Engine::default();
+    engine.set_history(RunHistory::new(&path));
+    engine
+        .schedule_dag("tick", "* * * * * *", OverlapPolicy::Skip, || {
+            let task = DefaultTask::with_closure("a", |_, _| Output::empty());
+            Ok(Dag::with_tasks(vec![task]))
+        })
+        .unwrap();
+    let start = Instant::now();
+    engine.run_daemon_until(|| start.elapsed() > Duration::from_millis(1200));
+
+    let runs = engine.history().unwrap().runs().unwrap();
+    assert!(!runs.is_empty());
+    assert!(runs.iter().all(|run| run.dag == "tick" && run.success));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn invalid_cron_expression() {
+    let mut engine = Engine::default();
+    let res = engine.schedule_dag("bad", "every minute", OverlapPolicy::Skip, || {
+        Ok(Dag::with_tasks(Vec::<DefaultTask>::new()))
+    });
+    assert!(res.is_err());
+}
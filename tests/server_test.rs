@@ -0,0 +1,194 @@
+//! Tests of the HTTP control API.
+
+use std::{sync::Arc, thread, time::Duration};
+
+use dagrs::{engine::RunHistory, server::Server, Engine};
+
+const FAST: &str = r#"
+dagrs:
+  a:
+    name: "first"
+    cmd: echo a
+  b:
+    name: "second"
+    after: [ a ]
+    cmd: echo b
+"#;
+
+const SLOW: &str = r#"
+dagrs:
+  a:
+    name: "sleep"
+    cmd: sleep 1
+  b:
+    name: "after sleep"
+    after: [ a ]
+    cmd: echo b
+"#;
+
+fn start_server() -> (Arc<Server>, String) {
+    serve(Server::bind("127.0.0.1:0").unwrap())
+}
+
+fn serve(server: Server) -> (Arc<Server>, String) {
+    let server = Arc::new(server);
+    let url = format!("http://{}", server.local_addr().unwrap());
+    let serving = server.clone();
+    thread::spawn(move || serving.serve());
+    (server, url)
+}
+
+fn get(url: &str) -> serde_json::Value {
+    ureq::get(url).call().unwrap().into_json().unwrap()
+}
+
+/// Poll a run until it is no longer running.
+fn wait_for(url: &str) -> serde_json::Value {
+    loop {
+        let run = get(url);
+        if run["status"] != "running" {
+            return run;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn submit_and_monitor_runs() {
+    let (server, url) = start_server();
+
+    let submitted: serde_json::Value = ureq::post(&format!("{url}/runs"))
+        .send_string(FAST)
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let run_url = format!("{url}/runs/{}", submitted["id"]);
+    let run = wait_for(&run_url);
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(get(&format!("{run_url}?verbose=1"))["status"], "succeeded");
+    assert_eq!(run["tasks"]["first"], "succeeded");
+    assert_eq!(run["tasks"]["second"], "succeeded");
+
+    let log = ureq::get(&format!("{run_url}/log"))
+        .call()
+        .unwrap()
+        .into_string()
+        .unwrap();
+    assert!(log.contains("\"event\":\"dag_finished\""));
+
+    let runs = get(&format!("{url}/runs"));
+    assert_eq!(runs.as_array().unwrap().len(), 1);
+
+    let invalid = ureq::post(&format!("{url}/runs")).send_string("dagrs: [");
+    assert!(matches!(invalid, Err(ureq::Error::Status(400, _))));
+    let large = ureq::post(&format!("{url}/runs")).send_string(&"#".repeat(2 * 1024 * 1024));
+    assert!(matches!(large, Err(ureq::Error::Status(413, _))));
+    let missing = ureq::get(&format!("{url}/runs/42")).call();
+    assert!(matches!(missing, Err(ureq::Error::Status(404, _))));
+
+    server.stop();
+}
+
+#[test]
+fn cancel_run() {
+    let (server, url) = start_server();
+
+    let submitted: serde_json::Value = ureq::post(&format!("{url}/runs"))
+        .send_string(SLOW)
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let run_url = format!("{url}/runs/{}", submitted["id"]);
+    ureq::post(&format!("{run_url}/cancel")).call().unwrap();
+
+    let run = wait_for(&run_url);
+    assert_eq!(run["status"], "cancelled");
+    assert_eq!(run["tasks"]["after sleep"], "skipped");
+
+    let again = ureq::post(&format!("{run_url}/cancel")).call();
+    assert!(matches!(again, Err(ureq::Error::Status(409, _))));
+
+    server.stop();
+}
+
+fn submit(url: &str, yaml: &str) -> String {
+    let submitted: serde_json::Value = ureq::post(&format!("{url}/runs"))
+        .send_string(yaml)
+        .unwrap()
+        .into_json()
+        .unwrap();
+    format!("{url}/runs/{}", submitted["id"])
+}
+
+#[test]
+fn token() {
+    let (server, url) = serve(Server::bind("127.0.0.1:0").unwrap().with_token("secret"));
+
+    for request in [
+        ureq::get(&format!("{url}/runs")),
+        ureq::post(&format!("{url}/runs")),
+    ] {
+        assert!(matches!(request.call(), Err(ureq::Error::Status(401, _))));
+    }
+    let wrong = ureq::get(&format!("{url}/runs"))
+        .set("Authorization", "Bearer guess")
+        .call();
+    assert!(matches!(wrong, Err(ureq::Error::Status(401, _))));
+    let runs: serde_json::Value = ureq::get(&format!("{url}/runs"))
+        .set("Authorization", "Bearer secret")
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(runs, serde_json::json!([]));
+
+    server.stop();
+}
+
+#[test]
+fn forget_runs() {
+    let (server, url) = serve(Server::bind("127.0.0.1:0").unwrap().keep_runs(2));
+
+    let slow = submit(&url, SLOW);
+    let deleted = ureq::delete(&slow).call();
+    assert!(matches!(deleted, Err(ureq::Error::Status(409, _))));
+    wait_for(&slow);
+    ureq::delete(&slow).call().unwrap();
+    let missing = ureq::get(&slow).call();
+    assert!(matches!(missing, Err(ureq::Error::Status(404, _))));
+
+    // Only the last runs that finished are kept.
+    for _ in 0..3 {
+        let run = submit(&url, FAST);
+        wait_for(&run);
+    }
+    let runs = get(&format!("{url}/runs"));
+    let ids: Vec<u64> = runs
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|run| run["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [3, 4]);
+
+    server.stop();
+}
+
+#[test]
+fn runs_of_the_engine() {
+    let path = std::env::temp_dir().join(format!("dagrs_server_history_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut engine = Engine::default();
+    engine.set_history(RunHistory::new(&path));
+    let (server, url) = serve(Server::bind("127.0.0.1:0").unwrap().with_engine(engine));
+
+    let run = submit(&url, FAST);
+    wait_for(&run);
+    // The run is recorded before its status changes.
+    let records = RunHistory::new(&path).runs().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].dag, "run 1");
+
+    std::fs::remove_file(&path).unwrap();
+    server.stop();
+}
@@ -0,0 +1,43 @@
+//! Tests of the sql query action.
+
+use dagrs::{Dag, DefaultTask, EnvVar, Output, SqlAction, SqlRows, SqlValue};
+
+#[test]
+fn sql_action() {
+    let id = DefaultTask::with_closure("id", |_, _| Output::new(41i64));
+    let mut query = DefaultTask::with_action(
+        "query",
+        SqlAction::new("SELECT ? + 1 AS n, ? AS label")
+            .bind_input("id")
+            .bind(SqlValue::Text("answer".to_string())),
+    );
+    query.set_predecessors(&[&id]);
+
+    let mut env = EnvVar::new();
+    env.set("DATABASE_URL", "sqlite::memory:".to_string());
+    let mut job = Dag::with_tasks(vec![id, query]);
+    job.set_env(env);
+    assert!(job.start().unwrap());
+
+    let rows = job.get_result::<SqlRows>().unwrap();
+    assert_eq!(rows.columns, ["n", "label"]);
+    assert_eq!(rows.get(0, "n"), Some(&SqlValue::Int(42)));
+    assert_eq!(
+        rows.get(0, "label"),
+        Some(&SqlValue::Text("answer".to_string()))
+    );
+}
+
+#[test]
+fn sql_errors() {
+    let missing_url = DefaultTask::with_action("no url", SqlAction::new("SELECT 1"));
+    let mut job = Dag::with_tasks(vec![missing_url]);
+    assert!(!job.start().unwrap());
+
+    let invalid = DefaultTask::with_action("invalid", SqlAction::new("SELEKT 1"));
+    let mut env = EnvVar::new();
+    env.set("DATABASE_URL", "sqlite::memory:".to_string());
+    let mut job = Dag::with_tasks(vec![invalid]);
+    job.set_env(env);
+    assert!(!job.start().unwrap());
+}
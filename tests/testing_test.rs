@@ -0,0 +1,73 @@
+//! Tests of the testing tools.
+
+use dagrs::{
+    testing::{assert_not_ran, assert_ran, assert_ran_before, assert_snapshot, MockAction},
+    Dag,
+};
+
+#[test]
+fn mock_action_script() {
+    let a = MockAction::new().returns(1usize).returns(2usize);
+    let b = MockAction::new().fails("boom");
+    let c = MockAction::new();
+
+    let task_a = a.task("A");
+    let mut task_b = b.task("B");
+    let mut task_c = c.task("C");
+    task_b.set_predecessors(&[&task_a]);
+    task_c.set_predecessors(&[&task_b]);
+
+    let mut dag = Dag::with_tasks(vec![task_a, task_b, task_c]);
+    assert!(!dag.start().unwrap());
+
+    assert_ran_before(&dag, "A", "B");
+    assert_ran(&dag, "B");
+    assert_not_ran(&dag, "C");
+    assert_eq!(a.call_count(), 1);
+    assert_eq!(b.calls()[0][0].get::<usize>(), Some(&1));
+    assert_eq!(c.call_count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "expected task 'B' to run before task 'A'")]
+fn ran_before_violated() {
+    let a = MockAction::new().task("A");
+    let mut b = MockAction::new().task("B");
+    b.set_predecessors(&[&a]);
+
+    let mut dag = Dag::with_tasks(vec![a, b]);
+    assert!(dag.start().unwrap());
+    assert_ran_before(&dag, "B", "A");
+}
+
+#[test]
+fn mock_action_panics() {
+    let a = MockAction::new().panics("oops");
+    let mut dag = Dag::with_tasks(vec![a.task("A")]);
+    assert!(!dag.start().unwrap());
+    assert_eq!(a.call_count(), 1);
+}
+
+#[test]
+fn execution_snapshot() {
+    let a = MockAction::new().task("A");
+    let mut b = MockAction::new().task("B");
+    let mut c = MockAction::new().task("C");
+    let mut d = MockAction::new().fails("boom").task("D");
+    let mut e = MockAction::new().task("E");
+    b.set_predecessors(&[&a]);
+    c.set_predecessors(&[&a]);
+    d.set_predecessors(&[&b]);
+    e.set_predecessors(&[&c, &d]);
+
+    let mut dag = Dag::with_tasks(vec![a, b, c, d, e]).keep_going();
+    assert!(!dag.start().unwrap());
+
+    let snapshot = dag.execution_snapshot();
+    assert_eq!(snapshot.blocks, [vec!["A"], vec!["B", "C"], vec!["D"]]);
+    assert_eq!(snapshot.not_run, ["E"]);
+
+    let path = std::env::temp_dir().join("dagrs_execution_snapshot.json");
+    snapshot.save(&path).unwrap();
+    assert_snapshot(&dag, &path);
+}
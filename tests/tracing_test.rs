@@ -0,0 +1,146 @@
+//! Tests of the tracing spans of a run.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use dagrs::{Dag, DefaultTask, Output};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+use tracing_core::span::Current;
+
+type Fields = HashMap<String, String>;
+
+/// Records the spans and the events inside them.
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, (String, Fields)>>,
+    metadata: Mutex<HashMap<u64, &'static Metadata<'static>>>,
+    /// The message of each event, with the span it happened in.
+    events: Mutex<Vec<(String, Option<u64>)>>,
+}
+
+thread_local! {
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Collect<'a>(&'a mut Fields);
+
+impl Visit for Collect<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+struct Subscribe(Arc<Recorder>);
+
+impl Subscriber for Subscribe {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut fields = Fields::new();
+        span.record(&mut Collect(&mut fields));
+        self.0.metadata.lock().unwrap().insert(id, span.metadata());
+        self.0
+            .spans
+            .lock()
+            .unwrap()
+            .insert(id, (span.metadata().name().to_string(), fields));
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::new();
+        event.record(&mut Collect(&mut fields));
+        let span = ENTERED.with(|entered| entered.borrow().last().copied());
+        self.0
+            .events
+            .lock()
+            .unwrap()
+            .push((fields.remove("message").unwrap_or_default(), span));
+    }
+
+    fn current_span(&self) -> Current {
+        match ENTERED.with(|entered| entered.borrow().last().copied()) {
+            Some(id) => Current::new(Id::from_u64(id), self.0.metadata.lock().unwrap()[&id]),
+            None => Current::none(),
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, _span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().pop());
+    }
+}
+
+#[test]
+fn spans_of_a_run() {
+    let recorder = Arc::new(Recorder::default());
+    tracing::subscriber::set_global_default(Subscribe(recorder.clone())).unwrap();
+
+    let a = DefaultTask::with_closure("a", |_, _| {
+        tracing::info!("inside a");
+        Output::new(1)
+    });
+    let mut b = DefaultTask::with_closure("b", |_, _| {
+        tracing::info!("inside b");
+        Output::new(2)
+    });
+    // An action with a timeout runs on a blocking thread.
+    b.set_timeout(Duration::from_secs(10));
+    b.set_predecessors(&[&a]);
+    let mut dag = Dag::with_tasks(vec![a, b]);
+    assert!(dag.start().unwrap());
+    let run_id = dag.run_id().unwrap();
+
+    let spans = recorder.spans.lock().unwrap();
+    let (dag_span, _) = spans
+        .iter()
+        .find(|(_, (name, fields))| name == "dag" && fields["run.id"] == run_id)
+        .unwrap();
+    assert_eq!(spans[dag_span].1["tasks"], "2");
+    let task_span = |task: &str| {
+        spans
+            .iter()
+            .find(|(_, (name, fields))| {
+                name == "task" && fields["task.name"] == task && fields["run.id"] == run_id
+            })
+            .map(|(id, _)| *id)
+            .unwrap()
+    };
+    let events = recorder.events.lock().unwrap();
+    let span_of = |message: &str| {
+        events
+            .iter()
+            .find(|(event, _)| event == message)
+            .and_then(|(_, span)| *span)
+    };
+    assert_eq!(span_of("inside a"), Some(task_span("a")));
+    assert_eq!(span_of("inside b"), Some(task_span("b")));
+}
@@ -1,11 +1,11 @@
 use std::collections::HashMap;
 
-use dagrs::{ParseError, Parser, Task, YamlParser};
+use dagrs::{Action, Output, ParseError, Parser, Task, YamlParser};
 
 #[test]
 fn file_not_found_test() {
     let no_such_file: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("./no_such_file.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("./no_such_file.yaml", HashMap::new());
     // let err = no_such_file.unwrap_err().to_string();
     // println!("{err}");
     assert!(no_such_file.is_err())
@@ -14,7 +14,7 @@ fn file_not_found_test() {
 #[test]
 fn illegal_yaml_content() {
     let illegal_content: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("tests/config/illegal_content.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("tests/config/illegal_content.yaml", HashMap::new());
     // let err = illegal_content.unwrap_err().to_string();
     // println!("{err}");
     assert!(illegal_content.is_err())
@@ -23,7 +23,7 @@ fn illegal_yaml_content() {
 #[test]
 fn empty_content() {
     let empty_content: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("tests/config/empty_file.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("tests/config/empty_file.yaml", HashMap::new());
     // let err = empty_content.unwrap_err().to_string();
     // println!("{err}");
     assert!(empty_content.is_err())
@@ -32,7 +32,7 @@ fn empty_content() {
 #[test]
 fn yaml_no_start_with_dagrs() {
     let forget_dagrs: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("tests/config/no_start_with_dagrs.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("tests/config/no_start_with_dagrs.yaml", HashMap::new());
     // let err = forget_dagrs.unwrap_err().to_string();
     // println!("{err}");
     assert!(forget_dagrs.is_err())
@@ -41,7 +41,7 @@ fn yaml_no_start_with_dagrs() {
 #[test]
 fn yaml_task_no_name() {
     let no_task_name: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("tests/config/no_task_name.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("tests/config/no_task_name.yaml", HashMap::new());
     // let err = no_task_name.unwrap_err().to_string();
     // println!("{err}");
     assert!(no_task_name.is_err())
@@ -50,7 +50,7 @@ fn yaml_task_no_name() {
 #[test]
 fn yaml_task_not_found_precursor() {
     let not_found_pre: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("tests/config/precursor_not_found.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("tests/config/precursor_not_found.yaml", HashMap::new());
     // let err = not_found_pre.unwrap_err().to_string();
     // println!("{err}");
     assert!(not_found_pre.is_err())
@@ -59,7 +59,7 @@ fn yaml_task_not_found_precursor() {
 #[test]
 fn yaml_task_no_script_config() {
     let script: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("tests/config/no_script.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("tests/config/no_script.yaml", HashMap::new());
     // let err = script.unwrap_err().to_string();
     // println!("{err}");
     assert!(script.is_err())
@@ -68,6 +68,203 @@ fn yaml_task_no_script_config() {
 #[test]
 fn correct_parse() {
     let tasks: Result<Vec<Box<dyn Task>>, ParseError> =
-        YamlParser.parse_tasks("tests/config/correct.yaml", HashMap::new());
+        YamlParser::new().parse_tasks("tests/config/correct.yaml", HashMap::new());
     assert!(tasks.is_ok());
 }
+
+#[test]
+fn json_schema() {
+    let schema = YamlParser::json_schema();
+    assert_eq!(schema["required"][0], "dagrs");
+    let task = &schema["$defs"]["task"]["properties"];
+    // The schema gives the attributes the parser knows, checked by `json_schema` in debug
+    // builds, and a strict parser accepts each of them.
+    for key in task.as_object().unwrap().keys() {
+        let yaml = format!(
+            "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    {}: ~\n",
+            key
+        );
+        let err = YamlParser::new()
+            .strict()
+            .parse_tasks_from_str(&yaml, HashMap::new())
+            .err()
+            .map(|err| err.to_string())
+            .unwrap_or_default();
+        assert!(!err.contains("is unknown"), "{key}: {err}");
+    }
+    // Every shell of the schema is accepted by the parser.
+    for shell in task["shell"]["enum"].as_array().unwrap() {
+        let yaml = format!(
+            "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    shell: {}\n",
+            shell.as_str().unwrap()
+        );
+        assert!(YamlParser::new()
+            .parse_tasks_from_str(&yaml, HashMap::new())
+            .is_ok());
+    }
+}
+
+#[test]
+fn strict_mode() {
+    let yaml = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    timout: 5\n";
+    assert!(YamlParser::new()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .is_ok());
+    let err = YamlParser::new()
+        .strict()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("'timout'"), "{err}");
+    assert!(err.contains("did you mean 'timeout'"), "{err}");
+
+    let yaml = "dagrs:\n  a:\n    name: a\n    cmd: echo a\nprofile:\n  prod: {}\n";
+    assert!(YamlParser::new()
+        .strict()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .is_err());
+    let correct = std::fs::read_to_string("tests/config/correct.yaml").unwrap();
+    assert!(YamlParser::new()
+        .strict()
+        .parse_tasks_from_str(&correct, HashMap::new())
+        .is_ok());
+}
+
+#[test]
+fn vars() {
+    let yaml = "vars:\n  greeting: hello\n  wait: 5\ndagrs:\n  a:\n    name: ${vars.greeting} a\n    cmd: echo ${vars.greeting} ${HOME}\n    timeout: ${vars.wait}\n";
+    let tasks = YamlParser::new()
+        .strict()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .unwrap();
+    assert_eq!(tasks[0].name(), "hello a");
+    assert_eq!(
+        tasks[0].config().timeout,
+        Some(std::time::Duration::from_secs(5))
+    );
+
+    let undefined = "dagrs:\n  a:\n    name: a\n    cmd: echo ${vars.missing}\n";
+    let err = YamlParser::new()
+        .parse_tasks_from_str(undefined, HashMap::new())
+        .unwrap_err();
+    assert!(err.to_string().contains("vars.missing"));
+}
+
+#[test]
+fn templates() {
+    let yaml = "templates:\n  deploy:\n    cmd: ./deploy.sh ${with.region}\n    timeout: 60\ndagrs:\n  eu:\n    name: Deploy ${with.region}\n    uses: deploy\n    with:\n      region: eu\n  us:\n    name: Deploy us\n    uses: deploy\n    with:\n      region: us\n    timeout: 5\n";
+    let tasks = YamlParser::new()
+        .strict()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .unwrap();
+    let timeouts: Vec<_> = tasks.iter().map(|task| task.config().timeout).collect();
+    assert_eq!(
+        timeouts,
+        [
+            Some(std::time::Duration::from_secs(60)),
+            Some(std::time::Duration::from_secs(5))
+        ]
+    );
+    // The arguments only apply to the template.
+    assert_eq!(tasks[0].name(), "Deploy ${with.region}");
+
+    let missing_arg =
+        "templates:\n  t:\n    cmd: echo ${with.x}\ndagrs:\n  a:\n    name: a\n    uses: t\n";
+    assert!(YamlParser::new()
+        .parse_tasks_from_str(missing_arg, HashMap::new())
+        .is_err());
+    let unknown = "dagrs:\n  a:\n    name: a\n    uses: t\n";
+    let err = YamlParser::new()
+        .parse_tasks_from_str(unknown, HashMap::new())
+        .unwrap_err();
+    assert!(err.to_string().contains("template 't'"));
+}
+
+#[test]
+fn multiple_files() {
+    let tasks = YamlParser::with_profile("prod")
+        .parse_files(&["tests/config/split"], HashMap::new())
+        .unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[1].name(), "Deploy");
+    assert_eq!(tasks[1].precursors(), [tasks[0].id()]);
+    assert_eq!(tasks[1].config().retries, Some(2));
+
+    let collision = YamlParser::new()
+        .parse_files(
+            &[
+                "tests/config/split/build.yaml",
+                "tests/config/split/build.yaml",
+            ],
+            HashMap::new(),
+        )
+        .unwrap_err();
+    assert!(collision.to_string().contains("'build'"));
+    assert!(YamlParser::with_profile("dev")
+        .parse_files(&["tests/config/split"], HashMap::new())
+        .is_err());
+}
+
+#[test]
+fn locks() {
+    let yaml = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    lock: db\n  b:\n    name: b\n    cmd: echo b\n    lock: [db, cache]\n";
+    let tasks = YamlParser::new()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .unwrap();
+    assert_eq!(tasks[0].locks(), ["db"]);
+    assert_eq!(tasks[1].locks(), ["db", "cache"]);
+
+    let illegal = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    lock: 3\n";
+    assert!(YamlParser::new()
+        .parse_tasks_from_str(illegal, HashMap::new())
+        .is_err());
+}
+
+#[test]
+fn pools() {
+    let yaml =
+        "pools:\n  downloads: 4\ndagrs:\n  a:\n    name: a\n    cmd: echo a\n    pool: downloads\n";
+    let tasks = YamlParser::new()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .unwrap();
+    let pool = tasks[0].pool().unwrap();
+    assert_eq!((pool.name.as_str(), pool.size), ("downloads", 4));
+
+    let undeclared = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    pool: downloads\n";
+    assert!(YamlParser::new()
+        .parse_tasks_from_str(undeclared, HashMap::new())
+        .is_err());
+}
+
+#[test]
+fn execution() {
+    let yaml = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    execution: blocking\n";
+    let tasks = YamlParser::new()
+        .parse_tasks_from_str(yaml, HashMap::new())
+        .unwrap();
+    assert_eq!(
+        tasks[0].config().execution,
+        Some(dagrs::engine::Execution::Blocking)
+    );
+
+    let illegal = "dagrs:\n  a:\n    name: a\n    cmd: echo a\n    execution: fast\n";
+    assert!(YamlParser::new()
+        .parse_tasks_from_str(illegal, HashMap::new())
+        .is_err());
+}
+
+#[test]
+fn shell_without_command() {
+    let actions = || HashMap::from([("a".to_owned(), Action::from_fn(|_, _| Output::empty()))]);
+    // The shell of a task whose action is given only runs its condition.
+    let yaml = "dagrs:\n  a:\n    name: a\n    shell: bash\n    if: test -n \"$BASH\"\n";
+    let tasks = YamlParser::new()
+        .parse_tasks_from_str(yaml, actions())
+        .unwrap();
+    assert!(tasks[0].condition().is_some());
+
+    let ignored = "dagrs:\n  a:\n    name: a\n    shell: bash\n";
+    assert!(YamlParser::new()
+        .parse_tasks_from_str(ignored, actions())
+        .is_err());
+}